@@ -0,0 +1,74 @@
+//! Benchmarks of the detection kernels: the monotonic detector across chunk sizes, and each
+//! algorithm on one chunk. Run them with `cargo bench`, or `cargo bench -- <filter>` for those
+//! whose names contain `<filter>`; compare runs before and after a change.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use monophonic_detector::{
+    generate::{Generator, Tones},
+    Algorithm, PitchDetector,
+};
+
+/// How long each benchmark is timed for, after warming up for a tenth of it.
+const MEASUREMENT_TIME: Duration = Duration::from_secs(2);
+
+const CHUNK_SIZES: &[usize] = &[1024, 2048, 4096, 8192, 17640];
+
+const ALGORITHMS: &[Algorithm] = &[
+    Algorithm::Monotonic,
+    Algorithm::Yin,
+    Algorithm::Mpm,
+    Algorithm::Cepstrum,
+    Algorithm::Hps,
+    Algorithm::Zcr,
+];
+
+/// Time `f` and print the mean time per call, if `name` contains `filter`.
+fn bench<T>(filter: Option<&str>, name: &str, mut f: impl FnMut() -> T) {
+    if filter.is_some_and(|filter| !name.contains(filter)) {
+        return;
+    }
+
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < MEASUREMENT_TIME / 10 {
+        black_box(f());
+    }
+
+    let start = Instant::now();
+    let mut iterations = 0u32;
+    while start.elapsed() < MEASUREMENT_TIME {
+        black_box(f());
+        iterations += 1;
+    }
+    let mean = start.elapsed() / iterations;
+    println!("{:<32} {:>12.1?} ({} iterations)", name, mean, iterations);
+}
+
+fn main() {
+    // `cargo bench` passes `--bench` along with any filter.
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let filter = filter.as_deref();
+
+    // A tone in the middle of the range, with some harmonics.
+    let samples = Generator::new(Tones::Steps(vec![220.0]))
+        .with_duration(1.0)
+        .with_harmonics(vec![1.0, 0.5, 0.3])
+        .samples();
+
+    for &chunk_size in CHUNK_SIZES {
+        let detector = PitchDetector::default().with_chunk_size(chunk_size);
+        let chunk = &samples[..chunk_size];
+        bench(filter, &format!("monotonic/{}", chunk_size), || {
+            detector.compute_monotonic_freq(black_box(chunk))
+        });
+    }
+
+    let chunk = &samples[..PitchDetector::default().chunk_size()];
+    for &algorithm in ALGORITHMS {
+        let detector = PitchDetector::default().with_algorithm(algorithm);
+        bench(filter, &format!("detect/{}", algorithm), || {
+            detector.detect(black_box(chunk))
+        });
+    }
+}
@@ -0,0 +1,73 @@
+/*
+ * The C API of the pitch detector, built with the `ffi` feature, e.g. as a static library:
+ *
+ *     cargo rustc --release --lib --features ffi --crate-type staticlib
+ *
+ * then link target/release/libmonophonic_detector.a. See src/ffi.rs, which this must match.
+ */
+
+#ifndef PITCH_DETECTOR_H
+#define PITCH_DETECTOR_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A detector and the samples it has been fed. */
+typedef struct PdDetector PdDetector;
+
+/* An estimate. */
+typedef struct PdPitch {
+    /* The estimated frequency in Hz, or 0 if there isn't one. */
+    double freq;
+    /* The MIDI note number of the pitch matching `freq`, or -1 if there isn't one. */
+    int32_t midi;
+    /* How far `freq` is from the pitch, in cents. */
+    double cents;
+    /* How periodic the signal is at `freq`, from 0 to 1. */
+    double confidence;
+    /* Whether the chunk was silent. */
+    bool rest;
+} PdPitch;
+
+/*
+ * A detector for a mono signal sampled at `sample_rate` Hz, with the default settings, or NULL if
+ * the sample rate is 0. Free it with `pd_destroy`.
+ */
+PdDetector *pd_create(uint32_t sample_rate);
+
+/*
+ * Feed `len` samples, from -1 to 1, to `detector`. Returns the number of estimates made from
+ * them; the latest is what `pd_get_pitch` gets. The pitch is estimated every quarter chunk.
+ */
+size_t pd_feed_samples(PdDetector *detector, const float *samples, size_t len);
+
+/* The number of samples `detector` estimates the pitch of at a time. */
+size_t pd_chunk_size(const PdDetector *detector);
+
+/*
+ * Estimate the pitch of each whole chunk of the `len` samples at `samples`, as `analyze` does,
+ * apart from what `detector` has been fed. The first `max` estimates are written to `pitches`, and
+ * the number of chunks is returned.
+ */
+size_t pd_detect(const PdDetector *detector, const float *samples, size_t len, PdPitch *pitches,
+                 size_t max);
+
+/*
+ * Write the latest estimate of `detector` to `pitch`. Returns false, leaving `pitch` alone, if it
+ * hasn't been fed a whole chunk yet.
+ */
+bool pd_get_pitch(const PdDetector *detector, PdPitch *pitch);
+
+/* Free `detector`. NULL is ignored. */
+void pd_destroy(PdDetector *detector);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
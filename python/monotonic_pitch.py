@@ -0,0 +1,137 @@
+"""The pitch detector from Python, e.g. in a notebook.
+
+It loads the library built with the `python` feature:
+
+    cargo rustc --release --lib --features python --crate-type cdylib
+
+from target/release, or from the path in the MONOTONIC_PITCH_LIB environment variable. Samples
+can be a NumPy array or any sequence of numbers from -1 to 1.
+
+    >>> import monotonic_pitch
+    >>> monotonic_pitch.detect(samples, 44100)[0]
+    {'freq_hz': 440.0, 'midi': 69, 'note': 'A4', 'cents': 0.0, 'confidence': 0.99, 'rest': False}
+"""
+
+import array
+import ctypes
+import os
+import sys
+
+__all__ = ["Detector", "detect"]
+
+NOTES = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"]
+
+
+class _Pitch(ctypes.Structure):
+    _fields_ = [
+        ("freq", ctypes.c_double),
+        ("midi", ctypes.c_int32),
+        ("cents", ctypes.c_double),
+        ("confidence", ctypes.c_double),
+        ("rest", ctypes.c_bool),
+    ]
+
+    def to_dict(self):
+        """The estimate, with `None` for what it doesn't have, like the JSON `analyze` writes."""
+        has_pitch = self.midi >= 0
+        return {
+            "freq_hz": self.freq if self.freq > 0 else None,
+            "midi": self.midi if has_pitch else None,
+            "note": NOTES[self.midi % 12] + str(self.midi // 12 - 1) if has_pitch else None,
+            "cents": self.cents if has_pitch else None,
+            "confidence": self.confidence,
+            "rest": self.rest,
+        }
+
+
+def _library_path():
+    if "MONOTONIC_PITCH_LIB" in os.environ:
+        return os.environ["MONOTONIC_PITCH_LIB"]
+    name = {"darwin": "libmonophonic_detector.dylib", "win32": "monophonic_detector.dll"}.get(
+        sys.platform, "libmonophonic_detector.so"
+    )
+    root = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+    return os.path.join(root, "target", "release", name)
+
+
+_lib = ctypes.CDLL(_library_path())
+_Detector_p = ctypes.c_void_p
+_samples_p = ctypes.POINTER(ctypes.c_float)
+
+_lib.pd_create.argtypes = [ctypes.c_uint32]
+_lib.pd_create.restype = _Detector_p
+_lib.pd_feed_samples.argtypes = [_Detector_p, _samples_p, ctypes.c_size_t]
+_lib.pd_feed_samples.restype = ctypes.c_size_t
+_lib.pd_chunk_size.argtypes = [_Detector_p]
+_lib.pd_chunk_size.restype = ctypes.c_size_t
+_lib.pd_detect.argtypes = [
+    _Detector_p,
+    _samples_p,
+    ctypes.c_size_t,
+    ctypes.POINTER(_Pitch),
+    ctypes.c_size_t,
+]
+_lib.pd_detect.restype = ctypes.c_size_t
+_lib.pd_get_pitch.argtypes = [_Detector_p, ctypes.POINTER(_Pitch)]
+_lib.pd_get_pitch.restype = ctypes.c_bool
+_lib.pd_destroy.argtypes = [_Detector_p]
+_lib.pd_destroy.restype = None
+
+
+def _as_floats(samples):
+    """`samples` as contiguous 32-bit floats, with a pointer to them and their length. The first
+    element must be kept alive while the pointer is used."""
+    if type(samples).__module__ == "numpy":
+        import numpy
+
+        floats = numpy.ascontiguousarray(samples, dtype=numpy.float32)
+        return floats, floats.ctypes.data_as(_samples_p), len(floats)
+    floats = array.array("f", samples)
+    address, length = floats.buffer_info()
+    return floats, ctypes.cast(address, _samples_p), length
+
+
+class Detector:
+    """A detector for a mono signal sampled at `sample_rate` Hz, fed samples as they arrive. It
+    estimates the pitch of the latest chunk every quarter chunk."""
+
+    def __init__(self, sample_rate):
+        self._detector = _lib.pd_create(sample_rate)
+        if not self._detector:
+            raise ValueError("the sample rate must be positive")
+
+    def __del__(self):
+        if getattr(self, "_detector", None):
+            _lib.pd_destroy(self._detector)
+            self._detector = None
+
+    @property
+    def chunk_size(self):
+        """The number of samples the pitch is estimated from."""
+        return _lib.pd_chunk_size(self._detector)
+
+    def feed(self, samples):
+        """Add `samples`, returning the number of new estimates made from them."""
+        floats, pointer, length = _as_floats(samples)
+        return _lib.pd_feed_samples(self._detector, pointer, length)
+
+    def pitch(self):
+        """The latest estimate, or `None` if a whole chunk hasn't been fed yet."""
+        pitch = _Pitch()
+        if not _lib.pd_get_pitch(self._detector, ctypes.byref(pitch)):
+            return None
+        return pitch.to_dict()
+
+    def detect(self, samples):
+        """The raw estimates of each whole chunk of `samples`, unsmoothed, as `analyze --raw`
+        makes them. This doesn't feed them."""
+        floats, pointer, length = _as_floats(samples)
+        count = length // self.chunk_size
+        pitches = (_Pitch * count)()
+        _lib.pd_detect(self._detector, pointer, length, pitches, count)
+        return [pitch.to_dict() for pitch in pitches]
+
+
+def detect(samples, sample_rate):
+    """The estimates of each whole chunk of `samples`, sampled at `sample_rate` Hz."""
+    return Detector(sample_rate).detect(samples)
@@ -0,0 +1,137 @@
+//! ABC notation export, for rendering a transcription to sheet music with tools like abcm2ps.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::notes::NoteEvent;
+use crate::score::{self, Accidentals, Layout, Written};
+
+/// How many measures are written on each line.
+const MEASURES_PER_LINE: usize = 4;
+
+/// The default note length (the `L:` field), as a fraction of a whole note. Note lengths in the
+/// tune are written as multiples of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteLength {
+    /// The denominator: 8 for eighth notes. Always a power of two.
+    pub denominator: u32,
+}
+
+impl Default for NoteLength {
+    fn default() -> Self {
+        NoteLength { denominator: 8 }
+    }
+}
+
+impl FromStr for NoteLength {
+    type Err = String;
+
+    /// Parse a length such as `1/8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix("1/")
+            .and_then(|d| d.parse::<u32>().ok())
+            .filter(|d| d.is_power_of_two() && *d <= 64)
+            .map(|denominator| NoteLength { denominator })
+            .ok_or_else(|| format!("unknown note length: {}", s))
+    }
+}
+
+impl fmt::Display for NoteLength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "1/{}", self.denominator)
+    }
+}
+
+/// Write `notes`, which must be in order and not overlap, as an ABC tune called `title`, with
+/// note lengths in multiples of `unit`.
+pub fn write_abc<W: Write>(
+    mut out: W,
+    title: &str,
+    notes: &[NoteEvent],
+    layout: &Layout,
+    unit: NoteLength,
+) -> io::Result<()> {
+    writeln!(out, "X:1")?;
+    writeln!(out, "T:{}", title)?;
+    writeln!(out, "M:{}", layout.time)?;
+    writeln!(out, "L:{}", unit)?;
+    writeln!(out, "Q:1/4={}", layout.tempo.round())?;
+    writeln!(out, "K:{}", layout.key)?;
+
+    let measures = score::measures(&score::quantize(notes, layout.tempo), layout.time);
+    for (i, line) in measures.chunks(MEASURES_PER_LINE).enumerate() {
+        let last_line = (i + 1) * MEASURES_PER_LINE >= measures.len();
+        for measure in line {
+            let mut accidentals = Accidentals::default();
+            for note in measure {
+                write!(out, "{} ", abc_note(note, layout, unit, &mut accidentals))?;
+            }
+            write!(out, "|")?;
+        }
+        writeln!(out, "{}", if last_line { "]" } else { "" })?;
+    }
+
+    out.flush()
+}
+
+/// The ABC for one `note`, given the `accidentals` written earlier in the measure.
+fn abc_note(
+    note: &Written,
+    layout: &Layout,
+    unit: NoteLength,
+    accidentals: &mut Accidentals,
+) -> String {
+    let mut abc = String::new();
+
+    match note.pitch {
+        None => abc.push('z'),
+        Some(pitch) => {
+            let spelling = layout.key.spell(pitch);
+            if let Some(alter) = accidentals.next(layout.key, spelling) {
+                abc.push(match alter {
+                    1 => '^',
+                    -1 => '_',
+                    _ => '=',
+                });
+            }
+
+            // Uppercase letters are the octave from middle C, lowercase the one above, and each
+            // comma or apostrophe moves an octave further.
+            if spelling.octave >= 5 {
+                abc.push(spelling.letter.to_ascii_lowercase());
+                abc.extend((5..spelling.octave).map(|_| '\''));
+            } else {
+                abc.push(spelling.letter);
+                abc.extend((spelling.octave..4).map(|_| ','));
+            }
+        }
+    }
+
+    // The length as a fraction p/q of the unit.
+    let steps_per_whole = 4 * score::DIVISION;
+    let (mut p, mut q) = (note.length * unit.denominator, steps_per_whole);
+    let divisor = gcd(p, q);
+    p /= divisor;
+    q /= divisor;
+    match (p, q) {
+        (1, 1) => {}
+        (p, 1) => abc.push_str(&p.to_string()),
+        (1, 2) => abc.push('/'),
+        (1, q) => abc.push_str(&format!("/{}", q)),
+        (p, q) => abc.push_str(&format!("{}/{}", p, q)),
+    }
+
+    if note.tie {
+        abc.push('-');
+    }
+    abc
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
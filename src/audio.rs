@@ -0,0 +1,377 @@
+//! Preparing decoded audio for analysis.
+//!
+//! Samples are handled internally as `f32` in `-1.0..=1.0`, whatever their original format.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use hound::{SampleFormat, WavIntoSamples, WavReader};
+
+use crate::resample::Decimator;
+use crate::{Error, Result};
+
+/// Which channels of a multi-channel signal to analyze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channels {
+    /// Average all channels into one.
+    #[default]
+    Mix,
+    /// Only the channel with this (zero-based) index.
+    Only(usize),
+    /// Every channel separately.
+    Each,
+}
+
+impl FromStr for Channels {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mix" => Ok(Channels::Mix),
+            "each" => Ok(Channels::Each),
+            n => n
+                .parse()
+                .map(Channels::Only)
+                .map_err(|_| format!("invalid channel: {}", s)),
+        }
+    }
+}
+
+/// Split `interleaved` samples of a signal with `channel_count` channels into mono buffers as
+/// selected by `channels`: one buffer for `Mix` and `Only`, or one per channel for `Each`.
+pub fn select_channels(
+    interleaved: &[f32],
+    channel_count: usize,
+    channels: Channels,
+) -> Result<Vec<Vec<f32>>> {
+    let frames = interleaved.chunks_exact(channel_count);
+
+    match channels {
+        Channels::Mix => Ok(vec![frames
+            .map(|frame| frame.iter().sum::<f32>() / channel_count as f32)
+            .collect()]),
+
+        Channels::Only(n) => {
+            check_channels(channels, channel_count)?;
+            Ok(vec![frames.map(|frame| frame[n]).collect()])
+        }
+
+        Channels::Each => Ok((0..channel_count)
+            .map(|n| {
+                interleaved
+                    .chunks_exact(channel_count)
+                    .map(|frame| frame[n])
+                    .collect()
+            })
+            .collect()),
+    }
+}
+
+/// Check that `channels` can be selected from a signal with `channel_count` channels.
+pub fn check_channels(channels: Channels, channel_count: usize) -> Result<()> {
+    match channels {
+        Channels::Only(channel) if channel >= channel_count => Err(Error::NoSuchChannel {
+            channel,
+            channel_count,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The encoding of headerless PCM samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PcmFormat {
+    /// Signed 16-bit little-endian integers.
+    S16Le,
+    /// Signed 32-bit little-endian integers.
+    S32Le,
+    /// 32-bit little-endian floats.
+    F32Le,
+}
+
+impl PcmFormat {
+    /// The size of a sample, in bytes.
+    pub fn bytes(self) -> usize {
+        match self {
+            PcmFormat::S16Le => 2,
+            PcmFormat::S32Le | PcmFormat::F32Le => 4,
+        }
+    }
+
+    /// Decode the sample in `bytes`, which are `self.bytes()` long.
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            PcmFormat::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 * int_scale(16),
+            PcmFormat::S32Le => {
+                i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32 * int_scale(32)
+            }
+            PcmFormat::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+}
+
+impl FromStr for PcmFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "s16le" => Ok(PcmFormat::S16Le),
+            "s32le" => Ok(PcmFormat::S32Le),
+            "f32le" => Ok(PcmFormat::F32Le),
+            _ => Err(format!("unknown PCM format: {}", s)),
+        }
+    }
+}
+
+/// What headerless PCM input holds, since it can't say.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pcm {
+    pub format: PcmFormat,
+    pub sample_rate: usize,
+    pub channel_count: usize,
+}
+
+/// Where samples are read from.
+pub enum Source<R: Read> {
+    Wav(WavReader<R>),
+    /// Headerless, interleaved PCM samples.
+    Pcm(R, Pcm),
+}
+
+impl<R: Read> Source<R> {
+    pub fn sample_rate(&self) -> usize {
+        match self {
+            Source::Wav(reader) => reader.spec().sample_rate as usize,
+            Source::Pcm(_, pcm) => pcm.sample_rate,
+        }
+    }
+}
+
+impl<R: Read> From<WavReader<R>> for Source<R> {
+    fn from(reader: WavReader<R>) -> Self {
+        Source::Wav(reader)
+    }
+}
+
+/// The path that stands for stdin.
+pub const STDIN: &str = "-";
+
+/// Whether the file at `path` can be read, going by its extension: WAV files, and with the
+/// `decode` feature, compressed audio.
+pub fn is_supported(path: &Path) -> bool {
+    let wav = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+    #[cfg(feature = "decode")]
+    let wav = wav || crate::decode::is_decoded(path);
+    wav
+}
+
+/// Open the file at `path`, or stdin if it is `STDIN`, as a WAV file or, with `pcm`, headerless
+/// PCM. With the `decode` feature, compressed files are decoded.
+pub fn open_source(path: impl AsRef<Path>, pcm: Option<Pcm>) -> Result<Source<Box<dyn Read>>> {
+    let path = path.as_ref();
+    #[cfg(feature = "decode")]
+    if pcm.is_none() && crate::decode::is_decoded(path) {
+        return crate::decode::decode(path);
+    }
+    let reader: Box<dyn Read> = if path == Path::new(STDIN) {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(path).map_err(|e| {
+            io::Error::new(e.kind(), format!("can't read {}: {}", path.display(), e))
+        })?;
+        Box::new(BufReader::new(file))
+    };
+    Ok(match pcm {
+        Some(pcm) => Source::Pcm(reader, pcm),
+        None => Source::Wav(WavReader::new(reader)?),
+    })
+}
+
+/// Read all (interleaved) samples of a WAV file, converting them to `f32`.
+pub fn read_samples<R: Read>(reader: &mut WavReader<R>) -> Result<Vec<f32>> {
+    let spec = reader.spec();
+    let samples: hound::Result<_> = match spec.sample_format {
+        SampleFormat::Float => reader.samples::<f32>().collect(),
+        SampleFormat::Int => {
+            let scale = int_scale(spec.bits_per_sample);
+            reader
+                .samples::<i32>()
+                .map(|s| s.map(|s| s as f32 * scale))
+                .collect()
+        }
+    };
+    Ok(samples?)
+}
+
+/// The factor mapping `bits`-bit signed integer samples into `-1.0..=1.0`.
+pub fn int_scale(bits: u16) -> f32 {
+    1.0 / (1u64 << (bits - 1)) as f32
+}
+
+/// The samples of a WAV file of any format, or of headerless PCM, as `f32`.
+enum Samples<R: Read> {
+    Float(WavIntoSamples<R, f32>),
+    Int(WavIntoSamples<R, i32>, f32),
+    Pcm(R, PcmFormat),
+}
+
+impl<R: Read> Iterator for Samples<R> {
+    type Item = Result<f32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Samples::Float(samples) => samples.next().map(|s| Ok(s?)),
+            Samples::Int(samples, scale) => {
+                let scale = *scale;
+                samples.next().map(|s| Ok(s? as f32 * scale))
+            }
+            Samples::Pcm(reader, format) => {
+                let mut bytes = [0; 4];
+                let bytes = &mut bytes[..format.bytes()];
+                match read_full(reader, bytes) {
+                    // A partial sample at the end would only be in a partial chunk.
+                    Ok(n) if n < bytes.len() => None,
+                    Ok(_) => Some(Ok(format.decode(bytes))),
+                    Err(e) => Some(Err(e.into())),
+                }
+            }
+        }
+    }
+}
+
+/// Read into `buf` until it's full or the input ends, returning how much was read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Reads a WAV file or PCM stream `chunk_size` frames at a time, so memory use doesn't depend on
+/// the length of the input. Each item holds one buffer per selected channel (see
+/// `select_channels`). A trailing partial chunk is dropped, and a WAV file shorter than one chunk
+/// is an error.
+pub struct ChunkReader<R: Read> {
+    samples: Samples<R>,
+    channel_count: usize,
+    channels: Channels,
+    chunk_size: usize,
+    interleaved: Vec<f32>,
+    /// One per buffer, if the input is decimated.
+    decimators: Vec<Decimator>,
+    /// The number of frames of the input, if it says.
+    frames: Option<usize>,
+}
+
+impl<R: Read> ChunkReader<R> {
+    pub fn new(
+        source: impl Into<Source<R>>,
+        channels: Channels,
+        chunk_size: usize,
+    ) -> Result<Self> {
+        ChunkReader::decimated(source, channels, chunk_size, 1)
+    }
+
+    /// Like `new`, but decimating the input by `factor` (see `resample`), so each chunk covers
+    /// `chunk_size * factor` frames of it.
+    pub fn decimated(
+        source: impl Into<Source<R>>,
+        channels: Channels,
+        chunk_size: usize,
+        factor: usize,
+    ) -> Result<Self> {
+        let mut frames = None;
+        let (samples, channel_count) = match source.into() {
+            Source::Wav(reader) => {
+                let spec = reader.spec();
+                let duration = reader.duration() as usize;
+                if duration < chunk_size * factor {
+                    return Err(Error::TooShort {
+                        frames: duration,
+                        chunk_size: chunk_size * factor,
+                    });
+                }
+                frames = Some(duration);
+                let samples = match spec.sample_format {
+                    SampleFormat::Float => Samples::Float(reader.into_samples()),
+                    SampleFormat::Int => {
+                        Samples::Int(reader.into_samples(), int_scale(spec.bits_per_sample))
+                    }
+                };
+                (samples, spec.channels as usize)
+            }
+            Source::Pcm(reader, pcm) => (Samples::Pcm(reader, pcm.format), pcm.channel_count),
+        };
+        check_channels(channels, channel_count)?;
+
+        let mut reader = ChunkReader {
+            samples,
+            channel_count,
+            channels,
+            chunk_size: chunk_size * factor,
+            interleaved: Vec::with_capacity(chunk_size * factor * channel_count),
+            decimators: vec![],
+            frames,
+        };
+        if factor > 1 {
+            reader.decimators = vec![Decimator::new(factor); reader.buffer_count()];
+        }
+        Ok(reader)
+    }
+}
+
+impl<R: Read> ChunkReader<R> {
+    /// The number of buffers in each item.
+    pub fn buffer_count(&self) -> usize {
+        match self.channels {
+            Channels::Each => self.channel_count,
+            _ => 1,
+        }
+    }
+
+    /// The number of items the input holds, if it says how long it is: WAV files do, but PCM
+    /// streams don't.
+    pub fn chunk_count(&self) -> Option<usize> {
+        self.frames.map(|frames| frames / self.chunk_size)
+    }
+}
+
+impl<R: Read> Iterator for ChunkReader<R> {
+    type Item = Result<Vec<Vec<f32>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.interleaved.clear();
+        for _ in 0..(self.chunk_size * self.channel_count) {
+            match self.samples.next()? {
+                Ok(sample) => self.interleaved.push(sample),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+
+        let buffers = select_channels(&self.interleaved, self.channel_count, self.channels);
+        if self.decimators.is_empty() {
+            return Some(buffers);
+        }
+        Some(buffers.map(|buffers| {
+            buffers
+                .iter()
+                .zip(&mut self.decimators)
+                .map(|(buffer, decimator)| {
+                    let mut decimated = Vec::with_capacity(buffer.len() / decimator.factor());
+                    decimator.process(buffer, &mut decimated);
+                    decimated
+                })
+                .collect()
+        }))
+    }
+}
@@ -0,0 +1,61 @@
+//! Pitch detection from the real cepstrum.
+//!
+//! The harmonics of a periodic signal are evenly spaced in its spectrum, so the log-magnitude
+//! spectrum is itself periodic, and its inverse transform (the cepstrum) peaks at the quefrency of
+//! that period. Since the log separates the harmonics from the spectral envelope, this is less
+//! thrown off by formants than correlation in the time domain, which suits speech-like signals.
+
+use crate::spectrum;
+use crate::window::Window;
+
+/// The highest frequency looked for. Shorter quefrencies are dominated by the spectral envelope.
+pub const MAX_FREQ: f64 = 2000.0;
+
+/// Added to the magnitude spectrum to keep the log finite.
+const FLOOR: f64 = 1e-10;
+
+/// Estimate the fundamental frequency of `buffer`, sampled at `sample_rate` Hz, between `min_freq`
+/// and `MAX_FREQ`. Returns `None` if the buffer is too short for that range or the cepstrum has no
+/// peak in it.
+pub fn cepstrum_freq(buffer: &[f32], sample_rate: usize, min_freq: f64) -> Option<f64> {
+    let cepstrum = real_cepstrum(&Window::Hann.apply(buffer));
+
+    let shortest = ((sample_rate as f64 / MAX_FREQ) as usize).max(2);
+    let longest = ((sample_rate as f64 / min_freq) as usize).min(cepstrum.len() / 2);
+    if shortest + 1 >= longest {
+        return None;
+    }
+
+    let quefrency = (shortest..longest)
+        .max_by(|a, b| cepstrum[*a].partial_cmp(&cepstrum[*b]).unwrap())
+        .filter(|q| cepstrum[*q] > 0.0)?;
+
+    Some((sample_rate as f64) / parabolic_interpolation(&cepstrum, quefrency))
+}
+
+/// The real cepstrum of `buffer`: the inverse Fourier transform of its log-magnitude spectrum.
+pub fn real_cepstrum(buffer: &[f32]) -> Vec<f64> {
+    let len = buffer.len();
+    let log_spectrum = spectrum::magnitudes(buffer, len)
+        .into_iter()
+        .map(|x| (x + FLOOR).ln());
+
+    spectrum::fft(log_spectrum, len, true)
+        .iter()
+        .map(|x| x.re / len as f64)
+        .collect()
+}
+
+/// Refine `quefrency` to a fractional one using the parabola through it and its neighbours.
+fn parabolic_interpolation(cepstrum: &[f64], quefrency: usize) -> f64 {
+    if quefrency < 1 || quefrency + 1 >= cepstrum.len() {
+        return quefrency as f64;
+    }
+
+    quefrency as f64
+        + crate::parabolic_offset(
+            cepstrum[quefrency - 1],
+            cepstrum[quefrency],
+            cepstrum[quefrency + 1],
+        )
+}
@@ -0,0 +1,177 @@
+//! Recognizing chords, from the same spectrum the polyphonic detector uses. The peaks of each
+//! chunk's spectrum are folded into a chroma vector, the strength of each of the twelve pitch
+//! classes, and matched against templates of the notes of each chord: the best match, by cosine
+//! similarity, is the chord. Harmonics fold into the chroma too, but mostly onto the notes of the
+//! chord itself (the octave, and the fifth), so they don't often change which matches best.
+
+use std::fmt;
+
+use crate::spectrum;
+use crate::window::Window;
+use crate::Note;
+
+/// How many times longer than the chunk the FFT is, for finer frequency resolution.
+const ZERO_PADDING: usize = 4;
+
+/// Peaks below this fraction (-40 dB) of the highest aren't counted.
+const PEAK_FLOOR: f64 = 0.01;
+
+/// Peaks above this frequency, in Hz, are mostly high harmonics, and aren't counted.
+const MAX_CHROMA_FREQ: f64 = 4000.0;
+
+/// How strong the lowest peak's pitch class must be, as a fraction of the strongest, to count as
+/// the bass.
+const MIN_BASS: f64 = 0.25;
+
+/// The kind of a chord, whatever its root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    /// The dominant seventh: a major triad and a minor seventh.
+    Seventh,
+    Diminished,
+    Augmented,
+}
+
+impl ChordQuality {
+    /// All qualities. Where chords match equally well, those first here win.
+    pub const ALL: [ChordQuality; 5] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Seventh,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+    ];
+
+    /// The notes of the chord, in semitones above the root.
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Seventh => &[0, 4, 7, 10],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+        }
+    }
+
+    /// What follows the root in the chord's symbol, such as `m` for minor.
+    pub fn suffix(self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Seventh => "7",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+        }
+    }
+}
+
+impl fmt::Display for ChordQuality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ChordQuality::Major => "major",
+            ChordQuality::Minor => "minor",
+            ChordQuality::Seventh => "seventh",
+            ChordQuality::Diminished => "diminished",
+            ChordQuality::Augmented => "augmented",
+        })
+    }
+}
+
+/// A chord, such as A minor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    pub root: Note,
+    pub quality: ChordQuality,
+}
+
+/// The chord's symbol, such as `Am` or `G7`.
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.root, self.quality.suffix())
+    }
+}
+
+/// The chord best matching a chroma.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChordEstimate {
+    pub chord: Chord,
+    /// The cosine similarity of the chroma and the chord's template, from 0 to 1.
+    pub confidence: f64,
+}
+
+/// The strength of each pitch class in a chunk, from C.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chroma {
+    pub classes: [f64; 12],
+    /// The pitch class of the lowest strong peak, if any.
+    pub bass: Option<u8>,
+}
+
+/// The chroma of `buffer`, sampled at `sample_rate` Hz, from the peaks of its spectrum from
+/// `min_freq` up, with notes tuned to `a4`.
+pub fn chroma(buffer: &[f32], sample_rate: usize, min_freq: f64, a4: f64) -> Chroma {
+    let len = buffer.len() * ZERO_PADDING;
+    let mut magnitudes = spectrum::magnitudes(&Window::Hann.apply(buffer), len);
+    magnitudes.truncate(len / 2);
+    let bin_hz = sample_rate as f64 / len as f64;
+    let highest = magnitudes.iter().cloned().fold(0.0, f64::max);
+
+    let mut classes = [0.0; 12];
+    let mut lowest = None;
+    let from = ((min_freq / bin_hz).ceil() as usize).max(1);
+    let to = ((MAX_CHROMA_FREQ / bin_hz) as usize).min(magnitudes.len().saturating_sub(1));
+    for bin in from..to {
+        let magnitude = magnitudes[bin];
+        if highest <= 0.0
+            || magnitude < PEAK_FLOOR * highest
+            || magnitudes[bin - 1] >= magnitude
+            || magnitude < magnitudes[bin + 1]
+        {
+            continue;
+        }
+        // A is 9 semitones above C.
+        let semitones = 12.0 * (bin as f64 * bin_hz / a4).log2();
+        let class = (semitones.round() as i64 + 9).rem_euclid(12) as u8;
+        classes[class as usize] += magnitude;
+        lowest.get_or_insert(class);
+    }
+
+    let strongest = classes.iter().cloned().fold(0.0, f64::max);
+    Chroma {
+        classes,
+        bass: lowest.filter(|class| classes[*class as usize] >= MIN_BASS * strongest),
+    }
+}
+
+/// The chord whose template best matches `chroma`, or none if it's empty. Of chords that match
+/// equally well, one on the bass wins, such as C augmented over E or G# augmented when C is lowest.
+pub fn match_chord(chroma: &Chroma) -> Option<ChordEstimate> {
+    let norm = chroma.classes.iter().map(|c| c * c).sum::<f64>().sqrt();
+    if norm <= 0.0 {
+        return None;
+    }
+
+    let mut best: Option<(ChordEstimate, bool)> = None;
+    for quality in ChordQuality::ALL {
+        for root in Note::ALL {
+            let intervals = quality.intervals();
+            let sum: f64 = intervals
+                .iter()
+                .map(|interval| chroma.classes[(root.semitone() + interval) as usize % 12])
+                .sum();
+            let confidence = sum / (norm * (intervals.len() as f64).sqrt());
+            let on_bass = chroma.bass == Some(root.semitone());
+            let better = best.is_none_or(|(best, best_on_bass)| {
+                let difference = confidence - best.confidence;
+                difference > 1e-9 || (difference > -1e-9 && on_bass && !best_on_bass)
+            });
+            if better {
+                let chord = Chord { root, quality };
+                best = Some((ChordEstimate { chord, confidence }, on_bass));
+            }
+        }
+    }
+    best.map(|(estimate, _)| estimate)
+}
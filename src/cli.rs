@@ -0,0 +1,1565 @@
+//! Command-line argument parsing.
+
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use monophonic_detector::{
+    abc::NoteLength,
+    audio::{Channels, Pcm, PcmFormat},
+    config::{self, Config},
+    filters::{Design, Filter},
+    generate::{Generator, Noise, Sweep, Tones, Waveform},
+    instrument::Instrument,
+    latency,
+    level::Meter,
+    live::LiveInput,
+    names::NoteNames,
+    output::Format,
+    plot::ImageFormat,
+    resample,
+    score::{Key, Layout, TimeSignature, DEFAULT_TEMPO},
+    scoring,
+    smooth::{self, Smoother},
+    temperament::Temperament,
+    training,
+    tuning::Tuning,
+    window::Window,
+    Acf, Algorithm, Note, Pitch, PitchDetector, DEFAULT_A4, DEFAULT_SAMPLE_RATE,
+    MIN_DETECTABLE_FREQ,
+};
+
+pub const USAGE: &str = "\
+usage: pitch-detector [COMMAND]
+       pitch-detector --help
+
+commands:
+    analyze <FILE> [OPTIONS]    detect the pitch of each chunk of a WAV file
+    analyze <DIR> [OPTIONS]     analyze every WAV file in a directory, several at once, writing
+                                the results for each file to one beside it (e.g. take.wav to
+                                take.json, or take.notes.json with --notes)
+        --recursive             also analyze the files in its subdirectories
+        --watch                 keep analyzing new WAV files as they appear in the directory
+        --channel <N|mix|each>  channel to analyze, or mix them down (default: mix)
+        --midi <FILE>           also write the detected notes to a MIDI file
+        --pitch-bend            bend the notes in the MIDI file to follow slides and vibrato
+        --spectrogram <FILE>    also draw a spectrogram with the detected pitch over it, as PNG
+        --plot <FILE>           also plot the pitch over time, coloured by how in tune it is,
+                                as PNG or SVG (by the file's extension)
+        --notes                 report note events (start, duration, pitch and velocity)
+                                instead of each chunk
+        --summary               report how long was spent on each note and how far from it on
+                                average, and how in tune the whole was, instead of each chunk
+        --vibrato               also report the rate and depth of the vibrato of each note
+                                (implies --notes)
+        --dual                  report up to two simultaneous pitches in each chunk, as voices
+                                0 and 1 from the lower up (e.g. for power chords)
+        --chords                report the chord in each chunk (e.g. Am or G7) instead of its
+                                pitch
+        --tuning <N-edo|FILE>   name each pitch after the nearest degree of a microtonal tuning,
+                                an EDO (e.g. 19-edo, 24-edo or 31-edo) or a Scala .scl file
+                                (whose first degree is on --tonic)
+        --threads <N>           analyze chunks, or files, on N threads (default: one per CPU)
+        --levels                also report the RMS and peak level of each chunk, in dBFS
+        --a-weighted            also report the A-weighted level of each chunk (implies --levels)
+    compare <FILE> [OPTIONS]    run several algorithms over a WAV file side by side
+        --algorithms <A,A,...>  the algorithms to compare (default: monotonic,yin,mpm)
+        --channel <N|mix>       channel to analyze, or mix them down (default: mix)
+    transcribe <FILE> [OPTIONS] write the notes of a WAV file, dropping short blips
+        --min-duration <S>      shortest note to keep, in seconds (default: 0.1)
+        --channel <N|mix>       channel to analyze, or mix them down (default: mix)
+        --midi <FILE>           also write the notes to a MIDI file
+        --pitch-bend            bend the notes in the MIDI file to follow slides and vibrato
+        --threads <N>           analyze chunks on N threads (default: one per CPU)
+        --format <FORMAT>       text, json, csv, abc, lilypond or musicxml, or scales for the
+                                scales and modes the notes fit, best first
+                                (default: text)
+        --intervals             write the interval from each note to the next (e.g. minor third
+                                up) instead of the notes
+        --tempo <BPM|auto>      quarter notes per minute to quantize scores to, or auto to
+                                estimate it from the notes' onsets (default: 120); if given,
+                                the MIDI file is quantized to it too, and set in it
+        --key <KEY>             key signature of scores, e.g. G, Bb or F#m (default: the key
+                                estimated from the notes)
+        --time-signature <N/D>  time signature of scores, and of the MIDI file with --tempo
+                                (default: 4/4); or --time
+        --note-length <1/N>     default note length of ABC tunes (default: 1/8)
+    live [OPTIONS]              detect pitches from raw s16le mono PCM on stdin
+        --sample-rate <HZ>      sample rate of the input (default: 44100, or the JACK server's)
+        --backend <BACKEND>     where to read the input: stdin; alsa for a capture device, with
+                                arecord (on Linux); or jack for an input port of the running
+                                JACK server (with the jack feature) (default: stdin)
+        --device <NAME>         the ALSA device to record from, as listed by devices
+                                (default: default)
+        --buffer-size <FRAMES>  the ALSA device's buffer, or the JACK server's, in frames: a
+                                smaller one has less latency, but drops out more easily
+        --connect <PORT>        connect a JACK output port, such as system:capture_1, to the
+                                input port; may be given more than once
+        --hop <N>               estimate the pitch every N samples (default: a quarter chunk)
+        --adaptive <PERIODS>    analyze just enough of the latest input to hold this many periods
+                                of the pitch, for less latency on high notes
+        --target-latency-ms <MS>
+                                size the chunk and hop so that a note is reported within MS
+                                milliseconds, leaving out notes too low to fit in the chunk
+        --loopback              analyze what the computer is playing instead of stdin, from the
+                                PulseAudio (or PipeWire) monitor of the default output, with parec
+        --capture <COMMAND>     read the input from the output of COMMAND instead of stdin, which
+                                is given the sample rate as $RATE and writes s16le mono PCM
+        --midi-out <DEVICE>     play the detected notes on a raw MIDI device
+        --record <FILE>         also record the input to a WAV file, to analyze again later
+        --latency               report how long each stage of each estimate took, on stderr
+                                once the input ends (and per estimate with -vv)
+        --summary               also sum up the notes played, on stderr once the input ends
+        --osc <HOST:PORT>       send a /pitch OSC message per estimate over UDP
+        --levels, --a-weighted  also report the levels of each window, as analyze does
+    tune [OPTIONS]              a tuner: show the nearest note and how far off it the live input
+                                on stdin is; takes the live input options
+        --strobe                show a strobe instead of a needle, for fine tuning
+    train [OPTIONS]             an ear-training game: play a random note, and listen on stdin, as
+                                live does, until it's sung or played back in tune (in any
+                                octave); takes the live input options
+        --range <LOW:HIGH>      the notes to play (default: C4:C5)
+        --tolerance <CENTS>     how far off an answer may be and count (default: 50)
+        --timeout <SECONDS>     how long to answer each note in (default: 10)
+        --exact-octave          only count answers in the note's own octave
+        --rounds <N>            stop after N notes (default: when the input ends)
+        --player <COMMAND>      the command to play notes with, which is given s16le mono PCM
+                                at the input's sample rate on stdin
+                                (default: aplay -q -f S16_LE -c 1 -r <HZ>)
+        --seed <N>              seed for the notes picked (default: the time)
+    serve [OPTIONS]             like live, but push each estimate as JSON to WebSocket clients
+        --listen <ADDR>         address to listen on (default: 127.0.0.1:8080)
+    api [OPTIONS]               serve POST /analyze, which takes a WAV file and returns the
+                                analysis as JSON (only with the `server` feature)
+        --listen <ADDR>         address to listen on (default: 127.0.0.1:8080)
+        --channel <N|mix|each>  channel to analyze, or mix them down (default: mix)
+    devices                     list the ALSA devices and JACK ports live input can be recorded
+                                from
+    generate [FILE] [OPTIONS]   write a test signal to FILE (default: sine.wav)
+        --waveform <W>          sine, square, saw or triangle (default: sine)
+        --freqs <HZ,HZ,...>     play these frequencies in turn (default: the test tones)
+        --sweep <FROM:TO>       glide from one frequency to another instead
+        --sweep-shape <SHAPE>   linear or exponential (default: linear)
+        --duration <SECONDS>    length of the signal (default: 20)
+        --amplitude <X>         peak amplitude from 0 to 1 (default: 1)
+        --harmonics <A,A,...>   relative amplitude of each partial (default: 1)
+        --inharmonicity <B>     stretch the partials as a piano string's, partial k at
+                                k * sqrt(1 + B k^2) times the frequency (default: 0)
+        --sample-rate <HZ>      sample rate of the signal (default: 44100)
+        --snr-db <DB>           mix in noise this many dB quieter than the tone
+        --noise <COLOUR>        white or pink (default: white)
+        --seed <N>              seed for the noise (default: 1)
+    evaluate [OPTIONS]          report how closely a generated signal is tracked; takes the
+                                generate options (default: --sweep 110:1760
+                                --sweep-shape exponential)
+    score <FILE> [OPTIONS]      score a sung or played performance against a reference melody:
+                                align them in time and report how much of each note, and of
+                                the whole melody, was in tune (in the nearest octave); <FILE>
+                                may be - with --pcm to score live input as it's recorded
+        --melody <FILE>         the melody: a MIDI file (.mid), or a note list with a note
+                                (e.g. A4, or rest) and a duration in seconds on each line
+        --tolerance <CENTS>     how far off a note may be and count as in tune (default: 50)
+        --hop <N>               estimate the pitch every N samples (default: a quarter chunk)
+        --channel <N|mix>       channel to analyze, or mix them down (default: mix)
+    eval <FILE> [OPTIONS]       score the pitch detected in a WAV file against a reference
+                                annotation: raw pitch and chroma accuracy, and voicing
+                                precision and recall
+        --reference <FILE>      the annotation: a time in seconds and a frequency in Hz (0 if
+                                unvoiced) on each line
+        --channel <N|mix>       channel to analyze, or mix them down (default: mix)
+
+With no command, the test tones are generated and then analyzed.
+
+input options (analyze, compare, transcribe, score and eval, whose <FILE> may be - to read stdin):
+    --pcm <FORMAT>         read headerless s16le, s32le or f32le PCM instead of a WAV file
+    --sample-rate <HZ>     sample rate of PCM input (default: 44100)
+    --pcm-channels <N>     number of interleaved channels of PCM input (default: 1)
+
+options:
+    --min-freq <HZ>        lowest detectable frequency (default: 40)
+    --max-freq <HZ>        highest detectable frequency (default: no limit)
+    --instrument <NAME>    detect the range of voice, guitar, bass, violin, cello, flute, piano
+                           or whistle; --min-freq and --max-freq override either end. Piano
+                           also fits the inharmonicity of the strings, estimating the fundamental
+                           from the partials, and analyze reports the idealized pitch as well
+    --fudge <N>            minimum number of cycles per chunk (default: 10)
+    --chunk-size <N>       samples per chunk; overrides --min-freq and --fudge
+    --format <FORMAT>      text, json or csv (default: text)
+    --note-names <NAMES>   english, german (H for B) or solfege (Do, Re, Mi) in analyze,
+                           transcribe and live output (default: english)
+    --a4 <HZ>              reference pitch of A4 (default: 440)
+    --strict               only name frequencies after the nearest note within
+                           --note-tolerance of it, and report the rest as Unknown
+    --note-tolerance <CENTS>
+                           how near the nearest note --strict needs frequencies to be, in cents
+                           (default: 25; implies --strict)
+    --temperament <T>      equal, just, pythagorean, meantone, or 12 comma-separated cents
+                           above the tonic (default: equal)
+    --tonic <NOTE>         the note the temperament is built on (default: C)
+    --transpose <N>        name notes N semitones above the pitch that sounds, as written for a
+                           transposing instrument
+    --concert-to <KEY>     name notes as written for an instrument in KEY: Bb (clarinet or
+                           trumpet, a tone up), A (a minor third), Eb (alto sax, a major sixth)
+                           or F (horn, a fifth)
+    --algorithm <NAME>     monotonic, yin, mpm, cepstrum, hps or zcr (default: monotonic)
+    --yin-threshold <X>    absolute threshold for yin (default: 0.1)
+    --mpm-cutoff <X>       key maximum cutoff for mpm (default: 0.9)
+    --min-confidence <X>   report estimates less confident than this (0 to 1) as Unknown
+    --silence-threshold <DBFS>
+                           report chunks quieter than this as rests (default: -60)
+    --window <WINDOW>      rectangular, hann, hamming or blackman (default: rectangular)
+    --acf <ACF>            for monotonic: biased, or unbiased to divide the autocorrelation at
+                           each lag by the overlap (default: biased)
+    --highpass <HZ>        filter out frequencies below this first (e.g. 60 for hum)
+    --lowpass <HZ>         filter out frequencies above this first (e.g. 5000 for hiss)
+    --filter-design <D>    one-pole or biquad (default: biquad)
+    --no-octave-correction don't check estimates for being an octave off, or a harmonic of a
+                           lower note
+    --formant-robust       for vocals: low-pass and center-clip chunks first, so that strong
+                           formants don't make the monotonic detector report a harmonic
+    --voicing              for speech: report noisy chunks (e.g. the s of speech) as Unvoiced
+                           rather than a random pitch, by how clearly they repeat and how often
+                           they cross zero
+    --median <N>           smooth over the median of the last N estimates (default: 3)
+    --hold <M>             only switch notes after M consistent estimates (default: 2)
+    --raw                  report each estimate as is, without smoothing
+    --max-rate <HZ>        decimate files sampled faster than this by a whole factor first,
+                           which is much faster; --chunk-size is then at the lower rate
+                           (default: 48000)
+    --quiet, -q            don't show a progress bar while analyzing a file in a terminal, or
+                           print the estimated key and tempo, on stderr
+    --verbose, -v          log why each chunk came out as it did on stderr: rests, unvoiced
+                           chunks and discarded estimates; -vv also logs every chunk's
+                           correlation stats and the smoothing
+    --config <FILE>        read default options from FILE instead of pitch-detector.toml in the
+                           working directory, or else in ~/.config/pitch-detector; options
+                           before any [section] apply to every command, and those in a
+                           [command] section to that command
+
+exit status:
+    0    success
+    1    reading or writing failed
+    2    invalid arguments
+    3    the input can't be analyzed: not a supported WAV file, no such channel, or too short
+    4    analyze or transcribe found no pitch in the input
+    130  interrupted by Ctrl-C, after writing out what was analyzed so far
+";
+
+#[derive(Debug)]
+pub enum Command {
+    Demo,
+    Help,
+    Analyze(AnalyzeArgs),
+    Compare(CompareArgs),
+    Transcribe(TranscribeArgs),
+    Live(LiveArgs),
+    Tune(TuneArgs),
+    Train(TrainArgs),
+    Serve(ServeArgs),
+    #[cfg(feature = "server")]
+    Api(ApiArgs),
+    Devices,
+    Generate(GenerateArgs),
+    Evaluate(EvaluateArgs),
+    Score(ScoreArgs),
+    Eval(EvalArgs),
+}
+
+impl Command {
+    /// The live input options of the command, if it takes them.
+    pub fn input(&self) -> Option<&InputOpts> {
+        match self {
+            Command::Live(args) => Some(&args.input),
+            Command::Tune(args) => Some(&args.input),
+            Command::Train(args) => Some(&args.input),
+            Command::Serve(args) => Some(&args.input),
+            _ => None,
+        }
+    }
+
+    /// The detector options of the command, if it takes them.
+    pub fn opts(&self) -> Option<&DetectorOpts> {
+        match self {
+            Command::Analyze(args) => Some(&args.opts),
+            Command::Compare(args) => Some(&args.opts),
+            Command::Transcribe(args) => Some(&args.opts),
+            Command::Live(args) => Some(&args.opts),
+            Command::Tune(args) => Some(&args.opts),
+            Command::Train(args) => Some(&args.opts),
+            Command::Serve(args) => Some(&args.opts),
+            #[cfg(feature = "server")]
+            Command::Api(args) => Some(&args.opts),
+            Command::Evaluate(args) => Some(&args.opts),
+            Command::Score(args) => Some(&args.opts),
+            Command::Eval(args) => Some(&args.opts),
+            Command::Demo | Command::Help | Command::Devices | Command::Generate(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct AnalyzeArgs {
+    pub input: PathBuf,
+    pub pcm: PcmOpts,
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    pub format: Format,
+    pub note_names: NoteNames,
+    /// Where to write the detected notes as a MIDI file.
+    pub midi: Option<PathBuf>,
+    /// Whether the MIDI file bends the notes to follow the pitch within them.
+    pub pitch_bend: bool,
+    /// Where to draw a spectrogram, as a PNG image.
+    pub spectrogram: Option<PathBuf>,
+    /// Where to plot the pitch over time, and in which format.
+    pub plot: Option<(PathBuf, ImageFormat)>,
+    /// Report note events instead of chunks.
+    pub notes: bool,
+    /// Report the time on each note and the intonation instead of chunks.
+    pub summary: bool,
+    /// Report the vibrato of each note event.
+    pub vibrato: bool,
+    /// Report up to two simultaneous pitches per chunk.
+    pub dual: bool,
+    /// Report the chord in each chunk instead of its pitch.
+    pub chords: bool,
+    /// The microtonal tuning to name pitches in, if any.
+    pub tuning: Option<TuningArg>,
+    /// How many threads to analyze chunks on, or `None` for one per CPU.
+    pub threads: Option<usize>,
+    /// Whether to analyze every file under the input, if it's a directory.
+    pub recursive: bool,
+    /// Whether to keep analyzing new files in the input directory.
+    pub watch: bool,
+    /// The levels to report of each chunk, if any.
+    pub levels: Option<Meter>,
+}
+
+#[derive(Debug, Default)]
+pub struct TranscribeArgs {
+    pub input: PathBuf,
+    pub pcm: PcmOpts,
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    pub format: TranscribeFormat,
+    pub note_names: NoteNames,
+    pub min_duration: Option<f64>,
+    pub midi: Option<PathBuf>,
+    pub pitch_bend: bool,
+    /// Write the intervals between consecutive notes instead of the notes.
+    pub intervals: bool,
+    pub threads: Option<usize>,
+    /// The tempo of scores, and to quantize the MIDI file to, if any.
+    pub tempo: Option<TempoArg>,
+    /// The key of scores, or `None` to estimate it from the notes.
+    pub key: Option<Key>,
+    pub time: TimeSignature,
+    /// The default note length of ABC tunes.
+    pub note_length: NoteLength,
+}
+
+impl TranscribeArgs {
+    /// The layout of scores, in `key` unless the key was given, at `tempo` if there is one.
+    pub fn layout(&self, key: Key, tempo: Option<f64>) -> Layout {
+        Layout {
+            tempo: tempo.unwrap_or(DEFAULT_TEMPO),
+            key: self.key.unwrap_or(key),
+            time: self.time,
+        }
+    }
+}
+
+/// The tempo given to `--tempo`: in beats per minute, or to be estimated from the notes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TempoArg {
+    Bpm(f64),
+    Auto,
+}
+
+impl FromStr for TempoArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(TempoArg::Auto),
+            _ => match s.parse() {
+                Ok(bpm) if bpm > 0.0 => Ok(TempoArg::Bpm(bpm)),
+                _ => Err(format!("invalid tempo: {}", s)),
+            },
+        }
+    }
+}
+
+/// Where live input is read from instead of stdin.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Capture {
+    /// The monitor of the default output: what the computer is playing.
+    Loopback,
+    /// The output of a command line, with `$RATE` for the sample rate.
+    Command(String),
+}
+
+impl Capture {
+    /// The command to run for input sampled at `sample_rate` Hz, split into its words.
+    pub fn command(&self, sample_rate: usize) -> Vec<String> {
+        let command = match self {
+            Capture::Loopback => LOOPBACK_COMMAND,
+            Capture::Command(command) => command,
+        };
+        let rate = sample_rate.to_string();
+        command
+            .split_whitespace()
+            .map(|word| word.replace("$RATE", &rate))
+            .collect()
+    }
+}
+
+/// What live input is read through.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Backend {
+    /// Raw PCM on stdin, or from a capture command.
+    #[default]
+    Stdin,
+    /// An ALSA capture device, recorded with `arecord`.
+    Alsa,
+    /// An input port of a JACK server.
+    Jack,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdin" => Ok(Backend::Stdin),
+            "alsa" if cfg!(target_os = "linux") => Ok(Backend::Alsa),
+            "alsa" => Err(
+                "ALSA is only on Linux; elsewhere, pass --capture a command that records the \
+                 device"
+                    .to_string(),
+            ),
+            "jack" if cfg!(feature = "jack") => Ok(Backend::Jack),
+            "jack" => {
+                Err("JACK input needs the jack feature: build with --features jack".to_string())
+            }
+            _ => Err(format!("unknown backend: {}", s)),
+        }
+    }
+}
+
+/// The ALSA device recorded from by default.
+const DEFAULT_DEVICE: &str = "default";
+
+/// Records what PulseAudio, or PipeWire's stand-in for it, is playing on the default output.
+const LOOPBACK_COMMAND: &str =
+    "parec --device=@DEFAULT_MONITOR@ --format=s16le --channels=1 --rate=$RATE";
+
+/// The tuning given to `--tuning`: an EDO, or a Scala file to read it from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TuningArg {
+    Edo(Tuning),
+    Scala(PathBuf),
+}
+
+impl FromStr for TuningArg {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path = PathBuf::from(s);
+        let scala = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("scl"));
+        if scala {
+            Ok(TuningArg::Scala(path))
+        } else {
+            s.parse().map(TuningArg::Edo)
+        }
+    }
+}
+
+/// How `transcribe` writes the notes: as a list, or as a score, or the scales they fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscribeFormat {
+    List(Format),
+    Abc,
+    LilyPond,
+    MusicXml,
+    Scales,
+}
+
+impl Default for TranscribeFormat {
+    fn default() -> Self {
+        TranscribeFormat::List(Format::default())
+    }
+}
+
+impl FromStr for TranscribeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "abc" => Ok(TranscribeFormat::Abc),
+            "lilypond" => Ok(TranscribeFormat::LilyPond),
+            "musicxml" => Ok(TranscribeFormat::MusicXml),
+            "scales" => Ok(TranscribeFormat::Scales),
+            _ => s.parse().map(TranscribeFormat::List),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CompareArgs {
+    pub input: PathBuf,
+    pub pcm: PcmOpts,
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    pub algorithms: Vec<Algorithm>,
+}
+
+/// Options for reading headerless PCM rather than a WAV file.
+#[derive(Debug)]
+pub struct PcmOpts {
+    /// The encoding of the samples, if the input is PCM.
+    pub format: Option<PcmFormat>,
+    pub sample_rate: usize,
+    pub channel_count: usize,
+}
+
+impl Default for PcmOpts {
+    fn default() -> Self {
+        PcmOpts {
+            format: None,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            channel_count: 1,
+        }
+    }
+}
+
+impl PcmOpts {
+    /// What the input holds, if it's PCM.
+    pub fn pcm(&self) -> Option<Pcm> {
+        self.format.map(|format| Pcm {
+            format,
+            sample_rate: self.sample_rate,
+            channel_count: self.channel_count,
+        })
+    }
+}
+
+/// Options for reading a live stream of samples.
+#[derive(Debug)]
+pub struct InputOpts {
+    pub sample_rate: usize,
+    pub backend: Backend,
+    /// What to read the input from, if not stdin.
+    pub capture: Option<Capture>,
+    /// The ALSA device to record from, if not the default.
+    pub device: Option<String>,
+    /// The size of the device's or server's buffer, in frames.
+    pub buffer_size: Option<usize>,
+    /// The JACK ports to connect to the input port.
+    pub connect: Vec<String>,
+    /// The number of samples between estimates, if not a quarter chunk.
+    pub hop: Option<usize>,
+    /// The number of periods an adaptive window holds.
+    pub adaptive: Option<usize>,
+    /// The latency to size the chunk and hop for, in milliseconds.
+    pub target_latency: Option<f64>,
+}
+
+impl Default for InputOpts {
+    fn default() -> Self {
+        InputOpts {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            backend: Backend::default(),
+            capture: None,
+            device: None,
+            buffer_size: None,
+            connect: vec![],
+            hop: None,
+            adaptive: None,
+            target_latency: None,
+        }
+    }
+}
+
+impl InputOpts {
+    /// The command recording from the ALSA device, split into its words.
+    pub fn alsa_command(&self) -> Vec<String> {
+        let device = self.device.as_deref().unwrap_or(DEFAULT_DEVICE);
+        let mut command: Vec<String> = vec![
+            "arecord".into(),
+            "-q".into(),
+            "-t".into(),
+            "raw".into(),
+            "-f".into(),
+            "S16_LE".into(),
+            "-c".into(),
+            "1".into(),
+            "-r".into(),
+            self.sample_rate.to_string(),
+            "-D".into(),
+            device.into(),
+        ];
+        if let Some(frames) = self.buffer_size {
+            command.push(format!("--buffer-size={}", frames));
+        }
+        command
+    }
+
+    /// Read samples from `reader`, sampled at `sample_rate` Hz, and analyze them with a detector
+    /// configured by `opts`.
+    pub fn input<R: Read>(
+        &self,
+        reader: R,
+        sample_rate: usize,
+        opts: &DetectorOpts,
+    ) -> LiveInput<R> {
+        let mut detector = opts.detector(sample_rate);
+        let mut hop = self.hop;
+        if let Some(target) = self.target_latency {
+            let (fitted, fitted_hop) = latency::fit(&detector, target);
+            detector = fitted;
+            hop = Some(fitted_hop);
+        }
+        let mut input = LiveInput::new(reader, detector);
+        if let Some(hop) = hop {
+            input = input.with_hop(hop);
+        }
+        match self.adaptive {
+            Some(periods) => input.with_adaptive(periods),
+            None => input,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LiveArgs {
+    pub opts: DetectorOpts,
+    pub input: InputOpts,
+    pub format: Format,
+    pub note_names: NoteNames,
+    /// The raw MIDI device to play the detected notes on.
+    pub midi_out: Option<PathBuf>,
+    /// Where to send OSC messages.
+    pub osc: Option<String>,
+    /// The levels to report of each window, if any.
+    pub levels: Option<Meter>,
+    /// Where to record the input, as a WAV file.
+    pub record: Option<PathBuf>,
+    /// Whether to report the latency of each stage.
+    pub latency: bool,
+    /// Whether to sum up the notes played once the input ends.
+    pub summary: bool,
+}
+
+#[derive(Debug)]
+pub struct TuneArgs {
+    pub opts: DetectorOpts,
+    pub input: InputOpts,
+    pub strobe: bool,
+}
+
+/// The notes `train` plays when no range is given.
+pub const DEFAULT_TRAIN_RANGE: &str = "C4:C5";
+
+#[derive(Debug)]
+pub struct TrainArgs {
+    pub opts: DetectorOpts,
+    pub input: InputOpts,
+    /// The lowest and highest notes to play.
+    pub range: (Pitch, Pitch),
+    pub tolerance: f64,
+    pub timeout: f64,
+    pub exact_octave: bool,
+    /// How many notes to play, or `None` to play until the input ends.
+    pub rounds: Option<usize>,
+    /// The command to play notes with, if not `aplay`.
+    pub player: Option<String>,
+    pub seed: Option<u64>,
+}
+
+/// The address `serve` listens on when none is given.
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+#[derive(Debug)]
+pub struct ServeArgs {
+    pub opts: DetectorOpts,
+    pub input: InputOpts,
+    pub listen: String,
+}
+
+#[cfg(feature = "server")]
+#[derive(Debug)]
+pub struct ApiArgs {
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    pub listen: String,
+}
+
+#[derive(Debug, Default)]
+pub struct GenerateArgs {
+    pub output: PathBuf,
+    pub waveform: Waveform,
+    pub freqs: Option<Vec<f64>>,
+    pub sweep: Option<(f64, f64)>,
+    pub sweep_shape: Sweep,
+    pub duration: Option<f64>,
+    pub amplitude: Option<f64>,
+    pub harmonics: Option<Vec<f64>>,
+    pub inharmonicity: Option<f64>,
+    pub sample_rate: Option<usize>,
+    pub snr_db: Option<f64>,
+    pub noise: Noise,
+    pub seed: Option<u64>,
+}
+
+impl GenerateArgs {
+    /// Build the generator, playing `default_tones` unless told otherwise.
+    pub fn generator(&self, default_tones: Tones) -> Generator {
+        let tones = match (self.sweep, &self.freqs) {
+            (Some((from, to)), _) => Tones::Sweep {
+                from,
+                to,
+                sweep: self.sweep_shape,
+            },
+            (None, Some(freqs)) => Tones::Steps(freqs.clone()),
+            (None, None) => default_tones,
+        };
+        let mut generator = Generator::new(tones).with_waveform(self.waveform);
+        if let Some(duration) = self.duration {
+            generator = generator.with_duration(duration);
+        }
+        if let Some(amplitude) = self.amplitude {
+            generator = generator.with_amplitude(amplitude);
+        }
+        if let Some(harmonics) = &self.harmonics {
+            generator = generator.with_harmonics(harmonics.clone());
+        }
+        if let Some(b) = self.inharmonicity {
+            generator = generator.with_inharmonicity(b);
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            generator = generator.with_sample_rate(sample_rate);
+        }
+        if let Some(snr_db) = self.snr_db {
+            generator = generator.with_noise(self.noise, snr_db);
+        }
+        if let Some(seed) = self.seed {
+            generator = generator.with_seed(seed);
+        }
+        generator
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct EvaluateArgs {
+    pub signal: GenerateArgs,
+    pub opts: DetectorOpts,
+}
+
+#[derive(Debug)]
+pub struct EvalArgs {
+    pub input: PathBuf,
+    pub pcm: PcmOpts,
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    /// The annotation of the input's pitch to score against.
+    pub reference: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct ScoreArgs {
+    pub input: PathBuf,
+    pub pcm: PcmOpts,
+    pub opts: DetectorOpts,
+    pub channels: Channels,
+    /// The melody to score the input against.
+    pub melody: PathBuf,
+    pub tolerance: f64,
+    /// The number of samples between estimates, if not a quarter chunk.
+    pub hop: Option<usize>,
+}
+
+/// Options that configure the `PitchDetector`.
+#[derive(Debug, Default)]
+pub struct DetectorOpts {
+    pub min_freq: Option<usize>,
+    pub max_freq: Option<usize>,
+    /// The instrument whose range to detect, where `min_freq` or `max_freq` aren't given.
+    pub instrument: Option<Instrument>,
+    pub fudge: Option<usize>,
+    pub chunk_size: Option<usize>,
+    pub a4: Option<f64>,
+    /// How far from the nearest note frequencies may be to be named after it, in cents.
+    pub note_tolerance: Option<f64>,
+    pub strict: bool,
+    pub temperament: Temperament,
+    pub tonic: Option<Note>,
+    /// How many semitones above the sounding pitch notes are written.
+    pub transpose: Option<i32>,
+    pub algorithm: Algorithm,
+    pub yin_threshold: Option<f64>,
+    pub mpm_cutoff: Option<f64>,
+    pub min_confidence: Option<f64>,
+    pub silence_threshold: Option<f64>,
+    pub window: Window,
+    pub acf: Acf,
+    pub highpass: Option<f64>,
+    pub lowpass: Option<f64>,
+    pub filter_design: Design,
+    pub no_octave_correction: bool,
+    pub formant_robust: bool,
+    pub voicing: bool,
+    /// Don't show progress, or what was estimated, on stderr.
+    pub quiet: bool,
+    /// How much to log on stderr: 1 for `logging::Level::Info`, 2 for `Debug`.
+    pub verbosity: u8,
+    /// Report raw estimates instead of smoothing them.
+    pub raw: bool,
+    pub median: Option<usize>,
+    pub hold: Option<usize>,
+    /// Decimate files sampled faster than this.
+    pub max_rate: Option<usize>,
+}
+
+impl DetectorOpts {
+    /// How much to decimate a file sampled at `sample_rate` Hz by before analysis.
+    pub fn decimation(&self, sample_rate: usize) -> usize {
+        resample::decimation_factor(
+            sample_rate,
+            self.max_rate.unwrap_or(resample::DEFAULT_MAX_RATE),
+        )
+    }
+
+    /// The frequency that sounds when A4 is written, as `PitchDetector::written_a4`.
+    pub fn written_a4(&self) -> f64 {
+        let a4 = self.a4.unwrap_or(DEFAULT_A4);
+        a4 * 2f64.powf(-(self.transpose.unwrap_or(0) as f64) / 12.0)
+    }
+
+    /// The smoother for the detector's output, unless raw output was asked for.
+    pub fn smoother(&self) -> Option<Smoother> {
+        if self.raw {
+            return None;
+        }
+        Some(Smoother::new(
+            self.median.unwrap_or(smooth::DEFAULT_MEDIAN_LEN),
+            self.hold.unwrap_or(smooth::DEFAULT_HOLD),
+        ))
+    }
+
+    /// The min and max freq given, or else those of the instrument, if any.
+    fn range(&self) -> (Option<usize>, Option<usize>) {
+        let range = self.instrument.map(Instrument::range);
+        (
+            self.min_freq.or(range.map(|(min, _)| min)),
+            self.max_freq.or(range.map(|(_, max)| max)),
+        )
+    }
+
+    /// Check that the options make sense together.
+    fn check(&self) -> Result<(), String> {
+        match self.range() {
+            (min_freq, Some(max_freq)) if max_freq <= min_freq.unwrap_or(MIN_DETECTABLE_FREQ) => {
+                Err(format!(
+                    "the max freq ({} Hz) must be above the min freq ({} Hz)",
+                    max_freq,
+                    min_freq.unwrap_or(MIN_DETECTABLE_FREQ)
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Build a detector for a signal sampled at `sample_rate` Hz.
+    pub fn detector(&self, sample_rate: usize) -> PitchDetector {
+        let mut detector = PitchDetector::new(sample_rate);
+        let (min_freq, max_freq) = self.range();
+        if let Some(min_freq) = min_freq {
+            detector = detector.with_min_freq(min_freq);
+        }
+        if let Some(max_freq) = max_freq {
+            detector = detector.with_max_freq(max_freq);
+        }
+        if let Some(fudge) = self.fudge {
+            detector = detector.with_fudge_factor(fudge);
+        }
+        if let Some(chunk_size) = self.chunk_size {
+            detector = detector.with_chunk_size(chunk_size);
+        }
+        if let Some(a4) = self.a4 {
+            detector = detector.with_a4(a4);
+        }
+        if let Some(cents) = self.note_tolerance {
+            detector = detector.with_note_tolerance(cents);
+        }
+        if self.strict {
+            detector = detector.with_strict_match(true);
+        }
+        if let Some(threshold) = self.yin_threshold {
+            detector = detector.with_yin_threshold(threshold);
+        }
+        if let Some(cutoff) = self.mpm_cutoff {
+            detector = detector.with_mpm_cutoff(cutoff);
+        }
+        if let Some(min_confidence) = self.min_confidence {
+            detector = detector.with_min_confidence(min_confidence);
+        }
+        if let Some(dbfs) = self.silence_threshold {
+            detector = detector.with_silence_threshold(dbfs);
+        }
+        if let Some(cutoff) = self.highpass {
+            detector = detector.with_filter(Filter::high_pass(self.filter_design, cutoff));
+        }
+        if let Some(cutoff) = self.lowpass {
+            detector = detector.with_filter(Filter::low_pass(self.filter_design, cutoff));
+        }
+        detector
+            .with_temperament(self.temperament, self.tonic.unwrap_or(Note::C))
+            .with_transposition(self.transpose.unwrap_or(0))
+            .with_algorithm(self.algorithm)
+            .with_window(self.window)
+            .with_acf(self.acf)
+            .with_octave_correction(!self.no_octave_correction)
+            .with_formant_robustness(self.formant_robust)
+            .with_voicing(self.voicing)
+            .with_inharmonicity(self.fits_inharmonicity())
+    }
+
+    /// Whether the instrument's strings are stiff enough to fit their inharmonicity.
+    pub fn fits_inharmonicity(&self) -> bool {
+        self.instrument.is_some_and(Instrument::is_inharmonic)
+    }
+}
+
+struct Args<I: Iterator<Item = String>> {
+    args: I,
+}
+
+impl<I: Iterator<Item = String>> Args<I> {
+    fn next(&mut self) -> Option<String> {
+        self.args.next()
+    }
+
+    /// Parse the value following `flag`.
+    fn value<T: FromStr>(&mut self, flag: &str) -> Result<T, String> {
+        let value = self
+            .args
+            .next()
+            .ok_or_else(|| format!("{} requires a value", flag))?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid value for {}: {}", flag, value))
+    }
+
+    /// Parse any detector option in `arg`. Returns `Ok(false)` if `arg` is not one.
+    fn detector_opt(&mut self, arg: &str, opts: &mut DetectorOpts) -> Result<bool, String> {
+        match arg {
+            "--min-freq" => opts.min_freq = Some(self.positive(arg)?),
+            "--max-freq" => opts.max_freq = Some(self.positive(arg)?),
+            "--instrument" => opts.instrument = Some(self.value(arg)?),
+            "--fudge" => opts.fudge = Some(self.positive(arg)?),
+            "--chunk-size" => opts.chunk_size = Some(self.positive(arg)?),
+            "--a4" => opts.a4 = Some(self.value(arg)?),
+            "--note-tolerance" => opts.note_tolerance = Some(self.value(arg)?),
+            "--strict" => opts.strict = true,
+            "--temperament" => opts.temperament = self.value(arg)?,
+            "--tonic" => opts.tonic = Some(self.value(arg)?),
+            "--transpose" => opts.transpose = Some(self.value(arg)?),
+            "--concert-to" => {
+                // Written C sounds the instrument's key, so its music is written up from there.
+                let key: Note = self.value(arg)?;
+                opts.transpose = Some((12 - key.semitone() as i32) % 12);
+            }
+            "--algorithm" => opts.algorithm = self.value(arg)?,
+            "--yin-threshold" => opts.yin_threshold = Some(self.value(arg)?),
+            "--mpm-cutoff" => opts.mpm_cutoff = Some(self.value(arg)?),
+            "--min-confidence" => opts.min_confidence = Some(self.value(arg)?),
+            "--silence-threshold" => opts.silence_threshold = Some(self.value(arg)?),
+            "--window" => opts.window = self.value(arg)?,
+            "--acf" => opts.acf = self.value(arg)?,
+            "--highpass" => opts.highpass = Some(self.value(arg)?),
+            "--lowpass" => opts.lowpass = Some(self.value(arg)?),
+            "--filter-design" => opts.filter_design = self.value(arg)?,
+            "--no-octave-correction" => opts.no_octave_correction = true,
+            "--formant-robust" => opts.formant_robust = true,
+            "--voicing" => opts.voicing = true,
+            "--raw" => opts.raw = true,
+            "--quiet" | "-q" => opts.quiet = true,
+            "--verbose" | "-v" => opts.verbosity += 1,
+            "-vv" => opts.verbosity += 2,
+            "--median" => opts.median = Some(self.positive(arg)?),
+            "--hold" => opts.hold = Some(self.positive(arg)?),
+            "--max-rate" => opts.max_rate = Some(self.positive(arg)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse any option for PCM input in `arg`. Returns `Ok(false)` if `arg` is not one.
+    fn pcm_opt(&mut self, arg: &str, pcm: &mut PcmOpts) -> Result<bool, String> {
+        match arg {
+            "--pcm" => pcm.format = Some(self.value(arg)?),
+            "--sample-rate" => pcm.sample_rate = self.positive(arg)?,
+            "--pcm-channels" => pcm.channel_count = self.positive(arg)?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse any option for the levels to report in `arg`. Returns `Ok(false)` if `arg` is not
+    /// one.
+    fn level_opt(&mut self, arg: &str, levels: &mut Option<Meter>) -> Result<bool, String> {
+        match arg {
+            "--levels" => {
+                levels.get_or_insert_with(Meter::default);
+            }
+            "--a-weighted" => *levels = Some(Meter { a_weighted: true }),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse any option for a live input in `arg`. Returns `Ok(false)` if `arg` is not one.
+    fn input_opt(&mut self, arg: &str, input: &mut InputOpts) -> Result<bool, String> {
+        match arg {
+            "--sample-rate" => input.sample_rate = self.positive(arg)?,
+            "--hop" => input.hop = Some(self.positive(arg)?),
+            "--adaptive" => input.adaptive = Some(self.positive(arg)?),
+            "--target-latency-ms" => match self.value(arg)? {
+                ms if ms > 0.0 => input.target_latency = Some(ms),
+                _ => return Err(format!("{} must be positive", arg)),
+            },
+            "--loopback" if cfg!(target_os = "linux") => input.capture = Some(Capture::Loopback),
+            "--loopback" => {
+                return Err(
+                    "--loopback records PulseAudio's monitor of the output, on Linux; \
+                            elsewhere, pass --capture a command that records the output"
+                        .to_string(),
+                )
+            }
+            "--capture" => input.capture = Some(Capture::Command(self.value(arg)?)),
+            "--backend" => input.backend = self.value::<String>(arg)?.parse()?,
+            "--device" => input.device = Some(self.value(arg)?),
+            "--buffer-size" => input.buffer_size = Some(self.positive(arg)?),
+            "--connect" => input.connect.push(self.value(arg)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse any option for a generated signal in `arg`. Returns `Ok(false)` if `arg` is not one.
+    fn generate_opt(&mut self, arg: &str, generate: &mut GenerateArgs) -> Result<bool, String> {
+        match arg {
+            "--waveform" => generate.waveform = self.value(arg)?,
+            "--freqs" => generate.freqs = Some(self.freqs(arg, ',')?),
+            "--sweep" => match self.freqs(arg, ':')?.as_slice() {
+                [from, to] => generate.sweep = Some((*from, *to)),
+                _ => return Err(format!("{} requires FROM:TO", arg)),
+            },
+            "--sweep-shape" => generate.sweep_shape = self.value(arg)?,
+            "--duration" => generate.duration = Some(self.value(arg)?),
+            "--amplitude" => generate.amplitude = Some(self.value(arg)?),
+            "--harmonics" => generate.harmonics = Some(self.list(arg, ',')?),
+            "--inharmonicity" => generate.inharmonicity = Some(self.value(arg)?),
+            "--sample-rate" => generate.sample_rate = Some(self.positive(arg)?),
+            "--snr-db" => generate.snr_db = Some(self.value(arg)?),
+            "--noise" => generate.noise = self.value(arg)?,
+            "--seed" => generate.seed = Some(self.value(arg)?),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Parse the value following `flag` as frequencies separated by `separator`.
+    fn freqs(&mut self, flag: &str, separator: char) -> Result<Vec<f64>, String> {
+        let freqs = self.list(flag, separator)?;
+        if freqs.iter().any(|freq| *freq <= 0.0) {
+            return Err(format!("{} must be positive", flag));
+        }
+        Ok(freqs)
+    }
+
+    /// Parse the value following `flag` as values separated by `separator`.
+    fn list<T: FromStr>(&mut self, flag: &str, separator: char) -> Result<Vec<T>, String> {
+        let value: String = self.value(flag)?;
+        value
+            .split(separator)
+            .map(|x| x.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid value for {}: {}", flag, value))
+    }
+
+    fn positive(&mut self, flag: &str) -> Result<usize, String> {
+        match self.value(flag)? {
+            0 => Err(format!("{} must be positive", flag)),
+            n => Ok(n),
+        }
+    }
+
+    fn non_negative(&mut self, flag: &str) -> Result<f64, String> {
+        match self.value(flag)? {
+            x if x >= 0.0 && f64::is_finite(x) => Ok(x),
+            _ => Err(format!("{} must be a finite number, 0 or more", flag)),
+        }
+    }
+}
+
+/// The commands that take detector options.
+const DETECTOR_COMMANDS: [&str; 11] = [
+    "analyze",
+    "compare",
+    "transcribe",
+    "live",
+    "tune",
+    "train",
+    "serve",
+    "api",
+    "evaluate",
+    "score",
+    "eval",
+];
+
+/// `args` with the options set for their command in the configuration file before them, so that
+/// `parse` takes those as defaults: the file given with `--config`, or else the first
+/// `config::FILENAME` in the working directory and in the user's configuration directory.
+pub fn with_config(mut args: Vec<String>) -> Result<Vec<String>, String> {
+    let path = match args.iter().position(|arg| arg == "--config") {
+        Some(i) if i + 1 < args.len() => {
+            let path = PathBuf::from(args.remove(i + 1));
+            args.remove(i);
+            path
+        }
+        Some(_) => return Err("--config requires a value".to_string()),
+        None => match default_config() {
+            Some(path) => path,
+            None => return Ok(args),
+        },
+    };
+    let in_file = |e: String| format!("{}: {}", path.display(), e);
+    let text = fs::read_to_string(&path).map_err(|e| in_file(e.to_string()))?;
+    let config = Config::parse(&text).map_err(in_file)?;
+
+    if let Some(section) = config
+        .sections()
+        .find(|section| !DETECTOR_COMMANDS.contains(section) && *section != "generate")
+    {
+        return Err(in_file(format!("unknown command: [{}]", section)));
+    }
+    // The options for every command must be detector options, since that's all they share.
+    let defaults = config.args(None);
+    let mut check = Args {
+        args: defaults.clone().into_iter(),
+    };
+    while let Some(arg) = check.next() {
+        if !check
+            .detector_opt(&arg, &mut DetectorOpts::default())
+            .map_err(in_file)?
+        {
+            return Err(in_file(format!(
+                "{} isn't a detector option; set it in a [command] section",
+                arg.trim_start_matches('-')
+            )));
+        }
+    }
+
+    let command = match args.first() {
+        Some(command) => command.clone(),
+        None => return Ok(args),
+    };
+    let mut merged = vec![command.clone()];
+    if DETECTOR_COMMANDS.contains(&command.as_str()) {
+        merged.extend(defaults);
+    }
+    merged.extend(config.args(Some(&command)));
+    merged.extend(args.into_iter().skip(1));
+    Ok(merged)
+}
+
+/// The configuration file to read when none is given, if there is one.
+fn default_config() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    let candidates = vec![
+        Some(PathBuf::from(config::FILENAME)),
+        dir.map(|dir| dir.join("pitch-detector").join(config::FILENAME)),
+    ];
+    candidates.into_iter().flatten().find(|path| path.is_file())
+}
+
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let command = parse_command(args)?;
+    if let Some(opts) = command.opts() {
+        opts.check()?;
+    }
+    Ok(command)
+}
+
+fn parse_command(args: impl Iterator<Item = String>) -> Result<Command, String> {
+    let mut args = Args { args };
+
+    let command = match args.next() {
+        None => return Ok(Command::Demo),
+        Some(command) => command,
+    };
+
+    match command.as_str() {
+        "-h" | "--help" | "help" => Ok(Command::Help),
+
+        "analyze" => {
+            let mut input = None;
+            let mut analyze = AnalyzeArgs::default();
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut analyze.opts)?
+                    || args.pcm_opt(&arg, &mut analyze.pcm)?
+                    || args.level_opt(&arg, &mut analyze.levels)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--channel" => analyze.channels = args.value(&arg)?,
+                    "--format" => analyze.format = args.value(&arg)?,
+                    "--note-names" => analyze.note_names = args.value(&arg)?,
+                    "--midi" => analyze.midi = Some(args.value(&arg)?),
+                    "--pitch-bend" => analyze.pitch_bend = true,
+                    "--spectrogram" => analyze.spectrogram = Some(args.value(&arg)?),
+                    "--plot" => {
+                        let path: PathBuf = args.value(&arg)?;
+                        let format = ImageFormat::of(&path)
+                            .ok_or_else(|| "--plot writes .png or .svg files".to_string())?;
+                        analyze.plot = Some((path, format));
+                    }
+                    "--notes" => analyze.notes = true,
+                    "--summary" => analyze.summary = true,
+                    "--vibrato" => {
+                        analyze.notes = true;
+                        analyze.vibrato = true;
+                    }
+                    "--dual" => analyze.dual = true,
+                    "--chords" => analyze.chords = true,
+                    "--tuning" => analyze.tuning = Some(args.value(&arg)?),
+                    "--recursive" => analyze.recursive = true,
+                    "--watch" => analyze.watch = true,
+                    "--threads" => analyze.threads = Some(args.positive(&arg)?),
+                    _ if arg.starts_with("--") || input.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => input = Some(PathBuf::from(arg)),
+                }
+            }
+            analyze.input = input.ok_or("analyze requires an input file or directory")?;
+            Ok(Command::Analyze(analyze))
+        }
+
+        "transcribe" => {
+            let mut input = None;
+            let mut transcribe = TranscribeArgs::default();
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut transcribe.opts)?
+                    || args.pcm_opt(&arg, &mut transcribe.pcm)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--channel" => transcribe.channels = args.value(&arg)?,
+                    "--format" => transcribe.format = args.value(&arg)?,
+                    "--note-names" => transcribe.note_names = args.value(&arg)?,
+                    "--min-duration" => transcribe.min_duration = Some(args.value(&arg)?),
+                    "--midi" => transcribe.midi = Some(args.value(&arg)?),
+                    "--pitch-bend" => transcribe.pitch_bend = true,
+                    "--intervals" => transcribe.intervals = true,
+                    "--threads" => transcribe.threads = Some(args.positive(&arg)?),
+                    "--tempo" => transcribe.tempo = Some(args.value(&arg)?),
+                    "--key" => transcribe.key = Some(args.value(&arg)?),
+                    "--time-signature" | "--time" => transcribe.time = args.value(&arg)?,
+                    "--note-length" => transcribe.note_length = args.value(&arg)?,
+                    _ if arg.starts_with("--") || input.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => input = Some(PathBuf::from(arg)),
+                }
+            }
+            if transcribe.channels == Channels::Each {
+                return Err("transcribe needs a single channel or the mix".into());
+            }
+            if transcribe.intervals && !matches!(transcribe.format, TranscribeFormat::List(_)) {
+                return Err("--intervals are written as text, json or csv".into());
+            }
+            if transcribe.pitch_bend && transcribe.midi.is_none() {
+                return Err("--pitch-bend bends the notes of --midi".into());
+            }
+            transcribe.input = input.ok_or("transcribe requires an input file")?;
+            Ok(Command::Transcribe(transcribe))
+        }
+
+        "compare" => {
+            let mut input = None;
+            let mut compare = CompareArgs {
+                input: PathBuf::new(),
+                pcm: PcmOpts::default(),
+                opts: DetectorOpts::default(),
+                channels: Channels::default(),
+                algorithms: vec![Algorithm::Monotonic, Algorithm::Yin, Algorithm::Mpm],
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut compare.opts)?
+                    || args.pcm_opt(&arg, &mut compare.pcm)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--channel" => compare.channels = args.value(&arg)?,
+                    "--algorithms" => {
+                        let algorithms: String = args.value(&arg)?;
+                        compare.algorithms = algorithms
+                            .split(',')
+                            .map(str::parse)
+                            .collect::<Result<_, _>>()?;
+                    }
+                    _ if arg.starts_with("--") || input.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => input = Some(PathBuf::from(arg)),
+                }
+            }
+            if compare.channels == Channels::Each {
+                return Err("compare needs a single channel or the mix".into());
+            }
+            compare.input = input.ok_or("compare requires an input file")?;
+            Ok(Command::Compare(compare))
+        }
+
+        "live" => {
+            let mut live = LiveArgs {
+                opts: DetectorOpts::default(),
+                input: InputOpts::default(),
+                format: Format::default(),
+                note_names: NoteNames::default(),
+                midi_out: None,
+                osc: None,
+                levels: None,
+                record: None,
+                latency: false,
+                summary: false,
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut live.opts)?
+                    || args.input_opt(&arg, &mut live.input)?
+                    || args.level_opt(&arg, &mut live.levels)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--format" => live.format = args.value(&arg)?,
+                    "--note-names" => live.note_names = args.value(&arg)?,
+                    "--midi-out" => live.midi_out = Some(args.value(&arg)?),
+                    "--osc" => live.osc = Some(args.value(&arg)?),
+                    "--record" => live.record = Some(args.value(&arg)?),
+                    "--latency" => live.latency = true,
+                    "--summary" => live.summary = true,
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Live(live))
+        }
+
+        "tune" => {
+            let mut tune = TuneArgs {
+                opts: DetectorOpts::default(),
+                input: InputOpts::default(),
+                strobe: false,
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut tune.opts)?
+                    || args.input_opt(&arg, &mut tune.input)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--strobe" => tune.strobe = true,
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Tune(tune))
+        }
+
+        "train" => {
+            let (low, high) = DEFAULT_TRAIN_RANGE.split_once(':').unwrap();
+            let mut train = TrainArgs {
+                opts: DetectorOpts::default(),
+                input: InputOpts::default(),
+                range: (low.parse()?, high.parse()?),
+                tolerance: training::DEFAULT_TOLERANCE,
+                timeout: training::DEFAULT_TIMEOUT,
+                exact_octave: false,
+                rounds: None,
+                player: None,
+                seed: None,
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut train.opts)?
+                    || args.input_opt(&arg, &mut train.input)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--range" => match args.list(&arg, ':')?.as_slice() {
+                        [low, high] => train.range = (*low, *high),
+                        _ => return Err(format!("{} requires LOW:HIGH", arg)),
+                    },
+                    "--tolerance" => train.tolerance = args.non_negative(&arg)?,
+                    "--timeout" => train.timeout = args.non_negative(&arg)?,
+                    "--exact-octave" => train.exact_octave = true,
+                    "--rounds" => train.rounds = Some(args.positive(&arg)?),
+                    "--player" => train.player = Some(args.value(&arg)?),
+                    "--seed" => train.seed = Some(args.value(&arg)?),
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Train(train))
+        }
+
+        "serve" => {
+            let mut serve = ServeArgs {
+                opts: DetectorOpts::default(),
+                input: InputOpts::default(),
+                listen: DEFAULT_LISTEN.to_string(),
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut serve.opts)?
+                    || args.input_opt(&arg, &mut serve.input)?
+                {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--listen" => serve.listen = args.value(&arg)?,
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Serve(serve))
+        }
+
+        #[cfg(feature = "server")]
+        "api" => {
+            let mut api = ApiArgs {
+                opts: DetectorOpts::default(),
+                channels: Channels::default(),
+                listen: DEFAULT_LISTEN.to_string(),
+            };
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut api.opts)? {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--channel" => api.channels = args.value(&arg)?,
+                    "--listen" => api.listen = args.value(&arg)?,
+                    _ => return Err(format!("unexpected argument: {}", arg)),
+                }
+            }
+            Ok(Command::Api(api))
+        }
+
+        "devices" => match args.next() {
+            Some(arg) => Err(format!("unexpected argument: {}", arg)),
+            None => Ok(Command::Devices),
+        },
+
+        "generate" => {
+            let mut output = None;
+            let mut generate = GenerateArgs::default();
+            while let Some(arg) = args.next() {
+                if args.generate_opt(&arg, &mut generate)? {
+                    continue;
+                }
+                match arg.as_str() {
+                    _ if arg.starts_with("--") || output.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => output = Some(PathBuf::from(arg)),
+                }
+            }
+            generate.output = output.unwrap_or_else(|| crate::FILENAME.into());
+            Ok(Command::Generate(generate))
+        }
+
+        "evaluate" => {
+            let mut evaluate = EvaluateArgs::default();
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut evaluate.opts)?
+                    || args.generate_opt(&arg, &mut evaluate.signal)?
+                {
+                    continue;
+                }
+                return Err(format!("unexpected argument: {}", arg));
+            }
+            Ok(Command::Evaluate(evaluate))
+        }
+
+        "score" => {
+            let (mut input, mut melody, mut hop) = (None, None, None);
+            let (mut pcm, mut opts, mut channels) = Default::default();
+            let mut tolerance = scoring::DEFAULT_TOLERANCE;
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut opts)? || args.pcm_opt(&arg, &mut pcm)? {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--melody" => melody = Some(args.value(&arg)?),
+                    "--tolerance" => tolerance = args.value(&arg)?,
+                    "--hop" => hop = Some(args.positive(&arg)?),
+                    "--channel" => channels = args.value(&arg)?,
+                    _ if arg.starts_with("--") || input.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => input = Some(PathBuf::from(arg)),
+                }
+            }
+            if channels == Channels::Each {
+                return Err("score needs a single channel or the mix".into());
+            }
+            Ok(Command::Score(ScoreArgs {
+                input: input.ok_or("score requires an input file")?,
+                pcm,
+                opts,
+                channels,
+                melody: melody.ok_or("score requires a --melody to score against")?,
+                tolerance,
+                hop,
+            }))
+        }
+
+        "eval" => {
+            let (mut input, mut reference) = (None, None);
+            let (mut pcm, mut opts, mut channels) = Default::default();
+            while let Some(arg) = args.next() {
+                if args.detector_opt(&arg, &mut opts)? || args.pcm_opt(&arg, &mut pcm)? {
+                    continue;
+                }
+                match arg.as_str() {
+                    "--reference" => reference = Some(args.value(&arg)?),
+                    "--channel" => channels = args.value(&arg)?,
+                    _ if arg.starts_with("--") || input.is_some() => {
+                        return Err(format!("unexpected argument: {}", arg))
+                    }
+                    _ => input = Some(PathBuf::from(arg)),
+                }
+            }
+            if channels == Channels::Each {
+                return Err("eval needs a single channel or the mix".into());
+            }
+            Ok(Command::Eval(EvalArgs {
+                input: input.ok_or("eval requires an input file")?,
+                pcm,
+                opts,
+                channels,
+                reference: reference.ok_or("eval requires a --reference annotation")?,
+            }))
+        }
+
+        _ => Err(format!("unknown command: {}", command)),
+    }
+}
@@ -0,0 +1,233 @@
+//! Reading defaults for the command line from a configuration file, `pitch-detector.toml`, so that
+//! the same flags needn't be given every time.
+//!
+//! The file is a small subset of TOML: `key = value` lines, where each key is the name of a flag
+//! without its dashes, and values are strings, numbers, booleans or arrays of them. Keys before
+//! any `[section]` are detector options for every command; those in a `[command]` section only
+//! apply to that command:
+//!
+//! ```toml
+//! algorithm = "yin"
+//! instrument = "guitar"
+//! a4 = 442
+//! voicing = true
+//!
+//! [analyze]
+//! format = "json"
+//! ```
+//!
+//! Each setting becomes a flag, given before those on the command line, so the command line has
+//! the last word: a string or number is the flag's value, an array its items joined by commas,
+//! `true` the flag alone and `false` nothing at all.
+
+use std::fmt;
+
+/// The name of the configuration file looked for by default.
+pub const FILENAME: &str = "pitch-detector.toml";
+
+/// A setting's value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    /// A number, as written but for any `_` separating its digits.
+    Number(String),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl fmt::Display for Value {
+    /// The value as a flag's argument.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::String(s) | Value::Number(s) => f.write_str(s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A section's name (`None` before the first) and settings, in the order they're written.
+type Section = (Option<String>, Vec<(String, Value)>);
+
+/// The settings of a configuration file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Config {
+    sections: Vec<Section>,
+}
+
+impl Config {
+    /// Parse the text of a configuration file.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Config {
+            sections: vec![(None, vec![])],
+        };
+        for (i, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at = |e: String| format!("line {}: {}", i + 1, e);
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .map(str::trim)
+                    .filter(|name| is_key(name))
+                    .ok_or_else(|| at(format!("invalid section: {}", line)))?;
+                if config.section(Some(name)).is_some() {
+                    return Err(at(format!("duplicate section: {}", name)));
+                }
+                config.sections.push((Some(name.to_string()), vec![]));
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| at("expected key = value".to_string()))?;
+            let key = key.trim();
+            if !is_key(key) {
+                return Err(at(format!("invalid key: {}", key)));
+            }
+            let value = parse_value(value.trim()).map_err(at)?;
+            let (_, settings) = config.sections.last_mut().unwrap();
+            if settings.iter().any(|(k, _)| k == key) {
+                return Err(at(format!("duplicate key: {}", key)));
+            }
+            settings.push((key.to_string(), value));
+        }
+        Ok(config)
+    }
+
+    /// The names of the sections, in order.
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().filter_map(|(name, _)| name.as_deref())
+    }
+
+    /// The settings of the section named `name`, or those before any section if it's `None`, if
+    /// there is one.
+    pub fn section(&self, name: Option<&str>) -> Option<&[(String, Value)]> {
+        self.sections
+            .iter()
+            .find(|(section, _)| section.as_deref() == name)
+            .map(|(_, settings)| settings.as_slice())
+    }
+
+    /// The value of `key` in the section named `section` (or before any, if `None`), if it's set.
+    pub fn get(&self, section: Option<&str>, key: &str) -> Option<&Value> {
+        let settings = self.section(section)?;
+        settings.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// The settings of the section named `section` (or before any, if `None`) as command-line
+    /// arguments.
+    pub fn args(&self, section: Option<&str>) -> Vec<String> {
+        let mut args = vec![];
+        for (key, value) in self.section(section).unwrap_or_default() {
+            let flag = format!("--{}", key.replace('_', "-"));
+            match value {
+                Value::Boolean(true) => args.push(flag),
+                Value::Boolean(false) => {}
+                _ => args.extend([flag, value.to_string()]),
+            }
+        }
+        args
+    }
+}
+
+/// `line` up to any `#` outside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..i],
+            None => {}
+        }
+    }
+    line
+}
+
+/// Whether `key` is a bare TOML key: letters, digits, `-` and `_`.
+fn is_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn parse_value(s: &str) -> Result<Value, String> {
+    let (value, rest) = parse_prefix(s)?;
+    if !rest.trim().is_empty() {
+        return Err(format!("unexpected text after value: {}", rest.trim()));
+    }
+    Ok(value)
+}
+
+/// The value at the start of `s`, and the rest of `s` after it.
+fn parse_prefix(s: &str) -> Result<(Value, &str), String> {
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((Value::String(value), &rest[i + 1..])),
+                '\\' => value.push(match chars.next().map(|(_, c)| c) {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some(c) => return Err(format!("invalid escape: \\{}", c)),
+                    None => break,
+                }),
+                c => value.push(c),
+            }
+        }
+        return Err("unterminated string".to_string());
+    }
+    if let Some(rest) = s.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or("unterminated string")?;
+        return Ok((Value::String(rest[..end].to_string()), &rest[end + 1..]));
+    }
+    if let Some(mut rest) = s.strip_prefix('[') {
+        let mut values = vec![];
+        loop {
+            rest = rest.trim_start();
+            if let Some(rest) = rest.strip_prefix(']') {
+                return Ok((Value::Array(values), rest));
+            }
+            let (value, after) = parse_prefix(rest)?;
+            values.push(value);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after;
+            } else if !rest.starts_with(']') {
+                return Err("expected , or ] in array".to_string());
+            }
+        }
+    }
+
+    let end = s.find([',', ']']).unwrap_or(s.len());
+    let (word, rest) = (s[..end].trim_end(), &s[end..]);
+    match word {
+        "true" => Ok((Value::Boolean(true), rest)),
+        "false" => Ok((Value::Boolean(false), rest)),
+        _ => {
+            let number = word.replace('_', "");
+            match number.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok((Value::Number(number), rest)),
+                _ => Err(format!("invalid value: {}", word)),
+            }
+        }
+    }
+}
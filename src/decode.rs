@@ -0,0 +1,126 @@
+//! Decoding compressed audio (MP3, FLAC, Ogg Vorbis, Opus, AAC, ...) by streaming it through
+//! `ffmpeg`, which must be installed. Only built with the `decode` feature.
+
+use std::ffi::OsStr;
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use crate::audio::{Pcm, PcmFormat, Source};
+use crate::{Error, Result};
+
+/// The extensions of the files that are decoded rather than read as WAV files.
+pub const EXTENSIONS: &[&str] = &[
+    "aac", "aif", "aiff", "flac", "m4a", "mp3", "oga", "ogg", "opus", "wma",
+];
+
+/// Whether the file at `path` is decoded rather than read as a WAV file, going by its extension.
+pub fn is_decoded(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|extension| {
+            EXTENSIONS
+                .iter()
+                .any(|decoded| extension.eq_ignore_ascii_case(decoded))
+        })
+}
+
+/// Decode the file at `path` at its own sample rate and channel count.
+pub fn decode(path: &Path) -> Result<Source<Box<dyn Read>>> {
+    // A WAV stream of floats, so the sample rate and channels come with it. Its header can't
+    // give the length, since it's written before the file has been decoded, which is more than
+    // hound accepts, so it's read here and the samples after it as PCM until ffmpeg is done.
+    let mut child = Command::new("ffmpeg")
+        .args(["-nostdin", "-v", "error", "-i"])
+        .arg(path)
+        .args(["-vn", "-f", "wav", "-acodec", "pcm_f32le", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::UnsupportedFormat(format!(
+                "decoding {} needs ffmpeg, which isn't installed",
+                path.display()
+            )),
+            _ => io::Error::new(e.kind(), format!("can't run ffmpeg: {}", e)).into(),
+        })?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let mut decoder = Decoder { child, stdout };
+    let (sample_rate, channel_count) = read_header(&mut decoder).map_err(|_| {
+        // ffmpeg has already said why.
+        Error::UnsupportedFormat(format!("can't decode {}", path.display()))
+    })?;
+    Ok(Source::Pcm(
+        Box::new(decoder),
+        Pcm {
+            format: PcmFormat::F32Le,
+            sample_rate,
+            channel_count,
+        },
+    ))
+}
+
+/// Read a WAV header up to the start of the samples, returning the sample rate and the number
+/// of channels.
+fn read_header(reader: &mut impl Read) -> io::Result<(usize, usize)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not a WAV stream");
+
+    let mut riff = [0; 12];
+    reader.read_exact(&mut riff)?;
+    if &riff[..4] != b"RIFF" || &riff[8..] != b"WAVE" {
+        return Err(invalid());
+    }
+
+    let mut format = None;
+    loop {
+        let mut header = [0; 8];
+        reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        match &header[..4] {
+            b"data" => break,
+            b"fmt " => {
+                let mut fmt = vec![0; len];
+                reader.read_exact(&mut fmt)?;
+                if fmt.len() < 8 {
+                    return Err(invalid());
+                }
+                let channels = u16::from_le_bytes([fmt[2], fmt[3]]) as usize;
+                let rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]) as usize;
+                format = Some((rate, channels));
+            }
+            // Chunks are padded to an even length.
+            _ => {
+                io::copy(&mut reader.take((len + len % 2) as u64), &mut io::sink())?;
+            }
+        }
+    }
+    match format {
+        Some((rate, channels)) if rate > 0 && channels > 0 => Ok((rate, channels)),
+        _ => Err(invalid()),
+    }
+}
+
+/// The decoded output of a running ffmpeg. Reading it fails at the end if ffmpeg did.
+struct Decoder {
+    child: Child,
+    stdout: ChildStdout,
+}
+
+impl Read for Decoder {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.stdout.read(buf)?;
+        if n == 0 && !buf.is_empty() && !self.child.wait()?.success() {
+            return Err(io::Error::other("ffmpeg failed to decode the file"));
+        }
+        Ok(n)
+    }
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        // Stop decoding if the rest isn't wanted.
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
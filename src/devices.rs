@@ -0,0 +1,99 @@
+//! Finding the devices live input can be recorded from: ALSA's capture devices, which stand in for
+//! a sound card API, and (with the `jack` feature) the output ports of a running JACK server.
+
+use std::fmt;
+use std::fs;
+use std::io;
+
+/// Where ALSA lists the PCM devices of every card.
+pub const ALSA_PCM_LIST: &str = "/proc/asound/pcm";
+
+/// A device to record live input from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    /// What the device is reached through: `alsa` or `jack`.
+    pub backend: &'static str,
+    /// The name to give `--device` (or `--connect`, for JACK) to record from it.
+    pub name: String,
+    pub description: String,
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<5} {:<18} {}",
+            self.backend, self.name, self.description
+        )
+    }
+}
+
+/// The capture devices in `text`, a listing in the format of `ALSA_PCM_LIST`, such as
+/// `00-00: ALC3246 Analog : ALC3246 Analog : playback 1 : capture 1`. Each is named as a `plughw`
+/// device, which converts to the sample format and rate asked for, as sound cards seldom take
+/// 16-bit mono themselves.
+pub fn parse_alsa_pcm(text: &str) -> Vec<Device> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(" : ").map(str::trim).collect();
+            let (id, name) = fields.first()?.split_once(':')?;
+            if !fields.iter().any(|field| field.starts_with("capture")) {
+                return None;
+            }
+            let (card, device) = id.trim().split_once('-')?;
+            let card: usize = card.parse().ok()?;
+            let device: usize = device.parse().ok()?;
+            Some(Device {
+                backend: "alsa",
+                name: format!("plughw:{},{}", card, device),
+                description: name.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// ALSA's capture devices, after its `default` device, or none if there's no ALSA.
+pub fn alsa_devices() -> io::Result<Vec<Device>> {
+    let text = match fs::read_to_string(ALSA_PCM_LIST) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut devices = parse_alsa_pcm(&text);
+    if !devices.is_empty() {
+        devices.insert(
+            0,
+            Device {
+                backend: "alsa",
+                name: "default".to_string(),
+                description: "the default input".to_string(),
+            },
+        );
+    }
+    Ok(devices)
+}
+
+/// Every device found: ALSA's, then the output ports of the JACK server, if it's running.
+pub fn devices() -> io::Result<Vec<Device>> {
+    let mut devices = alsa_devices()?;
+    devices.extend(jack_devices());
+    Ok(devices)
+}
+
+#[cfg(feature = "jack")]
+fn jack_devices() -> Vec<Device> {
+    let ports = crate::jack::source_ports().unwrap_or_default();
+    ports
+        .into_iter()
+        .map(|name| Device {
+            backend: "jack",
+            name,
+            description: "JACK output port".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "jack"))]
+fn jack_devices() -> Vec<Device> {
+    vec![]
+}
@@ -0,0 +1,356 @@
+//! The monotonic detector with nothing from `std` and no allocation, for microcontrollers such as
+//! a Cortex-M guitar pedal. Chunks are fixed-size arrays, filled a sample at a time, and the
+//! arithmetic is `f32` apart from the bookkeeping for each lag. This is all that's built without
+//! the `std` feature, along with `Note` and `Pitch`.
+//!
+//! It's the same estimate as `Algorithm::Monotonic`, without the filters, windows, octave
+//! correction and confidence of `PitchDetector`.
+
+use crate::{parabolic_offset, Pitch, DEFAULT_A4, MIN_DETECTABLE_FREQ, MONOTONIC_PERIODS};
+
+/// The RMS amplitude below which a chunk is silent when none is given: -60 dBFS, the same as
+/// `DEFAULT_SILENCE_THRESHOLD`.
+pub const DEFAULT_SILENCE_AMPLITUDE: f32 = 0.001;
+
+/// The most maxima `Peaks` keeps to average the spacing of. Any after these are only counted;
+/// the spacings of the first are plenty to measure the period by.
+pub const MAX_PEAKS: usize = 32;
+
+/// Spacings longer than this many times the median are taken to skip a maximum, and aren't
+/// averaged.
+pub const OUTLIER_SPACING: f64 = 1.5;
+
+/// A local maximum of an autocorrelation.
+#[derive(Debug, Clone, Copy, Default)]
+struct Maximum {
+    /// The fractional lag.
+    lag: f64,
+    height: f64,
+    /// The lowest the autocorrelation got between the maximum before and this one.
+    valley: f64,
+}
+
+/// The local maxima of an autocorrelation, given one lag at a time from a lag of 1, and the
+/// average spacing between them. Up to `MAX_PEAKS` maxima are kept, so this doesn't allocate
+/// however many there are.
+///
+/// The spacings are weighted by the prominence of the maxima they're between: how far each rises
+/// above the shallower of the valleys either side of it. So a spurious maximum, a bump on the slope
+/// to a real one, hardly counts; and spacings over `OUTLIER_SPACING` times the median, across a
+/// maximum that was missed, don't count at all.
+#[derive(Debug, Clone)]
+pub struct Peaks {
+    /// Maxima closer together than this are ripple on one peak, and the highest is kept.
+    shortest: f64,
+    lag: usize,
+    prev_prev: f64,
+    prev: f64,
+    increasing: bool,
+    count: usize,
+    /// The first `MAX_PEAKS` maxima.
+    maxima: [Maximum; MAX_PEAKS],
+    /// The latest maximum, kept or not.
+    last: Maximum,
+    /// The valley after the last maximum kept, once there's another after it.
+    next_valley: Option<f64>,
+    /// The lowest the autocorrelation got since the latest maximum.
+    lowest: f64,
+}
+
+impl Peaks {
+    pub fn new(shortest: usize) -> Self {
+        Peaks {
+            shortest: shortest as f64,
+            lag: 0,
+            prev_prev: 0.0,
+            prev: 0.0,
+            increasing: false,
+            count: 0,
+            maxima: [Maximum::default(); MAX_PEAKS],
+            last: Maximum::default(),
+            next_valley: None,
+            lowest: f64::INFINITY,
+        }
+    }
+
+    /// Add the autocorrelation at the next lag.
+    pub fn push(&mut self, dot_prod: f64) {
+        self.lag += 1;
+
+        // Did we find a local max? If so, refine its position to a fractional lag, since integer
+        // lags make the frequency resolution coarse for short periods.
+        if self.increasing && dot_prod < self.prev {
+            let offset = parabolic_offset(self.prev_prev, self.prev, dot_prod);
+            let lag = (self.lag - 1) as f64 + offset;
+            if self.count > 0 && lag - self.last.lag < self.shortest {
+                // Ripple: the higher of the two is the maximum, with the valley before both.
+                if self.prev > self.last.height {
+                    self.last = Maximum {
+                        lag,
+                        height: self.prev,
+                        valley: self.last.valley,
+                    };
+                    if self.count <= MAX_PEAKS {
+                        self.maxima[self.count - 1] = self.last;
+                    }
+                    self.lowest = dot_prod;
+                }
+            } else if lag >= self.shortest {
+                self.last = Maximum {
+                    lag,
+                    height: self.prev,
+                    valley: self.lowest,
+                };
+                if self.count < MAX_PEAKS {
+                    self.maxima[self.count] = self.last;
+                } else if self.count == MAX_PEAKS {
+                    self.next_valley = Some(self.lowest);
+                }
+                self.count += 1;
+                self.lowest = dot_prod;
+            }
+        }
+
+        self.increasing = dot_prod > self.prev;
+        self.prev_prev = self.prev;
+        self.prev = dot_prod;
+        self.lowest = self.lowest.min(dot_prod);
+    }
+
+    /// The number of maxima found so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The weighted average spacing of the maxima after the first, in samples, or `None` if
+    /// there are too few to measure one. The first is skipped, since it's measured from lag 0
+    /// rather than from another maximum.
+    pub fn period(&self) -> Option<f64> {
+        // We skip the first spacing, so we need at least two more.
+        if self.count < 3 {
+            return None;
+        }
+        let maxima = &self.maxima[1..self.count.min(MAX_PEAKS)];
+        let spacings = maxima.len() - 1;
+        let mut sorted = [0.0; MAX_PEAKS];
+        for (i, pair) in maxima.windows(2).enumerate() {
+            sorted[i] = pair[1].lag - pair[0].lag;
+        }
+        let sorted = &mut sorted[..spacings];
+        sorted.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        let median = if spacings.is_multiple_of(2) {
+            (sorted[spacings / 2 - 1] + sorted[spacings / 2]) / 2.0
+        } else {
+            sorted[spacings / 2]
+        };
+
+        // The valley after each maximum is the one before the next, or the lowest since the last.
+        let prominence = |i: usize| {
+            let valley_after = match maxima.get(i + 1) {
+                Some(next) => next.valley,
+                None => self.next_valley.unwrap_or(self.lowest),
+            };
+            (maxima[i].height - maxima[i].valley.max(valley_after)).max(0.0)
+        };
+        let (mut total, mut weights, mut unweighted, mut kept) = (0.0, 0.0, 0.0, 0);
+        for i in 0..spacings {
+            let spacing = maxima[i + 1].lag - maxima[i].lag;
+            if spacing > OUTLIER_SPACING * median {
+                continue;
+            }
+            let weight = prominence(i).min(prominence(i + 1));
+            total += weight * spacing;
+            weights += weight;
+            unweighted += spacing;
+            kept += 1;
+        }
+        // Flat maxima have no prominence to go by.
+        Some(if weights > 0.0 {
+            total / weights
+        } else {
+            unweighted / kept as f64
+        })
+    }
+}
+
+/// A pitch and how far off it a frequency is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tuning {
+    pub pitch: Pitch,
+    pub cents: f32,
+}
+
+/// Detects the pitch of fixed-size chunks of a mono signal, without allocating.
+#[derive(Debug, Clone, Copy)]
+pub struct Detector {
+    sample_rate: f32,
+    min_freq: f32,
+    /// The highest frequency estimated, if there is a limit.
+    max_freq: Option<f32>,
+    a4: f32,
+    /// RMS amplitude below which a chunk is silent.
+    silence: f32,
+}
+
+impl Detector {
+    /// A detector for a signal sampled at `sample_rate` Hz.
+    pub const fn new(sample_rate: u32) -> Self {
+        Detector {
+            sample_rate: sample_rate as f32,
+            min_freq: MIN_DETECTABLE_FREQ as f32,
+            max_freq: None,
+            a4: DEFAULT_A4 as f32,
+            silence: DEFAULT_SILENCE_AMPLITUDE,
+        }
+    }
+
+    /// The lowest frequency (in Hz) searched for. Chunks should hold several periods of it.
+    pub const fn with_min_freq(mut self, min_freq: f32) -> Self {
+        self.min_freq = min_freq;
+        self
+    }
+
+    /// Don't estimate frequencies above `max_freq` Hz. There's no limit by default.
+    pub const fn with_max_freq(mut self, max_freq: f32) -> Self {
+        self.max_freq = Some(max_freq);
+        self
+    }
+
+    /// Tune A4 to `a4` Hz.
+    pub const fn with_a4(mut self, a4: f32) -> Self {
+        self.a4 = a4;
+        self
+    }
+
+    /// Treat chunks with an RMS amplitude below `amplitude` (from 0 to 1) as silent.
+    pub const fn with_silence_amplitude(mut self, amplitude: f32) -> Self {
+        self.silence = amplitude;
+        self
+    }
+
+    /// The number of samples a chunk needs to hold `periods` periods of the min freq.
+    pub fn chunk_size(&self, periods: usize) -> usize {
+        (periods as f32 * self.sample_rate / self.min_freq) as usize
+    }
+
+    /// Estimate the frequency of `chunk`, or `None` if it's silent or isn't periodic enough to
+    /// have one.
+    pub fn detect(&self, chunk: &[f32]) -> Option<f32> {
+        let energy: f32 = chunk.iter().map(|x| x * x).sum();
+        if chunk.is_empty() || energy < self.silence * self.silence * chunk.len() as f32 {
+            return None;
+        }
+
+        let shortest = self
+            .max_freq
+            .map_or(1, |max_freq| (self.sample_rate / max_freq) as usize)
+            .max(1);
+        let longest = ((MONOTONIC_PERIODS as f32 * self.sample_rate / self.min_freq) as usize)
+            .min(chunk.len() - 1);
+
+        let mut peaks = Peaks::new(shortest);
+        for lag in 1..=longest {
+            let dot_prod: f32 = chunk.iter().zip(&chunk[lag..]).map(|(a, b)| a * b).sum();
+            peaks.push(dot_prod as f64);
+        }
+
+        let freq = self.sample_rate / peaks.period()? as f32;
+        Some(freq).filter(|freq| self.max_freq.is_none_or(|max| *freq <= max))
+    }
+
+    /// The nearest pitch to `freq` and how far off it `freq` is, in cents.
+    pub fn tune(&self, freq: f32) -> Tuning {
+        let semitones = 12.0 * log2(freq / self.a4);
+        let nearest = round(semitones);
+        Tuning {
+            pitch: Pitch::from_midi(69 + nearest),
+            cents: 100.0 * (semitones - nearest as f32),
+        }
+    }
+}
+
+/// The types of sample a `Chunk` holds: `f32` for `Detector`, and Q15 `i16` for
+/// `fixed::Detector`.
+pub trait Sample: Copy {
+    const ZERO: Self;
+}
+
+impl Sample for f32 {
+    const ZERO: Self = 0.0;
+}
+
+impl Sample for i16 {
+    const ZERO: Self = 0;
+}
+
+/// The last `N` samples of a signal, given one at a time, e.g. from an ADC interrupt; a new chunk
+/// is ready every `hop` samples once the first is full.
+#[derive(Debug, Clone)]
+pub struct Chunk<const N: usize, S: Sample = f32> {
+    samples: [S; N],
+    len: usize,
+    hop: usize,
+}
+
+impl<const N: usize, S: Sample> Chunk<N, S> {
+    /// Chunks that don't overlap.
+    pub const fn new() -> Self {
+        Chunk::with_hop(N)
+    }
+
+    /// Chunks that start `hop` samples apart, so they overlap if `hop` is less than `N`.
+    pub const fn with_hop(hop: usize) -> Self {
+        Chunk {
+            samples: [S::ZERO; N],
+            len: 0,
+            hop: if hop == 0 || hop > N { N } else { hop },
+        }
+    }
+
+    /// Add `sample`, returning the chunk if this filled it.
+    pub fn push(&mut self, sample: S) -> Option<&[S; N]> {
+        if self.len == N {
+            self.samples.copy_within(self.hop.., 0);
+            self.len = N - self.hop;
+        }
+        self.samples[self.len] = sample;
+        self.len += 1;
+        if self.len == N {
+            Some(&self.samples)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const N: usize, S: Sample> Default for Chunk<N, S> {
+    fn default() -> Self {
+        Chunk::new()
+    }
+}
+
+/// `x` rounded to the nearest whole number, half away from zero.
+fn round(x: f32) -> i32 {
+    if x < 0.0 {
+        (x - 0.5) as i32
+    } else {
+        (x + 0.5) as i32
+    }
+}
+
+/// The base 2 logarithm of `x`, to within about 1e-6, which is a few thousandths of a cent. `core`
+/// has no logarithms, so it's the exponent of `x` plus a series for the log of its mantissa.
+fn log2(x: f32) -> f32 {
+    if x.is_nan() || x <= 0.0 {
+        return f32::NAN;
+    }
+    let bits = x.to_bits();
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+    let mantissa = f32::from_bits((bits & 0x007f_ffff) | 0x3f80_0000); // in 1..2
+
+    // ln(m) = 2 atanh((m - 1) / (m + 1)), and that is at most 1/3 here.
+    let t = (mantissa - 1.0) / (mantissa + 1.0);
+    let t2 = t * t;
+    let series = t * (1.0 + t2 * (1.0 / 3.0 + t2 * (1.0 / 5.0 + t2 * (1.0 / 7.0 + t2 / 9.0))));
+    exponent as f32 + 2.0 * series * core::f32::consts::LOG2_E
+}
@@ -0,0 +1,96 @@
+//! The errors of reading, analyzing and writing audio.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/// A `Result` with this crate's `Error`.
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing failed.
+    Io(io::Error),
+    /// The input isn't a well-formed WAV file.
+    Malformed(String),
+    /// The input is a WAV file, but not one that can be read.
+    UnsupportedFormat(String),
+    /// A channel was requested that the signal doesn't have.
+    NoSuchChannel {
+        channel: usize,
+        channel_count: usize,
+    },
+    /// The signal is shorter than one chunk, so there's nothing to analyze.
+    TooShort { frames: usize, chunk_size: usize },
+    /// No pitch was found anywhere in the signal.
+    NoPitchFound,
+    /// The options asked for don't go together.
+    Usage(String),
+    /// `failed` of `total` inputs couldn't be analyzed, each reported as it failed; `first` is
+    /// why the first of them couldn't.
+    Failures {
+        failed: usize,
+        total: usize,
+        first: Box<Error>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Malformed(msg) => write!(f, "not a valid WAV file: {}", msg),
+            Error::UnsupportedFormat(msg) => write!(f, "{}", msg),
+            Error::NoSuchChannel {
+                channel,
+                channel_count,
+            } => write!(
+                f,
+                "channel {} requested, but the signal only has {} channel(s)",
+                channel, channel_count
+            ),
+            Error::TooShort { frames, chunk_size } => write!(
+                f,
+                "the signal is {} samples long, shorter than one chunk of {}",
+                frames, chunk_size
+            ),
+            Error::NoPitchFound => write!(f, "no pitch found"),
+            Error::Usage(msg) => write!(f, "{}", msg),
+            Error::Failures { failed, total, .. } => {
+                write!(f, "{} of {} files couldn't be analyzed", failed, total)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Failures { first, .. } => Some(first),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<hound::Error> for Error {
+    fn from(e: hound::Error) -> Self {
+        match e {
+            hound::Error::IoError(e) => Error::Io(e),
+            hound::Error::FormatError(msg) => Error::Malformed(msg.to_string()),
+            hound::Error::UnfinishedSample => Error::Malformed("the data ends mid-sample".into()),
+            hound::Error::Unsupported => {
+                Error::UnsupportedFormat("this kind of WAV file isn't supported".into())
+            }
+            hound::Error::InvalidSampleFormat | hound::Error::TooWide => {
+                Error::UnsupportedFormat(format!("unsupported WAV file: {}", e))
+            }
+        }
+    }
+}
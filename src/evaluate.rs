@@ -0,0 +1,207 @@
+//! Measuring how well detectors track a signal whose frequency is known, and how much they agree.
+//! The frequency may be known because the signal was generated, or from a reference annotation of a
+//! recording, as in melody extraction datasets, which is scored with the standard metrics (as in
+//! MIREX and `mir_eval`).
+
+use crate::generate::Generator;
+use crate::{cents_between, DetectedPitch, PitchDetector};
+
+/// Tracking error statistics over the chunks of a generated signal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tracking {
+    /// The number of chunks analyzed.
+    pub chunks: usize,
+    /// The number of chunks a frequency was detected in. The errors are over these only.
+    pub detected: usize,
+    /// The mean absolute error, in cents.
+    pub mean_cents: f64,
+    /// The root mean square error, in cents.
+    pub rms_cents: f64,
+    /// The largest absolute error, in cents.
+    pub max_cents: f64,
+}
+
+/// Run `detector` over each chunk of the signal from `generator`, comparing each estimate with the
+/// frequency being played in the middle of the chunk. The detector must use the generator's sample
+/// rate.
+pub fn evaluate(detector: &PitchDetector, generator: &Generator) -> Tracking {
+    let samples = generator.samples();
+    let chunk_size = detector.chunk_size();
+    let sample_rate = detector.sample_rate() as f64;
+
+    let mut tracking = Tracking {
+        chunks: 0,
+        detected: 0,
+        mean_cents: 0.0,
+        rms_cents: 0.0,
+        max_cents: 0.0,
+    };
+    let mut sum_squares = 0.0;
+
+    for (i, chunk) in detector.chunks(&samples).enumerate() {
+        tracking.chunks += 1;
+
+        let freq = match detector.detect(chunk).freq {
+            Some(freq) => freq,
+            None => continue,
+        };
+        let middle = (i as f64 + 0.5) * chunk_size as f64 / sample_rate;
+        let error = cents_between(generator.freq_at(middle), freq).abs();
+
+        tracking.detected += 1;
+        tracking.mean_cents += error;
+        sum_squares += error * error;
+        tracking.max_cents = tracking.max_cents.max(error);
+    }
+
+    if tracking.detected > 0 {
+        tracking.mean_cents /= tracking.detected as f64;
+        tracking.rms_cents = (sum_squares / tracking.detected as f64).sqrt();
+    }
+
+    tracking
+}
+
+/// How far an estimate may be from the reference, in cents, to count as the right pitch.
+pub const PITCH_TOLERANCE: f64 = 50.0;
+
+/// Accuracy against a reference annotation, over the frames of the reference. Each ratio is 0 if
+/// there are no frames it's over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Accuracy {
+    /// The number of reference frames compared: those during the chunks analyzed.
+    pub frames: usize,
+    /// The number of those the reference has a pitch in.
+    pub voiced: usize,
+    /// The share of voiced frames estimated within `PITCH_TOLERANCE` of the reference.
+    pub raw_pitch: f64,
+    /// Likewise, ignoring octave errors.
+    pub raw_chroma: f64,
+    /// The share of voiced frames that were estimated to be voiced.
+    pub voicing_recall: f64,
+    /// The share of frames estimated to be voiced that are.
+    pub voicing_precision: f64,
+    /// The share of unvoiced frames that were estimated to be voiced.
+    pub voicing_false_alarm: f64,
+    /// The share of all frames with the right voicing, and if voiced, the right pitch.
+    pub overall: f64,
+}
+
+/// Parse a reference annotation: a time in seconds and a frequency in Hz per line, separated by
+/// whitespace or a comma, as in the MIR-1K, MDB and MedleyDB pitch annotations. A frequency of 0
+/// or less is unvoiced. Blank lines, `#` comments and a header line are skipped.
+pub fn parse_reference(text: &str) -> Result<Vec<(f64, Option<f64>)>, String> {
+    let mut frames = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let fields: Vec<&str> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        let parsed: Vec<Option<f64>> = fields.iter().map(|field| field.parse().ok()).collect();
+        match parsed[..] {
+            [] => {}
+            [Some(time), Some(freq), ..] => frames.push((time, Some(freq).filter(|f| *f > 0.0))),
+            // A header.
+            _ if frames.is_empty() && parsed.iter().all(Option::is_none) => {}
+            _ => return Err(format!("line {}: expected a time and a frequency", i + 1)),
+        }
+    }
+    Ok(frames)
+}
+
+/// Score `estimates`, the frequency (if voiced) of consecutive chunks `chunk_duration` seconds
+/// long from the start of the signal, against the `reference` frequency (if voiced) at each time.
+/// Each reference frame is compared with the chunk it falls in.
+pub fn accuracy(
+    reference: &[(f64, Option<f64>)],
+    estimates: &[Option<f64>],
+    chunk_duration: f64,
+) -> Accuracy {
+    let (mut frames, mut voiced, mut estimated_voiced, mut unvoiced_estimated) = (0, 0, 0, 0);
+    let (mut pitch, mut chroma, mut recalled, mut correct) = (0, 0, 0, 0);
+
+    for &(time, reference) in reference {
+        let chunk = (time / chunk_duration).floor();
+        let estimate = match estimates.get(chunk as usize) {
+            Some(estimate) if chunk >= 0.0 => *estimate,
+            _ => continue,
+        };
+        frames += 1;
+        estimated_voiced += estimate.is_some() as usize;
+
+        let reference = match reference {
+            Some(reference) => reference,
+            None => {
+                unvoiced_estimated += estimate.is_some() as usize;
+                correct += estimate.is_none() as usize;
+                continue;
+            }
+        };
+        voiced += 1;
+        if let Some(estimate) = estimate {
+            recalled += 1;
+            let cents = cents_between(reference, estimate);
+            if cents.abs() <= PITCH_TOLERANCE {
+                pitch += 1;
+                correct += 1;
+            }
+            // The distance to the nearest octave of the reference.
+            let octave_cents = cents - 1200.0 * (cents / 1200.0).round();
+            if octave_cents.abs() <= PITCH_TOLERANCE {
+                chroma += 1;
+            }
+        }
+    }
+
+    let ratio = |n: usize, d: usize| if d > 0 { n as f64 / d as f64 } else { 0.0 };
+    Accuracy {
+        frames,
+        voiced,
+        raw_pitch: ratio(pitch, voiced),
+        raw_chroma: ratio(chroma, voiced),
+        voicing_recall: ratio(recalled, voiced),
+        voicing_precision: ratio(recalled, estimated_voiced),
+        voicing_false_alarm: ratio(unvoiced_estimated, frames - voiced),
+        overall: ratio(correct, frames),
+    }
+}
+
+/// How often two detectors disagree over the same chunks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disagreement {
+    /// The number of chunks compared.
+    pub chunks: usize,
+    /// The number of chunks the detectors matched to different pitches (or only one to a pitch).
+    pub different_notes: usize,
+    /// The number of chunks both detected a frequency in.
+    pub both_detected: usize,
+    /// The mean absolute difference between the frequencies over those chunks, in cents.
+    pub mean_cents: f64,
+}
+
+/// Compare the results `a` and `b` of two detectors, chunk by chunk.
+pub fn disagreement(a: &[DetectedPitch], b: &[DetectedPitch]) -> Disagreement {
+    let mut disagreement = Disagreement {
+        chunks: a.len().min(b.len()),
+        different_notes: 0,
+        both_detected: 0,
+        mean_cents: 0.0,
+    };
+
+    for (a, b) in a.iter().zip(b) {
+        if a.pitch != b.pitch {
+            disagreement.different_notes += 1;
+        }
+        if let (Some(a), Some(b)) = (a.freq, b.freq) {
+            disagreement.both_detected += 1;
+            disagreement.mean_cents += cents_between(a, b).abs();
+        }
+    }
+
+    if disagreement.both_detected > 0 {
+        disagreement.mean_cents /= disagreement.both_detected as f64;
+    }
+
+    disagreement
+}
@@ -0,0 +1,144 @@
+//! Note events, for applications embedding the detector that want to hear when a note starts,
+//! stops or bends, rather than poll for the frequency of every estimate.
+//!
+//! An `EventTracker` turns a stream of estimates into events; `LiveInput::on_note_event` runs one
+//! over a live stream, smoothing the estimates first.
+
+use crate::{cents_between, notes, DetectedPitch, Pitch};
+
+/// How far the pitch of a note must move from the last reported, in cents, for a `PitchChanged`.
+pub const DEFAULT_BEND_STEP: f64 = 5.0;
+
+/// Something that happened to the note being played. Times are in seconds from the start of the
+/// stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// A note started, at `freq` Hz.
+    NoteOn {
+        time: f64,
+        pitch: Pitch,
+        freq: f64,
+        /// The MIDI velocity (1 to 127), from the level it started at.
+        velocity: u8,
+    },
+    /// The note playing moved to `freq` Hz, `cents` off its pitch, as in a slide or vibrato.
+    PitchChanged {
+        time: f64,
+        pitch: Pitch,
+        freq: f64,
+        cents: f64,
+    },
+    /// The note playing stopped, at a rest or as another started.
+    NoteOff { time: f64, pitch: Pitch },
+}
+
+impl Event {
+    pub fn time(&self) -> f64 {
+        match *self {
+            Event::NoteOn { time, .. }
+            | Event::PitchChanged { time, .. }
+            | Event::NoteOff { time, .. } => time,
+        }
+    }
+
+    /// The pitch of the note the event is about.
+    pub fn pitch(&self) -> Pitch {
+        match *self {
+            Event::NoteOn { pitch, .. }
+            | Event::PitchChanged { pitch, .. }
+            | Event::NoteOff { pitch, .. } => pitch,
+        }
+    }
+}
+
+/// Follows a stream of estimates, and reports the events of the note they play: one note at a
+/// time, as the detector is monophonic.
+#[derive(Debug, Clone)]
+pub struct EventTracker {
+    bend_step: f64,
+    /// The note playing, and the frequency it was last reported at.
+    playing: Option<(Pitch, f64)>,
+    /// The time of the latest estimate.
+    time: f64,
+}
+
+impl Default for EventTracker {
+    fn default() -> Self {
+        EventTracker::new()
+    }
+}
+
+impl EventTracker {
+    pub fn new() -> Self {
+        EventTracker {
+            bend_step: DEFAULT_BEND_STEP,
+            playing: None,
+            time: 0.0,
+        }
+    }
+
+    /// Report a `PitchChanged` once the pitch has moved `cents` from the last reported, or never,
+    /// if it's infinite.
+    pub fn with_bend_step(mut self, cents: f64) -> Self {
+        self.bend_step = cents.abs();
+        self
+    }
+
+    /// The note playing, if any.
+    pub fn playing(&self) -> Option<Pitch> {
+        self.playing.map(|(pitch, _)| pitch)
+    }
+
+    /// Feed what was `detected` at `time`, at an RMS level of `dbfs`, calling `on_event` with
+    /// whatever it changes. A new pitch stops the note playing before starting its own.
+    pub fn push(
+        &mut self,
+        time: f64,
+        detected: &DetectedPitch,
+        dbfs: f64,
+        mut on_event: impl FnMut(Event),
+    ) {
+        self.time = time;
+        match (self.playing, detected.pitch, detected.freq) {
+            (Some((playing, reported)), Some(pitch), Some(freq)) if pitch == playing => {
+                if cents_between(reported, freq).abs() >= self.bend_step {
+                    self.playing = Some((pitch, freq));
+                    on_event(Event::PitchChanged {
+                        time,
+                        pitch,
+                        freq,
+                        cents: detected.cents,
+                    });
+                }
+            }
+            // Still the same note, for want of a frequency to say otherwise.
+            (Some((playing, _)), Some(pitch), None) if pitch == playing => {}
+            (_, pitch, freq) => {
+                self.stop(&mut on_event);
+                if let (Some(pitch), Some(freq)) = (pitch, freq) {
+                    self.playing = Some((pitch, freq));
+                    on_event(Event::NoteOn {
+                        time,
+                        pitch,
+                        freq,
+                        velocity: notes::velocity(dbfs),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Stop any note still playing at the end of the stream, at the time of the latest estimate.
+    pub fn finish(&mut self, mut on_event: impl FnMut(Event)) {
+        self.stop(&mut on_event);
+    }
+
+    fn stop(&mut self, on_event: &mut impl FnMut(Event)) {
+        if let Some((pitch, _)) = self.playing.take() {
+            on_event(Event::NoteOff {
+                time: self.time,
+                pitch,
+            });
+        }
+    }
+}
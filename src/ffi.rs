@@ -0,0 +1,164 @@
+//! A C API, so audio plugins written in C or C++ can embed the detector. Only built with the `ffi`
+//! feature; `include/pitch_detector.h` declares it.
+//!
+//! A detector is fed samples as they arrive, in blocks of any size, and estimates the pitch of the
+//! latest chunk every quarter chunk, as `live` does.
+
+use std::slice;
+
+use crate::live::RingBuffer;
+use crate::{DetectedPitch, PitchDetector};
+
+/// A detector and the samples it has been fed.
+pub struct PdDetector {
+    detector: PitchDetector,
+    ring: RingBuffer,
+    hop: usize,
+    /// The number of samples fed since the last estimate.
+    pending: usize,
+    scratch: Vec<f32>,
+    latest: Option<DetectedPitch>,
+}
+
+/// An estimate, as C sees it.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdPitch {
+    /// The estimated frequency in Hz, or 0 if there isn't one.
+    pub freq: f64,
+    /// The MIDI note number of the pitch matching `freq`, or -1 if there isn't one.
+    pub midi: i32,
+    /// How far `freq` is from the pitch, in cents.
+    pub cents: f64,
+    /// How periodic the signal is at `freq`, from 0 to 1.
+    pub confidence: f64,
+    /// Whether the chunk was silent.
+    pub rest: bool,
+}
+
+impl From<&DetectedPitch> for PdPitch {
+    fn from(detected: &DetectedPitch) -> Self {
+        PdPitch {
+            freq: detected.freq.unwrap_or(0.0),
+            midi: detected.pitch.map_or(-1, |pitch| pitch.midi()),
+            cents: detected.cents,
+            confidence: detected.confidence,
+            rest: detected.rest,
+        }
+    }
+}
+
+/// A detector for a mono signal sampled at `sample_rate` Hz, with the default settings, or null
+/// if the sample rate is 0. Free it with `pd_destroy`.
+#[no_mangle]
+pub extern "C" fn pd_create(sample_rate: u32) -> *mut PdDetector {
+    if sample_rate == 0 {
+        return std::ptr::null_mut();
+    }
+    let detector = PitchDetector::new(sample_rate as usize);
+    let chunk_size = detector.chunk_size();
+    Box::into_raw(Box::new(PdDetector {
+        detector,
+        ring: RingBuffer::new(chunk_size),
+        hop: (chunk_size / 4).max(1),
+        pending: 0,
+        scratch: Vec::with_capacity(chunk_size),
+        latest: None,
+    }))
+}
+
+/// Feed `len` samples, from -1 to 1, to `detector`. Returns the number of estimates made from
+/// them; the latest is what `pd_get_pitch` gets.
+///
+/// # Safety
+///
+/// `detector` must be from `pd_create`, and `samples` must point to `len` samples.
+#[no_mangle]
+pub unsafe extern "C" fn pd_feed_samples(
+    detector: *mut PdDetector,
+    samples: *const f32,
+    len: usize,
+) -> usize {
+    let pd = &mut *detector;
+    if len == 0 {
+        return 0;
+    }
+    let mut estimates = 0;
+    for &sample in slice::from_raw_parts(samples, len) {
+        pd.ring.push(sample);
+        pd.pending += 1;
+        if pd.ring.is_full() && pd.pending >= pd.hop {
+            pd.ring.copy_to(&mut pd.scratch);
+            pd.latest = Some(pd.detector.detect(&pd.scratch));
+            pd.pending = 0;
+            estimates += 1;
+        }
+    }
+    estimates
+}
+
+/// The number of samples `detector` estimates the pitch of at a time.
+///
+/// # Safety
+///
+/// `detector` must be from `pd_create`.
+#[no_mangle]
+pub unsafe extern "C" fn pd_chunk_size(detector: *const PdDetector) -> usize {
+    (*detector).detector.chunk_size()
+}
+
+/// Estimate the pitch of each whole chunk of the `len` samples at `samples`, as `analyze` does,
+/// apart from what `detector` has been fed. The first `max` estimates are written to `pitches`,
+/// and the number of chunks is returned.
+///
+/// # Safety
+///
+/// `detector` must be from `pd_create`, `samples` must point to `len` samples, and `pitches` must
+/// be valid to write `max` estimates to.
+#[no_mangle]
+pub unsafe extern "C" fn pd_detect(
+    detector: *const PdDetector,
+    samples: *const f32,
+    len: usize,
+    pitches: *mut PdPitch,
+    max: usize,
+) -> usize {
+    let detector = &(*detector).detector;
+    if len == 0 {
+        return 0;
+    }
+    let samples = slice::from_raw_parts(samples, len);
+    for (i, chunk) in detector.chunks(samples).take(max).enumerate() {
+        *pitches.add(i) = (&detector.detect(chunk)).into();
+    }
+    len / detector.chunk_size()
+}
+
+/// Write the latest estimate of `detector` to `pitch`. Returns false, leaving `pitch` alone, if
+/// it hasn't been fed a whole chunk yet.
+///
+/// # Safety
+///
+/// `detector` must be from `pd_create`, and `pitch` must be valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn pd_get_pitch(detector: *const PdDetector, pitch: *mut PdPitch) -> bool {
+    match &(*detector).latest {
+        Some(detected) => {
+            *pitch = detected.into();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Free `detector`. Null is ignored.
+///
+/// # Safety
+///
+/// `detector` must be null or from `pd_create`, and not already freed.
+#[no_mangle]
+pub unsafe extern "C" fn pd_destroy(detector: *mut PdDetector) {
+    if !detector.is_null() {
+        drop(Box::from_raw(detector));
+    }
+}
@@ -0,0 +1,157 @@
+//! High-pass and low-pass pre-filtering, to remove DC offset, hum and hiss before analysis.
+//!
+//! Each chunk is filtered on its own, starting from a zero state.
+
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Response {
+    HighPass,
+    LowPass,
+}
+
+/// How steep the filter is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Design {
+    /// A 6 dB/octave first-order filter.
+    OnePole,
+    /// A 12 dB/octave second-order Butterworth filter.
+    #[default]
+    Biquad,
+}
+
+impl FromStr for Design {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "one-pole" => Ok(Design::OnePole),
+            "biquad" => Ok(Design::Biquad),
+            _ => Err(format!("unknown filter design: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Filter {
+    pub response: Response,
+    pub design: Design,
+    /// The cutoff frequency, in Hz.
+    pub cutoff: f64,
+}
+
+impl Filter {
+    pub fn high_pass(design: Design, cutoff: f64) -> Self {
+        Filter {
+            response: Response::HighPass,
+            design,
+            cutoff,
+        }
+    }
+
+    pub fn low_pass(design: Design, cutoff: f64) -> Self {
+        Filter {
+            response: Response::LowPass,
+            design,
+            cutoff,
+        }
+    }
+
+    /// Filter `buffer`, sampled at `sample_rate` Hz, in place.
+    pub fn apply(&self, buffer: &mut [f32], sample_rate: usize) {
+        let coeffs = match self.design {
+            Design::OnePole => self.one_pole(sample_rate as f64),
+            Design::Biquad => self.biquad(sample_rate as f64),
+        };
+
+        // Direct form I.
+        let [b0, b1, b2, a1, a2] = coeffs;
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        for sample in buffer.iter_mut() {
+            let x0 = *sample as f64;
+            let y0 = b0 * x0 + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+            x2 = x1;
+            x1 = x0;
+            y2 = y1;
+            y1 = y0;
+            *sample = y0 as f32;
+        }
+    }
+
+    /// Normalized `[b0, b1, b2, a1, a2]` coefficients of a first-order filter (bilinear
+    /// transform of an RC filter).
+    fn one_pole(&self, sample_rate: f64) -> [f64; 5] {
+        let k = (PI * self.cutoff / sample_rate).tan();
+        let a1 = (k - 1.0) / (k + 1.0);
+        match self.response {
+            Response::LowPass => [k / (k + 1.0), k / (k + 1.0), 0.0, a1, 0.0],
+            Response::HighPass => [1.0 / (k + 1.0), -1.0 / (k + 1.0), 0.0, a1, 0.0],
+        }
+    }
+
+    /// Normalized `[b0, b1, b2, a1, a2]` coefficients of a Butterworth biquad, per the Audio EQ
+    /// Cookbook.
+    fn biquad(&self, sample_rate: f64) -> [f64; 5] {
+        let w0 = 2.0 * PI * self.cutoff / sample_rate;
+        let alpha = w0.sin() / (2.0 * std::f64::consts::FRAC_1_SQRT_2);
+        let cos = w0.cos();
+        let a0 = 1.0 + alpha;
+
+        let (b0, b1, b2) = match self.response {
+            Response::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos, (1.0 - cos) / 2.0),
+            Response::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos), (1.0 + cos) / 2.0),
+        };
+
+        [
+            b0 / a0,
+            b1 / a0,
+            b2 / a0,
+            -2.0 * cos / a0,
+            (1.0 - alpha) / a0,
+        ]
+    }
+}
+
+/// The poles of the A-weighting curve of IEC 61672, in Hz: two high-pass poles at the first, one
+/// at each of the next two, and two low-pass poles at the last.
+const A_WEIGHTING_POLES: [f64; 4] = [20.6, 107.7, 737.9, 12194.0];
+
+/// A-weight `buffer`, sampled at `sample_rate` Hz, in place, with a gain of 0 dB at 1 kHz. The
+/// curve is a cascade of one-pole filters, so it's only approximate near the Nyquist frequency;
+/// low-pass poles above it are left out.
+pub fn a_weight(buffer: &mut [f32], sample_rate: usize) {
+    let [p1, p2, p3, p4] = A_WEIGHTING_POLES;
+    let mut filters = vec![
+        Filter::high_pass(Design::OnePole, p1),
+        Filter::high_pass(Design::OnePole, p1),
+        Filter::high_pass(Design::OnePole, p2),
+        Filter::high_pass(Design::OnePole, p3),
+    ];
+    if p4 < sample_rate as f64 / 2.0 {
+        filters.extend([Filter::low_pass(Design::OnePole, p4); 2]);
+    }
+
+    // The bilinear transform maps frequencies through tan, and one-pole coefficients are
+    // prewarped to match, so each filter's gain follows the analog response in warped terms.
+    let warp = |freq: f64| (PI * freq / sample_rate as f64).tan();
+    let at_1k = warp(1000.0);
+    let gain: f64 = filters
+        .iter()
+        .map(|filter| {
+            let cutoff = warp(filter.cutoff);
+            let normalized = match filter.response {
+                Response::HighPass => at_1k,
+                Response::LowPass => cutoff,
+            };
+            normalized / (at_1k * at_1k + cutoff * cutoff).sqrt()
+        })
+        .product();
+
+    for filter in &filters {
+        filter.apply(buffer, sample_rate);
+    }
+    buffer
+        .iter_mut()
+        .for_each(|sample| *sample = (*sample as f64 / gain) as f32);
+}
@@ -0,0 +1,302 @@
+//! The `embedded` detector in integer arithmetic, for microcontrollers without an FPU, where
+//! every `f32` operation is a library call. Its results are the same on every target, bit for
+//! bit.
+//!
+//! Samples are Q15 (`i16`, as most ADCs and codecs give them), autocorrelations are sums of their
+//! Q30 products in `i64`, and lags, periods, frequencies and cents are Q16.16: whole numbers
+//! scaled by 65536. Which detector runs is decided at compile time, by which is used: this one
+//! takes `i16` chunks, such as those of an `embedded::Chunk<N, i16>`, and never touches a float.
+
+use crate::embedded::{MAX_PEAKS, OUTLIER_SPACING};
+use crate::{Pitch, MIN_DETECTABLE_FREQ, MONOTONIC_PERIODS};
+
+/// One, in Q16.16.
+pub const ONE: i64 = 1 << 16;
+
+/// The RMS amplitude below which a chunk is silent when none is given, in Q15: -60 dBFS, as
+/// `embedded::DEFAULT_SILENCE_AMPLITUDE`.
+pub const DEFAULT_SILENCE_AMPLITUDE: i16 = 33;
+
+/// A4, 440 Hz, in Q16.16.
+pub const DEFAULT_A4: u32 = 440 << 16;
+
+/// `OUTLIER_SPACING`, in Q16.16.
+const OUTLIER: i64 = (OUTLIER_SPACING * ONE as f64) as i64;
+
+/// A local maximum of an autocorrelation.
+#[derive(Debug, Clone, Copy, Default)]
+struct Maximum {
+    /// The fractional lag, in Q16.16.
+    lag: i64,
+    height: i64,
+    /// The lowest the autocorrelation got between the maximum before and this one.
+    valley: i64,
+}
+
+/// The local maxima of an autocorrelation, and their weighted average spacing, as
+/// `embedded::Peaks` finds them, in integers.
+#[derive(Debug, Clone)]
+pub struct Peaks {
+    /// Maxima closer together than this are ripple on one peak, in Q16.16 samples.
+    shortest: i64,
+    lag: i64,
+    prev_prev: i64,
+    prev: i64,
+    increasing: bool,
+    count: usize,
+    /// The first `MAX_PEAKS` maxima.
+    maxima: [Maximum; MAX_PEAKS],
+    /// The latest maximum, kept or not.
+    last: Maximum,
+    /// The valley after the last maximum kept, once there's another after it.
+    next_valley: Option<i64>,
+    /// The lowest the autocorrelation got since the latest maximum.
+    lowest: i64,
+}
+
+impl Peaks {
+    pub fn new(shortest: usize) -> Self {
+        Peaks {
+            shortest: shortest as i64 * ONE,
+            lag: 0,
+            prev_prev: 0,
+            prev: 0,
+            increasing: false,
+            count: 0,
+            maxima: [Maximum::default(); MAX_PEAKS],
+            last: Maximum::default(),
+            next_valley: None,
+            lowest: i64::MAX,
+        }
+    }
+
+    /// Add the autocorrelation at the next lag.
+    pub fn push(&mut self, dot_prod: i64) {
+        self.lag += 1;
+
+        if self.increasing && dot_prod < self.prev {
+            let offset = parabolic_offset(self.prev_prev, self.prev, dot_prod);
+            let lag = (self.lag - 1) * ONE + offset;
+            if self.count > 0 && lag - self.last.lag < self.shortest {
+                if self.prev > self.last.height {
+                    self.last = Maximum {
+                        lag,
+                        height: self.prev,
+                        valley: self.last.valley,
+                    };
+                    if self.count <= MAX_PEAKS {
+                        self.maxima[self.count - 1] = self.last;
+                    }
+                    self.lowest = dot_prod;
+                }
+            } else if lag >= self.shortest {
+                self.last = Maximum {
+                    lag,
+                    height: self.prev,
+                    valley: self.lowest,
+                };
+                if self.count < MAX_PEAKS {
+                    self.maxima[self.count] = self.last;
+                } else if self.count == MAX_PEAKS {
+                    self.next_valley = Some(self.lowest);
+                }
+                self.count += 1;
+                self.lowest = dot_prod;
+            }
+        }
+
+        self.increasing = dot_prod > self.prev;
+        self.prev_prev = self.prev;
+        self.prev = dot_prod;
+        self.lowest = self.lowest.min(dot_prod);
+    }
+
+    /// The number of maxima found so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The weighted average spacing of the maxima after the first, in Q16.16 samples, or `None`
+    /// if there are too few to measure one.
+    pub fn period(&self) -> Option<i64> {
+        if self.count < 3 {
+            return None;
+        }
+        let maxima = &self.maxima[1..self.count.min(MAX_PEAKS)];
+        let spacings = maxima.len() - 1;
+        let mut sorted = [0; MAX_PEAKS];
+        for (i, pair) in maxima.windows(2).enumerate() {
+            sorted[i] = pair[1].lag - pair[0].lag;
+        }
+        let sorted = &mut sorted[..spacings];
+        sorted.sort_unstable();
+        let median = if spacings.is_multiple_of(2) {
+            (sorted[spacings / 2 - 1] + sorted[spacings / 2]) / 2
+        } else {
+            sorted[spacings / 2]
+        };
+
+        let prominence = |i: usize| {
+            let valley_after = match maxima.get(i + 1) {
+                Some(next) => next.valley,
+                None => self.next_valley.unwrap_or(self.lowest),
+            };
+            (maxima[i].height as i128 - maxima[i].valley.max(valley_after) as i128).max(0)
+        };
+        let (mut total, mut weights, mut unweighted, mut kept) = (0i128, 0i128, 0, 0);
+        for i in 0..spacings {
+            let spacing = maxima[i + 1].lag - maxima[i].lag;
+            if spacing as i128 * ONE as i128 > OUTLIER as i128 * median as i128 {
+                continue;
+            }
+            let weight = prominence(i).min(prominence(i + 1));
+            total += weight * spacing as i128;
+            weights += weight;
+            unweighted += spacing;
+            kept += 1;
+        }
+        Some(if weights > 0 {
+            (total / weights) as i64
+        } else {
+            unweighted / kept
+        })
+    }
+}
+
+/// `crate::parabolic_offset` in Q16.16. The products are wider than `i64` for loud signals, but
+/// this is only done once per maximum.
+fn parabolic_offset(prev: i64, cur: i64, next: i64) -> i64 {
+    let denom = 2 * (2 * cur as i128 - next as i128 - prev as i128);
+    if denom == 0 {
+        0
+    } else {
+        (((next as i128 - prev as i128) << 16) / denom) as i64
+    }
+}
+
+/// A pitch and how far off it a frequency is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tuning {
+    pub pitch: Pitch,
+    /// In Q16.16 cents, sharp if positive.
+    pub cents: i32,
+}
+
+/// Detects the pitch of fixed-size chunks of Q15 samples, as `embedded::Detector` does.
+#[derive(Debug, Clone, Copy)]
+pub struct Detector {
+    sample_rate: u32,
+    min_freq: u32,
+    /// The highest frequency estimated, if there is a limit.
+    max_freq: Option<u32>,
+    /// In Q16.16 Hz.
+    a4: u32,
+    silence: i16,
+}
+
+impl Detector {
+    /// A detector for a signal sampled at `sample_rate` Hz.
+    pub const fn new(sample_rate: u32) -> Self {
+        Detector {
+            sample_rate,
+            min_freq: MIN_DETECTABLE_FREQ as u32,
+            max_freq: None,
+            a4: DEFAULT_A4,
+            silence: DEFAULT_SILENCE_AMPLITUDE,
+        }
+    }
+
+    /// The lowest frequency (in Hz) searched for. Chunks should hold several periods of it.
+    pub const fn with_min_freq(mut self, min_freq: u32) -> Self {
+        self.min_freq = if min_freq == 0 { 1 } else { min_freq };
+        self
+    }
+
+    /// Don't estimate frequencies above `max_freq` Hz. There's no limit by default.
+    pub const fn with_max_freq(mut self, max_freq: u32) -> Self {
+        self.max_freq = Some(max_freq);
+        self
+    }
+
+    /// Tune A4 to `a4` Q16.16 Hz.
+    pub const fn with_a4(mut self, a4: u32) -> Self {
+        self.a4 = a4;
+        self
+    }
+
+    /// Treat chunks with an RMS amplitude below `amplitude` (in Q15) as silent.
+    pub const fn with_silence_amplitude(mut self, amplitude: i16) -> Self {
+        self.silence = amplitude;
+        self
+    }
+
+    /// The number of samples a chunk needs to hold `periods` periods of the min freq.
+    pub const fn chunk_size(&self, periods: usize) -> usize {
+        periods * self.sample_rate as usize / self.min_freq as usize
+    }
+
+    /// Estimate the frequency of `chunk`, in Q16.16 Hz, or `None` if it's silent or isn't
+    /// periodic enough to have one.
+    pub fn detect(&self, chunk: &[i16]) -> Option<u32> {
+        let energy: i64 = chunk.iter().map(|&x| x as i64 * x as i64).sum();
+        let silence = self.silence as i64;
+        if chunk.is_empty() || energy < silence * silence * chunk.len() as i64 {
+            return None;
+        }
+
+        let shortest = self
+            .max_freq
+            .map_or(1, |max_freq| (self.sample_rate / max_freq.max(1)) as usize)
+            .max(1);
+        let longest = (MONOTONIC_PERIODS * self.sample_rate as usize / self.min_freq as usize)
+            .min(chunk.len() - 1);
+
+        let mut peaks = Peaks::new(shortest);
+        for lag in 1..=longest {
+            let dot_prod: i64 = chunk
+                .iter()
+                .zip(&chunk[lag..])
+                .map(|(&a, &b)| a as i64 * b as i64)
+                .sum();
+            peaks.push(dot_prod);
+        }
+
+        let period = peaks.period().filter(|period| *period > 0)?;
+        let freq = ((self.sample_rate as i64) << 32) / period;
+        let max = self.max_freq.map_or(i64::MAX, |max| max as i64 * ONE);
+        Some(freq)
+            .filter(|freq| *freq <= max && *freq <= u32::MAX as i64)
+            .map(|freq| freq as u32)
+    }
+
+    /// The nearest pitch to `freq`, in Q16.16 Hz, and how far off it `freq` is.
+    pub fn tune(&self, freq: u32) -> Tuning {
+        let octaves = log2(freq) as i64 - log2(self.a4) as i64;
+        let semitones = 12 * octaves;
+        let nearest = (semitones + ONE / 2).div_euclid(ONE);
+        Tuning {
+            pitch: Pitch::from_midi(69 + nearest as i32),
+            cents: (100 * (semitones - nearest * ONE)) as i32,
+        }
+    }
+}
+
+/// The base 2 logarithm of the Q16.16 number `x`, in Q16.16, to within a bit: 1/65536 of an
+/// octave, or about 0.02 cents. It squares the mantissa a bit at a time. `x` must be positive.
+pub fn log2(x: u32) -> i32 {
+    if x == 0 {
+        return i32::MIN;
+    }
+    let top = 31 - x.leading_zeros() as i32;
+    // The mantissa, in Q30 from 1 up to 2.
+    let mut mantissa = (x as u64) << 30 >> top;
+    let mut log = (top - 16) << 16;
+    for bit in (0..16).rev() {
+        mantissa = (mantissa * mantissa) >> 30;
+        if mantissa >= 2 << 30 {
+            mantissa >>= 1;
+            log |= 1 << bit;
+        }
+    }
+    log
+}
@@ -0,0 +1,53 @@
+//! Flattening the spectrum of vocal chunks, so that the monotonic detector tracks the fundamental
+//! rather than a formant.
+//!
+//! A vowel's formants boost whichever harmonics fall near them, and the autocorrelation of a chunk
+//! then ripples at the period of those harmonics, with maxima between the fundamental's; the
+//! monotonic detector averages the spacing of every maximum, so it reports the harmonic. Whitening
+//! the spectrum (by spectral division or keeping an LPC residual) doesn't help it: with every
+//! harmonic as strong as the rest, there are more maxima between periods rather than fewer.
+//! Instead, as in Sondhi's classic autocorrelation pitch detector, chunks are low-passed below the
+//! second formant and then center-clipped: only the peaks of each period, where the glottal pulse
+//! excites the vocal tract, rise above the clipping level, so the formants' ringing between them
+//! is cut away, and the autocorrelation peaks only at multiples of the period.
+
+use crate::filters::{Design, Filter};
+
+/// The cutoff of the low-pass filters applied before clipping, in Hz. The second formant of most
+/// vowels is above it, and the fundamental of most singing below it, though fundamentals above
+/// about 500 Hz are attenuated enough for their harmonics to win again.
+pub const CUTOFF: f64 = 900.0;
+
+/// How many times the low-pass filter is applied, for a steeper slope.
+const FILTER_PASSES: usize = 2;
+
+/// The clipping level, as a fraction of the chunk's peak amplitude. Sondhi used 0.3, but 0.4
+/// copes with first formants several times as strong as the fundamental.
+pub const CLIPPING_LEVEL: f32 = 0.4;
+
+/// `buffer`, sampled at `sample_rate` Hz, low-passed below `CUTOFF` and center-clipped.
+pub fn flatten(buffer: &[f32], sample_rate: usize) -> Vec<f32> {
+    let mut flattened = buffer.to_vec();
+    let filter = Filter::low_pass(Design::Biquad, CUTOFF);
+    for _ in 0..FILTER_PASSES {
+        filter.apply(&mut flattened, sample_rate);
+    }
+    center_clip(&mut flattened, CLIPPING_LEVEL);
+    flattened
+}
+
+/// Clip the samples of `buffer` within `level` times its peak amplitude of 0 to 0, in place, and
+/// move the rest toward 0 by that much.
+pub fn center_clip(buffer: &mut [f32], level: f32) {
+    let peak = buffer.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    let level = level * peak;
+    for sample in buffer.iter_mut() {
+        *sample = if *sample > level {
+            *sample - level
+        } else if *sample < -level {
+            *sample + level
+        } else {
+            0.0
+        };
+    }
+}
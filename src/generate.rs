@@ -0,0 +1,357 @@
+//! Synthesizing test signals.
+
+use std::f64::consts::PI;
+use std::io::{self, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{level, DEFAULT_SAMPLE_RATE};
+
+/// The default length of a generated signal, in seconds.
+pub const DEFAULT_DURATION: f64 = 20.0;
+
+/// The shape of one cycle of a generated tone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Square,
+    Saw,
+    Triangle,
+}
+
+impl Waveform {
+    /// The value of the waveform at `phase` cycles into its period, in `-1.0..=1.0`.
+    pub fn sample(self, phase: f64) -> f64 {
+        let phase = phase.fract();
+        match self {
+            Waveform::Sine => (2.0 * PI * phase).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => 2.0 * phase - 1.0,
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sine" => Ok(Waveform::Sine),
+            "square" => Ok(Waveform::Square),
+            "saw" => Ok(Waveform::Saw),
+            "triangle" => Ok(Waveform::Triangle),
+            _ => Err(format!("unknown waveform: {}", s)),
+        }
+    }
+}
+
+/// How the frequency of a sweep changes over time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sweep {
+    /// By the same number of Hz per second.
+    #[default]
+    Linear,
+    /// By the same number of cents per second.
+    Exponential,
+}
+
+impl FromStr for Sweep {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" | "lin" => Ok(Sweep::Linear),
+            "exponential" | "exp" => Ok(Sweep::Exponential),
+            _ => Err(format!("unknown sweep: {}", s)),
+        }
+    }
+}
+
+/// The colour of noise mixed into a generated signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Noise {
+    /// Equal power at all frequencies.
+    #[default]
+    White,
+    /// Equal power per octave, falling 3 dB per octave.
+    Pink,
+}
+
+impl FromStr for Noise {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "white" => Ok(Noise::White),
+            "pink" => Ok(Noise::Pink),
+            _ => Err(format!("unknown noise: {}", s)),
+        }
+    }
+}
+
+/// The frequencies a generated signal goes through.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tones {
+    /// Each frequency in turn, for an equal share of the duration.
+    Steps(Vec<f64>),
+    /// A glide from one frequency to another over the whole duration.
+    Sweep { from: f64, to: f64, sweep: Sweep },
+}
+
+/// Generates a test signal playing some `Tones`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Generator {
+    tones: Tones,
+    waveform: Waveform,
+    duration: f64,
+    amplitude: f64,
+    sample_rate: usize,
+    /// The amplitude of each partial, starting with the fundamental.
+    harmonics: Vec<f64>,
+    /// The inharmonicity coefficient of the partials, 0 for harmonics.
+    inharmonicity: f64,
+    /// The noise to mix in and the signal-to-noise ratio in dB.
+    noise: Option<(Noise, f64)>,
+    seed: u64,
+    /// How far into its cycle the waveform starts, from 0 to 1.
+    phase: f64,
+}
+
+impl Generator {
+    pub fn new(tones: Tones) -> Self {
+        Generator {
+            tones,
+            waveform: Waveform::default(),
+            duration: DEFAULT_DURATION,
+            amplitude: 1.0,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            harmonics: vec![1.0],
+            inharmonicity: 0.0,
+            noise: None,
+            seed: 1,
+            phase: 0.0,
+        }
+    }
+
+    pub fn with_waveform(self, waveform: Waveform) -> Self {
+        Generator { waveform, ..self }
+    }
+
+    /// Set the length of the signal in seconds.
+    pub fn with_duration(self, duration: f64) -> Self {
+        Generator { duration, ..self }
+    }
+
+    /// Set the peak amplitude, from 0 to 1 (full scale).
+    pub fn with_amplitude(self, amplitude: f64) -> Self {
+        Generator { amplitude, ..self }
+    }
+
+    pub fn with_sample_rate(self, sample_rate: usize) -> Self {
+        Generator {
+            sample_rate,
+            ..self
+        }
+    }
+
+    /// Play a harmonic series instead of just the fundamental: partial `k` (from 1) is the waveform
+    /// at `k` times the frequency, with amplitude `harmonics[k - 1]`. The sum is scaled back so that
+    /// it peaks at no more than the set amplitude.
+    pub fn with_harmonics(self, harmonics: Vec<f64>) -> Self {
+        Generator { harmonics, ..self }
+    }
+
+    /// Stretch the partials as a stiff string, such as a piano's, does, with inharmonicity
+    /// coefficient `b`: partial `k` is at `k * sqrt(1 + b k^2)` times the frequency rather than `k`
+    /// times it. See `inharmonicity`.
+    pub fn with_inharmonicity(self, b: f64) -> Self {
+        Generator {
+            inharmonicity: b,
+            ..self
+        }
+    }
+
+    /// Mix in `noise`, `snr_db` dB quieter than the tone (by RMS).
+    pub fn with_noise(self, noise: Noise, snr_db: f64) -> Self {
+        Generator {
+            noise: Some((noise, snr_db)),
+            ..self
+        }
+    }
+
+    /// Set the seed of the noise, so that different noisy signals can be generated.
+    pub fn with_seed(self, seed: u64) -> Self {
+        Generator { seed, ..self }
+    }
+
+    /// Start `phase` cycles (from 0 to 1) into the waveform.
+    pub fn with_phase(self, phase: f64) -> Self {
+        Generator { phase, ..self }
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// The number of samples in the signal.
+    pub fn len(&self) -> usize {
+        (self.duration * self.sample_rate as f64) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The frequency being played `t` seconds into the signal.
+    pub fn freq_at(&self, t: f64) -> f64 {
+        let progress = (t / self.duration).clamp(0.0, 1.0);
+        match &self.tones {
+            Tones::Steps(freqs) => {
+                let step = (progress * freqs.len() as f64) as usize;
+                freqs[step.min(freqs.len() - 1)]
+            }
+            Tones::Sweep {
+                from,
+                to,
+                sweep: Sweep::Linear,
+            } => from + (to - from) * progress,
+            Tones::Sweep {
+                from,
+                to,
+                sweep: Sweep::Exponential,
+            } => from * (to / from).powf(progress),
+        }
+    }
+
+    /// All samples of the signal. The phase is kept continuous across frequency changes.
+    pub fn samples(&self) -> Vec<f32> {
+        let total: f64 = self.harmonics.iter().map(|a| a.abs()).sum();
+        let scale = if total > 0.0 {
+            self.amplitude / total
+        } else {
+            0.0
+        };
+
+        let mut phase = self.phase.rem_euclid(1.0);
+        // Stretched partials aren't whole cycles when the fundamental is, so each keeps its own
+        // phase.
+        let stretches: Vec<f64> = (1..=self.harmonics.len())
+            .map(|k| k as f64 * (1.0 + self.inharmonicity * (k * k) as f64).sqrt())
+            .collect();
+        let mut partials: Vec<f64> = stretches.iter().map(|s| (s * phase).fract()).collect();
+        let mut samples: Vec<f32> = (0..self.len())
+            .map(|i| {
+                let t = i as f64 / self.sample_rate as f64;
+                let sample: f64 = if self.inharmonicity == 0.0 {
+                    (self.harmonics.iter().enumerate())
+                        .map(|(k, a)| a * self.waveform.sample((k + 1) as f64 * phase))
+                        .sum()
+                } else {
+                    (self.harmonics.iter().zip(&partials))
+                        .map(|(a, phase)| a * self.waveform.sample(*phase))
+                        .sum()
+                };
+                let sample = scale * sample;
+                let step = self.freq_at(t) / self.sample_rate as f64;
+                phase = (phase + step).fract();
+                for (partial, stretch) in partials.iter_mut().zip(&stretches) {
+                    *partial = (*partial + stretch * step).fract();
+                }
+                sample as f32
+            })
+            .collect();
+
+        if let Some((noise, snr_db)) = self.noise {
+            let noise = noise_samples(noise, samples.len(), self.seed);
+            let noise_rms = level::rms(&noise);
+            if noise_rms > 0.0 {
+                let scale = (level::rms(&samples) / noise_rms / level::from_dbfs(snr_db)) as f32;
+                for (sample, noise) in samples.iter_mut().zip(noise) {
+                    *sample += noise * scale;
+                }
+            }
+        }
+
+        samples
+    }
+
+    /// Write the signal to `out` as headerless 16-bit little-endian mono PCM.
+    pub fn write_pcm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let bytes: Vec<u8> = self
+            .samples()
+            .into_iter()
+            .flat_map(|sample| {
+                let amplitude = i16::MAX as f32;
+                ((sample.clamp(-1.0, 1.0) * amplitude) as i16).to_le_bytes()
+            })
+            .collect();
+        out.write_all(&bytes)?;
+        out.flush()
+    }
+
+    /// Write the signal to `path` as a 16-bit mono WAV file.
+    pub fn write_wav<P: AsRef<Path>>(&self, path: P) -> crate::Result<()> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        for sample in self.samples() {
+            let amplitude = i16::MAX as f32;
+            writer.write_sample((sample.clamp(-1.0, 1.0) * amplitude) as i16)?;
+        }
+        Ok(writer.finalize()?)
+    }
+}
+
+/// `len` samples of `noise`, at some arbitrary level.
+fn noise_samples(noise: Noise, len: usize, seed: u64) -> Vec<f32> {
+    let mut rng = XorShift::new(seed);
+    let white = (0..len).map(move |_| rng.next_f32());
+
+    match noise {
+        Noise::White => white.collect(),
+        // Paul Kellet's economy filter, accurate to within 0.5 dB above 1/1000 of the sample rate.
+        Noise::Pink => {
+            let mut b = [0.0f32; 3];
+            white
+                .map(|white| {
+                    b[0] = 0.99765 * b[0] + white * 0.0990460;
+                    b[1] = 0.96300 * b[1] + white * 0.2965164;
+                    b[2] = 0.57000 * b[2] + white * 1.0526913;
+                    b[0] + b[1] + b[2] + white * 0.1848
+                })
+                .collect()
+        }
+    }
+}
+
+/// A xorshift64* generator: not for cryptography, but plenty for noise.
+#[derive(Debug, Clone)]
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    /// A generator seeded with `seed`, or 1 if that's 0 (which would only ever give 0).
+    pub(crate) fn new(seed: u64) -> Self {
+        XorShift(seed.max(1))
+    }
+
+    /// A uniformly distributed sample in `-1.0..1.0`.
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let bits = self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 40;
+        bits as f32 / (1u64 << 23) as f32 - 1.0
+    }
+}
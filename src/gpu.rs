@@ -0,0 +1,499 @@
+//! Autocorrelation on a GPU, for analyzing large archives: the autocorrelations of a whole batch
+//! of chunks, at every lag the monotonic detector searches, are taken at once by an OpenCL kernel
+//! with a work item per chunk and lag, leaving only finding their maxima to the CPU. Only built
+//! with the `gpu` feature.
+//!
+//! OpenCL is loaded when a GPU is first asked for, rather than linked, so a build with the feature
+//! runs anywhere: where there's no OpenCL library or no GPU, or the kernel fails, chunks are
+//! detected on the CPU as they are without it. Products are summed in double precision where the
+//! GPU supports it, so the results only differ from the CPU's by rounding, and in single precision
+//! otherwise.
+
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+use std::ptr;
+use std::sync::{Mutex, OnceLock};
+
+use crate::inharmonicity::Inharmonicity;
+use crate::logging::info;
+use crate::{DetectedPitch, PitchDetector};
+
+/// Where the OpenCL library may be, in the order tried.
+#[cfg(target_os = "macos")]
+const LIBRARIES: &[&str] = &["/System/Library/Frameworks/OpenCL.framework/OpenCL"];
+#[cfg(not(target_os = "macos"))]
+const LIBRARIES: &[&str] = &["libOpenCL.so.1", "libOpenCL.so"];
+
+/// The kernel: `acf[chunk][lag - 1]` is the sum of the products of the chunk's samples and those
+/// `lag` later, like `simd::dot`.
+const KERNEL: &str = "
+#ifdef DOUBLE
+#pragma OPENCL EXTENSION cl_khr_fp64 : enable
+typedef double sum_t;
+#else
+typedef float sum_t;
+#endif
+
+__kernel void autocorrelate(__global const float *chunks, uint len, uint lags,
+                            __global sum_t *acf) {
+    size_t chunk = get_global_id(0);
+    uint lag = get_global_id(1) + 1;
+    __global const float *x = chunks + chunk * len;
+    sum_t sum = 0;
+    for (uint i = 0; i + lag < len; i++) {
+        sum += (sum_t)x[i] * (sum_t)x[i + lag];
+    }
+    acf[chunk * lags + lag - 1] = sum;
+}
+\0";
+
+const CL_SUCCESS: c_int = 0;
+const CL_DEVICE_TYPE_GPU: u64 = 1 << 2;
+const CL_DEVICE_NAME: c_uint = 0x102B;
+const CL_DEVICE_EXTENSIONS: c_uint = 0x1030;
+const CL_MEM_WRITE_ONLY: u64 = 1 << 1;
+const CL_MEM_READ_ONLY: u64 = 1 << 2;
+const CL_MEM_COPY_HOST_PTR: u64 = 1 << 5;
+const CL_TRUE: c_uint = 1;
+
+/// A platform, device, context, queue, program, kernel or buffer.
+type Handle = *mut c_void;
+
+/// The OpenCL functions used, as loaded from the library.
+struct Api {
+    get_platform_ids: unsafe extern "system" fn(c_uint, *mut Handle, *mut c_uint) -> c_int,
+    get_device_ids:
+        unsafe extern "system" fn(Handle, u64, c_uint, *mut Handle, *mut c_uint) -> c_int,
+    get_device_info:
+        unsafe extern "system" fn(Handle, c_uint, usize, *mut c_void, *mut usize) -> c_int,
+    create_context: unsafe extern "system" fn(
+        *const isize,
+        c_uint,
+        *const Handle,
+        *const c_void,
+        *mut c_void,
+        *mut c_int,
+    ) -> Handle,
+    create_command_queue: unsafe extern "system" fn(Handle, Handle, u64, *mut c_int) -> Handle,
+    create_program_with_source: unsafe extern "system" fn(
+        Handle,
+        c_uint,
+        *const *const c_char,
+        *const usize,
+        *mut c_int,
+    ) -> Handle,
+    build_program: unsafe extern "system" fn(
+        Handle,
+        c_uint,
+        *const Handle,
+        *const c_char,
+        *const c_void,
+        *mut c_void,
+    ) -> c_int,
+    create_kernel: unsafe extern "system" fn(Handle, *const c_char, *mut c_int) -> Handle,
+    create_buffer: unsafe extern "system" fn(Handle, u64, usize, *mut c_void, *mut c_int) -> Handle,
+    set_kernel_arg: unsafe extern "system" fn(Handle, c_uint, usize, *const c_void) -> c_int,
+    enqueue_nd_range_kernel: unsafe extern "system" fn(
+        Handle,
+        Handle,
+        c_uint,
+        *const usize,
+        *const usize,
+        *const usize,
+        c_uint,
+        *const Handle,
+        *mut Handle,
+    ) -> c_int,
+    enqueue_read_buffer: unsafe extern "system" fn(
+        Handle,
+        Handle,
+        c_uint,
+        usize,
+        usize,
+        *mut c_void,
+        c_uint,
+        *const Handle,
+        *mut Handle,
+    ) -> c_int,
+    release_mem_object: unsafe extern "system" fn(Handle) -> c_int,
+    release_kernel: unsafe extern "system" fn(Handle) -> c_int,
+    release_program: unsafe extern "system" fn(Handle) -> c_int,
+    release_command_queue: unsafe extern "system" fn(Handle) -> c_int,
+    release_context: unsafe extern "system" fn(Handle) -> c_int,
+}
+
+#[cfg(unix)]
+mod dl {
+    use std::ffi::CString;
+    use std::os::raw::{c_char, c_int, c_void};
+
+    const RTLD_NOW: c_int = 2;
+
+    extern "C" {
+        fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+        fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    }
+
+    /// The library `name`, if it can be loaded. It's never unloaded.
+    pub fn open(name: &str) -> Option<*mut c_void> {
+        let name = CString::new(name).ok()?;
+        // Safe because the name is a C string.
+        let library = unsafe { dlopen(name.as_ptr(), RTLD_NOW) };
+        Some(library).filter(|library| !library.is_null())
+    }
+
+    /// The address of `symbol` in `library`, if it has it.
+    pub fn symbol(library: *mut c_void, symbol: &str) -> Option<*mut c_void> {
+        let symbol = CString::new(symbol).ok()?;
+        // Safe because the library is loaded and the symbol is a C string.
+        let address = unsafe { dlsym(library, symbol.as_ptr()) };
+        Some(address).filter(|address| !address.is_null())
+    }
+}
+
+#[cfg(not(unix))]
+mod dl {
+    use std::os::raw::c_void;
+
+    pub fn open(_: &str) -> Option<*mut c_void> {
+        None
+    }
+
+    pub fn symbol(_: *mut c_void, _: &str) -> Option<*mut c_void> {
+        None
+    }
+}
+
+impl Api {
+    /// The functions of the first OpenCL library in `LIBRARIES` that can be loaded.
+    fn load() -> Option<Api> {
+        let library = LIBRARIES.iter().find_map(|name| dl::open(name))?;
+        /// The function `name`, which must have the type `F`.
+        unsafe fn function<F: Copy>(library: *mut c_void, name: &str) -> Option<F> {
+            assert_eq!(mem::size_of::<F>(), mem::size_of::<*mut c_void>());
+            dl::symbol(library, name).map(|address| mem::transmute_copy(&address))
+        }
+        // Safe because each function has the type the OpenCL headers declare for it.
+        unsafe {
+            Some(Api {
+                get_platform_ids: function(library, "clGetPlatformIDs")?,
+                get_device_ids: function(library, "clGetDeviceIDs")?,
+                get_device_info: function(library, "clGetDeviceInfo")?,
+                create_context: function(library, "clCreateContext")?,
+                create_command_queue: function(library, "clCreateCommandQueue")?,
+                create_program_with_source: function(library, "clCreateProgramWithSource")?,
+                build_program: function(library, "clBuildProgram")?,
+                create_kernel: function(library, "clCreateKernel")?,
+                create_buffer: function(library, "clCreateBuffer")?,
+                set_kernel_arg: function(library, "clSetKernelArg")?,
+                enqueue_nd_range_kernel: function(library, "clEnqueueNDRangeKernel")?,
+                enqueue_read_buffer: function(library, "clEnqueueReadBuffer")?,
+                release_mem_object: function(library, "clReleaseMemObject")?,
+                release_kernel: function(library, "clReleaseKernel")?,
+                release_program: function(library, "clReleaseProgram")?,
+                release_command_queue: function(library, "clReleaseCommandQueue")?,
+                release_context: function(library, "clReleaseContext")?,
+            })
+        }
+    }
+}
+
+/// `Some(())` if an OpenCL call returned `status` for success.
+fn check(status: c_int) -> Option<()> {
+    Some(()).filter(|_| status == CL_SUCCESS)
+}
+
+/// A GPU with the kernel built for it.
+pub struct Gpu {
+    api: Api,
+    context: Handle,
+    queue: Handle,
+    program: Handle,
+    /// The kernel, whose arguments are set for each batch, so batches go one at a time.
+    kernel: Mutex<Handle>,
+    /// Whether the GPU sums in double precision.
+    double: bool,
+    name: String,
+}
+
+// Safe because OpenCL objects other than kernels can be used from any thread, and the kernel is
+// only used with its lock held.
+unsafe impl Send for Gpu {}
+unsafe impl Sync for Gpu {}
+
+impl Gpu {
+    /// The first GPU OpenCL finds, with the kernel built for it, or `None` if there's no OpenCL
+    /// library or GPU, or the kernel can't be built.
+    pub fn open() -> Option<Gpu> {
+        let gpu = Gpu::try_open();
+        match &gpu {
+            Some(gpu) => info!("gpu: device={:?} double={}", gpu.name, gpu.double),
+            None => info!("gpu: none, detecting on the CPU"),
+        }
+        gpu
+    }
+
+    fn try_open() -> Option<Gpu> {
+        let api = Api::load()?;
+        // Safe because the arguments are as OpenCL documents, and every object created is
+        // released by `drop`, once it's in `gpu`.
+        unsafe {
+            let device = gpu_device(&api)?;
+            let name = device_string(&api, device, CL_DEVICE_NAME)?;
+            let double = device_string(&api, device, CL_DEVICE_EXTENSIONS)?
+                .split_whitespace()
+                .any(|extension| extension == "cl_khr_fp64");
+
+            let mut status = CL_SUCCESS;
+            let context = (api.create_context)(
+                ptr::null(),
+                1,
+                &device,
+                ptr::null(),
+                ptr::null_mut(),
+                &mut status,
+            );
+            check(status)?;
+            let mut gpu = Gpu {
+                api,
+                context,
+                queue: ptr::null_mut(),
+                program: ptr::null_mut(),
+                kernel: Mutex::new(ptr::null_mut()),
+                double,
+                name,
+            };
+            gpu.queue = (gpu.api.create_command_queue)(context, device, 0, &mut status);
+            check(status)?;
+
+            let source = KERNEL.as_ptr() as *const c_char;
+            gpu.program =
+                (gpu.api.create_program_with_source)(context, 1, &source, ptr::null(), &mut status);
+            check(status)?;
+            let options = CString::new(if double { "-D DOUBLE" } else { "" }).ok()?;
+            check((gpu.api.build_program)(
+                gpu.program,
+                1,
+                &device,
+                options.as_ptr(),
+                ptr::null(),
+                ptr::null_mut(),
+            ))?;
+            let kernel = (gpu.api.create_kernel)(
+                gpu.program,
+                b"autocorrelate\0".as_ptr() as *const c_char,
+                &mut status,
+            );
+            check(status)?;
+            *gpu.kernel.get_mut().unwrap() = kernel;
+            Some(gpu)
+        }
+    }
+
+    /// The GPU, opened the first time it's asked for, or `None` if there's none to use.
+    pub fn shared() -> Option<&'static Gpu> {
+        static GPU: OnceLock<Option<Gpu>> = OnceLock::new();
+        GPU.get_or_init(Gpu::open).as_ref()
+    }
+
+    /// The name of the device.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The autocorrelation of each of `chunks`, which must all be the same length, at lags 1 to
+    /// `lags`, as `PitchDetector::detect_with_autocorrelation` takes it. Returns `None` if the
+    /// chunks aren't, or the GPU fails.
+    pub fn autocorrelations(&self, chunks: &[&[f32]], lags: usize) -> Option<Vec<Vec<f64>>> {
+        let len = chunks.first()?.len();
+        if lags == 0 || lags >= len || chunks.iter().any(|chunk| chunk.len() != len) {
+            return None;
+        }
+        let samples = chunks.concat();
+        let sums = chunks.len() * lags;
+        let width = if self.double {
+            mem::size_of::<f64>()
+        } else {
+            mem::size_of::<f32>()
+        };
+        let (len, lags_arg) = (c_uint::try_from(len).ok()?, c_uint::try_from(lags).ok()?);
+
+        let kernel = self.kernel.lock().unwrap();
+        // Safe because the buffers are the sizes the kernel reads and writes, and the host memory
+        // given to OpenCL outlives the calls that use it, the read being blocking.
+        let acf: Vec<f64> = unsafe {
+            let input = self.buffer(
+                CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR,
+                mem::size_of_val(samples.as_slice()),
+                samples.as_ptr() as *mut c_void,
+            )?;
+            let output = self.buffer(CL_MEM_WRITE_ONLY, sums * width, ptr::null_mut())?;
+            self.set_arg(*kernel, 0, &input.handle)?;
+            self.set_arg(*kernel, 1, &len)?;
+            self.set_arg(*kernel, 2, &lags_arg)?;
+            self.set_arg(*kernel, 3, &output.handle)?;
+            let global = [chunks.len(), lags];
+            check((self.api.enqueue_nd_range_kernel)(
+                self.queue,
+                *kernel,
+                2,
+                ptr::null(),
+                global.as_ptr(),
+                ptr::null(),
+                0,
+                ptr::null(),
+                ptr::null_mut(),
+            ))?;
+            if self.double {
+                let mut acf = vec![0.0f64; sums];
+                self.read(&output, acf.as_mut_ptr() as *mut c_void, sums * width)?;
+                acf
+            } else {
+                let mut acf = vec![0.0f32; sums];
+                self.read(&output, acf.as_mut_ptr() as *mut c_void, sums * width)?;
+                acf.into_iter().map(f64::from).collect()
+            }
+        };
+        Some(acf.chunks(lags).map(<[f64]>::to_vec).collect())
+    }
+
+    /// A buffer of `size` bytes on the GPU, created with `flags` from `host`.
+    unsafe fn buffer(&self, flags: u64, size: usize, host: *mut c_void) -> Option<Buffer<'_>> {
+        let mut status = CL_SUCCESS;
+        let handle = (self.api.create_buffer)(self.context, flags, size, host, &mut status);
+        check(status)?;
+        Some(Buffer { gpu: self, handle })
+    }
+
+    /// Set argument `index` of `kernel` to `value`.
+    unsafe fn set_arg<T>(&self, kernel: Handle, index: c_uint, value: &T) -> Option<()> {
+        check((self.api.set_kernel_arg)(
+            kernel,
+            index,
+            mem::size_of::<T>(),
+            value as *const T as *const c_void,
+        ))
+    }
+
+    /// Read the first `size` bytes of `buffer` into `host`, once the kernel has written them.
+    unsafe fn read(&self, buffer: &Buffer, host: *mut c_void, size: usize) -> Option<()> {
+        check((self.api.enqueue_read_buffer)(
+            self.queue,
+            buffer.handle,
+            CL_TRUE,
+            0,
+            size,
+            host,
+            0,
+            ptr::null(),
+            ptr::null_mut(),
+        ))
+    }
+}
+
+impl Drop for Gpu {
+    fn drop(&mut self) {
+        let kernel = *self.kernel.get_mut().unwrap();
+        // Safe because each object was created by this GPU, and nothing uses it after.
+        unsafe {
+            if !kernel.is_null() {
+                (self.api.release_kernel)(kernel);
+            }
+            if !self.program.is_null() {
+                (self.api.release_program)(self.program);
+            }
+            if !self.queue.is_null() {
+                (self.api.release_command_queue)(self.queue);
+            }
+            (self.api.release_context)(self.context);
+        }
+    }
+}
+
+/// A buffer on a GPU, released when dropped.
+struct Buffer<'a> {
+    gpu: &'a Gpu,
+    handle: Handle,
+}
+
+impl Drop for Buffer<'_> {
+    fn drop(&mut self) {
+        // Safe because the buffer was created by `gpu`, and nothing uses it after.
+        unsafe {
+            (self.gpu.api.release_mem_object)(self.handle);
+        }
+    }
+}
+
+/// The first GPU of the first platform that has one.
+unsafe fn gpu_device(api: &Api) -> Option<Handle> {
+    let mut count = 0;
+    check((api.get_platform_ids)(0, ptr::null_mut(), &mut count))?;
+    let mut platforms = vec![ptr::null_mut(); count as usize];
+    check((api.get_platform_ids)(
+        count,
+        platforms.as_mut_ptr(),
+        ptr::null_mut(),
+    ))?;
+    platforms.into_iter().find_map(|platform| {
+        let mut device = ptr::null_mut();
+        check((api.get_device_ids)(
+            platform,
+            CL_DEVICE_TYPE_GPU,
+            1,
+            &mut device,
+            ptr::null_mut(),
+        ))?;
+        Some(device).filter(|device| !device.is_null())
+    })
+}
+
+/// The string `param` says about `device`.
+unsafe fn device_string(api: &Api, device: Handle, param: c_uint) -> Option<String> {
+    let mut size = 0;
+    check((api.get_device_info)(
+        device,
+        param,
+        0,
+        ptr::null_mut(),
+        &mut size,
+    ))?;
+    let mut bytes = vec![0u8; size.max(1)];
+    check((api.get_device_info)(
+        device,
+        param,
+        bytes.len(),
+        bytes.as_mut_ptr() as *mut c_void,
+        ptr::null_mut(),
+    ))?;
+    let string = CStr::from_bytes_until_nul(&bytes).ok()?;
+    Some(string.to_string_lossy().into_owned())
+}
+
+/// Like `PitchDetector::detect_parallel_with_inharmonicity`, with the autocorrelations of
+/// `buffers` taken on `gpu`, all at once, if there is one, the detector `uses_autocorrelation`
+/// and the buffers are all the same length. Otherwise, or if the GPU fails, it's all done on the
+/// CPU.
+pub fn detect_parallel<B>(
+    detector: &PitchDetector,
+    gpu: Option<&Gpu>,
+    buffers: &[B],
+    threads: usize,
+) -> Vec<(DetectedPitch, Option<Inharmonicity>)>
+where
+    B: AsRef<[f32]> + Sync,
+{
+    let acfs = gpu
+        .filter(|_| detector.uses_autocorrelation())
+        .and_then(|gpu| {
+            let chunks: Vec<&[f32]> = buffers.iter().map(AsRef::as_ref).collect();
+            let lags = detector.monotonic_lags(chunks.first()?.len());
+            gpu.autocorrelations(&chunks, lags)
+        });
+    match acfs {
+        Some(acfs) => detector.detect_parallel_with_autocorrelations(buffers, &acfs, threads),
+        None => detector.detect_parallel_with_inharmonicity(buffers, threads),
+    }
+}
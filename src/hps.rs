@@ -0,0 +1,74 @@
+//! The Harmonic Product Spectrum pitch detector (Schroeder, 1968; Noll, 1969).
+//!
+//! The spectrum is multiplied by copies of itself downsampled by 2, 3, ..., so that the harmonics of
+//! the fundamental all line up on its bin and reinforce each other. This needs only one FFT, and
+//! copes with nearly pure tones such as whistles and flutes.
+
+use crate::spectrum;
+use crate::window::Window;
+
+/// The number of harmonics (including the fundamental) multiplied together.
+pub const HARMONICS: usize = 5;
+
+/// How many times longer than the chunk the FFT is, for finer frequency resolution.
+const ZERO_PADDING: usize = 4;
+
+/// Magnitudes are clamped to this fraction (-60 dB) of the peak, so that missing harmonics count
+/// the same everywhere instead of letting noise decide between candidates.
+const FLOOR: f64 = 1e-3;
+
+/// How much (as a log ratio) a peak may fall short of the highest one and still be picked for
+/// being higher in frequency. The subharmonics of a nearly pure tone all share its one strong
+/// partial, so they come out about as high as the tone itself.
+const TOLERANCE: f64 = std::f64::consts::LN_10;
+
+/// Estimate the fundamental frequency of `buffer`, sampled at `sample_rate` Hz, from `min_freq`
+/// up. Returns `None` if the buffer is too short to resolve `min_freq`.
+pub fn hps_freq(buffer: &[f32], sample_rate: usize, min_freq: f64) -> Option<f64> {
+    let len = buffer.len() * ZERO_PADDING;
+    let product = harmonic_product_spectrum(&Window::Hann.apply(buffer), len);
+
+    let lowest = ((min_freq * len as f64 / sample_rate as f64).ceil() as usize).max(1);
+    let peaks: Vec<usize> = (lowest..product.len().saturating_sub(1))
+        .filter(|bin| product[bin - 1] < product[*bin] && product[*bin] >= product[bin + 1])
+        .collect();
+    let highest = peaks
+        .iter()
+        .map(|bin| product[*bin])
+        .fold(f64::NEG_INFINITY, f64::max);
+    let bin = *peaks
+        .iter()
+        .rev()
+        .find(|bin| product[**bin] >= highest - TOLERANCE)?;
+
+    Some(parabolic_interpolation(&product, bin) * sample_rate as f64 / len as f64)
+}
+
+/// The log of the harmonic product spectrum of `buffer` zero-padded to `len` samples, for the bins
+/// whose `HARMONICS`th harmonic is below the Nyquist frequency.
+pub fn harmonic_product_spectrum(buffer: &[f32], len: usize) -> Vec<f64> {
+    let magnitudes = spectrum::magnitudes(buffer, len);
+    let peak = magnitudes.iter().cloned().fold(0.0, f64::max);
+    let log_magnitudes: Vec<f64> = magnitudes
+        .iter()
+        .map(|x| (x / peak).max(FLOOR).ln())
+        .collect();
+    let bins = len / 2 / HARMONICS;
+
+    (0..bins)
+        .map(|bin| {
+            (1..=HARMONICS)
+                .map(|harmonic| log_magnitudes[bin * harmonic])
+                .sum()
+        })
+        .collect()
+}
+
+/// Refine `bin` to a fractional one using the parabola through it and its neighbours.
+fn parabolic_interpolation(product: &[f64], bin: usize) -> f64 {
+    if bin < 1 || bin + 1 >= product.len() {
+        return bin as f64;
+    }
+
+    bin as f64 + crate::parabolic_offset(product[bin - 1], product[bin], product[bin + 1])
+}
@@ -0,0 +1,161 @@
+//! A minimal HTTP/1.1 server exposing `POST /analyze`, so the detector can run as a service
+//! behind a web frontend. Only built with the `server` feature.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::Result;
+
+/// The largest upload accepted, in bytes.
+pub const MAX_BODY: usize = 100 * 1024 * 1024;
+
+/// How long a client may leave the server waiting on its request, or on taking the response,
+/// before the connection is dropped: requests are served one at a time, so an idle client would
+/// stall everyone else.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listen on `addr` and answer `POST /analyze` requests, one at a time, with what `analyze`
+/// makes of the uploaded WAV file: JSON, or an error to report in a 400 response. The file may be
+/// the whole request body, or the first file of a `multipart/form-data` form.
+pub fn serve(
+    addr: impl ToSocketAddrs,
+    analyze: impl Fn(&[u8]) -> Result<String>,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        // A broken connection only loses that request.
+        let _ = stream.and_then(|stream| handle(stream, &analyze));
+    }
+    Ok(())
+}
+
+/// A response: its status line and body.
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.into() + "\n",
+        }
+    }
+}
+
+fn handle(mut stream: TcpStream, analyze: &impl Fn(&[u8]) -> Result<String>) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let response = respond(&stream, analyze)?;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.content_type,
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+fn respond(stream: &TcpStream, analyze: &impl Fn(&[u8]) -> Result<String>) -> io::Result<Response> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+    let mut content_length = None;
+    let mut content_type = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            let (name, value) = (name.trim(), value.trim());
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<usize>().ok();
+            } else if name.eq_ignore_ascii_case("content-type") {
+                content_type = value.to_string();
+            }
+        }
+    }
+
+    if path.split('?').next() != Some("/analyze") {
+        return Ok(Response::error("404 Not Found", "not found"));
+    }
+    if method != "POST" {
+        return Ok(Response::error(
+            "405 Method Not Allowed",
+            "use POST to upload a WAV file",
+        ));
+    }
+    let content_length = match content_length {
+        Some(len) if len > MAX_BODY => {
+            return Ok(Response::error("413 Payload Too Large", "upload too large"))
+        }
+        Some(len) => len,
+        None => return Ok(Response::error("411 Length Required", "no Content-Length")),
+    };
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let upload = match boundary(&content_type) {
+        Some(boundary) => match first_part(&body, &boundary) {
+            Some(part) => part,
+            None => return Ok(Response::error("400 Bad Request", "no file in the form")),
+        },
+        None => &body,
+    };
+
+    Ok(match analyze(upload) {
+        Ok(json) => Response {
+            status: "200 OK",
+            content_type: "application/json",
+            body: json,
+        },
+        Err(e) => Response::error("400 Bad Request", e.to_string()),
+    })
+}
+
+/// The boundary of a `multipart/form-data` body with `content_type`, if it is one.
+fn boundary(content_type: &str) -> Option<String> {
+    let mut params = content_type.split(';').map(str::trim);
+    if !params.next()?.eq_ignore_ascii_case("multipart/form-data") {
+        return None;
+    }
+    params
+        .filter_map(|param| param.strip_prefix("boundary="))
+        .map(|boundary| boundary.trim_matches('"').to_string())
+        .next()
+}
+
+/// The contents of the first part of a multipart `body`.
+fn first_part<'a>(body: &'a [u8], boundary: &str) -> Option<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let start = find(body, delimiter.as_bytes())? + delimiter.len();
+    let headers_end = start + find(&body[start..], b"\r\n\r\n")? + 4;
+    let end = headers_end
+        + find(
+            &body[headers_end..],
+            format!("\r\n{}", delimiter).as_bytes(),
+        )?;
+    Some(&body[headers_end..end])
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
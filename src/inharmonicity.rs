@@ -0,0 +1,180 @@
+//! Inharmonicity, for pianos and other stiff strings, whose partials are not quite harmonics: the
+//! stiffer (thicker and shorter) the string, the sharper each partial is of a whole multiple of
+//! the fundamental. Partial `n` of a string with inharmonicity coefficient `B` is at
+//! `n f0 sqrt(1 + B n^2)`, where `f0` is the fundamental of the same string were it perfectly
+//! flexible.
+//!
+//! A detector that assumes harmonics settles on a period that suits the stretched partials as a
+//! whole, so it comes out sharp of the fundamental, by most in the bass and the top octaves. `fit`
+//! finds the partials in the spectrum instead and fits `B` and `f0` to them, which gives both the
+//! fundamental as it sounds and the idealized one.
+
+use crate::shs;
+use crate::spectrum;
+use crate::window::Window;
+use crate::{parabolic_offset, DetectedPitch};
+
+/// The most partials (including the fundamental) looked for.
+pub const PARTIALS: usize = 16;
+
+/// How many partials must be found to fit.
+pub const MIN_PARTIALS: usize = 3;
+
+/// How far from where the fit so far puts it a partial is looked for, as a fraction of the
+/// fundamental.
+const SEARCH: f64 = 0.25;
+
+/// Peaks weaker than this fraction (-40 dB) of the strongest in the spectrum aren't partials.
+const FLOOR: f64 = 0.01;
+
+/// How many times longer than the chunk the FFT is, for finer frequency resolution.
+const ZERO_PADDING: usize = 4;
+
+/// The partials of a chunk, fit to those of a stiff string.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fit {
+    /// `B`, from 0 for harmonic partials up; about 0.0001 to 0.001 in the middle of a piano.
+    pub coefficient: f64,
+    /// `f0`, in Hz.
+    pub ideal_freq: f64,
+    /// How many partials were found and fit.
+    pub partials: usize,
+}
+
+impl Fit {
+    /// The frequency of partial `n` (from 1, the fundamental), in Hz.
+    pub fn partial(&self, n: usize) -> f64 {
+        let n = n as f64;
+        n * self.ideal_freq * (1.0 + self.coefficient * n * n).sqrt()
+    }
+
+    /// The frequency of the fundamental as it sounds, in Hz.
+    pub fn freq(&self) -> f64 {
+        self.partial(1)
+    }
+}
+
+/// The pitch of a chunk of an inharmonic instrument, idealized as `Fit` says.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Inharmonicity {
+    /// The inharmonicity coefficient `B`.
+    pub coefficient: f64,
+    /// `f0`, matched to a pitch.
+    pub ideal: DetectedPitch,
+}
+
+/// Fit the partials of `buffer`, sampled at `sample_rate` Hz, to those of a stiff string with a
+/// fundamental from `min_freq` Hz up. Returns `None` if fewer than `MIN_PARTIALS` partials stand
+/// out of the spectrum below the Nyquist frequency.
+///
+/// The stretched partials easily throw an estimate made assuming harmonics off by more than a
+/// partial, so the fit is started from `freq`, the estimate, and from the strongest peak of the
+/// spectrum taken as each partial in turn; the one whose partials add up to the most, the higher
+/// ones counting less as in `shs::score`, is kept.
+pub fn fit(buffer: &[f32], sample_rate: usize, min_freq: f64, freq: f64) -> Option<Fit> {
+    if buffer.is_empty() || !freq.is_finite() || freq <= 0.0 {
+        return None;
+    }
+    let len = buffer.len() * ZERO_PADDING;
+    let magnitudes = spectrum::magnitudes(&Window::Hann.apply(buffer), len);
+    let magnitudes = &magnitudes[..len / 2];
+    let bin_width = sample_rate as f64 / len as f64;
+
+    let lowest = ((min_freq / bin_width).ceil() as usize).clamp(1, magnitudes.len());
+    let strongest = (lowest..magnitudes.len().saturating_sub(1))
+        .max_by(|a, b| magnitudes[*a].partial_cmp(&magnitudes[*b]).unwrap())?;
+    let peak = interpolate(magnitudes, strongest) * bin_width;
+    let floor = FLOOR * magnitudes[strongest];
+
+    std::iter::once(freq)
+        .chain((1..=PARTIALS).map(|n| peak / n as f64))
+        .filter(|seed| *seed >= min_freq)
+        .filter_map(|seed| fit_from(magnitudes, bin_width, floor, seed))
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map(|(fit, _)| fit)
+}
+
+/// The fit of the partials in `magnitudes` (a spectrum with bins `bin_width` Hz apart, and
+/// partials no weaker than `floor`) found starting from a fundamental of `seed` Hz, and their sum.
+fn fit_from(magnitudes: &[f64], bin_width: f64, floor: f64, seed: f64) -> Option<(Fit, f64)> {
+    // Each partial found is looked for from where the fit to those before it puts it.
+    let mut fit = Fit {
+        coefficient: 0.0,
+        ideal_freq: seed,
+        partials: 0,
+    };
+    let mut found = vec![];
+    let mut sum = 0.0;
+    for n in 1..=PARTIALS {
+        let predicted = fit.partial(n);
+        // Bins with a neighbour either side, to tell peaks by.
+        let last = magnitudes.len().saturating_sub(2);
+        let lowest = ((predicted - SEARCH * fit.ideal_freq) / bin_width)
+            .ceil()
+            .max(1.0) as usize;
+        if lowest > last {
+            break;
+        }
+        let highest = (((predicted + SEARCH * fit.ideal_freq) / bin_width) as usize).min(last);
+        // The range is narrower than a bin, between two, in short chunks of low notes.
+        if lowest > highest {
+            continue;
+        }
+        let bin = (lowest..=highest)
+            .max_by(|a, b| magnitudes[*a].partial_cmp(&magnitudes[*b]).unwrap())
+            .unwrap();
+        let (prev, cur, next) = (magnitudes[bin - 1], magnitudes[bin], magnitudes[bin + 1]);
+        // A bin at the edge of the range is the slope of something else, not a peak.
+        if cur < floor || prev > cur || next > cur {
+            continue;
+        }
+        found.push((n, interpolate(magnitudes, bin) * bin_width));
+        sum += shs::COMPRESSION.powi(n as i32 - 1) * cur;
+        if found.len() >= MIN_PARTIALS {
+            fit = least_squares(&found).unwrap_or(fit);
+        }
+    }
+
+    if found.len() < MIN_PARTIALS {
+        return None;
+    }
+    Some((least_squares(&found)?, sum))
+}
+
+/// `bin`, refined to a fractional one using the parabola through it and its neighbours.
+fn interpolate(magnitudes: &[f64], bin: usize) -> f64 {
+    let (prev, cur, next) = (magnitudes[bin - 1], magnitudes[bin], magnitudes[bin + 1]);
+    bin as f64 + parabolic_offset(prev, cur, next)
+}
+
+/// The string best fitting the frequencies of `partials`, each numbered from 1: `(f_n / n)^2` is
+/// `f0^2 + f0^2 B n^2`, a line in `n^2`, so that's fit by least squares. `B` is clamped to 0, as
+/// no string is more flexible than perfectly so.
+fn least_squares(partials: &[(usize, f64)]) -> Option<Fit> {
+    let count = partials.len() as f64;
+    let points: Vec<(f64, f64)> = partials
+        .iter()
+        .map(|&(n, freq)| ((n * n) as f64, (freq / n as f64).powi(2)))
+        .collect();
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = (covariance / variance).max(0.0);
+    let intercept = mean_y - slope * mean_x;
+    if intercept <= 0.0 {
+        return None;
+    }
+    Some(Fit {
+        coefficient: slope / intercept,
+        ideal_freq: intercept.sqrt(),
+        partials: partials.len(),
+    })
+}
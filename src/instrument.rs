@@ -0,0 +1,91 @@
+//! Presets of the frequency range to detect for common instruments and voices, so that a narrower
+//! range than the default can be asked for by name. A narrower range rejects more octave errors
+//! and noise, and a higher lowest frequency makes for shorter chunks.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// An instrument, or voice, with a preset range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instrument {
+    /// Any singing voice, bass to soprano.
+    Voice,
+    /// A six-string guitar in standard tuning.
+    Guitar,
+    /// A four-string bass guitar, or double bass.
+    Bass,
+    Violin,
+    Cello,
+    Flute,
+    Piano,
+    Whistle,
+}
+
+/// Every instrument with a preset, in the order they're listed in.
+pub const INSTRUMENTS: [Instrument; 8] = [
+    Instrument::Voice,
+    Instrument::Guitar,
+    Instrument::Bass,
+    Instrument::Violin,
+    Instrument::Cello,
+    Instrument::Flute,
+    Instrument::Piano,
+    Instrument::Whistle,
+];
+
+impl Instrument {
+    /// The lowest and highest frequencies the instrument plays, in Hz, with some room below the
+    /// lowest note and above the highest for tuning and vibrato.
+    pub fn range(self) -> (usize, usize) {
+        match self {
+            // E2 to C6.
+            Instrument::Voice => (75, 1100),
+            // E2 to E6, at the 24th fret.
+            Instrument::Guitar => (75, 1400),
+            // E1 to G4.
+            Instrument::Bass => (38, 420),
+            // G3 to A7.
+            Instrument::Violin => (185, 3700),
+            // C2 to C6.
+            Instrument::Cello => (60, 1100),
+            // C4 to C7.
+            Instrument::Flute => (250, 2200),
+            // A0 to C8.
+            Instrument::Piano => (26, 4400),
+            Instrument::Whistle => (500, 4000),
+        }
+    }
+
+    /// Whether the instrument's strings are stiff enough for their partials to be noticeably
+    /// sharp of harmonics, as `inharmonicity` models.
+    pub fn is_inharmonic(self) -> bool {
+        self == Instrument::Piano
+    }
+}
+
+impl FromStr for Instrument {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        INSTRUMENTS
+            .iter()
+            .copied()
+            .find(|instrument| instrument.to_string() == s)
+            .ok_or_else(|| format!("unknown instrument: {}", s))
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Instrument::Voice => "voice",
+            Instrument::Guitar => "guitar",
+            Instrument::Bass => "bass",
+            Instrument::Violin => "violin",
+            Instrument::Cello => "cello",
+            Instrument::Flute => "flute",
+            Instrument::Piano => "piano",
+            Instrument::Whistle => "whistle",
+        })
+    }
+}
@@ -0,0 +1,48 @@
+//! Stopping cleanly on Ctrl-C. Once `catch` is called, the first SIGINT only marks the process
+//! as interrupted, for loops to notice and wind down, finishing what they were writing; a second
+//! exits at once, in case one doesn't. Elsewhere than on Unix, `catch` does nothing, and Ctrl-C
+//! stops the process as usual.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The exit status of a process stopped by SIGINT, by the shell's convention.
+pub const EXIT_STATUS: i32 = 130;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Catch Ctrl-C from now on, rather than being stopped by it.
+pub fn catch() {
+    #[cfg(unix)]
+    unix::catch();
+}
+
+/// Whether Ctrl-C has been pressed since `catch` was called.
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::os::raw::c_int;
+    use std::sync::atomic::Ordering;
+
+    use super::{EXIT_STATUS, INTERRUPTED};
+
+    const SIGINT: c_int = 2;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+        fn _exit(status: c_int) -> !;
+    }
+
+    pub fn catch() {
+        unsafe { signal(SIGINT, handle) };
+    }
+
+    /// Only does what's safe in a signal handler: an atomic swap, and `_exit`.
+    extern "C" fn handle(_: c_int) {
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { _exit(EXIT_STATUS) };
+        }
+    }
+}
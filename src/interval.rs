@@ -0,0 +1,110 @@
+//! Naming the melodic intervals between consecutive notes, for ear training and solfège tools.
+
+use std::fmt;
+
+use crate::notes::NoteEvent;
+use crate::Pitch;
+
+/// The names of the intervals up to two octaves, and their short names, by size in semitones.
+/// Augmented fourths and fifths are both called tritones, since only the size is known.
+const NAMES: [(&str, &str); 25] = [
+    ("unison", "P1"),
+    ("minor second", "m2"),
+    ("major second", "M2"),
+    ("minor third", "m3"),
+    ("major third", "M3"),
+    ("perfect fourth", "P4"),
+    ("tritone", "TT"),
+    ("perfect fifth", "P5"),
+    ("minor sixth", "m6"),
+    ("major sixth", "M6"),
+    ("minor seventh", "m7"),
+    ("major seventh", "M7"),
+    ("octave", "P8"),
+    ("minor ninth", "m9"),
+    ("major ninth", "M9"),
+    ("minor tenth", "m10"),
+    ("major tenth", "M10"),
+    ("perfect eleventh", "P11"),
+    ("augmented eleventh", "A11"),
+    ("perfect twelfth", "P12"),
+    ("minor thirteenth", "m13"),
+    ("major thirteenth", "M13"),
+    ("minor fourteenth", "m14"),
+    ("major fourteenth", "M14"),
+    ("two octaves", "P15"),
+];
+
+/// The interval from one pitch to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    /// The size in semitones, negative if the second pitch is lower.
+    pub semitones: i32,
+}
+
+impl Interval {
+    pub fn between(from: Pitch, to: Pitch) -> Self {
+        Interval {
+            semitones: to.midi() - from.midi(),
+        }
+    }
+
+    /// The name of the interval regardless of direction, such as `minor third`.
+    pub fn name(self) -> String {
+        match NAMES.get(self.semitones.unsigned_abs() as usize) {
+            Some((name, _)) => name.to_string(),
+            None => format!("{} semitones", self.semitones.abs()),
+        }
+    }
+
+    /// The short name regardless of direction, such as `m3`.
+    pub fn short_name(self) -> String {
+        match NAMES.get(self.semitones.unsigned_abs() as usize) {
+            Some((_, short)) => short.to_string(),
+            None => format!("{}st", self.semitones.abs()),
+        }
+    }
+
+    /// `up` or `down`, or `None` for a unison.
+    pub fn direction(self) -> Option<&'static str> {
+        match self.semitones {
+            0 => None,
+            s if s > 0 => Some("up"),
+            _ => Some("down"),
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    /// Write the name and direction, such as `perfect fifth down`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        if let Some(direction) = self.direction() {
+            write!(f, " {}", direction)?;
+        }
+        Ok(())
+    }
+}
+
+/// The interval from one note to the next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MelodicInterval {
+    /// When the second note starts, in seconds.
+    pub time: f64,
+    pub from: Pitch,
+    pub to: Pitch,
+    pub interval: Interval,
+}
+
+/// The intervals between each of `notes`, which must be in order, and the next, rests or not.
+pub fn melodic_intervals(notes: &[NoteEvent]) -> Vec<MelodicInterval> {
+    notes
+        .windows(2)
+        .map(|pair| MelodicInterval {
+            time: pair[1].start,
+            from: pair[0].pitch,
+            to: pair[1].pitch,
+            interval: Interval::between(pair[0].pitch, pair[1].pitch),
+        })
+        .collect()
+}
@@ -0,0 +1,375 @@
+//! Live input from a JACK server, so that any signal a JACK graph carries can be routed into the
+//! detector. Only built with the `jack` feature, which links against `libjack`.
+//!
+//! The client registers one input port, which JACK mixes whatever is connected to it into, and
+//! reads it as s16le mono PCM like any other live input. The process callback, on JACK's real-time
+//! thread, only pushes each block into a lock-free ring (`spsc`), which the analysis thread pops
+//! from; it never allocates, locks or waits. Any frames missing between blocks (from an xrun, or
+//! turned away because analysis fell behind and the ring filled up) are pushed as silence once
+//! there's room, so that timestamps stay in step with the server's clock to the sample.
+
+use std::ffi::{CStr, CString};
+use std::io::{self, Read};
+use std::os::raw::{c_char, c_int, c_ulong, c_void};
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::interrupt;
+use crate::logging::info;
+use crate::spsc::{self, Consumer, Producer};
+
+/// The name of the client, which its port names start with.
+pub const CLIENT_NAME: &str = "pitch-detector";
+
+/// The name of the input port.
+pub const PORT_NAME: &str = "in";
+
+/// How many seconds of input the ring holds for analysis before more is turned away.
+const RING_SECONDS: usize = 2;
+
+/// `next_time` before the first block.
+const NO_TIME: u64 = u64::MAX;
+
+/// The longest gap filled with silence, in frames; longer ones are from the frame clock wrapping
+/// or the server restarting, not dropped blocks.
+const MAX_GAP: u32 = 1 << 22;
+
+/// How long a read waits before looking for more samples in the ring, when it's empty.
+const POLL: Duration = Duration::from_millis(1);
+
+const JACK_DEFAULT_AUDIO_TYPE: &[u8] = b"32 bit float mono audio\0";
+const JACK_NO_START_SERVER: c_int = 0x01;
+const JACK_PORT_IS_INPUT: c_ulong = 0x1;
+const JACK_PORT_IS_OUTPUT: c_ulong = 0x2;
+
+#[repr(C)]
+struct Client {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct Port {
+    _private: [u8; 0],
+}
+
+#[link(name = "jack")]
+extern "C" {
+    fn jack_client_open(
+        client_name: *const c_char,
+        options: c_int,
+        status: *mut c_int,
+        ...
+    ) -> *mut Client;
+    fn jack_client_close(client: *mut Client) -> c_int;
+    fn jack_get_sample_rate(client: *mut Client) -> u32;
+    fn jack_port_register(
+        client: *mut Client,
+        port_name: *const c_char,
+        port_type: *const c_char,
+        flags: c_ulong,
+        buffer_size: c_ulong,
+    ) -> *mut Port;
+    fn jack_port_name(port: *const Port) -> *const c_char;
+    fn jack_port_get_buffer(port: *mut Port, frames: u32) -> *mut c_void;
+    fn jack_set_process_callback(
+        client: *mut Client,
+        callback: extern "C" fn(u32, *mut c_void) -> c_int,
+        arg: *mut c_void,
+    ) -> c_int;
+    fn jack_on_shutdown(
+        client: *mut Client,
+        callback: extern "C" fn(*mut c_void),
+        arg: *mut c_void,
+    );
+    fn jack_last_frame_time(client: *const Client) -> u32;
+    fn jack_activate(client: *mut Client) -> c_int;
+    fn jack_deactivate(client: *mut Client) -> c_int;
+    fn jack_connect(
+        client: *mut Client,
+        source_port: *const c_char,
+        destination_port: *const c_char,
+    ) -> c_int;
+    fn jack_set_buffer_size(client: *mut Client, frames: u32) -> c_int;
+    fn jack_get_ports(
+        client: *mut Client,
+        port_name_pattern: *const c_char,
+        type_name_pattern: *const c_char,
+        flags: c_ulong,
+    ) -> *mut *const c_char;
+    fn jack_free(ptr: *mut c_void);
+}
+
+/// What the process callback shares with the reader.
+struct Shared {
+    client: *mut Client,
+    port: *mut Port,
+    producer: Producer,
+    shut_down: AtomicBool,
+    /// The frame time the next block should start at, or `NO_TIME` before the first. Only the
+    /// callback uses it.
+    next_time: AtomicU64,
+    /// The frames of silence owed to the ring, for those missing or turned away.
+    owed: AtomicUsize,
+    /// The number of frames of silence pushed in their place.
+    filled: AtomicU64,
+}
+
+/// Reads the samples arriving at a JACK input port as s16le mono PCM.
+pub struct JackInput {
+    shared: *mut Shared,
+    consumer: Consumer,
+    port_name: String,
+    sample_rate: usize,
+    /// Samples popped from the ring, to be read as bytes.
+    samples: Vec<f32>,
+    /// The bytes of the samples popped not yet read.
+    pending: Vec<u8>,
+    offset: usize,
+    /// The number of frames filled with silence, as last logged.
+    logged_filled: u64,
+}
+
+impl JackInput {
+    /// Connect to the running JACK server as `client_name`, register an input port, and connect
+    /// each of `sources` (port names such as `system:capture_1`) to it.
+    pub fn open(client_name: &str, sources: &[String]) -> io::Result<Self> {
+        let port_name = c_string(PORT_NAME)?;
+        let client = open_client(client_name)?;
+        let port = unsafe {
+            jack_port_register(
+                client,
+                port_name.as_ptr(),
+                JACK_DEFAULT_AUDIO_TYPE.as_ptr() as *const c_char,
+                JACK_PORT_IS_INPUT,
+                0,
+            )
+        };
+        if port.is_null() {
+            unsafe { jack_client_close(client) };
+            return Err(io::Error::other("can't register a JACK input port"));
+        }
+
+        let sample_rate = unsafe { jack_get_sample_rate(client) } as usize;
+        let (producer, consumer) = spsc::ring(RING_SECONDS * sample_rate);
+        let shared = Box::into_raw(Box::new(Shared {
+            client,
+            port,
+            producer,
+            shut_down: AtomicBool::new(false),
+            next_time: AtomicU64::new(NO_TIME),
+            owed: AtomicUsize::new(0),
+            filled: AtomicU64::new(0),
+        }));
+        let mut input = JackInput {
+            shared,
+            consumer,
+            port_name: unsafe { CStr::from_ptr(jack_port_name(port)) }
+                .to_string_lossy()
+                .into_owned(),
+            sample_rate,
+            samples: vec![],
+            pending: vec![],
+            offset: 0,
+            logged_filled: 0,
+        };
+
+        let arg = shared as *mut c_void;
+        unsafe {
+            jack_on_shutdown(client, shut_down, arg);
+            if jack_set_process_callback(client, process, arg) != 0 || jack_activate(client) != 0 {
+                input.close();
+                return Err(io::Error::other("can't start the JACK client"));
+            }
+        }
+        for source in sources {
+            let source_name = c_string(source)?;
+            let port_name = c_string(&input.port_name)?;
+            let connected =
+                unsafe { jack_connect(client, source_name.as_ptr(), port_name.as_ptr()) };
+            if connected != 0 {
+                return Err(io::Error::other(format!(
+                    "can't connect {} to {}",
+                    source, input.port_name
+                )));
+            }
+        }
+        Ok(input)
+    }
+
+    /// Ask the server to process `frames` frames at a time, for less latency (or fewer xruns).
+    /// This sets it for every client of the server.
+    pub fn set_buffer_size(&self, frames: usize) -> io::Result<()> {
+        let client = unsafe { (*self.shared).client };
+        if unsafe { jack_set_buffer_size(client, frames as u32) } != 0 {
+            return Err(io::Error::other(format!(
+                "the JACK server can't process {} frames at a time",
+                frames
+            )));
+        }
+        Ok(())
+    }
+
+    /// The full name of the input port, for connecting sources to it.
+    pub fn port_name(&self) -> &str {
+        &self.port_name
+    }
+
+    /// The server's sample rate, which the input is sampled at.
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// The number of frames that went missing, from xruns or analysis falling behind, and were
+    /// filled with silence.
+    pub fn filled(&self) -> u64 {
+        unsafe { &*self.shared }.filled.load(Ordering::Relaxed)
+    }
+
+    /// Pop the samples waiting in the ring into `pending` as bytes, waiting for some to arrive.
+    /// Returns `false` once the server has shut down, or Ctrl-C was pressed.
+    fn refill(&mut self) -> bool {
+        while self.consumer.is_empty() {
+            if unsafe { &*self.shared }.shut_down.load(Ordering::Acquire)
+                || interrupt::interrupted()
+            {
+                return false;
+            }
+            thread::sleep(POLL);
+        }
+        let filled = self.filled();
+        if filled > self.logged_filled {
+            info!(
+                "jack: filled {} missing frames with silence",
+                filled - self.logged_filled
+            );
+            self.logged_filled = filled;
+        }
+
+        self.samples.resize(self.consumer.len(), 0.0);
+        let n = self.consumer.pop(&mut self.samples);
+        self.pending.resize(2 * n, 0);
+        for (sample, pair) in self.samples[..n]
+            .iter()
+            .zip(self.pending.chunks_exact_mut(2))
+        {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            pair.copy_from_slice(&sample.to_le_bytes());
+        }
+        self.offset = 0;
+        true
+    }
+
+    fn close(&mut self) {
+        if self.shared.is_null() {
+            return;
+        }
+        unsafe {
+            let client = (*self.shared).client;
+            jack_deactivate(client);
+            jack_client_close(client);
+            drop(Box::from_raw(self.shared));
+        }
+        self.shared = ptr::null_mut();
+    }
+}
+
+impl Read for JackInput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset >= self.pending.len() {
+            if !self.refill() {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.pending.len() - self.offset);
+        buf[..n].copy_from_slice(&self.pending[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+impl Drop for JackInput {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// JACK's process callback: push the port's latest block into the ring, after any silence owed
+/// for frames missing before it, turning away what there's no room for.
+extern "C" fn process(frames: u32, arg: *mut c_void) -> c_int {
+    let shared = unsafe { &*(arg as *const Shared) };
+    let samples = unsafe {
+        let buffer = jack_port_get_buffer(shared.port, frames) as *const f32;
+        slice::from_raw_parts(buffer, frames as usize)
+    };
+    let time = unsafe { jack_last_frame_time(shared.client) };
+    let expected = shared
+        .next_time
+        .swap(time.wrapping_add(frames) as u64, Ordering::Relaxed);
+
+    let mut owed = shared.owed.load(Ordering::Relaxed);
+    if expected != NO_TIME {
+        let gap = time.wrapping_sub(expected as u32);
+        if gap < MAX_GAP {
+            owed += gap as usize;
+        }
+    }
+    let filled = shared.producer.push_silence(owed);
+    shared.filled.fetch_add(filled as u64, Ordering::Relaxed);
+    owed -= filled;
+    // Until the silence is paid off, the block is owed too, to keep the samples in order.
+    owed += match owed {
+        0 => samples.len() - shared.producer.push(samples),
+        _ => samples.len(),
+    };
+    shared.owed.store(owed, Ordering::Relaxed);
+    0
+}
+
+extern "C" fn shut_down(arg: *mut c_void) {
+    let shared = unsafe { &*(arg as *const Shared) };
+    shared.shut_down.store(true, Ordering::Release);
+}
+
+/// The names of the running server's audio output ports, which can be connected to an input.
+pub fn source_ports() -> io::Result<Vec<String>> {
+    let client = open_client(CLIENT_NAME)?;
+    let mut names = vec![];
+    unsafe {
+        let ports = jack_get_ports(
+            client,
+            ptr::null(),
+            JACK_DEFAULT_AUDIO_TYPE.as_ptr() as *const c_char,
+            JACK_PORT_IS_OUTPUT,
+        );
+        if !ports.is_null() {
+            let mut port = ports;
+            while !(*port).is_null() {
+                names.push(CStr::from_ptr(*port).to_string_lossy().into_owned());
+                port = port.add(1);
+            }
+            jack_free(ports as *mut c_void);
+        }
+        jack_client_close(client);
+    }
+    Ok(names)
+}
+
+fn open_client(client_name: &str) -> io::Result<*mut Client> {
+    let name = c_string(client_name)?;
+    let mut status = 0;
+    let client = unsafe { jack_client_open(name.as_ptr(), JACK_NO_START_SERVER, &mut status) };
+    if client.is_null() {
+        return Err(io::Error::other(format!(
+            "can't connect to a JACK server (status {:#x}); is jackd running?",
+            status
+        )));
+    }
+    Ok(client)
+}
+
+fn c_string(s: &str) -> io::Result<CString> {
+    CString::new(s).map_err(|_| io::Error::other(format!("invalid JACK name: {}", s)))
+}
@@ -0,0 +1,747 @@
+//! Reading and writing results as JSON, so other programs can store and send them on without
+//! converting them first.
+//!
+//! `ToJson` and `FromJson` are implemented for pitches, estimates, note events and summaries (and
+//! `ToJson` for intervals and chords), with the same fields as the commands write with
+//! `--format json`, as their writers go through them: the records `analyze` writes read back as
+//! the `DetectedPitch` they were, and the notes `transcribe` writes as the `NoteEvent`s they were.
+//! A `Value` is any JSON document, which `parse` reads and `Display` writes.
+//!
+//! Only built with the `json` feature (on by default), in place of a `serde` one, so as not to
+//! depend on serde.
+
+use std::fmt::{self, Write};
+
+use crate::events::Event;
+use crate::interval::MelodicInterval;
+use crate::notes::NoteEvent;
+use crate::output::{ChordRecord, Record};
+use crate::summary::{NoteSummary, Summary};
+use crate::{DetectedPitch, Note, Pitch};
+
+/// A JSON value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// The fields of an object, in order.
+    Object(Vec<(String, Value)>),
+}
+
+/// Types that can be written as JSON.
+pub trait ToJson {
+    fn to_json(&self) -> Value;
+}
+
+/// Types that can be read back from the JSON they're written as.
+pub trait FromJson: Sized {
+    fn from_json(value: &Value) -> Result<Self, String>;
+}
+
+impl Value {
+    /// The field `key` of an object, if it is one and has it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Set the field `key` of an object to `value`, adding it last if it has no such field.
+    pub fn set(&mut self, key: &str, value: Value) {
+        if let Value::Object(fields) = self {
+            match fields.iter_mut().find(|(k, _)| k == key) {
+                Some((_, field)) => *field = value,
+                None => fields.push((key.to_string(), value)),
+            }
+        }
+    }
+
+    /// The field `key` of an object, or an error saying it's missing.
+    fn field(&self, key: &str) -> Result<&Value, String> {
+        self.get(key)
+            .ok_or_else(|| format!("missing field: {}", key))
+    }
+
+    /// The number in the field `key` of an object.
+    fn number(&self, key: &str) -> Result<f64, String> {
+        self.field(key)?
+            .as_f64()
+            .ok_or_else(|| format!("{} isn't a number", key))
+    }
+
+    /// The number in the field `key` of an object, or `None` if it's null or missing.
+    fn optional_number(&self, key: &str) -> Result<Option<f64>, String> {
+        match self.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| format!("{} isn't a number", key)),
+        }
+    }
+
+    /// The string in the field `key` of an object.
+    fn string(&self, key: &str) -> Result<&str, String> {
+        self.field(key)?
+            .as_str()
+            .ok_or_else(|| format!("{} isn't a string", key))
+    }
+}
+
+/// A number, or null if it's not representable in JSON (NaN or infinite).
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        if n.is_finite() {
+            Value::Number(n)
+        } else {
+            Value::Null
+        }
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+/// An object with `fields`.
+fn object(fields: Vec<(&str, Value)>) -> Value {
+    Value::Object(
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+/// Written as on one line, with a space after each colon and comma, as `analyze` writes records.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write_string(f, s),
+            Value::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Value::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Parse the JSON document `text`.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    /// The byte offset of the next character.
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some('"') => self.string().map(Value::String),
+            Some('t') => self.keyword("true", Value::Bool(true)),
+            Some('f') => self.keyword("false", Value::Bool(false)),
+            Some('n') => self.keyword("null", Value::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut fields = vec![];
+        self.skip_whitespace();
+        if self.eat('}') {
+            return Ok(Value::Object(fields));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a field name"));
+            }
+            let key = self.string()?;
+            self.skip_whitespace();
+            if !self.eat(':') {
+                return Err(self.error("expected ':'"));
+            }
+            fields.push((key, self.value()?));
+            self.skip_whitespace();
+            if self.eat('}') {
+                return Ok(Value::Object(fields));
+            }
+            if !self.eat(',') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut values = vec![];
+        self.skip_whitespace();
+        if self.eat(']') {
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(Value::Array(values));
+            }
+            if !self.eat(',') {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            let c = self
+                .next()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escaped = match self.next() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    s.push(escaped);
+                }
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// The character of a `\u` escape, after the `u`, joining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if !(self.eat('\\') && self.eat('u')) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("invalid escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error("invalid escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() || "+-.eE".contains(c) {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let number = &self.text[start..self.pos];
+        // Rust accepts what JSON doesn't, like "1." and ".5", though not the other way round.
+        let valid = !number.ends_with('.')
+            && !number.contains(".e")
+            && !number.contains(".E")
+            && !number.trim_start_matches('-').starts_with('.');
+        match number.parse() {
+            Ok(n) if valid => Ok(Value::Number(n)),
+            _ => {
+                self.pos = start;
+                Err(self.error("invalid number"))
+            }
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !matches!(c, ' ' | '\t' | '\n' | '\r') {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    /// Skip `c` if it's next, returning whether it was.
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("invalid JSON at byte {}: {}", self.pos, message)
+    }
+}
+
+impl ToJson for Note {
+    fn to_json(&self) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl FromJson for Note {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        value
+            .as_str()
+            .ok_or_else(|| "a note is a string".to_string())?
+            .parse()
+    }
+}
+
+/// The `note` and `octave` fields of `pitch`.
+fn pitch_fields(pitch: Option<Pitch>) -> Vec<(&'static str, Value)> {
+    let [note, octave] = pitch_values(pitch);
+    vec![("note", note), ("octave", octave)]
+}
+
+/// The values of the `note` and `octave` fields of `pitch`.
+fn pitch_values(pitch: Option<Pitch>) -> [Value; 2] {
+    [
+        pitch.map(|p| p.note.to_json()).unwrap_or(Value::Null),
+        pitch.map(|p| p.octave as f64).into(),
+    ]
+}
+
+/// The pitch in the `note` and `octave` fields of `value`, or `None` if they're null.
+fn read_pitch(value: &Value) -> Result<Option<Pitch>, String> {
+    match value.field("note")? {
+        Value::Null => Ok(None),
+        note => Ok(Some(Pitch {
+            note: Note::from_json(note)?,
+            octave: value.number("octave")? as i8,
+        })),
+    }
+}
+
+/// The pitch in the `note` and `octave` fields of `value`, which must have one.
+fn read_some_pitch(value: &Value) -> Result<Pitch, String> {
+    read_pitch(value)?.ok_or_else(|| "missing note".to_string())
+}
+
+impl ToJson for Pitch {
+    fn to_json(&self) -> Value {
+        object(pitch_fields(Some(*self)))
+    }
+}
+
+impl FromJson for Pitch {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        read_some_pitch(value)
+    }
+}
+
+/// The `note`, `octave` and `cents` of an estimate, as they're written. Writers that name notes
+/// otherwise, or in another tuning, pass their own.
+pub(crate) type Naming<'a> = &'a dyn Fn(&DetectedPitch) -> [Value; 3];
+
+/// `detected` named as one of the twelve notes, in English.
+fn english(detected: &DetectedPitch) -> [Value; 3] {
+    let [note, octave] = pitch_values(detected.pitch);
+    [note, octave, detected.pitch.map(|_| detected.cents).into()]
+}
+
+/// The fields of `detected`, named by `name`.
+fn detected_fields(detected: &DetectedPitch, name: Naming) -> Vec<(&'static str, Value)> {
+    let [note, octave, cents] = name(detected);
+    vec![
+        ("freq_hz", detected.freq.into()),
+        ("note", note),
+        ("octave", octave),
+        ("cents", cents),
+        ("confidence", detected.confidence.into()),
+        ("rest", detected.rest.into()),
+        ("unvoiced", detected.unvoiced.into()),
+    ]
+}
+
+impl ToJson for DetectedPitch {
+    fn to_json(&self) -> Value {
+        object(detected_fields(self, &english))
+    }
+}
+
+/// Missing flags are false, as in records `analyze` wrote without them.
+impl FromJson for DetectedPitch {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let flag = |key| value.get(key).and_then(Value::as_bool).unwrap_or(false);
+        Ok(DetectedPitch {
+            freq: value.optional_number("freq_hz")?,
+            pitch: read_pitch(value)?,
+            cents: value.optional_number("cents")?.unwrap_or(0.0),
+            confidence: value.optional_number("confidence")?.unwrap_or(0.0),
+            rest: flag("rest"),
+            unvoiced: flag("unvoiced"),
+        })
+    }
+}
+
+/// The same fields as `transcribe --format json` writes.
+impl ToJson for NoteEvent {
+    fn to_json(&self) -> Value {
+        let mut fields = vec![
+            ("start_s", self.start.into()),
+            ("duration_s", self.duration().into()),
+        ];
+        fields.extend(pitch_fields(Some(self.pitch)));
+        fields.push(("velocity", (self.velocity as f64).into()));
+        object(fields)
+    }
+}
+
+/// The end is `start_s + duration_s`, which can round differently from the end written.
+impl FromJson for NoteEvent {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let start = value.number("start_s")?;
+        Ok(NoteEvent {
+            pitch: read_some_pitch(value)?,
+            start,
+            end: start + value.number("duration_s")?,
+            velocity: value.number("velocity")?.clamp(0.0, 127.0) as u8,
+        })
+    }
+}
+
+/// The same fields as `transcribe --intervals --format json` writes.
+impl ToJson for MelodicInterval {
+    fn to_json(&self) -> Value {
+        let interval = self.interval;
+        object(vec![
+            ("time_s", self.time.into()),
+            ("from", self.from.to_string().into()),
+            ("to", self.to.to_string().into()),
+            ("semitones", f64::from(interval.semitones).into()),
+            ("interval", interval.name().into()),
+            ("short", interval.short_name().into()),
+            ("direction", interval.direction().into()),
+        ])
+    }
+}
+
+/// The same fields as `analyze --chords --format json` writes, naming roots in English.
+impl ToJson for ChordRecord {
+    fn to_json(&self) -> Value {
+        let chord = self.chord.map(|estimate| estimate.chord);
+        object(vec![
+            ("timestamp_s", self.timestamp.into()),
+            ("channel", self.channel.map(|channel| channel as f64).into()),
+            ("chord", chord.map(|chord| chord.to_string()).into()),
+            ("root", chord.map(|chord| chord.root.to_json()).into()),
+            (
+                "quality",
+                chord.map(|chord| chord.quality.to_string()).into(),
+            ),
+            (
+                "confidence",
+                self.chord.map(|estimate| estimate.confidence).into(),
+            ),
+        ])
+    }
+}
+
+/// An object with an `event` field saying which: `note_on`, `pitch_changed` or `note_off`.
+impl ToJson for Event {
+    fn to_json(&self) -> Value {
+        let (name, mut fields) = match *self {
+            Event::NoteOn { freq, velocity, .. } => (
+                "note_on",
+                vec![
+                    ("freq_hz", freq.into()),
+                    ("velocity", (velocity as f64).into()),
+                ],
+            ),
+            Event::PitchChanged { freq, cents, .. } => (
+                "pitch_changed",
+                vec![("freq_hz", freq.into()), ("cents", cents.into())],
+            ),
+            Event::NoteOff { .. } => ("note_off", vec![]),
+        };
+        let mut all = vec![("event", name.into()), ("time_s", self.time().into())];
+        all.extend(pitch_fields(Some(self.pitch())));
+        all.append(&mut fields);
+        object(all)
+    }
+}
+
+impl FromJson for Event {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        let time = value.number("time_s")?;
+        let pitch = read_some_pitch(value)?;
+        match value.string("event")? {
+            "note_on" => Ok(Event::NoteOn {
+                time,
+                pitch,
+                freq: value.number("freq_hz")?,
+                velocity: value.number("velocity")?.clamp(0.0, 127.0) as u8,
+            }),
+            "pitch_changed" => Ok(Event::PitchChanged {
+                time,
+                pitch,
+                freq: value.number("freq_hz")?,
+                cents: value.number("cents")?,
+            }),
+            "note_off" => Ok(Event::NoteOff { time, pitch }),
+            other => Err(format!("unknown event: {}", other)),
+        }
+    }
+}
+
+impl ToJson for NoteSummary {
+    fn to_json(&self) -> Value {
+        let mut fields = pitch_fields(Some(self.pitch));
+        fields.extend(vec![
+            ("time_s", self.time.into()),
+            ("share", self.share.into()),
+            ("mean_cents", self.mean_cents.into()),
+            ("spread_cents", self.spread_cents.into()),
+        ]);
+        object(fields)
+    }
+}
+
+impl FromJson for NoteSummary {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        Ok(NoteSummary {
+            pitch: read_some_pitch(value)?,
+            time: value.number("time_s")?,
+            share: value.number("share")?,
+            mean_cents: value.number("mean_cents")?,
+            spread_cents: value.number("spread_cents")?,
+        })
+    }
+}
+
+/// `record` as `analyze --format json` writes it, with its pitches named by `name`: its estimate's
+/// fields, after when and where it's from, and then its levels and inharmonicity if it has them.
+pub(crate) fn record(record: &Record, name: Naming) -> Value {
+    let mut fields = vec![
+        ("timestamp_s", record.timestamp.into()),
+        (
+            "channel",
+            record.channel.map(|channel| channel as f64).into(),
+        ),
+    ];
+    if let Some(voice) = record.voice {
+        fields.push(("voice", (voice as f64).into()));
+    }
+    fields.extend(detected_fields(&record.detected, name));
+    if let Some(level) = record.level {
+        fields.extend(vec![
+            ("rms_dbfs", level.rms.into()),
+            ("peak_dbfs", level.peak.into()),
+            ("a_weighted_dbfs", level.a_weighted.into()),
+        ]);
+    }
+    if let Some(inharmonicity) = &record.inharmonicity {
+        let [note, octave, cents] = name(&inharmonicity.ideal);
+        fields.extend(vec![
+            ("inharmonicity", inharmonicity.coefficient.into()),
+            ("ideal_freq_hz", inharmonicity.ideal.freq.into()),
+            ("ideal_note", note),
+            ("ideal_octave", octave),
+            ("ideal_cents", cents),
+        ]);
+    }
+    object(fields)
+}
+
+/// The same fields as `analyze --format json` writes, naming notes in English.
+impl ToJson for Record {
+    fn to_json(&self) -> Value {
+        record(self, &english)
+    }
+}
+
+/// The same fields as `analyze --summary --format json` writes. Only its notes can be read back,
+/// as `NoteSummary`s, as the totals it's made from aren't written.
+impl ToJson for Summary {
+    fn to_json(&self) -> Value {
+        let intonation = self.intonation();
+        object(vec![
+            ("duration_s", self.duration().into()),
+            ("pitched_s", self.pitched_time().into()),
+            ("rest_s", self.rest_time().into()),
+            ("unknown_s", self.unknown_time().into()),
+            ("mean_cents", self.mean_cents().into()),
+            ("mean_absolute_cents", self.mean_absolute_cents().into()),
+            ("spread_cents", self.spread_cents().into()),
+            ("in_tune", intonation.map(|i| i.0).into()),
+            ("sharp", intonation.map(|i| i.1).into()),
+            ("flat", intonation.map(|i| i.2).into()),
+            (
+                "notes",
+                Value::Array(self.notes().iter().map(ToJson::to_json).collect()),
+            ),
+        ])
+    }
+}
+
+#[cfg(feature = "async")]
+impl ToJson for crate::stream::PitchFrame {
+    fn to_json(&self) -> Value {
+        let mut value = self.detected.to_json();
+        if let Value::Object(fields) = &mut value {
+            fields.insert(0, ("timestamp_s".to_string(), self.timestamp.into()));
+            fields.push(("rms_dbfs".to_string(), self.level.into()));
+        }
+        value
+    }
+}
+
+#[cfg(feature = "async")]
+impl FromJson for crate::stream::PitchFrame {
+    fn from_json(value: &Value) -> Result<Self, String> {
+        Ok(crate::stream::PitchFrame {
+            timestamp: value.number("timestamp_s")?,
+            detected: DetectedPitch::from_json(value)?,
+            level: value
+                .optional_number("rms_dbfs")?
+                .unwrap_or(f64::NEG_INFINITY),
+        })
+    }
+}
@@ -0,0 +1,78 @@
+//! Estimating the key of a melody with the Krumhansl-Schmuckler algorithm: how long each pitch
+//! class sounds is correlated with the profile of every major and minor key, and the key that
+//! correlates best is the most likely.
+
+use crate::notes::NoteEvent;
+use crate::score::Key;
+
+/// How well each pitch class fits a major key, from the tonic up (Krumhansl and Kessler's probe
+/// tone ratings).
+const MAJOR_PROFILE: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+
+/// The same for a minor key.
+const MINOR_PROFILE: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// A key, and how well a melody fits it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyEstimate {
+    pub key: Key,
+    /// The correlation of the melody's pitch classes with the key's profile, from -1 to 1.
+    pub correlation: f64,
+}
+
+/// How long notes of each pitch class, from C, sound in `notes`, in seconds.
+pub fn pitch_class_durations(notes: &[NoteEvent]) -> [f64; 12] {
+    let mut durations = [0.0; 12];
+    for note in notes {
+        durations[note.pitch.note.semitone() as usize] += note.duration();
+    }
+    durations
+}
+
+/// All 24 major and minor keys, best fitting `notes` first. Empty if there are no notes to go on.
+pub fn rank_keys(notes: &[NoteEvent]) -> Vec<KeyEstimate> {
+    let durations = pitch_class_durations(notes);
+    if durations.iter().all(|duration| *duration <= 0.0) {
+        return vec![];
+    }
+
+    let mut estimates = vec![];
+    for &(profile, minor) in &[(&MAJOR_PROFILE, false), (&MINOR_PROFILE, true)] {
+        for tonic in 0..12 {
+            // The profile rotated so that `tonic` is first.
+            let rotated: Vec<f64> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+            estimates.push(KeyEstimate {
+                key: Key::from_tonic(tonic as u8, minor),
+                correlation: correlation(&durations, &rotated),
+            });
+        }
+    }
+    estimates.sort_by(|a, b| b.correlation.total_cmp(&a.correlation));
+    estimates
+}
+
+/// The most likely key of `notes`, if there are any.
+pub fn estimate_key(notes: &[NoteEvent]) -> Option<KeyEstimate> {
+    rank_keys(notes).first().copied()
+}
+
+/// Pearson's correlation coefficient of `a` and `b`, or 0 if either is constant.
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let n = a.len() as f64;
+    let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+    let (mut cov, mut var_a, mut var_b) = (0.0, 0.0, 0.0);
+    for (a, b) in a.iter().zip(b) {
+        cov += (a - mean_a) * (b - mean_b);
+        var_a += (a - mean_a) * (a - mean_a);
+        var_b += (b - mean_b) * (b - mean_b);
+    }
+    if var_a == 0.0 || var_b == 0.0 {
+        0.0
+    } else {
+        cov / (var_a * var_b).sqrt()
+    }
+}
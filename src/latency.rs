@@ -0,0 +1,163 @@
+//! Measuring how long live detection takes to report what it hears, and sizing the window and hop
+//! to meet a target.
+//!
+//! Latency has two parts. Buffering: a new note only wins out once it fills about half the window,
+//! and the estimate that reports it may be up to a hop later. And processing: once the samples of
+//! an estimate have been read, the time to detect its pitch and write it out. The first follows
+//! from the window, hop and sample rate; the second is timed, stage by stage, as it happens.
+//!
+//! To meet a target, `fit` alternates between sizing the window for the time left over after
+//! processing and timing how long a detector with that window takes, as a bigger window takes
+//! longer to analyze.
+
+use std::f64::consts::PI;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::PitchDetector;
+
+/// The fewest periods of a pitch a window must hold for it to be found.
+pub const MIN_PERIODS: f64 = 2.0;
+
+/// The shortest window a target sizes, in samples.
+const MIN_WINDOW: usize = 64;
+
+/// How many times `fit` resizes the window for the processing time it measured.
+const FIT_ROUNDS: usize = 3;
+
+/// How many times the processing time it measured `fit` leaves for processing, since real signals
+/// (and a busy machine) take longer than its tone.
+const FIT_HEADROOM: f64 = 2.0;
+
+/// The frequency of the tone `fit` times detections of, in Hz.
+const FIT_TONE: f64 = 220.0;
+
+/// A stage of producing an estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Waiting for the hop's samples to arrive.
+    Input,
+    /// Estimating the pitch of the window.
+    Detect,
+    /// Smoothing the estimate, and writing it out.
+    Output,
+    /// From the last sample of the window being read to the estimate being written.
+    Total,
+}
+
+/// Every stage, in order.
+pub const STAGES: [Stage; 4] = [Stage::Input, Stage::Detect, Stage::Output, Stage::Total];
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Stage::Input => "input",
+            Stage::Detect => "detect",
+            Stage::Output => "output",
+            Stage::Total => "total",
+        };
+        f.pad(name)
+    }
+}
+
+/// The times one stage took, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StageStats {
+    pub count: usize,
+    pub mean: f64,
+    /// The time 95% of estimates took no longer than.
+    pub p95: f64,
+    pub max: f64,
+}
+
+/// The time each stage took for every estimate.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    /// Milliseconds, for each of `STAGES`.
+    times: [Vec<f64>; 4],
+}
+
+impl Timings {
+    pub fn new() -> Self {
+        Timings::default()
+    }
+
+    /// Count one estimate's `time` in `stage`.
+    pub fn record(&mut self, stage: Stage, time: Duration) {
+        self.times[stage as usize].push(1000.0 * time.as_secs_f64());
+    }
+
+    /// How long `stage` took, or `None` if it was never timed.
+    pub fn stats(&self, stage: Stage) -> Option<StageStats> {
+        let times = &self.times[stage as usize];
+        if times.is_empty() {
+            return None;
+        }
+        let mut sorted = times.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = (0.95 * sorted.len() as f64).ceil() as usize;
+        Some(StageStats {
+            count: times.len(),
+            mean: times.iter().fold(0.0, |sum, time| sum + time) / times.len() as f64,
+            p95: sorted[rank.clamp(1, sorted.len()) - 1],
+            max: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// The latency of buffering, in milliseconds, of estimates from `window` samples every `hop`, at
+/// `sample_rate` Hz: half the window, and a hop.
+pub fn buffering(window: usize, hop: usize, sample_rate: usize) -> f64 {
+    1000.0 * (window as f64 / 2.0 + hop as f64) / sample_rate as f64
+}
+
+/// The window and hop, in samples, whose buffering takes `budget` milliseconds at `sample_rate`
+/// Hz, with a quarter window per hop as by default.
+pub fn plan(budget: f64, sample_rate: usize) -> (usize, usize) {
+    let samples = budget.max(0.0) / 1000.0 * sample_rate as f64;
+    // Half a window and a quarter of one.
+    let window = ((samples * 4.0 / 3.0) as usize).max(MIN_WINDOW);
+    (window, (window / 4).max(1))
+}
+
+/// `detector`, with a chunk size, and the hop to go with it, that report a note within `target`
+/// milliseconds, processing included, as far as they can. Its minimum frequency is raised to what
+/// the chunk can hold.
+pub fn fit(detector: &PitchDetector, target: f64) -> (PitchDetector, usize) {
+    let sample_rate = detector.sample_rate();
+    let mut processing = 0.0;
+    let mut fitted = (detector.clone(), 1);
+    for _ in 0..FIT_ROUNDS {
+        let (window, hop) = plan(target - FIT_HEADROOM * processing, sample_rate);
+        let lowest = lowest_freq(window, sample_rate).ceil() as usize;
+        let sized = detector
+            .clone()
+            .with_chunk_size(window)
+            .with_min_freq(detector.min_freq().max(lowest));
+        processing = time_detection(&sized);
+        fitted = (sized, hop);
+    }
+    fitted
+}
+
+/// How long `detector` takes to detect the pitch of a chunk of a tone, in milliseconds: the
+/// quickest of a few tries, so that a hiccup doesn't count.
+fn time_detection(detector: &PitchDetector) -> f64 {
+    let step = 2.0 * PI * FIT_TONE / detector.sample_rate() as f64;
+    let tone: Vec<f32> = (0..detector.chunk_size())
+        .map(|i| (0.5 * (step * i as f64).sin()) as f32)
+        .collect();
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            detector.detect(&tone);
+            1000.0 * start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// The lowest frequency, in Hz, with `MIN_PERIODS` periods in `window` samples at `sample_rate`
+/// Hz.
+pub fn lowest_freq(window: usize, sample_rate: usize) -> f64 {
+    MIN_PERIODS * sample_rate as f64 / window as f64
+}
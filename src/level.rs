@@ -0,0 +1,64 @@
+//! Signal level measurements.
+
+use crate::filters;
+
+/// The root mean square of `buffer`.
+pub fn rms(buffer: &[f32]) -> f64 {
+    if buffer.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = buffer.iter().map(|s| (*s as f64) * (*s as f64)).sum();
+    (sum / buffer.len() as f64).sqrt()
+}
+
+/// The largest absolute sample of `buffer`.
+pub fn peak(buffer: &[f32]) -> f64 {
+    buffer.iter().fold(0.0, |peak, s| peak.max(s.abs() as f64))
+}
+
+/// The RMS of `buffer`, sampled at `sample_rate` Hz, after A-weighting, which follows how loud
+/// the ear finds each frequency.
+pub fn a_weighted_rms(buffer: &[f32], sample_rate: usize) -> f64 {
+    let mut weighted = buffer.to_vec();
+    filters::a_weight(&mut weighted, sample_rate);
+    rms(&weighted)
+}
+
+/// The levels of a chunk, in dBFS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    pub rms: f64,
+    pub peak: f64,
+    /// The A-weighted RMS level, if it was measured.
+    pub a_weighted: Option<f64>,
+}
+
+/// Which levels of each chunk to measure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Meter {
+    /// Whether to measure the A-weighted level as well.
+    pub a_weighted: bool,
+}
+
+impl Meter {
+    /// Measure `buffer`, sampled at `sample_rate` Hz.
+    pub fn measure(&self, buffer: &[f32], sample_rate: usize) -> Level {
+        Level {
+            rms: to_dbfs(rms(buffer)),
+            peak: to_dbfs(peak(buffer)),
+            a_weighted: Some(self.a_weighted)
+                .filter(|a_weighted| *a_weighted)
+                .map(|_| to_dbfs(a_weighted_rms(buffer, sample_rate))),
+        }
+    }
+}
+
+/// `amplitude` (with 1.0 being full scale) in decibels relative to full scale.
+pub fn to_dbfs(amplitude: f64) -> f64 {
+    20.0 * amplitude.log10()
+}
+
+/// The amplitude of `dbfs` decibels relative to full scale.
+pub fn from_dbfs(dbfs: f64) -> f64 {
+    10f64.powf(dbfs / 20.0)
+}
@@ -0,0 +1,1324 @@
+//! A monotonic pitch finder.
+//!
+//! Everything needs the default `std` feature except `embedded`, `fixed`, `Note` and `Pitch`,
+//! which build with `no_std` for targets without an operating system.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+pub mod abc;
+#[cfg(feature = "std")]
+pub mod audio;
+#[cfg(feature = "std")]
+pub mod cepstrum;
+#[cfg(feature = "std")]
+pub mod chord;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "decode")]
+pub mod decode;
+#[cfg(feature = "std")]
+pub mod devices;
+pub mod embedded;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "std")]
+pub mod evaluate;
+#[cfg(feature = "std")]
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod filters;
+pub mod fixed;
+#[cfg(feature = "std")]
+pub mod formants;
+#[cfg(feature = "std")]
+pub mod generate;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "std")]
+pub mod hps;
+#[cfg(feature = "server")]
+pub mod http;
+#[cfg(feature = "std")]
+pub mod inharmonicity;
+#[cfg(feature = "std")]
+pub mod instrument;
+#[cfg(feature = "std")]
+pub mod interrupt;
+#[cfg(feature = "std")]
+pub mod interval;
+#[cfg(feature = "jack")]
+pub mod jack;
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod key;
+#[cfg(feature = "std")]
+pub mod latency;
+#[cfg(feature = "std")]
+pub mod level;
+#[cfg(feature = "std")]
+pub mod lilypond;
+#[cfg(feature = "std")]
+pub mod live;
+#[cfg(feature = "std")]
+pub mod logging;
+#[cfg(feature = "std")]
+pub mod midi;
+#[cfg(feature = "std")]
+pub mod mpm;
+#[cfg(feature = "std")]
+pub mod musicxml;
+#[cfg(feature = "std")]
+pub mod names;
+#[cfg(feature = "std")]
+pub mod notes;
+#[cfg(feature = "std")]
+pub mod osc;
+#[cfg(feature = "json")]
+pub mod output;
+#[cfg(feature = "std")]
+pub mod plot;
+#[cfg(feature = "std")]
+pub mod png;
+#[cfg(feature = "std")]
+pub mod polyphony;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod recording;
+#[cfg(feature = "std")]
+pub mod resample;
+#[cfg(feature = "std")]
+pub mod scale;
+#[cfg(feature = "std")]
+pub mod score;
+#[cfg(feature = "std")]
+pub mod scoring;
+#[cfg(feature = "std")]
+pub mod shs;
+#[cfg(feature = "std")]
+pub mod simd;
+#[cfg(feature = "std")]
+pub mod smooth;
+#[cfg(feature = "std")]
+pub mod spectrogram;
+#[cfg(feature = "std")]
+pub mod spectrum;
+#[cfg(feature = "std")]
+pub mod spsc;
+#[cfg(feature = "async")]
+pub mod stream;
+#[cfg(feature = "std")]
+pub mod summary;
+#[cfg(feature = "std")]
+pub mod temperament;
+#[cfg(feature = "std")]
+pub mod tempo;
+#[cfg(feature = "std")]
+pub mod training;
+#[cfg(feature = "std")]
+pub mod tuner;
+#[cfg(feature = "std")]
+pub mod tuning;
+#[cfg(feature = "std")]
+pub mod vibrato;
+#[cfg(feature = "std")]
+pub mod voicing;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+pub mod websocket;
+#[cfg(feature = "std")]
+pub mod window;
+#[cfg(feature = "std")]
+pub mod yin;
+#[cfg(feature = "std")]
+pub mod zcr;
+
+use core::fmt;
+#[cfg(feature = "std")]
+use std::borrow::Cow;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+#[cfg(feature = "std")]
+use std::thread;
+
+#[cfg(feature = "std")]
+use crate::chord::ChordEstimate;
+#[cfg(feature = "std")]
+use crate::embedded::Peaks;
+#[cfg(feature = "std")]
+pub use crate::error::{Error, Result};
+#[cfg(feature = "std")]
+use crate::filters::Filter;
+#[cfg(feature = "std")]
+use crate::inharmonicity::{Fit, Inharmonicity};
+#[cfg(feature = "std")]
+use crate::logging::{debug, info, Level};
+#[cfg(feature = "std")]
+use crate::temperament::Temperament;
+#[cfg(feature = "std")]
+use crate::window::Window;
+
+/// The sample rate assumed when none is given.
+pub const DEFAULT_SAMPLE_RATE: usize = 44100;
+
+/// The lowest frequency detected when none is given.
+pub const MIN_DETECTABLE_FREQ: usize = 40; // Hz
+
+/// How many periods of the lowest frequency the monotonic detector searches for maxima over. It
+/// needs at least three maxima.
+const MONOTONIC_PERIODS: usize = 6;
+
+/// The number of minimum cycles in a buffer. We want more than one to make cycle detection more
+/// relaiable.
+pub const FUDGE_FACTOR: usize = 10;
+
+/// How far from the nearest note a frequency may be, in cents, to be named after it when matching
+/// strictly, unless another tolerance is given: half way to the quarter tones between notes.
+pub const DEFAULT_NOTE_TOLERANCE: f64 = 25.0; // cents
+
+/// Chunks quieter than this are rests.
+pub const DEFAULT_SILENCE_THRESHOLD: f64 = -60.0; // dBFS
+
+/// The reference pitch used when none is given.
+pub const DEFAULT_A4: f64 = 440.0; // Hz
+
+/// The range of pitches notes are matched against: C0 to B8.
+#[cfg(feature = "std")]
+const LOWEST_MIDI: i32 = 12;
+#[cfg(feature = "std")]
+const HIGHEST_MIDI: i32 = 119;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Note {
+    A,
+    ASharp,
+    B,
+    C,
+    CSharp,
+    D,
+    DSharp,
+    E,
+    F,
+    FSharp,
+    G,
+    GSharp,
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Note::A => "A",
+            Note::ASharp => "A#",
+            Note::B => "B",
+            Note::C => "C",
+            Note::CSharp => "C#",
+            Note::D => "D",
+            Note::DSharp => "D#",
+            Note::E => "E",
+            Note::F => "F",
+            Note::FSharp => "F#",
+            Note::G => "G",
+            Note::GSharp => "G#",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Note {
+    type Err = String;
+
+    /// Parse a note name such as `C`, `F#` or `Bb`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("unknown note: {}", s);
+
+        let mut chars = s.chars();
+        let natural = match chars.next().map(|c| c.to_ascii_uppercase()) {
+            Some('C') => 0,
+            Some('D') => 2,
+            Some('E') => 4,
+            Some('F') => 5,
+            Some('G') => 7,
+            Some('A') => 9,
+            Some('B') => 11,
+            _ => return Err(err()),
+        };
+        let alter = match chars.as_str() {
+            "" => 0,
+            "#" => 1,
+            "b" => 11,
+            _ => return Err(err()),
+        };
+        Ok(Note::ALL[(natural + alter) % 12])
+    }
+}
+
+impl Note {
+    /// All notes, in order of `semitone`.
+    pub const ALL: [Note; 12] = [
+        Note::C,
+        Note::CSharp,
+        Note::D,
+        Note::DSharp,
+        Note::E,
+        Note::F,
+        Note::FSharp,
+        Note::G,
+        Note::GSharp,
+        Note::A,
+        Note::ASharp,
+        Note::B,
+    ];
+
+    /// The number of semitones above C.
+    pub fn semitone(self) -> u8 {
+        match self {
+            Note::C => 0,
+            Note::CSharp => 1,
+            Note::D => 2,
+            Note::DSharp => 3,
+            Note::E => 4,
+            Note::F => 5,
+            Note::FSharp => 6,
+            Note::G => 7,
+            Note::GSharp => 8,
+            Note::A => 9,
+            Note::ASharp => 10,
+            Note::B => 11,
+        }
+    }
+}
+
+/// A note in a particular octave, in scientific pitch notation (so `A4` is 440 Hz and octaves
+/// start at C).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pitch {
+    pub note: Note,
+    pub octave: i8,
+}
+
+impl Pitch {
+    /// The pitch with MIDI note number `midi`.
+    pub fn from_midi(midi: i32) -> Self {
+        Pitch {
+            note: Note::ALL[midi.rem_euclid(12) as usize],
+            octave: (midi.div_euclid(12) - 1) as i8,
+        }
+    }
+
+    /// The MIDI note number (`A4` is 69).
+    pub fn midi(self) -> i32 {
+        12 * (self.octave as i32 + 1) + self.note.semitone() as i32
+    }
+
+    /// The equally-tempered frequency of this pitch, in Hz, when A4 is tuned to `a4` Hz.
+    #[cfg(feature = "std")]
+    pub fn freq(self, a4: f64) -> f64 {
+        a4 * 2f64.powf((self.midi() - 69) as f64 / 12.0)
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.note, self.octave)
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Pitch {
+    type Err = String;
+
+    /// Parse a pitch such as `A4`, `F#3` or `Bb-1`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s
+            .find(|c: char| c.is_ascii_digit() || c == '-')
+            .ok_or_else(|| format!("pitch without an octave: {}", s))?;
+        let (note, octave) = s.split_at(split);
+        let octave = octave
+            .parse()
+            .map_err(|_| format!("unknown octave: {}", s))?;
+        Ok(Pitch {
+            note: note.parse()?,
+            octave,
+        })
+    }
+}
+
+/// The method used to estimate the frequency of a chunk.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// The spacing of local maxima of the autocorrelation.
+    #[default]
+    Monotonic,
+    /// See the `yin` module.
+    Yin,
+    /// See the `mpm` module.
+    Mpm,
+    /// See the `cepstrum` module.
+    Cepstrum,
+    /// See the `hps` module.
+    Hps,
+    /// See the `zcr` module.
+    Zcr,
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "monotonic" => Ok(Algorithm::Monotonic),
+            "yin" => Ok(Algorithm::Yin),
+            "mpm" => Ok(Algorithm::Mpm),
+            "cepstrum" => Ok(Algorithm::Cepstrum),
+            "hps" => Ok(Algorithm::Hps),
+            "zcr" => Ok(Algorithm::Zcr),
+            _ => Err(format!("unknown algorithm: {}", s)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::Monotonic => "monotonic",
+            Algorithm::Yin => "yin",
+            Algorithm::Mpm => "mpm",
+            Algorithm::Cepstrum => "cepstrum",
+            Algorithm::Hps => "hps",
+            Algorithm::Zcr => "zcr",
+        })
+    }
+}
+
+/// How the monotonic detector scales the autocorrelation at each lag before looking for maxima.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Acf {
+    /// The dot product of the chunk with itself shifted, as it is. It shrinks as the lag grows
+    /// and the overlap shortens, which pulls each maximum a little towards shorter lags, so
+    /// estimates come out sharp when the chunk holds few periods.
+    #[default]
+    Biased,
+    /// The dot product divided by the length of the overlap, so that only the periodicity shapes
+    /// the maxima. Its longest lags average few samples, so it's only the closer of the two when
+    /// the chunk is well over the longest lag searched.
+    Unbiased,
+}
+
+#[cfg(feature = "std")]
+impl FromStr for Acf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "biased" => Ok(Acf::Biased),
+            "unbiased" => Ok(Acf::Unbiased),
+            _ => Err(format!("unknown autocorrelation: {}", s)),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Acf {
+    /// `dot_prod`, the autocorrelation at `lag` of a chunk of `len` samples, scaled.
+    pub fn scale(self, dot_prod: f64, lag: usize, len: usize) -> f64 {
+        match self {
+            Acf::Biased => dot_prod,
+            Acf::Unbiased => dot_prod / len.saturating_sub(lag).max(1) as f64,
+        }
+    }
+}
+
+/// The result of analyzing a chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedPitch {
+    /// The estimated frequency, in Hz, if the chunk was periodic enough to have one.
+    pub freq: Option<f64>,
+    /// The pitch matching `freq`, if there is one.
+    pub pitch: Option<Pitch>,
+    /// How far `freq` is from `pitch`, in cents. Zero if there is no pitch.
+    pub cents: f64,
+    /// How periodic the signal is at `freq`, from 0 (noise) to 1 (perfectly periodic).
+    pub confidence: f64,
+    /// Whether the chunk was silent. If so, no frequency was estimated at all.
+    pub rest: bool,
+    /// Whether the voicing decision found the chunk unvoiced: sound, but noise rather than a
+    /// pitch, like the `s` of speech. If so, no frequency is reported.
+    pub unvoiced: bool,
+}
+
+impl fmt::Display for DetectedPitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.pitch {
+            Some(pitch) => write!(f, "{} {:+.0}¢", pitch, self.cents),
+            None if self.rest => f.write_str("Rest"),
+            None if self.unvoiced => f.write_str("Unvoiced"),
+            None if self.freq.is_none() => f.write_str("No pitch found"),
+            None => f.write_str("Unknown"),
+        }
+    }
+}
+
+/// The normalized autocorrelation of `buffer` at a lag of `period` samples (rounded), clamped to
+/// `0.0..=1.0`. This is 1 for a signal that repeats exactly every `period` samples.
+#[cfg(feature = "std")]
+pub fn confidence(buffer: &[f32], period: f64) -> f64 {
+    if !period.is_finite() || period < 1.0 || period >= buffer.len() as f64 {
+        return 0.0;
+    }
+
+    let lag = period.round() as usize;
+    let (mut cross, mut energy_a, mut energy_b) = (0.0, 0.0, 0.0);
+    for (a, b) in buffer.iter().zip(buffer[lag..].iter()) {
+        let (a, b) = (*a as f64, *b as f64);
+        cross += a * b;
+        energy_a += a * a;
+        energy_b += b * b;
+    }
+
+    if energy_a == 0.0 || energy_b == 0.0 {
+        return 0.0;
+    }
+    (cross / (energy_a * energy_b).sqrt()).clamp(0.0, 1.0)
+}
+
+/// How much more periodic a chunk must be at twice the estimated period before the estimate is
+/// taken to be an overtone, and at most how much less periodic at half of it before the estimate is
+/// taken to be a subharmonic.
+#[cfg(feature = "std")]
+const OCTAVE_MARGIN: f64 = 0.1;
+
+/// Like `confidence`, but interpolated linearly between the lags either side of `period`.
+#[cfg(feature = "std")]
+fn periodicity(buffer: &[f32], period: f64) -> f64 {
+    let (below, above) = (period.floor(), period.ceil());
+    let weight = period - below;
+    (1.0 - weight) * confidence(buffer, below) + weight * confidence(buffer, above)
+}
+
+/// Run `detect` on each of `items`, spreading them over up to `threads` threads, in order.
+#[cfg(feature = "std")]
+fn parallel<I, T>(items: &[I], threads: usize, detect: impl Fn(&I) -> T + Sync) -> Vec<T>
+where
+    I: Sync,
+    T: Send,
+{
+    let threads = threads.clamp(1, items.len().max(1));
+    if threads == 1 {
+        return items.iter().map(&detect).collect();
+    }
+
+    let per_thread = items.len().div_ceil(threads);
+    let detect = &detect;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(per_thread)
+            .map(|part| scope.spawn(move || part.iter().map(detect).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    })
+}
+
+/// The offset from the middle point of the vertex of the parabola through `(-1, prev)`,
+/// `(0, cur)` and `(1, next)`. This is within `-0.5..=0.5` if `cur` is an extremum.
+pub(crate) fn parabolic_offset(prev: f64, cur: f64, next: f64) -> f64 {
+    let denom = 2.0 * (2.0 * cur - next - prev);
+    if denom == 0.0 {
+        0.0
+    } else {
+        (next - prev) / denom
+    }
+}
+
+/// The distance from `reference` to `freq`, in cents.
+#[cfg(feature = "std")]
+pub fn cents_between(reference: f64, freq: f64) -> f64 {
+    1200.0 * (freq / reference).log2()
+}
+
+/// Detects the pitch of fixed-size chunks of a mono signal.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct PitchDetector {
+    sample_rate: usize,
+    min_freq: usize,
+    /// The highest frequency estimated, if there is a limit.
+    max_freq: Option<usize>,
+    fudge_factor: usize,
+    /// Overrides the chunk size derived from `min_freq` and `fudge_factor`.
+    chunk_size: Option<usize>,
+    /// How far from a note frequencies may be to be named after it, in cents, if `strict`.
+    tolerance: f64,
+    /// Whether frequencies further than `tolerance` from every note have no pitch, rather than
+    /// being named after the nearest.
+    strict: bool,
+    a4: f64,
+    temperament: Temperament,
+    /// The note `temperament` is built on.
+    tonic: Note,
+    /// How many semitones above the sounding pitch notes are written.
+    transposition: i32,
+    algorithm: Algorithm,
+    yin_threshold: f64,
+    mpm_cutoff: f64,
+    min_confidence: f64,
+    octave_correction: bool,
+    /// Whether to decide if chunks are voiced, with `voicing::is_voiced`.
+    voicing: bool,
+    /// Whether to flatten chunks with `formants::flatten` before detection.
+    formant_robust: bool,
+    /// Whether to move estimates to the fundamental of the partials fit by `inharmonicity::fit`.
+    inharmonic: bool,
+    /// RMS amplitude below which a chunk is silent.
+    silence_threshold: f64,
+    filters: Vec<Filter>,
+    window: Window,
+    acf: Acf,
+}
+
+#[cfg(feature = "std")]
+impl Default for PitchDetector {
+    fn default() -> Self {
+        PitchDetector::new(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+#[cfg(feature = "std")]
+impl PitchDetector {
+    /// A detector for a signal sampled at `sample_rate` Hz.
+    pub fn new(sample_rate: usize) -> Self {
+        PitchDetector {
+            sample_rate,
+            min_freq: MIN_DETECTABLE_FREQ,
+            max_freq: None,
+            fudge_factor: FUDGE_FACTOR,
+            chunk_size: None,
+            tolerance: DEFAULT_NOTE_TOLERANCE,
+            strict: false,
+            a4: DEFAULT_A4,
+            temperament: Temperament::default(),
+            tonic: Note::C,
+            transposition: 0,
+            algorithm: Algorithm::default(),
+            yin_threshold: yin::DEFAULT_THRESHOLD,
+            mpm_cutoff: mpm::DEFAULT_CUTOFF,
+            min_confidence: 0.0,
+            octave_correction: true,
+            voicing: false,
+            formant_robust: false,
+            inharmonic: false,
+            silence_threshold: level::from_dbfs(DEFAULT_SILENCE_THRESHOLD),
+            filters: vec![],
+            window: Window::default(),
+            acf: Acf::default(),
+        }
+    }
+
+    /// The lowest frequency (in Hz) that a chunk must be able to hold `fudge_factor` cycles of.
+    pub fn with_min_freq(mut self, min_freq: usize) -> Self {
+        self.min_freq = min_freq;
+        self
+    }
+
+    /// Don't estimate frequencies above `max_freq` Hz, whether from noise or real partials.
+    /// There's no limit by default.
+    pub fn with_max_freq(mut self, max_freq: usize) -> Self {
+        self.max_freq = Some(max_freq);
+        self
+    }
+
+    /// The minimum number of cycles of `min_freq` in a chunk.
+    pub fn with_fudge_factor(mut self, fudge_factor: usize) -> Self {
+        self.fudge_factor = fudge_factor;
+        self
+    }
+
+    /// Use chunks of `chunk_size` samples, regardless of the min freq and fudge factor.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    /// Only match a frequency to its nearest note if it is within `cents` of it, the same at any
+    /// height, rather than `DEFAULT_NOTE_TOLERANCE`. Implies `with_strict_match(true)`.
+    pub fn with_note_tolerance(mut self, cents: f64) -> Self {
+        self.tolerance = cents;
+        self.strict = true;
+        self
+    }
+
+    /// Only match a frequency to a note if it is within as many cents of it as `epsilon` Hz is
+    /// above A4.
+    #[deprecated(note = "use `with_note_tolerance`, which is in cents")]
+    pub fn with_epsilon(self, epsilon: f64) -> Self {
+        let cents = cents_between(self.a4, self.a4 + epsilon);
+        self.with_note_tolerance(cents)
+    }
+
+    /// Whether to only match frequencies within the note tolerance of their nearest note, and
+    /// report the rest as having no pitch, rather than always naming the nearest note.
+    pub fn with_strict_match(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Tune A4 to `a4` Hz (e.g. 442 for some orchestras, 415 for baroque pitch).
+    pub fn with_a4(mut self, a4: f64) -> Self {
+        self.a4 = a4;
+        self
+    }
+
+    /// Tune notes to `temperament` built on `tonic`, rather than equal temperament. A4 stays at
+    /// the reference pitch.
+    pub fn with_temperament(mut self, temperament: Temperament, tonic: Note) -> Self {
+        self.temperament = temperament;
+        self.tonic = tonic;
+        self
+    }
+
+    /// Name pitches as written for a transposing instrument whose music is written `semitones`
+    /// above the pitch that sounds (e.g. 2 for a Bb clarinet or trumpet), rather than at concert
+    /// pitch.
+    pub fn with_transposition(mut self, semitones: i32) -> Self {
+        self.transposition = semitones;
+        self
+    }
+
+    /// Estimate frequencies with `algorithm`.
+    pub fn with_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// The absolute threshold used by `Algorithm::Yin`.
+    pub fn with_yin_threshold(mut self, threshold: f64) -> Self {
+        self.yin_threshold = threshold;
+        self
+    }
+
+    /// The key maximum cutoff used by `Algorithm::Mpm`.
+    pub fn with_mpm_cutoff(mut self, cutoff: f64) -> Self {
+        self.mpm_cutoff = cutoff;
+        self
+    }
+
+    /// Treat chunks with an RMS level below `dbfs` decibels relative to full scale as rests.
+    pub fn with_silence_threshold(mut self, dbfs: f64) -> Self {
+        self.silence_threshold = level::from_dbfs(dbfs);
+        self
+    }
+
+    /// Apply `filter` to each chunk before analyzing it. Filters are applied in the order they
+    /// are added.
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Multiply each chunk by `window` before estimating its frequency.
+    pub fn with_window(mut self, window: Window) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Scale the autocorrelation the monotonic detector looks for maxima in as `acf` says.
+    pub fn with_acf(mut self, acf: Acf) -> Self {
+        self.acf = acf;
+        self
+    }
+
+    /// Don't match estimates with a confidence below `min_confidence` to a pitch.
+    pub fn with_min_confidence(mut self, min_confidence: f64) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// For pianos and other stiff strings: estimate the fundamental from the partials found, fit
+    /// to those of a string with inharmonicity (see `inharmonicity::fit`), rather than from the
+    /// period of the whole chunk, which the sharp upper partials pull sharp. Off by default.
+    pub fn with_inharmonicity(mut self, inharmonic: bool) -> Self {
+        self.inharmonic = inharmonic;
+        self
+    }
+
+    /// Whether to check estimates for octave errors (on by default; see `correct_octave` and
+    /// `shs::fundamental`).
+    pub fn with_octave_correction(mut self, octave_correction: bool) -> Self {
+        self.octave_correction = octave_correction;
+        self
+    }
+
+    /// Whether to report chunks as unvoiced when they're noise rather than pitched, as
+    /// `voicing::is_voiced` decides from the min freq up, rather than estimating a frequency in
+    /// them. Off by default; it's meant for speech.
+    pub fn with_voicing(mut self, voicing: bool) -> Self {
+        self.voicing = voicing;
+        self
+    }
+
+    /// Whether to flatten the spectrum of each chunk before estimating its frequency, after the
+    /// filters and before the window, so that the formants of vocals don't pull estimates to the
+    /// harmonics near them. Off by default.
+    pub fn with_formant_robustness(mut self, formant_robust: bool) -> Self {
+        self.formant_robust = formant_robust;
+        self
+    }
+
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    pub fn min_freq(&self) -> usize {
+        self.min_freq
+    }
+
+    pub fn max_freq(&self) -> Option<usize> {
+        self.max_freq
+    }
+
+    /// Number of samples needed to relaiably detect the minimum detectable freq.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+            .unwrap_or(self.fudge_factor * self.sample_rate / self.min_freq)
+    }
+
+    /// The note tolerance, if matching strictly.
+    pub fn note_tolerance(&self) -> Option<f64> {
+        Some(self.tolerance).filter(|_| self.strict)
+    }
+
+    /// The note tolerance as how many Hz it is above A4, or infinite if not matching strictly.
+    #[deprecated(note = "use `note_tolerance`, which is in cents")]
+    pub fn epsilon(&self) -> f64 {
+        self.note_tolerance().map_or(f64::INFINITY, |cents| {
+            self.a4 * (2f64.powf(cents / 1200.0) - 1.0)
+        })
+    }
+
+    pub fn a4(&self) -> f64 {
+        self.a4
+    }
+
+    pub fn temperament(&self) -> Temperament {
+        self.temperament
+    }
+
+    pub fn tonic(&self) -> Note {
+        self.tonic
+    }
+
+    pub fn transposition(&self) -> i32 {
+        self.transposition
+    }
+
+    /// The frequency that sounds when A4 is written, which is `a4` unless notes are transposed.
+    pub fn written_a4(&self) -> f64 {
+        self.a4 * 2f64.powf(-self.transposition as f64 / 12.0)
+    }
+
+    /// The frequency of `pitch`, as written, in the detector's temperament and reference pitch.
+    pub fn pitch_freq(&self, pitch: Pitch) -> f64 {
+        let sounding = Pitch::from_midi(pitch.midi() - self.transposition);
+        self.temperament.freq(sounding, self.tonic, self.a4)
+    }
+
+    pub fn min_confidence(&self) -> f64 {
+        self.min_confidence
+    }
+
+    /// Whether estimates are moved to the fundamental of an inharmonicity fit.
+    pub fn fits_inharmonicity(&self) -> bool {
+        self.inharmonic
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Split `buffer` into the chunks this detector analyzes. A trailing partial chunk is dropped.
+    pub fn chunks<'a>(&self, buffer: &'a [f32]) -> impl Iterator<Item = &'a [f32]> {
+        buffer.chunks_exact(self.chunk_size())
+    }
+
+    /// Estimate the frequency of `buffer` with the configured algorithm, or `None` if it isn't
+    /// periodic enough to have one.
+    pub fn detect_freq(&self, buffer: &[f32]) -> Option<f64> {
+        self.detect_fit(buffer).map(|(freq, _)| freq)
+    }
+
+    /// Like `detect_freq`, with the inharmonicity fit the estimate was moved to the fundamental
+    /// of, if there was one.
+    fn detect_fit(&self, buffer: &[f32]) -> Option<(f64, Option<Fit>)> {
+        let freq = match self.algorithm {
+            Algorithm::Monotonic => self.compute_monotonic_freq(buffer),
+            Algorithm::Yin => yin::yin_freq(buffer, self.sample_rate, self.yin_threshold),
+            Algorithm::Mpm => mpm::mpm_freq(buffer, self.sample_rate, self.mpm_cutoff),
+            Algorithm::Cepstrum => {
+                cepstrum::cepstrum_freq(buffer, self.sample_rate, self.min_freq as f64)
+            }
+            Algorithm::Hps => hps::hps_freq(buffer, self.sample_rate, self.min_freq as f64),
+            Algorithm::Zcr => zcr::zcr_freq(buffer, self.sample_rate),
+        }?;
+        self.refine_freq(buffer, freq)
+    }
+
+    /// Correct the octave of `freq`, an estimate of the frequency of `buffer`, and check it for
+    /// being a harmonic with `shs::fundamental`, if configured to, and drop it if it's above the
+    /// max freq. It's returned with the inharmonicity fit it was moved to, if fitting one.
+    fn refine_freq(&self, buffer: &[f32], freq: f64) -> Option<(f64, Option<Fit>)> {
+        let freq = if self.octave_correction {
+            let freq = self.correct_octave(buffer, freq);
+            let fundamental =
+                shs::fundamental(buffer, self.sample_rate, self.min_freq as f64, freq);
+            if fundamental != freq {
+                debug!(
+                    "sub-harmonic summation: freq={:.1}Hz fundamental={:.1}Hz",
+                    freq, fundamental
+                );
+            }
+            fundamental
+        } else {
+            freq
+        };
+        let fit = Some(freq).filter(|_| self.inharmonic).and_then(|freq| {
+            inharmonicity::fit(buffer, self.sample_rate, self.min_freq as f64, freq)
+        });
+        let freq = match fit {
+            Some(fit) => {
+                debug!(
+                    "inharmonicity: freq={:.1}Hz fundamental={:.1}Hz B={:.5}",
+                    freq,
+                    fit.freq(),
+                    fit.coefficient
+                );
+                fit.freq()
+            }
+            _ => freq,
+        };
+        match self.max_freq {
+            Some(max_freq) if freq > max_freq as f64 => {
+                info!("discarded: freq={:.1}Hz max_freq={}Hz", freq, max_freq);
+                None
+            }
+            _ => Some((freq, fit)),
+        }
+    }
+
+    /// Fix octave errors in `freq`, an estimate of the frequency of `buffer`. On harmonic-rich
+    /// signals, the estimated period `T` is often really `T/2` or `2T`; so compare how periodic the
+    /// buffer is at each of those and move to the more plausible one, an octave at a time. Longer
+    /// periods are only accepted if they're clearly better, since a signal that repeats every `T`
+    /// also repeats every `2T`.
+    pub fn correct_octave(&self, buffer: &[f32], freq: f64) -> f64 {
+        let longest = self.sample_rate as f64 / self.min_freq as f64;
+        let mut period = self.sample_rate as f64 / freq;
+        let mut score = periodicity(buffer, period);
+
+        let mut corrected = false;
+        while 2.0 * period <= longest {
+            let double = periodicity(buffer, 2.0 * period);
+            if double <= score + OCTAVE_MARGIN {
+                break;
+            }
+            period *= 2.0;
+            score = double;
+            corrected = true;
+        }
+
+        while !corrected && period / 2.0 >= 2.0 {
+            let half = periodicity(buffer, period / 2.0);
+            // A bad estimate is about as aperiodic at half its period, so the half has to be
+            // periodic in its own right too.
+            if half < score - OCTAVE_MARGIN || half < 0.5 {
+                break;
+            }
+            period /= 2.0;
+            score = half;
+        }
+
+        let corrected = self.sample_rate as f64 / period;
+        if corrected != freq {
+            debug!(
+                "octave correction: freq={:.1}Hz corrected={:.1}Hz",
+                freq, corrected
+            );
+        }
+        corrected
+    }
+
+    /// `buffer` with the configured filters applied.
+    pub fn filter<'a>(&self, buffer: &'a [f32]) -> Cow<'a, [f32]> {
+        if self.filters.is_empty() {
+            return Cow::Borrowed(buffer);
+        }
+        let mut filtered = buffer.to_vec();
+        for filter in &self.filters {
+            filter.apply(&mut filtered, self.sample_rate);
+        }
+        Cow::Owned(filtered)
+    }
+
+    /// Estimate the frequency of `buffer` and match it to a pitch.
+    pub fn detect(&self, buffer: &[f32]) -> DetectedPitch {
+        self.detect_with(buffer, |buffer| self.detect_freq(buffer))
+    }
+
+    /// Like `detect`, with the inharmonicity detecting it found, as fit to move the estimate to
+    /// the fundamental (see `with_inharmonicity`). There's none unless the chunk was named.
+    pub fn detect_with_inharmonicity(
+        &self,
+        buffer: &[f32],
+    ) -> (DetectedPitch, Option<Inharmonicity>) {
+        self.detect_fitting(buffer, |buffer| self.detect_fit(buffer))
+    }
+
+    /// Like `detect_with_inharmonicity`, estimating the frequency and fit of the filtered and
+    /// windowed `buffer` with `detect_fit`.
+    fn detect_fitting(
+        &self,
+        buffer: &[f32],
+        detect_fit: impl FnOnce(&[f32]) -> Option<(f64, Option<Fit>)>,
+    ) -> (DetectedPitch, Option<Inharmonicity>) {
+        let mut fit = None;
+        let detected = self.detect_with(buffer, |buffer| {
+            let (freq, found) = detect_fit(buffer)?;
+            fit = found;
+            Some(freq)
+        });
+        let inharmonicity = fit
+            .filter(|_| detected.pitch.is_some())
+            .map(|fit| Inharmonicity {
+                coefficient: fit.coefficient,
+                ideal: self.identify(fit.ideal_freq),
+            });
+        (detected, inharmonicity)
+    }
+
+    /// Detect up to two simultaneous pitches in `buffer` with `polyphony::dual_freqs`, lower
+    /// first, whatever the algorithm and window. Each one's confidence is its share of the
+    /// spectrum. A rest, or a chunk with no pitch, is a single result as from `detect`.
+    pub fn detect_dual(&self, buffer: &[f32]) -> Vec<DetectedPitch> {
+        let buffer = &*self.filter(buffer);
+        let none = DetectedPitch {
+            freq: None,
+            pitch: None,
+            cents: 0.0,
+            confidence: 0.0,
+            rest: false,
+            unvoiced: false,
+        };
+        if level::rms(buffer) < self.silence_threshold {
+            return vec![DetectedPitch { rest: true, ..none }];
+        }
+
+        let max_freq = self.max_freq.map(|max| max as f64);
+        let mut voices =
+            polyphony::dual_freqs(buffer, self.sample_rate, self.min_freq as f64, max_freq);
+        if voices.is_empty() {
+            return vec![none];
+        }
+        voices.sort_by(|a, b| a.freq.total_cmp(&b.freq));
+        voices
+            .iter()
+            .map(|voice| match voice.share {
+                share if share < self.min_confidence => DetectedPitch {
+                    freq: Some(voice.freq),
+                    confidence: share,
+                    ..none
+                },
+                share => DetectedPitch {
+                    confidence: share,
+                    ..self.identify(voice.freq)
+                },
+            })
+            .collect()
+    }
+
+    /// Recognize the chord in `buffer` with `chord::match_chord`, from its spectrum from the
+    /// lowest frequency up, whatever the algorithm and window. There's none in silent chunks, or
+    /// if it's less confident than the least confidence.
+    pub fn detect_chord(&self, buffer: &[f32]) -> Option<ChordEstimate> {
+        let buffer = &*self.filter(buffer);
+        if level::rms(buffer) < self.silence_threshold {
+            return None;
+        }
+        let chroma = chord::chroma(
+            buffer,
+            self.sample_rate,
+            self.min_freq as f64,
+            self.written_a4(),
+        );
+        chord::match_chord(&chroma).filter(|estimate| estimate.confidence >= self.min_confidence)
+    }
+
+    /// Whether `detect_with_autocorrelation` can be used: the algorithm is monotonic, and there
+    /// are no filters, flattening or window to change chunks before their autocorrelation is
+    /// taken.
+    pub fn uses_autocorrelation(&self) -> bool {
+        self.algorithm == Algorithm::Monotonic
+            && self.filters.is_empty()
+            && !self.formant_robust
+            && self.window == Window::Rectangular
+    }
+
+    /// Like `detect`, given `acf`, the autocorrelation of `buffer` at lags 1 to
+    /// `monotonic_lags(buffer.len())`, so it needn't be computed again. The detector must
+    /// `uses_autocorrelation`.
+    pub fn detect_with_autocorrelation(&self, buffer: &[f32], acf: &[f64]) -> DetectedPitch {
+        debug_assert!(self.uses_autocorrelation());
+        self.detect_with(buffer, |buffer| {
+            let freq = self.monotonic_freq_from_autocorrelation(acf, buffer.len())?;
+            self.refine_freq(buffer, freq).map(|(freq, _)| freq)
+        })
+    }
+
+    /// Like `detect`, estimating the frequency of the filtered and windowed `buffer` with
+    /// `detect_freq`.
+    fn detect_with(
+        &self,
+        buffer: &[f32],
+        detect_freq: impl FnOnce(&[f32]) -> Option<f64>,
+    ) -> DetectedPitch {
+        let buffer = &*self.filter(buffer);
+
+        let rms = level::rms(buffer);
+        if rms < self.silence_threshold {
+            info!(
+                "rest: level={:.1}dBFS silence_threshold={:.1}dBFS",
+                level::to_dbfs(rms),
+                level::to_dbfs(self.silence_threshold)
+            );
+            return DetectedPitch {
+                freq: None,
+                pitch: None,
+                cents: 0.0,
+                confidence: 0.0,
+                rest: true,
+                unvoiced: false,
+            };
+        }
+
+        let (clarity, crossings) = if self.voicing {
+            voicing::voicing(buffer, self.sample_rate, self.min_freq as f64)
+        } else {
+            (1.0, 0.0)
+        };
+        if clarity < voicing::MIN_CLARITY || crossings > voicing::MAX_CROSSINGS_PER_PERIOD {
+            info!(
+                "unvoiced: clarity={:.2} min_clarity={} crossings_per_period={:.1} \
+                 max_crossings_per_period={}",
+                clarity,
+                voicing::MIN_CLARITY,
+                crossings,
+                voicing::MAX_CROSSINGS_PER_PERIOD
+            );
+            return DetectedPitch {
+                freq: None,
+                pitch: None,
+                cents: 0.0,
+                confidence: 0.0,
+                rest: false,
+                unvoiced: true,
+            };
+        }
+
+        let buffer = &*if self.formant_robust {
+            Cow::Owned(formants::flatten(buffer, self.sample_rate))
+        } else {
+            Cow::Borrowed(buffer)
+        };
+        let buffer = &*self.window.apply(buffer);
+        let freq = match detect_freq(buffer) {
+            Some(freq) => freq,
+            None => {
+                info!("no pitch: algorithm={} found no period", self.algorithm);
+                return DetectedPitch {
+                    freq: None,
+                    pitch: None,
+                    cents: 0.0,
+                    confidence: 0.0,
+                    rest: false,
+                    unvoiced: false,
+                };
+            }
+        };
+        let confidence = confidence(buffer, self.sample_rate as f64 / freq);
+        if confidence < self.min_confidence {
+            info!(
+                "unknown: freq={:.1}Hz confidence={:.2} min_confidence={:.2}",
+                freq, confidence, self.min_confidence
+            );
+            return DetectedPitch {
+                freq: Some(freq),
+                pitch: None,
+                cents: 0.0,
+                confidence,
+                rest: false,
+                unvoiced: false,
+            };
+        }
+
+        let detected = DetectedPitch {
+            confidence,
+            ..self.identify(freq)
+        };
+        if logging::enabled(Level::Debug) {
+            debug!(
+                "pitch: freq={:.2}Hz pitch={} cents={:+.1} confidence={:.2}",
+                freq,
+                detected
+                    .pitch
+                    .map_or("none".to_string(), |pitch| pitch.to_string()),
+                detected.cents,
+                confidence
+            );
+        }
+        detected
+    }
+
+    /// `detect` each of `buffers`, spreading them over up to `threads` threads. The results are
+    /// in the same order as `buffers`.
+    pub fn detect_parallel<B>(&self, buffers: &[B], threads: usize) -> Vec<DetectedPitch>
+    where
+        B: AsRef<[f32]> + Sync,
+    {
+        parallel(buffers, threads, |buffer| self.detect(buffer.as_ref()))
+    }
+
+    /// Like `detect_parallel`, with `detect_with_inharmonicity`.
+    pub fn detect_parallel_with_inharmonicity<B>(
+        &self,
+        buffers: &[B],
+        threads: usize,
+    ) -> Vec<(DetectedPitch, Option<Inharmonicity>)>
+    where
+        B: AsRef<[f32]> + Sync,
+    {
+        parallel(buffers, threads, |buffer| {
+            self.detect_with_inharmonicity(buffer.as_ref())
+        })
+    }
+
+    /// Like `detect_parallel_with_inharmonicity`, given `acfs`, the autocorrelation of each of
+    /// `buffers` as `detect_with_autocorrelation` takes it, as `gpu::Gpu` takes them. The
+    /// detector must `uses_autocorrelation`.
+    pub fn detect_parallel_with_autocorrelations<B>(
+        &self,
+        buffers: &[B],
+        acfs: &[Vec<f64>],
+        threads: usize,
+    ) -> Vec<(DetectedPitch, Option<Inharmonicity>)>
+    where
+        B: AsRef<[f32]> + Sync,
+    {
+        debug_assert!(self.uses_autocorrelation() && acfs.len() == buffers.len());
+        let chunks: Vec<_> = buffers.iter().zip(acfs).collect();
+        parallel(&chunks, threads, |(buffer, acf)| {
+            self.detect_fitting(buffer.as_ref(), |buffer| {
+                let freq = self.monotonic_freq_from_autocorrelation(acf, buffer.len())?;
+                self.refine_freq(buffer, freq)
+            })
+        })
+    }
+
+    /// Match `freq` to a pitch and compute how far off it is. The frequency is taken at face
+    /// value, so the confidence is 1.
+    pub fn identify(&self, freq: f64) -> DetectedPitch {
+        let pitch = self.hz_to_pitch(freq);
+        DetectedPitch {
+            freq: Some(freq),
+            pitch,
+            cents: pitch.map_or(0.0, |pitch| cents_between(self.pitch_freq(pitch), freq)),
+            confidence: 1.0,
+            rest: false,
+            unvoiced: false,
+        }
+    }
+
+    /// Estimate the frequency of `buffer` from the spacing of the local maxima of its
+    /// autocorrelation. Returns `None` if there are too few maxima to measure a spacing.
+    ///
+    /// Only lags up to `MONOTONIC_PERIODS` periods of the min freq are searched, and maxima closer
+    /// together than a period of the max freq are taken to be ripple on one peak, keeping the
+    /// highest. Spacings count by the prominence of their maxima, as `embedded::Peaks` says.
+    pub fn compute_monotonic_freq(&self, buffer: &[f32]) -> Option<f64> {
+        let mut peaks = self.monotonic_peaks();
+        for i in 1..=self.monotonic_lags(buffer.len()) {
+            // Take a suffix of the cloned_buf and align with the beginning of buffer (we're
+            // shifting backwards technically).
+            let dot_prod = simd::dot(buffer, &buffer[i..]);
+            peaks.push(self.acf.scale(dot_prod, i, buffer.len()));
+        }
+        self.monotonic_freq(&peaks)
+    }
+
+    /// Like `compute_monotonic_freq`, from the autocorrelation of a buffer of `len` samples at
+    /// lags 1 to `monotonic_lags(len)`.
+    pub fn monotonic_freq_from_autocorrelation(&self, acf: &[f64], len: usize) -> Option<f64> {
+        let mut peaks = self.monotonic_peaks();
+        for (i, dot_prod) in acf.iter().enumerate() {
+            peaks.push(self.acf.scale(*dot_prod, i + 1, len));
+        }
+        self.monotonic_freq(&peaks)
+    }
+
+    /// The frequency of the weighted average spacing of `peaks`, if there are enough to measure
+    /// one.
+    fn monotonic_freq(&self, peaks: &Peaks) -> Option<f64> {
+        let period = peaks.period();
+        if logging::enabled(Level::Debug) {
+            debug!(
+                "autocorrelation: maxima={} period={}",
+                peaks.count(),
+                period.map_or("none".to_string(), |period| format!("{:.2}", period))
+            );
+        }
+        Some((self.sample_rate as f64) / period?)
+    }
+
+    /// The longest lag the monotonic detector searches for maxima at in a buffer of `len`
+    /// samples.
+    pub fn monotonic_lags(&self, len: usize) -> usize {
+        (MONOTONIC_PERIODS * self.sample_rate / self.min_freq.max(1)).min(len.saturating_sub(1))
+    }
+
+    fn monotonic_peaks(&self) -> Peaks {
+        let shortest = self
+            .max_freq
+            .map_or(1, |max_freq| (self.sample_rate / max_freq.max(1)).max(1));
+        Peaks::new(shortest)
+    }
+
+    /// The pitch nearest `freq`, as written, if it's in the range matched (and when matching
+    /// strictly, if it's within the note tolerance of it).
+    pub fn hz_to_pitch(&self, freq: f64) -> Option<Pitch> {
+        if freq.is_nan() || freq <= 0.0 {
+            return None;
+        }
+        // The nearest equally-tempered note, and its neighbours, since other temperaments can put
+        // a note nearer a neighbour's equally-tempered frequency than its own.
+        let nearest = (69.0 + 12.0 * (freq / self.a4).log2()).round();
+        if !(LOWEST_MIDI as f64..=HIGHEST_MIDI as f64).contains(&nearest) {
+            return None;
+        }
+        let nearest = nearest as i32;
+        (nearest - 1..=nearest + 1)
+            .filter(|midi| (LOWEST_MIDI..=HIGHEST_MIDI).contains(midi))
+            .map(|midi| Pitch::from_midi(midi + self.transposition))
+            .map(|pitch| (pitch, cents_between(self.pitch_freq(pitch), freq).abs()))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .filter(|(_, cents)| !self.strict || *cents <= self.tolerance)
+            .map(|(pitch, _)| pitch)
+    }
+}
@@ -0,0 +1,83 @@
+//! LilyPond export, for engraving a transcription.
+
+use std::io::{self, Write};
+
+use crate::notes::NoteEvent;
+use crate::score::{self, Layout, Written};
+
+/// The LilyPond version the output is written for.
+const VERSION: &str = "2.24.0";
+
+/// Write `notes`, which must be in order and not overlap, as a LilyPond score, one measure per
+/// line.
+pub fn write_lilypond<W: Write>(
+    mut out: W,
+    notes: &[NoteEvent],
+    layout: &Layout,
+) -> io::Result<()> {
+    let key = layout.key;
+
+    writeln!(out, "\\version \"{}\"", VERSION)?;
+    writeln!(out)?;
+    writeln!(out, "{{")?;
+    writeln!(
+        out,
+        "  \\key {} \\{}",
+        note_name(key.letter, key.alter),
+        if key.minor { "minor" } else { "major" }
+    )?;
+    writeln!(out, "  \\time {}", layout.time)?;
+    writeln!(out, "  \\tempo 4 = {}", layout.tempo.round())?;
+
+    for measure in score::measures(&score::quantize(notes, layout.tempo), layout.time) {
+        write!(out, " ")?;
+        for note in &measure {
+            write!(out, " {}", lilypond_note(note, layout))?;
+        }
+        writeln!(out, " |")?;
+    }
+
+    writeln!(out, "}}")?;
+    out.flush()
+}
+
+/// The LilyPond for one `note`, in absolute pitch.
+fn lilypond_note(note: &Written, layout: &Layout) -> String {
+    let mut ly = match note.pitch {
+        None => "r".to_string(),
+        Some(pitch) => {
+            let spelling = layout.key.spell(pitch);
+            let mut ly = note_name(spelling.letter, spelling.alter);
+            // `c` is C3, and each apostrophe or comma moves an octave up or down from there.
+            if spelling.octave >= 3 {
+                ly.extend((3..spelling.octave).map(|_| '\''));
+            } else {
+                ly.extend((spelling.octave..3).map(|_| ','));
+            }
+            ly
+        }
+    };
+
+    let (value, dotted) = note.note_value();
+    ly.push_str(&value.to_string());
+    if dotted {
+        ly.push('.');
+    }
+
+    if note.tie {
+        ly.push('~');
+    }
+    ly
+}
+
+/// The Dutch note name LilyPond uses by default, such as `fis` or `bes`.
+fn note_name(letter: char, alter: i8) -> String {
+    let letter = letter.to_ascii_lowercase();
+    match (letter, alter) {
+        (_, 1) => format!("{}is", letter),
+        ('e', -1) => "es".to_string(),
+        ('a', -1) => "as".to_string(),
+        (_, -1) => format!("{}es", letter),
+        _ => letter.to_string(),
+    }
+}
@@ -0,0 +1,338 @@
+//! Continuous detection over a live stream of samples.
+
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use crate::events::{Event, EventTracker};
+use crate::smooth::Smoother;
+use crate::{audio, level, simd, DetectedPitch, PitchDetector};
+
+/// A fixed-capacity buffer that keeps the most recent samples pushed into it.
+#[derive(Debug, Clone)]
+pub struct RingBuffer {
+    buf: Vec<f32>,
+    /// Index of the oldest sample (and the next slot to be overwritten).
+    head: usize,
+    len: usize,
+}
+
+impl RingBuffer {
+    pub fn new(capacity: usize) -> Self {
+        RingBuffer {
+            buf: vec![0.0; capacity],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.buf.len()
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        let cap = self.buf.len();
+        self.buf[(self.head + self.len) % cap] = sample;
+        if self.is_full() {
+            self.head = (self.head + 1) % cap;
+        } else {
+            self.len += 1;
+        }
+    }
+
+    /// Copy the contents, oldest first, into `out`.
+    pub fn copy_to(&self, out: &mut Vec<f32>) {
+        self.copy_latest_to(self.len, out);
+    }
+
+    /// Copy the latest `n` samples (or all of them, if there are fewer), oldest first, into `out`.
+    pub fn copy_latest_to(&self, n: usize, out: &mut Vec<f32>) {
+        out.clear();
+        let n = n.min(self.len);
+        let cap = self.buf.len();
+        let start = (self.head + self.len - n) % cap;
+        let end = (start + n).min(cap);
+        out.extend_from_slice(&self.buf[start..end]);
+        out.extend_from_slice(&self.buf[..(n - (end - start))]);
+    }
+}
+
+/// How many times a `SlidingAutocorrelation` slides before it's computed from scratch again, so
+/// rounding errors don't build up.
+const REFRESH_INTERVAL: usize = 64;
+
+/// The autocorrelation of a window sliding over a signal, at lags 1 to `lags`. Each slide
+/// subtracts the products of the samples leaving the window and adds those of the samples
+/// arriving, so a hop of `h` samples costs about `2 * h * lags` multiplications rather than
+/// `window * lags`.
+#[derive(Debug, Clone)]
+pub struct SlidingAutocorrelation {
+    acf: Vec<f64>,
+    /// The number of slides since it was last computed from scratch.
+    slides: usize,
+}
+
+impl SlidingAutocorrelation {
+    pub fn new(lags: usize) -> Self {
+        SlidingAutocorrelation {
+            acf: vec![0.0; lags],
+            slides: 0,
+        }
+    }
+
+    /// The autocorrelation, from a lag of 1.
+    pub fn get(&self) -> &[f64] {
+        &self.acf
+    }
+
+    /// Compute the autocorrelation of `window` from scratch.
+    pub fn reset(&mut self, window: &[f32]) {
+        for (i, dot_prod) in self.acf.iter_mut().enumerate() {
+            *dot_prod = simd::dot(window, &window[(i + 1).min(window.len())..]);
+        }
+        self.slides = 0;
+    }
+
+    /// Slide the window `hop` samples on. `extended` is the last window followed by the `hop`
+    /// samples that have arrived since.
+    pub fn slide(&mut self, extended: &[f32], hop: usize) {
+        let len = extended.len() - hop;
+        // Past `len - hop`, products leave and arrive at the same time, so it's no cheaper.
+        if self.slides >= REFRESH_INTERVAL || self.acf.len() + hop > len {
+            self.reset(&extended[hop..]);
+            return;
+        }
+
+        let (leaving, arriving) = (&extended[..hop], &extended[len..]);
+        for (i, dot_prod) in self.acf.iter_mut().enumerate() {
+            let lag = i + 1;
+            *dot_prod +=
+                simd::dot(&extended[len - lag..], arriving) - simd::dot(leaving, &extended[lag..]);
+        }
+        self.slides += 1;
+    }
+}
+
+/// The shortest window adaptive mode shrinks to, in samples.
+const MIN_ADAPTIVE_WINDOW: usize = 256;
+
+/// Reads little-endian 16-bit mono PCM from `reader` and runs the detector every `hop` samples
+/// over the last `chunk_size` samples, so results arrive much more often than once per chunk.
+///
+/// In adaptive mode, the window analyzed instead follows the pitch, holding a set number of its
+/// periods: high notes are detected sooner, and low notes still get the whole chunk.
+pub struct LiveInput<R> {
+    reader: R,
+    detector: PitchDetector,
+    ring: RingBuffer,
+    hop: usize,
+    /// The number of periods the window holds in adaptive mode.
+    adaptive: Option<usize>,
+    /// The number of latest samples analyzed.
+    window: usize,
+    scratch: Vec<f32>,
+    /// The autocorrelation of the window, when the detector can use it and the window doesn't
+    /// change size, so it's updated each hop rather than recomputed.
+    acf: Option<SlidingAutocorrelation>,
+    /// The window of the last estimate, when there's an `acf` to update.
+    last_window: Vec<f32>,
+    bytes: Vec<u8>,
+    /// The number of samples read so far.
+    position: u64,
+    /// When the last samples of the latest estimate's window were read.
+    arrived: Instant,
+    /// How long reading them waited for them to arrive.
+    input_time: Duration,
+    /// How long the latest estimate took to detect.
+    detect_time: Duration,
+}
+
+impl<R: Read> LiveInput<R> {
+    pub fn new(reader: R, detector: PitchDetector) -> Self {
+        let chunk_size = detector.chunk_size();
+        let acf = Some(SlidingAutocorrelation::new(
+            detector.monotonic_lags(chunk_size),
+        ))
+        .filter(|_| detector.uses_autocorrelation());
+        LiveInput {
+            reader,
+            detector,
+            ring: RingBuffer::new(chunk_size),
+            hop: (chunk_size / 4).max(1),
+            adaptive: None,
+            window: chunk_size,
+            scratch: Vec::with_capacity(chunk_size),
+            acf,
+            last_window: vec![],
+            bytes: vec![],
+            position: 0,
+            arrived: Instant::now(),
+            input_time: Duration::ZERO,
+            detect_time: Duration::ZERO,
+        }
+    }
+
+    /// Run the detector every `hop` new samples.
+    pub fn with_hop(mut self, hop: usize) -> Self {
+        self.hop = hop.max(1);
+        self
+    }
+
+    /// Size the window to hold `periods` periods of the latest pitch, from `MIN_ADAPTIVE_WINDOW`
+    /// samples up to the chunk size, and hop a quarter of it at a time.
+    pub fn with_adaptive(mut self, periods: usize) -> Self {
+        self.adaptive = Some(periods.max(1));
+        self.acf = None;
+        self
+    }
+
+    pub fn detector(&self) -> &PitchDetector {
+        &self.detector
+    }
+
+    /// The reader samples are read from.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// The number of samples the latest estimate was made from.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// When the window of the latest estimate starts, in seconds from the start of the stream.
+    pub fn timestamp(&self) -> f64 {
+        let start = self.position.saturating_sub(self.window as u64);
+        start as f64 / self.detector.sample_rate() as f64
+    }
+
+    /// The window of the latest estimate.
+    pub fn samples(&self) -> &[f32] {
+        &self.scratch
+    }
+
+    /// The number of samples between estimates: a quarter of the window, in adaptive mode.
+    pub fn hop(&self) -> usize {
+        match self.adaptive {
+            Some(_) => (self.window / 4).max(1),
+            None => self.hop,
+        }
+    }
+
+    /// When the last samples of the latest estimate's window were read, which its latency is
+    /// counted from.
+    pub fn arrived(&self) -> Instant {
+        self.arrived
+    }
+
+    /// How long the latest estimate waited for its last samples to arrive.
+    pub fn input_time(&self) -> Duration {
+        self.input_time
+    }
+
+    /// How long the latest estimate took to detect.
+    pub fn detect_time(&self) -> Duration {
+        self.detect_time
+    }
+
+    /// The RMS level of the window of the latest estimate, in dBFS.
+    pub fn level(&self) -> f64 {
+        level::to_dbfs(level::rms(&self.scratch))
+    }
+
+    /// Block until the next estimate is available. Returns `None` at the end of the stream.
+    pub fn next_estimate(&mut self) -> io::Result<Option<DetectedPitch>> {
+        loop {
+            // Until the ring is full, read enough to fill it; afterwards, one hop at a time.
+            let wanted = if !self.ring.is_full() {
+                self.ring.capacity() - self.ring.len
+            } else if self.adaptive.is_some() {
+                (self.window / 4).max(1)
+            } else {
+                self.hop
+            };
+
+            self.bytes.resize(wanted * 2, 0);
+            let reading = Instant::now();
+            if let Err(e) = self.reader.read_exact(&mut self.bytes) {
+                if e.kind() == io::ErrorKind::UnexpectedEof {
+                    return Ok(None);
+                }
+                return Err(e);
+            }
+            self.arrived = Instant::now();
+            self.input_time = self.arrived - reading;
+            self.position += wanted as u64;
+            for pair in self.bytes.chunks_exact(2) {
+                let sample = i16::from_le_bytes([pair[0], pair[1]]);
+                self.ring.push(sample as f32 * audio::int_scale(16));
+            }
+
+            if self.ring.is_full() {
+                let detecting = Instant::now();
+                self.ring.copy_latest_to(self.window, &mut self.scratch);
+                let detected = match &mut self.acf {
+                    Some(acf) => {
+                        if self.last_window.is_empty() || wanted >= self.scratch.len() {
+                            acf.reset(&self.scratch);
+                        } else {
+                            let window = self.scratch.len();
+                            self.last_window
+                                .extend_from_slice(&self.scratch[window - wanted..]);
+                            acf.slide(&self.last_window, wanted);
+                        }
+                        self.last_window.clone_from(&self.scratch);
+                        self.detector
+                            .detect_with_autocorrelation(&self.scratch, acf.get())
+                    }
+                    None => self.detector.detect(&self.scratch),
+                };
+                self.detect_time = detecting.elapsed();
+                if let Some(periods) = self.adaptive {
+                    self.window = self.adapt(periods, detected.freq);
+                }
+                return Ok(Some(detected));
+            }
+        }
+    }
+
+    /// Stream the estimates asynchronously, reading the input on a thread of its own.
+    #[cfg(feature = "async")]
+    pub fn into_stream(self) -> crate::stream::PitchStream
+    where
+        R: Send + 'static,
+    {
+        crate::stream::PitchStream::new(self)
+    }
+
+    /// Run to the end of the stream, calling `on_event` as notes start, bend and stop, at the end
+    /// of the window that showed it. The estimates are smoothed with a default `Smoother` first,
+    /// so that a note doesn't flicker on and off.
+    pub fn on_note_event(&mut self, mut on_event: impl FnMut(Event)) -> io::Result<()> {
+        let mut smoother = Smoother::default();
+        let mut tracker = EventTracker::new();
+        while let Some(detected) = self.next_estimate()? {
+            let detected = smoother.push(&self.detector, detected);
+            let time = self.position as f64 / self.detector.sample_rate() as f64;
+            tracker.push(time, &detected, self.level(), &mut on_event);
+        }
+        tracker.finish(on_event);
+        Ok(())
+    }
+
+    /// The window for holding `periods` periods of `freq`, or the whole chunk if there's no
+    /// frequency to go by.
+    fn adapt(&self, periods: usize, freq: Option<f64>) -> usize {
+        let chunk_size = self.ring.capacity();
+        match freq {
+            Some(freq) => {
+                let window = (periods as f64 * self.detector.sample_rate() as f64 / freq).ceil();
+                (window as usize).clamp(MIN_ADAPTIVE_WINDOW.min(chunk_size), chunk_size)
+            }
+            None => chunk_size,
+        }
+    }
+}
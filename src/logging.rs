@@ -0,0 +1,92 @@
+//! Logging what the detector decides about each chunk, and why, to stderr: a small stand-in for
+//! the `log` crate, which the detector doesn't depend on. Each message has a level, and is only
+//! written if the verbosity has reached it (`-v` for `Info` and `-vv` for `Debug`, on the command
+//! line), so that a chunk that came out as `Unknown` can be traced to the test that rejected it.
+//!
+//! Messages are written as a level, the context they were logged in (usually the chunk's time)
+//! and `key=value` fields, one per line, to be read or grepped:
+//!
+//! ```text
+//! [info] 1.250s: unknown: freq=187.3Hz confidence=0.31 min_confidence=0.50
+//! ```
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much detail a message goes into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// The settings in use, and why chunks have no pitch.
+    Info = 1,
+    /// Every chunk: where it is, what was found in it, and the statistics behind that.
+    Debug = 2,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Level::Info => "info",
+            Level::Debug => "debug",
+        })
+    }
+}
+
+/// The verbosity: 0 logs nothing, and each level up logs one more level of messages.
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+thread_local! {
+    /// What this thread is working on, to put before each message.
+    static CONTEXT: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+pub fn set_verbosity(verbosity: u8) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> u8 {
+    VERBOSITY.load(Ordering::Relaxed)
+}
+
+/// Whether messages at `level` are written. Check this before working anything out just to log
+/// it.
+pub fn enabled(level: Level) -> bool {
+    verbosity() >= level as u8
+}
+
+/// Put `context`, such as the time of the chunk being analyzed, before the messages this thread
+/// logs from now on, or nothing if it's `None`.
+pub fn set_context(context: Option<String>) {
+    CONTEXT.with(|current| *current.borrow_mut() = context);
+}
+
+/// `message` at `level` as it's written, in this thread's context.
+pub fn format(level: Level, message: fmt::Arguments) -> String {
+    CONTEXT.with(|context| match &*context.borrow() {
+        Some(context) => format!("[{}] {}: {}", level, context, message),
+        None => format!("[{}] {}", level, message),
+    })
+}
+
+/// Write `message` to stderr, if messages at `level` are.
+pub fn log(level: Level, message: fmt::Arguments) {
+    if enabled(level) {
+        eprintln!("{}", format(level, message));
+    }
+}
+
+/// Log a message at `Level::Info`, formatted as by `format!`.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Info, format_args!($($arg)*))
+    };
+}
+
+/// Log a message at `Level::Debug`, formatted as by `format!`.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logging::log($crate::logging::Level::Debug, format_args!($($arg)*))
+    };
+}
+
+pub(crate) use {debug, info};
@@ -1,146 +1,1733 @@
 //! A monotonic pitch finder.
 
-const SAMPLE_RATE: usize = 44100;
+mod cli;
 
-//const FREQ: usize = 1661;
-const FILE_DURATION: usize = 20; // seconds
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const MIN_DETECTABLE_FREQ: usize = 40; // Hz
+use monophonic_detector::{
+    abc,
+    audio::{self, Channels, ChunkReader, Pcm, Source},
+    devices, evaluate,
+    generate::{Generator, Sweep, Tones},
+    inharmonicity::Inharmonicity,
+    interrupt, interval,
+    json::ToJson,
+    key,
+    latency::{self, Stage, Timings},
+    level::Meter,
+    lilypond,
+    live::LiveInput,
+    logging::{self, Level},
+    midi, musicxml,
+    notes::{self, NoteEvent, NoteTracker},
+    osc::OscOut,
+    output::{
+        self, ChordRecord, ChordWriter, Columns, Format, IntervalWriter, NoteWriter, Record,
+        RecordWriter,
+    },
+    plot,
+    progress::Progress,
+    recording::Recorder,
+    scale,
+    score::{Key, TimeSignature, DEFAULT_TEMPO},
+    scoring,
+    smooth::Smoother,
+    spectrogram::Spectrogram,
+    summary::Summary,
+    tempo,
+    training::{self, Outcome, Trainer},
+    tuner,
+    tuning::Tuning,
+    vibrato::PitchTrack,
+    websocket, DetectedPitch, Error, Note, Pitch, PitchDetector, Result,
+};
 
-/// The number of minimum cycles in a buffer. We want more than one to make cycle detection more
-/// relaiable.
-const FUDGE_FACTOR: usize = 10;
+use crate::cli::{
+    AnalyzeArgs, Backend, Command, CompareArgs, DetectorOpts, EvalArgs, EvaluateArgs, GenerateArgs,
+    InputOpts, LiveArgs, ScoreArgs, ServeArgs, TempoArg, TrainArgs, TranscribeArgs,
+    TranscribeFormat, TuneArgs, TuningArg,
+};
 
-/// Number of samples needed to relaiably detect the minimum detectable freq.
-const CHUNK_SIZE: usize = FUDGE_FACTOR * SAMPLE_RATE / MIN_DETECTABLE_FREQ;
+const FILENAME: &str = "sine.wav";
 
-const NOTE_EPSILON: f64 = 1.0; // Hz
+/// The staircase of test tones generated by default.
+const TEST_FREQS: &[f64] = &[
+    130.81, 138.59, 146.83, 155.56, 164.81, 174.61, 185.00, 196.00, 207.65, 220.00, 233.08, 246.94,
+    261.63, 277.18, 293.66, 2349.32, 2489.02, 2637.02, 2793.83, 2959.96, 3135.96, 3322.44, 3520.00,
+    3729.31, 3951.07, 4186.01, 4434.92, 4698.63, 4978.03, 5274.04, 5587.65, 5919.91, 6271.93,
+    6644.88,
+];
 
-const FILENAME: &str = "sine.wav";
+/// How many chunks per thread are read and analyzed at once.
+const CHUNKS_PER_THREAD: usize = 16;
+
+/// How often `--watch` looks for new files. A file is analyzed once its size is the same two looks
+/// in a row.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How many of the best fitting scales `transcribe --format scales` lists.
+const SCALES_SHOWN: usize = 10;
+
+fn generate(args: &GenerateArgs) -> Result<()> {
+    let generator = args.generator(Tones::Steps(TEST_FREQS.to_vec()));
+    generator
+        .write_wav(&args.output)
+        .map_err(|e| context(e, format!("can't write {}", args.output.display())))
+}
+
+fn evaluate(args: &EvaluateArgs) -> Result<()> {
+    let generator = args.signal.generator(Tones::Sweep {
+        from: 110.0,
+        to: 1760.0,
+        sweep: Sweep::Exponential,
+    });
+    let detector = args.opts.detector(generator.sample_rate());
+    let tracking = evaluate::evaluate(&detector, &generator);
+
+    println!("chunks:     {}", tracking.chunks);
+    println!(
+        "detected:   {} ({:0.1}%)",
+        tracking.detected,
+        100.0 * tracking.detected as f64 / tracking.chunks.max(1) as f64
+    );
+    println!("mean error: {:0.2}¢", tracking.mean_cents);
+    println!("rms error:  {:0.2}¢", tracking.rms_cents);
+    println!("max error:  {:0.2}¢", tracking.max_cents);
+    Ok(())
+}
+
+/// Score what is detected in `args.input` against the reference annotation `args.reference`.
+fn eval(args: &EvalArgs) -> Result<()> {
+    let path = &args.reference;
+    let read = || -> Result<_> {
+        let text = fs::read_to_string(path)?;
+        evaluate::parse_reference(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    };
+    let reference = read().map_err(|e| context(e, format!("can't read {}", path.display())))?;
+
+    let mut estimates = vec![];
+    let mut chunk_duration = 0.0;
+    process_file(
+        &args.input,
+        args.pcm.pcm(),
+        &args.opts,
+        args.channels,
+        None,
+        show_progress(&args.opts),
+        |detector, _, _, detected, _| {
+            chunk_duration = detector.chunk_size() as f64 / detector.sample_rate() as f64;
+            let detected = &detected[0];
+            let voiced = !detected.rest && detected.confidence >= detector.min_confidence();
+            estimates.push(detected.freq.filter(|_| voiced));
+            Ok(())
+        },
+    )?;
+    let accuracy = evaluate::accuracy(&reference, &estimates, chunk_duration);
+
+    let percent = |ratio: f64| format!("{:0.1}%", 100.0 * ratio);
+    println!(
+        "frames:              {} ({} voiced)",
+        accuracy.frames, accuracy.voiced
+    );
+    println!("raw pitch accuracy:  {}", percent(accuracy.raw_pitch));
+    println!("raw chroma accuracy: {}", percent(accuracy.raw_chroma));
+    println!("voicing recall:      {}", percent(accuracy.voicing_recall));
+    println!(
+        "voicing precision:   {}",
+        percent(accuracy.voicing_precision)
+    );
+    println!(
+        "voicing false alarm: {}",
+        percent(accuracy.voicing_false_alarm)
+    );
+    println!("overall accuracy:    {}", percent(accuracy.overall));
+    Ok(())
+}
+
+fn score(args: &ScoreArgs) -> Result<()> {
+    let path = &args.melody;
+    let read = || -> Result<_> {
+        let midi = path.extension().is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("mid") || extension.eq_ignore_ascii_case("midi")
+        });
+        let melody = if midi {
+            midi::read_midi(&fs::read(path)?)
+        } else {
+            scoring::parse_melody(&fs::read_to_string(path)?)
+        };
+        melody.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    };
+    let melody = read().map_err(|e| context(e, format!("can't read {}", path.display())))?;
+
+    // Estimate the pitch of the latest chunk every hop, as `live` does, for a finer pitch track
+    // than a chunk at a time.
+    let source = audio::open_source(&args.input, args.pcm.pcm())?;
+    let factor = args.opts.decimation(source.sample_rate());
+    let detector = args.opts.detector(source.sample_rate() / factor);
+    let chunk_size = detector.chunk_size();
+    let hop = args.hop.unwrap_or(chunk_size / 4).max(1);
+    let mut smoother = args.opts.smoother();
+    let (mut window, mut performance) = (vec![], vec![]);
+    for (i, buffers) in ChunkReader::decimated(source, args.channels, hop, factor)?.enumerate() {
+        window.extend_from_slice(&buffers?[0]);
+        if window.len() > chunk_size {
+            window.drain(..window.len() - chunk_size);
+        }
+        if window.len() == chunk_size {
+            let detected = smooth(&mut smoother, &detector, detector.detect(&window));
+            let voiced = !detected.rest && detected.confidence >= detector.min_confidence();
+            // Each estimate is of the middle of its chunk.
+            let end = ((i + 1) * hop) as f64 / detector.sample_rate() as f64;
+            let duration = chunk_size as f64 / detector.sample_rate() as f64;
+            performance.push((end - duration / 2.0, detected.freq.filter(|_| voiced)));
+        }
+    }
+    if performance.iter().all(|(_, freq)| freq.is_none()) {
+        return Err(Error::NoPitchFound);
+    }
+
+    let score = scoring::score(&melody, &performance, detector.a4(), args.tolerance);
+    let percent = |ratio: f64| format!("{:.0}%", 100.0 * ratio);
+    println!("note    start   sung  in tune  cents");
+    for note in &score.notes {
+        let sung = note.sung as f64 / note.frames.max(1) as f64;
+        println!(
+            "{:<6} {:>6.2} {:>6} {:>8} {:>6}",
+            note.note.pitch.to_string(),
+            note.note.start,
+            percent(sung),
+            percent(note.accuracy()),
+            note.cents
+                .map_or("-".to_string(), |cents| format!("{:+.0}", cents)),
+        );
+    }
+    println!();
+    println!(
+        "offset: {:+.2} s (how far the performance is behind the melody)",
+        score.offset
+    );
+    println!("in tune: {:.1}%", 100.0 * score.accuracy);
+    Ok(())
+}
+
+fn main() {
+    let args = cli::with_config(std::env::args().skip(1).collect());
+    let command = match args.and_then(|args| cli::parse(args.into_iter())) {
+        Ok(command) => command,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, cli::USAGE);
+            process::exit(2);
+        }
+    };
+    if let Some(opts) = command.opts() {
+        logging::set_verbosity(opts.verbosity);
+    }
+    if let (Some(opts), Some(input)) = (command.opts(), command.input()) {
+        let sized = opts.chunk_size.is_some() || input.hop.is_some() || input.adaptive.is_some();
+        if input.target_latency.is_some() && sized {
+            eprintln!(
+                "error: --target-latency-ms sizes the chunk and hop, so not with --chunk-size, \
+                 --hop or --adaptive"
+            );
+            process::exit(2);
+        }
+    }
+
+    // These stop cleanly on Ctrl-C, finishing what they were writing.
+    if matches!(
+        command,
+        Command::Analyze(_) | Command::Transcribe(_) | Command::Live(_)
+    ) {
+        interrupt::catch();
+    }
 
-#[derive(Debug)]
-enum Note {
-    A,
-    ASharp,
-    B,
-    C,
-    CSharp,
-    D,
-    DSharp,
-    E,
-    F,
-    FSharp,
-    G,
-    GSharp,
-    Unknown,
-}
-
-macro_rules! notes {
-    ($test:expr, $($freq:literal => $note:ident),+ $(,)?) => {{
-        if false { Note::Unknown } else
-
-        $(
-            if f64_eq_ish($test, $freq) {
-                Note::$note
-            } else
-        )+
-
-        {
-            Note::Unknown
-        }
-    }}
-}
-
-fn generate_sound() {
-    let spec = hound::WavSpec {
-        channels: 1,
-        sample_rate: SAMPLE_RATE as u32,
-        bits_per_sample: 16,
-        sample_format: hound::SampleFormat::Int,
+    let result = match command {
+        Command::Demo => generate(&GenerateArgs {
+            output: FILENAME.into(),
+            ..GenerateArgs::default()
+        })
+        .and_then(|_| {
+            analyze(&AnalyzeArgs {
+                input: FILENAME.into(),
+                ..AnalyzeArgs::default()
+            })
+        }),
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::Analyze(args) => analyze(&args),
+        Command::Compare(args) => compare(&args),
+        Command::Transcribe(args) => transcribe(&args),
+        Command::Live(args) => live(&args),
+        Command::Tune(args) => tune(&args),
+        Command::Train(args) => train(&args),
+        Command::Serve(args) => serve(&args),
+        #[cfg(feature = "server")]
+        Command::Api(args) => api(&args),
+        Command::Devices => list_devices(),
+        Command::Generate(args) => generate(&args),
+        Command::Evaluate(args) => evaluate(&args),
+        Command::Score(args) => score(&args),
+        Command::Eval(args) => eval(&args),
     };
-    let mut writer = hound::WavWriter::create(FILENAME, spec).unwrap();
-    const N_SAMPLES: usize = SAMPLE_RATE * FILE_DURATION;
-    for i in 0..N_SAMPLES {
-        let t = i as f32 / (SAMPLE_RATE as f32);
 
-        const FREQS: &[f32] = &[
-            130.81, 138.59, 146.83, 155.56, 164.81, 174.61, 185.00, 196.00, 207.65, 220.00, 233.08,
-            246.94, 261.63, 277.18, 293.66, 2349.32, 2489.02, 2637.02, 2793.83, 2959.96, 3135.96,
-            3322.44, 3520.00, 3729.31, 3951.07, 4186.01, 4434.92, 4698.63, 4978.03, 5274.04,
-            5587.65, 5919.91, 6271.93, 6644.88,
-        ];
+    if let Err(e) = result {
+        // Whatever was reading the output, e.g. `head`, has seen all it wants.
+        if matches!(&e, Error::Io(e) if e.kind() == io::ErrorKind::BrokenPipe) {
+            return;
+        }
+        eprintln!("error: {}", e);
+        if let Error::TooShort { .. } = e {
+            eprintln!("try a smaller --chunk-size");
+        }
+        process::exit(exit_code(&e));
+    }
+    if interrupt::interrupted() {
+        eprintln!("interrupted");
+        process::exit(interrupt::EXIT_STATUS);
+    }
+}
 
-        let step = FREQS.len() * i / N_SAMPLES;
-        let f = FREQS[step];
+/// The exit status for a failure with `e`, as listed in the usage.
+fn exit_code(e: &Error) -> i32 {
+    match e {
+        Error::Io(_) => 1,
+        Error::Malformed(_)
+        | Error::UnsupportedFormat(_)
+        | Error::NoSuchChannel { .. }
+        | Error::TooShort { .. } => 3,
+        Error::NoPitchFound => 4,
+        Error::Usage(_) => 2,
+        Error::Failures { first, .. } => exit_code(first),
+    }
+}
 
-        let sample = (t * f * 2.0 * std::f32::consts::PI).sin();
-        let amplitude = i16::MAX as f32;
-        writer.write_sample((sample * amplitude) as i16).unwrap();
+/// Add `what` was being done to an I/O error `e`.
+fn context(e: Error, what: impl fmt::Display) -> Error {
+    match e {
+        Error::Io(e) => io::Error::new(e.kind(), format!("{}: {}", what, e)).into(),
+        e => e,
     }
 }
 
-fn compute_monotonic_freq(buffer: &[i16]) -> f64 {
-    let mut prev_dp = 0;
-    let mut is_increasing = false;
+fn analyze(args: &AnalyzeArgs) -> Result<()> {
+    let images = args.spectrogram.is_some() || args.plot.is_some();
+    if (args.midi.is_some() || args.notes || images) && args.channels == Channels::Each {
+        return Err(Error::Usage(
+            "--midi, --notes, --spectrogram and --plot need a single channel or the mix".into(),
+        ));
+    }
+    if args.pitch_bend && args.midi.is_none() {
+        return Err(Error::Usage(
+            "--pitch-bend bends the notes of --midi".into(),
+        ));
+    }
+    if args.dual && (args.notes || args.midi.is_some() || args.levels.is_some()) {
+        return Err(Error::Usage(
+            "--dual reports the pitches of each chunk, not --notes, --midi or --levels".into(),
+        ));
+    }
+    if args.chords && (args.notes || args.midi.is_some() || args.levels.is_some() || args.dual) {
+        return Err(Error::Usage(
+            "--chords reports the chord of each chunk, not --notes, --midi, --levels or \
+             --dual"
+                .into(),
+        ));
+    }
+    if args.tuning.is_some() && (args.notes || args.chords) {
+        return Err(Error::Usage(
+            "--tuning names the pitch of each chunk, not --notes or --chords".into(),
+        ));
+    }
+    if args.notes && args.levels.is_some() {
+        return Err(Error::Usage(
+            "--levels and --a-weighted report chunks, not --notes".into(),
+        ));
+    }
+    if args.summary
+        && (args.notes
+            || args.chords
+            || args.dual
+            || args.levels.is_some()
+            || args.tuning.is_some())
+    {
+        return Err(Error::Usage(
+            "--summary sums up the notes of the chunks, not --notes, --chords, --dual, \
+             --levels or --tuning"
+                .into(),
+        ));
+    }
+    if args.summary && args.channels == Channels::Each {
+        return Err(Error::Usage(
+            "--summary needs a single channel or the mix".into(),
+        ));
+    }
 
-    let mut maxes = vec![];
+    if args.watch && !args.input.is_dir() {
+        return Err(Error::Usage("--watch needs a directory".into()));
+    }
+    if args.input.is_dir() {
+        if args.midi.is_some() || images || args.pcm.format.is_some() {
+            return Err(Error::Usage(
+                "--midi, --spectrogram, --plot and --pcm need a single input file".into(),
+            ));
+        }
+        return analyze_dir(args);
+    }
+    analyze_file(
+        args,
+        &args.input,
+        io::stdout(),
+        args.threads,
+        show_progress(&args.opts),
+    )?;
+    Ok(())
+}
+
+/// Analyze every audio file in the directory `args.input` (and with `--recursive`, in its
+/// subdirectories), several files at a time. The results for each file go to a file beside it.
+fn analyze_dir(args: &AnalyzeArgs) -> Result<()> {
+    if args.watch {
+        return watch(args);
+    }
+
+    let mut inputs = vec![];
+    find_inputs(&args.input, args.recursive, &mut inputs)?;
+    inputs.sort();
+
+    let failures = analyze_files(args, &inputs);
+    if interrupt::interrupted() {
+        return Ok(());
+    }
+    let failed = failures.len();
+    eprintln!(
+        "analyzed {} of {} files",
+        inputs.len() - failed,
+        inputs.len()
+    );
+    match failures.into_iter().next() {
+        Some((_, first)) => Err(Error::Failures {
+            failed,
+            total: inputs.len(),
+            first: Box::new(first),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Keep analyzing audio files in the directory `args.input` as they appear (or change), once
+/// they've stopped growing, until interrupted. Files analyzed before are skipped if their
+/// results are newer than they are.
+fn watch(args: &AnalyzeArgs) -> Result<()> {
+    eprintln!("Watching {} for audio files", args.input.display());
+
+    // The size of each new file at the last look, to tell when it's been written.
+    let mut sizes = HashMap::new();
+    // When each file that couldn't be analyzed was last modified, so it's only retried once it
+    // changes.
+    let mut failed = HashMap::new();
+    while !interrupt::interrupted() {
+        let mut inputs = vec![];
+        find_inputs(&args.input, args.recursive, &mut inputs)?;
+        inputs.sort();
+
+        let mut ready = vec![];
+        for input in inputs {
+            let metadata = match fs::metadata(&input) {
+                Ok(metadata) => metadata,
+                // Moved or deleted since.
+                Err(_) => continue,
+            };
+            let modified = metadata.modified()?;
+            let analyzed = fs::metadata(output_path(&input, args))
+                .and_then(|output| output.modified())
+                .is_ok_and(|output| output >= modified);
+            if analyzed || failed.get(&input) == Some(&modified) {
+                continue;
+            }
+            if sizes.insert(input.clone(), metadata.len()) == Some(metadata.len()) {
+                sizes.remove(&input);
+                ready.push((input, modified));
+            }
+        }
+
+        let (inputs, modified): (Vec<_>, Vec<_>) = ready.into_iter().unzip();
+        let failures = analyze_files(args, &inputs);
+        for (input, modified) in inputs.into_iter().zip(modified) {
+            if failures.iter().any(|(failure, _)| *failure == input) {
+                failed.insert(input, modified);
+            } else {
+                failed.remove(&input);
+            }
+        }
 
-    for i in 1..buffer.len() {
-        // Take a suffix of the cloned_buf and align with the beginning of buffer (we're shifting
-        // backwards technically).
-        let shifted = &buffer[i..];
-        let dot_prod: i64 = buffer
+        thread::sleep(WATCH_INTERVAL);
+    }
+    Ok(())
+}
+
+/// Analyze `inputs`, several at a time, writing the results for each to a file beside it.
+/// Returns the files that couldn't be analyzed, and why.
+fn analyze_files(args: &AnalyzeArgs, inputs: &[PathBuf]) -> Vec<(PathBuf, Error)> {
+    // Spread the threads over the files, and any left over over each file's chunks.
+    let threads = args
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from));
+    let workers = threads.min(inputs.len()).max(1);
+    let threads_per_file = (threads / workers).max(1);
+
+    let queue = Mutex::new(inputs.iter());
+    let failures = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let input = match queue.lock().unwrap().next() {
+                    Some(input) if !interrupt::interrupted() => input,
+                    _ => break,
+                };
+                let output = output_path(input, args);
+                let result = File::create(&output)
+                    .map_err(|e| context(e.into(), format!("can't write {}", output.display())))
+                    .and_then(|file| {
+                        // Files are analyzed several at once, so their bars would overwrite
+                        // each other.
+                        let threads = Some(threads_per_file);
+                        analyze_file(args, input, BufWriter::new(file), threads, false)
+                    });
+                match result {
+                    // A file cut short is analyzed again next time.
+                    Ok(false) => {
+                        let _ = fs::remove_file(&output);
+                        break;
+                    }
+                    // A silent file is still analyzed.
+                    Ok(true) | Err(Error::NoPitchFound) => eprintln!("{}", output.display()),
+                    Err(e) => {
+                        let _ = fs::remove_file(&output);
+                        match e {
+                            // These already name the file.
+                            Error::Io(_) => eprintln!("error: {}", e),
+                            _ => eprintln!("error: {}: {}", input.display(), e),
+                        }
+                        failures.lock().unwrap().push((input.clone(), e));
+                    }
+                }
+            });
+        }
+    });
+    failures.into_inner().unwrap()
+}
+
+/// Add the files in `dir` that can be analyzed, and with `recursive`, those in its subdirectories,
+/// to `inputs`.
+fn find_inputs(dir: &Path, recursive: bool, inputs: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .map_err(|e| context(e.into(), format!("can't read {}", dir.display())))?;
+    for entry in entries {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if recursive {
+                find_inputs(&path, recursive, inputs)?;
+            }
+        } else if audio::is_supported(&path) {
+            inputs.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Where `analyze` writes the results for `input` when given a directory: `take.wav` to
+/// `take.json`, say. Other files keep their extension, so `take.mp3` goes to `take.mp3.json`
+/// rather than overwriting the results for `take.wav`.
+fn output_path(input: &Path, args: &AnalyzeArgs) -> PathBuf {
+    let mut extension = args.format.extension().to_string();
+    if args.notes {
+        extension = format!("notes.{}", extension);
+    } else if args.summary {
+        extension = format!("summary.{}", extension);
+    }
+    let wav = input
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+    if wav {
+        input.with_extension(extension)
+    } else {
+        let mut path = input.as_os_str().to_owned();
+        path.push(".");
+        path.push(extension);
+        path.into()
+    }
+}
+
+/// The tuning `--tuning` gives, reading it from its Scala file if need be, tuned to the detector's
+/// tonic and to its reference pitch as written, so transposed pitches are named as they are the
+/// twelve-tone way.
+fn tuning_of(tuning: &TuningArg, opts: &DetectorOpts) -> Result<Tuning> {
+    let tuning = match tuning {
+        TuningArg::Edo(tuning) => tuning.clone(),
+        TuningArg::Scala(path) => {
+            let read = || -> Result<_> {
+                let text = fs::read_to_string(path)?;
+                Tuning::from_scala(&text)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+            };
+            read().map_err(|e| context(e, format!("can't read {}", path.display())))?
+        }
+    };
+    Ok(tuning.with_reference(opts.written_a4(), opts.tonic.unwrap_or(Note::C)))
+}
+
+/// Analyze the audio file at `input` on `threads` threads, writing the results to `out`. Returns
+/// whether the whole file was analyzed, rather than cut short by an interrupt.
+fn analyze_file<W: io::Write>(
+    args: &AnalyzeArgs,
+    input: &Path,
+    out: W,
+    threads: Option<usize>,
+    progress: bool,
+) -> Result<bool> {
+    let mut summary = None;
+    let (mut records, mut note_writer, mut chords) = if args.summary {
+        summary = Some((out, Summary::new()));
+        (None, None, None)
+    } else if args.chords {
+        let chords = ChordWriter::new(out, args.format)?;
+        (None, None, Some(chords.with_note_names(args.note_names)))
+    } else if args.notes {
+        let notes = if args.vibrato {
+            NoteWriter::with_vibrato(out, args.format)?
+        } else {
+            NoteWriter::new(out, args.format)?
+        };
+        (None, Some(notes.with_note_names(args.note_names)), None)
+    } else {
+        let columns = Columns {
+            levels: args.levels.is_some(),
+            voices: args.dual,
+            inharmonicity: !args.dual && args.opts.fits_inharmonicity(),
+        };
+        let mut records =
+            RecordWriter::with_columns(out, args.format, columns)?.with_note_names(args.note_names);
+        if let Some(tuning) = &args.tuning {
+            records = records.with_tuning(tuning_of(tuning, &args.opts)?);
+        }
+        (Some(records), None, None)
+    };
+    let mut tracker = None;
+    let mut track = None;
+    let mut spectrogram = None;
+    let mut notes = vec![];
+    let mut found = false;
+
+    let whole = process_file(
+        input,
+        args.pcm.pcm(),
+        &args.opts,
+        args.channels,
+        threads,
+        progress,
+        |detector, timestamp, buffers, detected, fits| {
+            found |= detected.iter().any(|detected| detected.freq.is_some());
+            if let Some((_, summary)) = &mut summary {
+                let duration = detector.chunk_size() as f64 / detector.sample_rate() as f64;
+                summary.push(&counted(detector, detected[0]), duration);
+            }
+            match &mut records {
+                Some(out) if args.dual => write_voices(out, detector, timestamp, buffers)?,
+                Some(out) => write_records(
+                    out,
+                    detector,
+                    args.levels,
+                    timestamp,
+                    buffers,
+                    detected,
+                    fits,
+                )?,
+                None => {}
+            }
+            if let Some(out) = &mut chords {
+                write_chords(out, detector, timestamp, buffers)?;
+            }
+            if let [chunk] = buffers {
+                let tracker =
+                    tracker.get_or_insert_with(|| NoteTracker::new(detector.sample_rate()));
+                notes.extend(tracker.push(chunk, &detected[0]));
+                if args.vibrato || args.pitch_bend || args.plot.is_some() {
+                    let track =
+                        track.get_or_insert_with(|| PitchTrack::new(detector.sample_rate()));
+                    track.push(chunk, &detected[0]);
+                }
+                if args.spectrogram.is_some() {
+                    let spectrogram = spectrogram.get_or_insert_with(|| {
+                        let max_freq = detector.max_freq().map(|max| max as f64);
+                        Spectrogram::new(
+                            detector.sample_rate(),
+                            detector.min_freq() as f64,
+                            max_freq,
+                        )
+                    });
+                    spectrogram.push(chunk, &detected[0]);
+                }
+            }
+            if let Some(out) = &mut note_writer {
+                write_notes(out, &notes, track.as_ref().filter(|_| args.vibrato))?;
+            }
+            Ok(())
+        },
+    )?;
+
+    notes.extend(tracker.and_then(NoteTracker::finish));
+    if let Some(out) = records {
+        out.finish()?;
+    }
+    if let Some(out) = chords {
+        out.finish()?;
+    }
+    if let Some(mut out) = note_writer {
+        write_notes(&mut out, &notes, track.as_ref().filter(|_| args.vibrato))?;
+        out.finish()?;
+    }
+    if let Some((out, summary)) = summary {
+        output::write_summary(out, &summary, args.format, args.note_names)?;
+    }
+
+    if let Some(path) = &args.midi {
+        let bends = track.as_ref().filter(|_| args.pitch_bend);
+        write_midi(path, &notes, bends, args.opts.written_a4(), None)?;
+    }
+    if let (Some(path), Some(spectrogram)) = (&args.spectrogram, &spectrogram) {
+        let write = || spectrogram.write_png(BufWriter::new(File::create(path)?));
+        write().map_err(|e| context(e.into(), format!("can't write {}", path.display())))?;
+    }
+    if let Some((path, format)) = &args.plot {
+        let points = track.as_ref().map_or(&[][..], PitchTrack::points);
+        let a4 = args.opts.written_a4();
+        let write = || plot::write_plot(BufWriter::new(File::create(path)?), *format, points, a4);
+        write().map_err(|e| context(e.into(), format!("can't write {}", path.display())))?;
+    }
+
+    if found || !whole {
+        Ok(whole)
+    } else {
+        Err(Error::NoPitchFound)
+    }
+}
+
+/// Write the cleaned-up notes of a file.
+fn transcribe(args: &TranscribeArgs) -> Result<()> {
+    let mut tracker = None;
+    let mut track = None;
+    let mut notes = vec![];
+
+    process_file(
+        &args.input,
+        args.pcm.pcm(),
+        &args.opts,
+        args.channels,
+        args.threads,
+        show_progress(&args.opts),
+        |detector, _, buffers, detected, _| {
+            let tracker = tracker.get_or_insert_with(|| NoteTracker::new(detector.sample_rate()));
+            notes.extend(tracker.push(&buffers[0], &detected[0]));
+            if args.pitch_bend {
+                let track = track.get_or_insert_with(|| PitchTrack::new(detector.sample_rate()));
+                track.push(&buffers[0], &detected[0]);
+            }
+            Ok(())
+        },
+    )?;
+    notes.extend(tracker.and_then(NoteTracker::finish));
+
+    let min_duration = args.min_duration.unwrap_or(notes::DEFAULT_MIN_DURATION);
+    let notes = notes::clean_up(&notes, min_duration);
+    let estimate = key::estimate_key(&notes);
+    if let (None, Some(estimate), false) = (args.key, estimate, args.opts.quiet) {
+        eprintln!(
+            "Estimated key: {} (correlation {:.2})",
+            estimate.key, estimate.correlation
+        );
+    }
+    let tempo = match args.tempo {
+        Some(TempoArg::Bpm(bpm)) => Some(bpm),
+        Some(TempoArg::Auto) => match tempo::estimate_tempo(&tempo::onsets(&notes)) {
+            Some(estimate) => {
+                if !args.opts.quiet {
+                    eprintln!(
+                        "Estimated tempo: {:.1} bpm (strength {:.2})",
+                        estimate.bpm, estimate.strength
+                    );
+                }
+                Some(estimate.bpm)
+            }
+            None => {
+                if !args.opts.quiet {
+                    eprintln!(
+                        "Too few notes to estimate the tempo of; using {} bpm",
+                        DEFAULT_TEMPO
+                    );
+                }
+                Some(DEFAULT_TEMPO)
+            }
+        },
+        None => None,
+    };
+    let key = estimate.map_or_else(Key::default, |estimate| estimate.key);
+    let layout = args.layout(key, tempo);
+    let title = if args.input == Path::new(audio::STDIN) {
+        "Untitled".into()
+    } else {
+        args.input.file_stem().unwrap_or_default().to_string_lossy()
+    };
+
+    match args.format {
+        TranscribeFormat::List(format) if args.intervals => {
+            let mut out = IntervalWriter::new(io::stdout(), format)?;
+            for interval in interval::melodic_intervals(&notes) {
+                out.write(&interval)?;
+            }
+            out.finish()?;
+        }
+        TranscribeFormat::List(format) => {
+            let mut out = NoteWriter::new(io::stdout(), format)?.with_note_names(args.note_names);
+            write_notes(&mut out, &notes, None)?;
+            out.finish()?;
+        }
+        TranscribeFormat::Abc => {
+            abc::write_abc(io::stdout(), &title, &notes, &layout, args.note_length)?
+        }
+        TranscribeFormat::LilyPond => lilypond::write_lilypond(io::stdout(), &notes, &layout)?,
+        TranscribeFormat::MusicXml => {
+            musicxml::write_musicxml(io::stdout(), &title, &notes, &layout)?
+        }
+        TranscribeFormat::Scales => {
+            let mut out = io::stdout().lock();
+            for fit in scale::rank_scales(&notes).iter().take(SCALES_SHOWN) {
+                writeln!(
+                    out,
+                    "{:>3.0}%  {}/{}  {} {}",
+                    100.0 * fit.fit,
+                    fit.used,
+                    fit.scale.intervals().len(),
+                    fit.tonic,
+                    fit.scale
+                )?;
+            }
+        }
+    }
+
+    if let Some(path) = &args.midi {
+        let meter = tempo.map(|tempo| (tempo, args.time));
+        write_midi(path, &notes, track.as_ref(), args.opts.written_a4(), meter)?;
+    }
+
+    if notes.is_empty() {
+        Err(Error::NoPitchFound)
+    } else {
+        Ok(())
+    }
+}
+
+/// Read the WAV file (or with `pcm`, the PCM) at `input` a chunk at a time, and detect and smooth
+/// the pitch of each selected channel of each chunk. `f` is called in order with the detector,
+/// timestamp, buffers and results of each chunk, and the inharmonicity of each result, if the
+/// detector fits it. Returns whether that was every chunk, rather than
+/// being cut short by an interrupt.
+fn process_file(
+    input: &Path,
+    pcm: Option<Pcm>,
+    opts: &DetectorOpts,
+    channels: Channels,
+    threads: Option<usize>,
+    progress: bool,
+    f: impl FnMut(
+        &PitchDetector,
+        f64,
+        &[Vec<f32>],
+        &[DetectedPitch],
+        &[Option<Inharmonicity>],
+    ) -> Result<()>,
+) -> Result<bool> {
+    let source = audio::open_source(input, pcm)?;
+    process_source(source, opts, channels, threads, progress, f)
+}
+
+/// `detector.detect_parallel_with_inharmonicity`, with the autocorrelations on the GPU if there's
+/// one to use.
+#[cfg(feature = "gpu")]
+fn detect_batch(
+    detector: &PitchDetector,
+    buffers: &[&[f32]],
+    threads: usize,
+) -> Vec<(DetectedPitch, Option<Inharmonicity>)> {
+    use monophonic_detector::gpu::{self, Gpu};
+
+    gpu::detect_parallel(detector, Gpu::shared(), buffers, threads)
+}
+
+#[cfg(not(feature = "gpu"))]
+fn detect_batch(
+    detector: &PitchDetector,
+    buffers: &[&[f32]],
+    threads: usize,
+) -> Vec<(DetectedPitch, Option<Inharmonicity>)> {
+    detector.detect_parallel_with_inharmonicity(buffers, threads)
+}
+
+/// Whether to show a progress bar while analyzing a file: unless told not to, or stderr isn't a
+/// terminal to draw it in.
+fn show_progress(opts: &DetectorOpts) -> bool {
+    !opts.quiet && io::stderr().is_terminal()
+}
+
+/// Like `process_file`, for samples being read from `source`.
+fn process_source<R: io::Read>(
+    source: impl Into<Source<R>>,
+    opts: &DetectorOpts,
+    channels: Channels,
+    threads: Option<usize>,
+    progress: bool,
+    mut f: impl FnMut(
+        &PitchDetector,
+        f64,
+        &[Vec<f32>],
+        &[DetectedPitch],
+        &[Option<Inharmonicity>],
+    ) -> Result<()>,
+) -> Result<bool> {
+    let source = source.into();
+    let factor = opts.decimation(source.sample_rate());
+    let detector = opts.detector(source.sample_rate() / factor);
+    let chunks = ChunkReader::decimated(source, channels, detector.chunk_size(), factor)?;
+    log_settings(opts, &detector, factor);
+
+    let threads = threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from));
+    let seconds_per_chunk = detector.chunk_size() as f64 / detector.sample_rate() as f64;
+    let buffer_count = chunks.buffer_count();
+    let mut smoothers = vec![opts.smoother(); buffer_count];
+    let mut progress = Some(chunks.chunk_count())
+        .filter(|_| progress)
+        .map(|total| Progress::new(io::stderr(), total, seconds_per_chunk));
+    let mut chunks = chunks.enumerate();
+
+    // Detection is independent per chunk, so it's done a batch at a time in parallel; smoothing
+    // and output then go through the batch in order.
+    let mut whole = true;
+    loop {
+        let batch: Vec<_> = chunks.by_ref().take(threads * CHUNKS_PER_THREAD).collect();
+        if batch.is_empty() {
+            break;
+        }
+        if interrupt::interrupted() {
+            whole = false;
+            break;
+        }
+        let batch = batch
+            .into_iter()
+            .map(|(i, buffers)| buffers.map(|buffers| (i, buffers)))
+            .collect::<Result<Vec<_>>>()?;
+        let buffers: Vec<&[f32]> = batch
             .iter()
-            .zip(shifted.iter())
-            .map(|(a, b)| (*a as i64) * (*b as i64))
-            .sum();
+            .flat_map(|(_, buffers)| buffers.iter().map(Vec::as_slice))
+            .collect();
+        // What's logged about each chunk goes with its time, so when logging, chunks are detected
+        // one at a time, in order.
+        let logging = logging::enabled(Level::Info);
+        let detected = if logging {
+            batch
+                .iter()
+                .flat_map(|(i, buffers)| {
+                    let detector = &detector;
+                    buffers.iter().enumerate().map(move |(channel, buffer)| {
+                        let timestamp = *i as f64 * seconds_per_chunk;
+                        logging::set_context(Some(chunk_context(timestamp, channel, buffer_count)));
+                        logging::log(
+                            Level::Debug,
+                            format_args!(
+                                "chunk: samples={}..{}",
+                                i * detector.chunk_size(),
+                                (i + 1) * detector.chunk_size()
+                            ),
+                        );
+                        detector.detect_with_inharmonicity(buffer)
+                    })
+                })
+                .collect()
+        } else {
+            detect_batch(&detector, &buffers, threads)
+        };
 
-        // Did we find a local max?
-        if is_increasing && dot_prod < prev_dp {
-            maxes.push(i - 1);
+        for ((i, buffers), detected) in batch.iter().zip(detected.chunks(buffer_count)) {
+            let timestamp = *i as f64 * seconds_per_chunk;
+            let (detected, fits): (Vec<_>, Vec<_>) = detected
+                .iter()
+                .zip(&mut smoothers)
+                .enumerate()
+                .map(|(channel, ((detected, fit), smoother))| {
+                    if logging {
+                        logging::set_context(Some(chunk_context(timestamp, channel, buffer_count)));
+                    }
+                    (smooth(smoother, &detector, *detected), *fit)
+                })
+                .unzip();
+            logging::set_context(None);
+            f(&detector, timestamp, buffers, &detected, &fits)?;
+        }
+        if let Some(progress) = &mut progress {
+            progress.advance(batch.len())?;
         }
+    }
 
-        is_increasing = dot_prod > prev_dp;
-        prev_dp = dot_prod;
+    if let Some(progress) = progress {
+        progress.finish()?;
     }
+    Ok(whole)
+}
+
+/// Log the settings `detector` was made with from `opts`, for input decimated by `factor`.
+fn log_settings(opts: &DetectorOpts, detector: &PitchDetector, factor: usize) {
+    let hz = |freq: Option<f64>| freq.map_or("none".to_string(), |freq| format!("{}Hz", freq));
+    logging::log(
+        Level::Info,
+        format_args!(
+            "settings: sample_rate={}Hz decimation={} chunk_size={} algorithm={} window={} \
+             min_freq={}Hz max_freq={} min_confidence={:.2}",
+            detector.sample_rate(),
+            factor,
+            detector.chunk_size(),
+            detector.algorithm(),
+            format!("{:?}", opts.window).to_lowercase(),
+            detector.min_freq(),
+            hz(detector.max_freq().map(|max_freq| max_freq as f64)),
+            detector.min_confidence(),
+        ),
+    );
+    logging::log(
+        Level::Info,
+        format_args!(
+            "filters: highpass={} lowpass={} design={} formant_robust={} voicing={} \
+             octave_correction={}",
+            hz(opts.highpass),
+            hz(opts.lowpass),
+            format!("{:?}", opts.filter_design).to_lowercase(),
+            opts.formant_robust,
+            opts.voicing,
+            !opts.no_octave_correction,
+        ),
+    );
+}
+
+/// What's logged about the chunk at `timestamp` seconds, and `channel` of its `channels`
+/// buffers, goes with.
+fn chunk_context(timestamp: f64, channel: usize, channels: usize) -> String {
+    if channels > 1 {
+        format!("{:.3}s ch{}", timestamp, channel)
+    } else {
+        format!("{:.3}s", timestamp)
+    }
+}
+
+#[cfg(feature = "server")]
+fn api(args: &cli::ApiArgs) -> Result<()> {
+    eprintln!("Listening on http://{}", args.listen);
+    let result = monophonic_detector::http::serve(&args.listen, |upload| {
+        let reader = hound::WavReader::new(io::Cursor::new(upload))?;
+        let mut out = RecordWriter::new(vec![], Format::Json)?;
+        process_source(
+            reader,
+            &args.opts,
+            args.channels,
+            None,
+            false,
+            |_, timestamp, _, detected, _| {
+                let channels = detected.len();
+                for (channel, detected) in detected.iter().enumerate() {
+                    out.write(&Record {
+                        timestamp,
+                        channel: Some(channel).filter(|_| channels > 1),
+                        voice: None,
+                        detected: *detected,
+                        level: None,
+                        inharmonicity: None,
+                    })?;
+                }
+                Ok(())
+            },
+        )?;
+        // JSON output is always UTF-8.
+        Ok(String::from_utf8(out.finish()?).unwrap())
+    });
+    result.map_err(|e| context(e.into(), format!("can't listen on {}", args.listen)))
+}
+
+/// Write `notes` to a MIDI file at `path`, bent to follow `track` if there is one, relative to
+/// A4 at `a4` Hz, and quantized at the tempo and in the time signature of `meter` if there is one.
+fn write_midi(
+    path: &Path,
+    notes: &[NoteEvent],
+    track: Option<&PitchTrack>,
+    a4: f64,
+    meter: Option<(f64, TimeSignature)>,
+) -> Result<()> {
+    let write = || -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        let bends = track.map(|track| (track.points(), a4));
+        match (meter, bends) {
+            (Some((tempo, time)), bends) => {
+                midi::write_quantized_midi(file, notes, tempo, time, bends)?
+            }
+            (None, Some((points, a4))) => midi::write_midi_with_bends(file, notes, points, a4)?,
+            (None, None) => midi::write_midi(file, notes)?,
+        }
+        Ok(())
+    };
+    write().map_err(|e| context(e, format!("can't write {}", path.display())))
+}
+
+/// Write the `detected` pitches of one chunk's `buffers` as records, with the levels `meter`
+/// measures if given, and the inharmonicity `fits` found detecting them.
+fn write_records<W: io::Write>(
+    out: &mut RecordWriter<W>,
+    detector: &PitchDetector,
+    meter: Option<Meter>,
+    timestamp: f64,
+    buffers: &[Vec<f32>],
+    detected: &[DetectedPitch],
+    fits: &[Option<Inharmonicity>],
+) -> Result<()> {
+    let level = |buffer: &[f32]| meter.map(|meter| meter.measure(buffer, detector.sample_rate()));
+    match buffers {
+        [chunk] => {
+            out.write(&Record {
+                timestamp,
+                channel: None,
+                voice: None,
+                detected: detected[0],
+                level: level(chunk),
+                // Smoothing may leave a chunk unnamed that detection named.
+                inharmonicity: fits[0].filter(|_| detected[0].pitch.is_some()),
+            })?;
+            if out.format() == Format::Text && detected[0].freq.is_some() {
+                fft_stuff(out, detector, chunk)?;
+            }
+        }
 
-    // Compute the average difference between elements of `maxes`.
-    let sum: usize = maxes
+        // Report every channel's pitch for each chunk.
+        _ => {
+            for (channel, (buffer, detected)) in buffers.iter().zip(detected).enumerate() {
+                out.write(&Record {
+                    timestamp,
+                    channel: Some(channel),
+                    voice: None,
+                    detected: *detected,
+                    level: level(buffer),
+                    inharmonicity: None,
+                })?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the pitches detected at once in each of one chunk's `buffers`, as a record per voice.
+fn write_voices<W: io::Write>(
+    out: &mut RecordWriter<W>,
+    detector: &PitchDetector,
+    timestamp: f64,
+    buffers: &[Vec<f32>],
+) -> Result<()> {
+    for (channel, buffer) in buffers.iter().enumerate() {
+        for (voice, detected) in detector.detect_dual(buffer).into_iter().enumerate() {
+            out.write(&Record {
+                timestamp,
+                channel: Some(channel).filter(|_| buffers.len() > 1),
+                voice: Some(voice),
+                detected,
+                level: None,
+                inharmonicity: None,
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the chord recognized in each of one chunk's `buffers`.
+fn write_chords<W: io::Write>(
+    out: &mut ChordWriter<W>,
+    detector: &PitchDetector,
+    timestamp: f64,
+    buffers: &[Vec<f32>],
+) -> Result<()> {
+    for (channel, buffer) in buffers.iter().enumerate() {
+        out.write(&ChordRecord {
+            timestamp,
+            channel: Some(channel).filter(|_| buffers.len() > 1),
+            chord: detector.detect_chord(buffer),
+        })?;
+    }
+    Ok(())
+}
+
+/// Write the notes that ended since the last call, which are at the end of `notes`, with their
+/// vibrato on `track` if there is one.
+fn write_notes<W: io::Write>(
+    out: &mut NoteWriter<W>,
+    notes: &[NoteEvent],
+    track: Option<&PitchTrack>,
+) -> Result<()> {
+    for note in &notes[out.count()..] {
+        out.write_with_vibrato(note, track.and_then(|track| track.vibrato(note)))?;
+    }
+    Ok(())
+}
+
+/// Print what each of `args.algorithms` detects in each chunk, and then how much they disagree.
+fn compare(args: &CompareArgs) -> Result<()> {
+    let source = audio::open_source(&args.input, args.pcm.pcm())?;
+    let factor = args.opts.decimation(source.sample_rate());
+    let detector = args.opts.detector(source.sample_rate() / factor);
+    let detectors: Vec<_> = args
+        .algorithms
         .iter()
-        .zip(maxes.iter().skip(1))
-        .map(|(a, b)| b - a)
-        .skip(1)
-        .sum();
-    let avg_period = sum as f64 / ((maxes.len() - 2) as f64);
+        .map(|algorithm| detector.clone().with_algorithm(*algorithm))
+        .collect();
+
+    let chunks = ChunkReader::decimated(source, args.channels, detector.chunk_size(), factor)?;
 
-    (SAMPLE_RATE as f64) / avg_period
+    const WIDTH: usize = 18;
+    print!("{:>8}", "time");
+    for algorithm in &args.algorithms {
+        print!("  {:>width$}", algorithm.to_string(), width = WIDTH);
+    }
+    println!();
+
+    let seconds_per_chunk = detector.chunk_size() as f64 / detector.sample_rate() as f64;
+    let mut results = vec![vec![]; detectors.len()];
+    for (i, buffers) in chunks.enumerate() {
+        let chunk = &buffers?[0];
+        print!("{:>7.2}s", i as f64 * seconds_per_chunk);
+        for (detector, results) in detectors.iter().zip(&mut results) {
+            let detected = detector.detect(chunk);
+            let cell = match (detected.freq, detected.pitch) {
+                (Some(freq), Some(pitch)) => format!("{:.1} Hz {:<3}", freq, pitch.to_string()),
+                (Some(freq), None) => format!("{:.1} Hz {:<3}", freq, "?"),
+                (None, _) if detected.rest => "rest".into(),
+                (None, _) => "-".into(),
+            };
+            print!("  {:>width$}", cell, width = WIDTH);
+            results.push(detected);
+        }
+        println!();
+    }
+
+    println!();
+    for (i, a) in args.algorithms.iter().enumerate() {
+        for (j, b) in args.algorithms.iter().enumerate().skip(i + 1) {
+            let d = evaluate::disagreement(&results[i], &results[j]);
+            println!(
+                "{} vs {}: different notes in {} of {} chunks ({:.1}%), mean difference {:.2}¢",
+                a,
+                b,
+                d.different_notes,
+                d.chunks,
+                100.0 * d.different_notes as f64 / d.chunks.max(1) as f64,
+                d.mean_cents,
+            );
+        }
+    }
+    Ok(())
 }
 
-fn main() {
-    generate_sound();
+/// Detect pitches from raw 16-bit little-endian mono PCM on stdin, e.g.
+/// `arecord -f S16_LE -r 44100 -c 1 | pitch-detector live`.
+fn live(args: &LiveArgs) -> Result<()> {
+    let (reader, sample_rate) = live_reader(&args.input)?;
+    let path = match &args.record {
+        Some(path) => path,
+        None => return live_from(args, reader, sample_rate).map(drop),
+    };
+    let can_write = |e| context(e, format!("can't write {}", path.display()));
+    let recorder = Recorder::create(reader, path, sample_rate).map_err(can_write)?;
+    live_from(args, recorder, sample_rate)?
+        .finish()
+        .map_err(can_write)
+}
+
+/// Detect the pitch of the live input `args` describes, read from `reader` at `sample_rate` Hz,
+/// which is returned once it ends.
+fn live_from<R: io::Read>(args: &LiveArgs, reader: R, sample_rate: usize) -> Result<R> {
+    let mut input = live_input(&args.input, reader, sample_rate, &args.opts);
+    let mut out = match args.levels {
+        Some(_) => RecordWriter::with_levels(io::stdout(), args.format)?,
+        None => RecordWriter::new(io::stdout(), args.format)?,
+    }
+    .with_note_names(args.note_names);
+    let mut smoother = args.opts.smoother();
+    let mut midi_out = match &args.midi_out {
+        Some(path) => Some(midi::MidiOut::new(File::create(path).map_err(|e| {
+            context(e.into(), format!("can't open {}", path.display()))
+        })?)),
+        None => None,
+    };
+    let mut timings = Some(Timings::new()).filter(|_| args.latency);
+    let mut summary = Some(Summary::new()).filter(|_| args.summary);
+    let osc = match &args.osc {
+        Some(addr) => Some(
+            OscOut::connect(addr)
+                .map_err(|e| context(e.into(), format!("can't send to {}", addr)))?,
+        ),
+        None => None,
+    };
+
+    while let Some(detected) = input.next_estimate()? {
+        if interrupt::interrupted() {
+            break;
+        }
+        let output_start = Instant::now();
+        if let Some(summary) = &mut summary {
+            // Each estimate stands for the hop since the last.
+            let detector = input.detector();
+            let duration = input.hop() as f64 / detector.sample_rate() as f64;
+            summary.push(&counted(detector, detected), duration);
+        }
+        let detected = smooth(&mut smoother, input.detector(), detected);
+        if let Some(midi_out) = &mut midi_out {
+            let velocity = notes::velocity(input.level());
+            midi_out.play(detected.pitch, velocity)?;
+        }
+        if let Some(osc) = &osc {
+            osc.send(&detected)?;
+        }
+        out.write(&Record {
+            timestamp: input.timestamp(),
+            channel: None,
+            voice: None,
+            detected,
+            level: args
+                .levels
+                .map(|meter| meter.measure(input.samples(), input.detector().sample_rate())),
+            inharmonicity: None,
+        })?;
+        if let Some(timings) = &mut timings {
+            time_stages(timings, &input, output_start);
+        }
+    }
+
+    out.finish()?;
+    if let Some(timings) = &timings {
+        report_latency(timings, &input, args.input.target_latency);
+    }
+    if let Some(midi_out) = midi_out {
+        midi_out.finish()?;
+    }
+    if let Some(summary) = &summary {
+        output::write_summary(io::stderr(), summary, Format::Text, args.note_names)?;
+    }
+    Ok(input.into_inner())
+}
+
+/// Analyze `reader`, sampled at `sample_rate` Hz, as `input` and `opts` say, telling how a latency
+/// target was met.
+fn live_input<R: io::Read>(
+    input: &InputOpts,
+    reader: R,
+    sample_rate: usize,
+    opts: &DetectorOpts,
+) -> LiveInput<R> {
+    let live = input.input(reader, sample_rate, opts);
+    if let Some(target) = input.target_latency {
+        let detector = live.detector();
+        eprintln!(
+            "Targeting {} ms: a chunk of {} samples every {}, for notes above {} Hz",
+            target,
+            detector.chunk_size(),
+            live.hop(),
+            detector.min_freq()
+        );
+    }
+    live
+}
+
+/// Count the time each stage of `input`'s latest estimate took, the output having started at
+/// `output_start` and just finished.
+fn time_stages<R: io::Read>(timings: &mut Timings, input: &LiveInput<R>, output_start: Instant) {
+    let now = Instant::now();
+    let stages = [
+        (Stage::Input, input.input_time()),
+        (Stage::Detect, input.detect_time()),
+        (Stage::Output, now - output_start),
+        (Stage::Total, now - input.arrived()),
+    ];
+    for (stage, time) in stages.iter() {
+        timings.record(*stage, *time);
+    }
+    if logging::enabled(Level::Debug) {
+        let ms = |time: Duration| 1000.0 * time.as_secs_f64();
+        logging::log(
+            Level::Debug,
+            format_args!(
+                "latency: input={:.2}ms detect={:.2}ms output={:.2}ms total={:.2}ms",
+                ms(stages[0].1),
+                ms(stages[1].1),
+                ms(stages[2].1),
+                ms(stages[3].1)
+            ),
+        );
+    }
+}
 
-    let mut reader = hound::WavReader::open(FILENAME).unwrap();
-    let buffer = reader
-        .samples::<i16>()
-        .map(Result::unwrap)
-        .collect::<Vec<_>>();
+/// Write how long each stage took, and whether that met `target` milliseconds, on stderr.
+fn report_latency<R: io::Read>(timings: &Timings, input: &LiveInput<R>, target: Option<f64>) {
+    let detector = input.detector();
+    let buffering = latency::buffering(detector.chunk_size(), input.hop(), detector.sample_rate());
+    eprintln!(
+        "buffering: {:.1} ms (half a chunk of {} samples, and a hop of {}, at {} Hz)",
+        buffering,
+        detector.chunk_size(),
+        input.hop(),
+        detector.sample_rate()
+    );
+    eprintln!("{:<8} {:>8} {:>8} {:>8}", "stage", "mean", "p95", "max");
+    for stage in latency::STAGES.iter() {
+        if let Some(stats) = timings.stats(*stage) {
+            eprintln!(
+                "{:<8} {:>8.2} {:>8.2} {:>8.2}",
+                stage, stats.mean, stats.p95, stats.max
+            );
+        }
+    }
+    if let (Some(target), Some(total)) = (target, timings.stats(Stage::Total)) {
+        let latency = buffering + total.p95;
+        let verdict = if latency <= target { "met" } else { "missed" };
+        eprintln!(
+            "latency: {:.1} ms (buffering and the p95 of processing), {} the {} ms target",
+            latency, verdict, target
+        );
+    }
+}
+
+fn tune(args: &TuneArgs) -> Result<()> {
+    let (reader, sample_rate) = live_reader(&args.input)?;
+    let mut input = live_input(&args.input, reader, sample_rate, &args.opts);
+    let mut smoother = args.opts.smoother();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut strobe = Some(tuner::Strobe::new()).filter(|_| args.strobe);
+    let mut last_timestamp = 0.0;
+
+    write!(out, "{}", tuner::CLEAR)?;
+    while let Some(detected) = input.next_estimate()? {
+        let detected = smooth(&mut smoother, input.detector(), detected);
+        if let (Some(strobe), Some(_)) = (&mut strobe, detected.pitch) {
+            strobe.advance(detected.cents, input.timestamp() - last_timestamp);
+        }
+        last_timestamp = input.timestamp();
 
-    for i in 0..(buffer.len() / CHUNK_SIZE) {
-        let freq = compute_monotonic_freq(&buffer[(i * CHUNK_SIZE)..((i + 1) * CHUNK_SIZE)]);
-        let note = hz_to_note(freq);
-        println!("Estimated freq: {:0.0} Hz, {:?}", freq, note);
-        fft_stuff(&buffer[(i * CHUNK_SIZE)..((i + 1) * CHUNK_SIZE)]);
+        let frame = tuner::render(&detected, input.level(), strobe.as_ref());
+        write!(out, "{}{}", tuner::HOME, frame)?;
+        out.flush()?;
     }
+    Ok(())
 }
 
-fn fft_stuff(buffer: &[i16]) {
+/// Play random notes, and listen on stdin for each to be answered.
+fn train(args: &TrainArgs) -> Result<()> {
+    let (reader, sample_rate) = live_reader(&args.input)?;
+    let mut input = live_input(&args.input, reader, sample_rate, &args.opts);
+    let mut smoother = args.opts.smoother();
+    let sample_rate = input.detector().sample_rate();
+    let player: Vec<String> = match &args.player {
+        Some(player) => player.split_whitespace().map(String::from).collect(),
+        None => vec![
+            "aplay".into(),
+            "-q".into(),
+            "-f".into(),
+            "S16_LE".into(),
+            "-c".into(),
+            "1".into(),
+            "-r".into(),
+            sample_rate.to_string(),
+        ],
+    };
+    let seed = args.seed.unwrap_or_else(|| {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH);
+        now.map_or(1, |now| now.as_nanos() as u64)
+    });
+    let (low, high) = args.range;
+    let mut trainer = Trainer::new(low, high, input.detector().a4(), seed)
+        .with_tolerance(args.tolerance)
+        .with_timeout(args.timeout)
+        .with_exact_octave(args.exact_octave);
+
+    'rounds: while args
+        .rounds
+        .is_none_or(|rounds| trainer.stats().rounds < rounds)
+    {
+        let from = input.timestamp() + training::NOTE_DURATION + training::ECHO;
+        let target = trainer.next_note(from);
+        println!("round {}: listen...", trainer.stats().rounds + 1);
+        let note = Generator::new(Tones::Steps(vec![target.freq(trainer.a4())]))
+            .with_duration(training::NOTE_DURATION)
+            .with_amplitude(0.5)
+            .with_sample_rate(sample_rate);
+        play(&player, note)?;
+
+        let outcome = loop {
+            let detected = match input.next_estimate()? {
+                Some(detected) => smooth(&mut smoother, input.detector(), detected),
+                None => break 'rounds,
+            };
+            if let Some(outcome) =
+                trainer.listen(detected.pitch.and(detected.freq), input.timestamp())
+            {
+                break outcome;
+            }
+        };
+        match outcome {
+            Outcome::Hit { time, cents, .. } => println!(
+                "{}: got it in {:.1} s, {:+.0}¢ (streak: {})",
+                target,
+                time,
+                cents,
+                trainer.stats().streak
+            ),
+            Outcome::Miss {
+                heard: Some(freq), ..
+            } => println!(
+                "{}: missed, heard {}",
+                target,
+                input.detector().identify(freq)
+            ),
+            Outcome::Miss { heard: None, .. } => println!("{}: missed, heard nothing", target),
+        }
+    }
+
+    let stats = trainer.stats();
+    println!();
+    println!(
+        "got {} of {} ({:.1}%), best streak: {}",
+        stats.hits,
+        stats.rounds,
+        100.0 * stats.accuracy(),
+        stats.best_streak
+    );
+    if let (Some(time), Some(cents)) = (stats.mean_answer_time(), stats.mean_cents()) {
+        println!("on average: answered in {:.1} s, {:.0}¢ off", time, cents);
+    }
+    for (midi, note) in &stats.notes {
+        if note.hits < note.rounds {
+            let pitch = Pitch::from_midi(*midi);
+            println!(
+                "missed {}: {} of {}",
+                pitch,
+                note.rounds - note.hits,
+                note.rounds
+            );
+        }
+    }
+    Ok(())
+}
+
+/// List the devices live input can be recorded from.
+fn list_devices() -> Result<()> {
+    let found = devices::devices()?;
+    if found.is_empty() {
+        eprintln!("No ALSA devices or JACK ports found; --capture can record from any other");
+    }
+    for device in found {
+        println!("{}", device);
+    }
+    Ok(())
+}
+
+/// The live input `opts` describes, and its sample rate: stdin, the output of the command
+/// capturing it, an ALSA device or a JACK input port.
+fn live_reader(opts: &InputOpts) -> Result<(Box<dyn io::Read>, usize)> {
+    if !opts.connect.is_empty() && opts.backend != Backend::Jack {
+        return Err(Error::Usage(
+            "--connect connects JACK ports, with --backend jack".into(),
+        ));
+    }
+    if opts.device.is_some() && opts.backend != Backend::Alsa {
+        return Err(Error::Usage(
+            "--device is an ALSA device, with --backend alsa".into(),
+        ));
+    }
+    if opts.buffer_size.is_some() && opts.backend == Backend::Stdin {
+        return Err(Error::Usage(
+            "--buffer-size is the buffer of --backend alsa or jack".into(),
+        ));
+    }
+    if opts.capture.is_some() && opts.backend != Backend::Stdin {
+        return Err(Error::Usage(
+            "--loopback and --capture read instead of stdin, not --backend alsa or jack".into(),
+        ));
+    }
+    let command = match (opts.backend, &opts.capture) {
+        (Backend::Jack, _) => return jack_reader(opts),
+        (Backend::Alsa, _) => opts.alsa_command(),
+        (Backend::Stdin, Some(capture)) => capture.command(opts.sample_rate),
+        (Backend::Stdin, None) => return Ok((Box::new(io::stdin().lock()), opts.sample_rate)),
+    };
+    let (program, args) = match command.split_first() {
+        Some(command) => command,
+        None => return Ok((Box::new(io::empty()), opts.sample_rate)),
+    };
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdin(process::Stdio::null())
+        .stdout(process::Stdio::piped())
+        .spawn()
+        .map_err(|e| context(e.into(), format!("can't run {}", program)))?;
+    let stdout = child.stdout.take().unwrap();
+    Ok((Box::new(Captured { child, stdout }), opts.sample_rate))
+}
+
+/// An input port of the running JACK server, at the server's sample rate.
+#[cfg(feature = "jack")]
+fn jack_reader(opts: &InputOpts) -> Result<(Box<dyn io::Read>, usize)> {
+    use monophonic_detector::jack::{self, JackInput};
+
+    let input = JackInput::open(jack::CLIENT_NAME, &opts.connect)?;
+    if let Some(frames) = opts.buffer_size {
+        input.set_buffer_size(frames)?;
+    }
+    eprintln!(
+        "Listening on JACK port {} at {} Hz",
+        input.port_name(),
+        input.sample_rate()
+    );
+    let sample_rate = input.sample_rate();
+    Ok((Box::new(input), sample_rate))
+}
+
+#[cfg(not(feature = "jack"))]
+fn jack_reader(_: &InputOpts) -> Result<(Box<dyn io::Read>, usize)> {
+    unreachable!("--backend jack is only parsed with the jack feature")
+}
+
+/// The output of a command capturing live input, which is stopped once it's no longer read.
+struct Captured {
+    child: process::Child,
+    stdout: process::ChildStdout,
+}
+
+impl io::Read for Captured {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stdout.read(buf)
+    }
+}
+
+impl Drop for Captured {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Play `note` with `command`, which reads s16le PCM on stdin, without waiting for it to finish.
+fn play(command: &[String], note: Generator) -> Result<()> {
+    let (program, args) = match command.split_first() {
+        Some(command) => command,
+        None => return Ok(()),
+    };
+    let mut child = process::Command::new(program)
+        .args(args)
+        .stdin(process::Stdio::piped())
+        .spawn()
+        .map_err(|e| context(e.into(), format!("can't run {}", program)))?;
+    let stdin = child.stdin.take();
+    thread::spawn(move || {
+        if let Some(stdin) = stdin {
+            // The player may have quit early; the game goes on without the note.
+            let _ = note.write_pcm(stdin);
+        }
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+fn serve(args: &ServeArgs) -> Result<()> {
+    let server = websocket::Server::bind(&args.listen)
+        .map_err(|e| context(e.into(), format!("can't listen on {}", args.listen)))?;
+    eprintln!("Listening on ws://{}", args.listen);
+
+    let (reader, sample_rate) = live_reader(&args.input)?;
+    let mut input = live_input(&args.input, reader, sample_rate, &args.opts);
+    let mut smoother = args.opts.smoother();
+
+    while let Some(detected) = input.next_estimate()? {
+        let record = Record {
+            timestamp: input.timestamp(),
+            channel: None,
+            voice: None,
+            detected: smooth(&mut smoother, input.detector(), detected),
+            level: None,
+            inharmonicity: None,
+        };
+        server.send(&record.to_json().to_string());
+    }
+    Ok(())
+}
+
+fn smooth(
+    smoother: &mut Option<Smoother>,
+    detector: &PitchDetector,
+    detected: DetectedPitch,
+) -> DetectedPitch {
+    match smoother {
+        Some(smoother) => {
+            let smoothed = smoother.push(detector, detected);
+            if smoothed.pitch != detected.pitch {
+                let name =
+                    |pitch: Option<Pitch>| pitch.map_or("none".to_string(), |p| p.to_string());
+                logging::log(
+                    Level::Debug,
+                    format_args!(
+                        "smoothed: pitch={} smoothed={}",
+                        name(detected.pitch),
+                        name(smoothed.pitch)
+                    ),
+                );
+            }
+            smoothed
+        }
+        None => detected,
+    }
+}
+
+/// `detected` as a summary counts it: on its own nearest note, as smoothing holds on to a note
+/// through the next one's first chunks. Estimates too unsure to have been named stay unnamed.
+fn counted(detector: &PitchDetector, detected: DetectedPitch) -> DetectedPitch {
+    match detected.freq {
+        Some(freq) if detected.pitch.is_some() => DetectedPitch {
+            confidence: detected.confidence,
+            ..detector.identify(freq)
+        },
+        _ => detected,
+    }
+}
+
+fn fft_stuff<W: io::Write>(
+    out: &mut RecordWriter<W>,
+    detector: &PitchDetector,
+    buffer: &[f32],
+) -> io::Result<()> {
     use rustfft::{num_complex::Complex, num_traits::Zero, FFTplanner};
 
     // Play around with FFT...
-    let mut buffer: Vec<_> = buffer
-        .iter()
-        .map(|re| Complex::new(*re as f32, 0.0))
-        .collect();
+    let mut buffer: Vec<_> = buffer.iter().map(|re| Complex::new(*re, 0.0)).collect();
     let mut fft_buffer = vec![Complex::zero(); buffer.len()];
     let mut planner = FFTplanner::new(false);
     let fft = planner.plan_fft(buffer.len());
@@ -154,124 +1741,7 @@ fn fft_stuff(buffer: &[i16]) {
         .map(|(i, v)| (i, v.norm().log10() * 20.0))
         .max_by_key(|(_, v)| *v as usize)
         .unwrap();
-    let freq = i as f64 * (SAMPLE_RATE as f64) / (len as f64);
-    println!("                {} Hz, {:?}", freq, hz_to_note(freq));
-}
-
-fn hz_to_note(freq: f64) -> Note {
-    fn f64_eq_ish(a: f64, b: f64) -> bool {
-        (a - b).abs() < NOTE_EPSILON
-    }
-
-    notes! {
-        freq,
-    16.35 => C,
-    17.32 => CSharp,
-    18.35 => D,
-    19.45 => DSharp,
-    20.60 => E,
-    21.83 => F,
-    23.12 => FSharp,
-    24.50 => G,
-    25.96 => GSharp,
-    27.50 => A,
-    29.14 => ASharp,
-    30.87 => B,
-    32.70 => C,
-    34.65 => CSharp,
-    36.71 => D,
-    38.89 => DSharp,
-    41.20 => E,
-    43.65 => F,
-    46.25 => FSharp,
-    49.00 => G,
-    51.91 => GSharp,
-    55.00 => A,
-    58.27 => ASharp,
-    61.74 => B,
-    65.41 => C,
-    69.30 => CSharp,
-    73.42 => D,
-    77.78 => DSharp,
-    82.41 => E,
-    87.31 => F,
-    92.50 => FSharp,
-    98.00 => G,
-    103.83 => GSharp,
-    110.00 => A,
-    116.54 => ASharp,
-    123.47 => B,
-    130.81 => C,
-    138.59 => CSharp,
-    146.83 => D,
-    155.56 => DSharp,
-    164.81 => E,
-    174.61 => F,
-    185.00 => FSharp,
-    196.00 => G,
-    207.65 => GSharp,
-    220.00 => A,
-    233.08 => ASharp,
-    246.94 => B,
-    261.63 => C,
-    277.18 => CSharp,
-    293.66 => D,
-    311.13 => DSharp,
-    329.63 => E,
-    349.23 => F,
-    369.99 => FSharp,
-    392.00 => G,
-    415.30 => GSharp,
-    440.00 => A,
-    466.16 => ASharp,
-    493.88 => B,
-    523.25 => C,
-    554.37 => CSharp,
-    587.33 => D,
-    622.25 => DSharp,
-    659.25 => E,
-    698.46 => F,
-    739.99 => FSharp,
-    783.99 => G,
-    830.61 => GSharp,
-    880.00 => A,
-    932.33 => ASharp,
-    987.77 => B,
-    1046.50 => C,
-    1108.73 => CSharp,
-    1174.66 => D,
-    1244.51 => DSharp,
-    1318.51 => E,
-    1396.91 => F,
-    1479.98 => FSharp,
-    1567.98 => G,
-    1661.22 => GSharp,
-    1760.00 => A,
-    1864.66 => ASharp,
-    1975.53 => B,
-    2093.00 => C,
-    2217.46 => CSharp,
-    2349.32 => D,
-    2489.02 => DSharp,
-    2637.02 => E,
-    2793.83 => F,
-    2959.96 => FSharp,
-    3135.96 => G,
-    3322.44 => GSharp,
-    3520.00 => A,
-    3729.31 => ASharp,
-    3951.07 => B,
-    4186.01 => C,
-    4434.92 => CSharp,
-    4698.63 => D,
-    4978.03 => DSharp,
-    5274.04 => E,
-    5587.65 => F,
-    5919.91 => FSharp,
-    6271.93 => G,
-    6644.88 => GSharp,
-    7040.00 => A,
-    7458.62 => ASharp,
-    7902.13 => B,
-    }
+    let freq = i as f64 * (detector.sample_rate() as f64) / (len as f64);
+    let pitch = out.describe(&detector.identify(freq));
+    writeln!(out.get_mut(), "                {} Hz, {}", freq, pitch)
 }
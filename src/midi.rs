@@ -0,0 +1,414 @@
+//! Standard MIDI File export, and reading melodies back from Standard MIDI Files.
+
+use std::io::{self, Write};
+
+use crate::notes::NoteEvent;
+use crate::score::{self, TimeSignature};
+use crate::{cents_between, Pitch};
+
+/// Ticks per quarter note.
+const DIVISION: u16 = 480;
+
+/// Microseconds per quarter note (i.e. 120 bpm), so there are `2 * DIVISION` ticks per second,
+/// unless the file is written at another tempo.
+const TEMPO: u32 = 500_000;
+
+/// MIDI clocks per quarter note, which time signatures give the metronome's clicks in.
+const CLOCKS: u32 = 24;
+
+/// How far the pitch bend wheel bends, either way, in semitones. It's set explicitly, though this
+/// is the General MIDI default.
+const BEND_RANGE: u8 = 2;
+
+/// The pitch bend value of the unbent pitch. Values go from 0 to twice this, less one.
+const BEND_CENTER: u16 = 8192;
+
+/// Bends are only sent when the pitch has moved this far since the last, in cents, to keep the
+/// file small.
+const BEND_STEP: f64 = 1.0;
+
+/// Write `notes`, which must be in order and not overlap, as a single-track (format 0) MIDI file.
+pub fn write_midi<W: Write>(out: W, notes: &[NoteEvent]) -> io::Result<()> {
+    write_track(out, notes, None, None)
+}
+
+/// Write `notes` as `write_midi` does, with pitch bends on top following `track`, the time in
+/// seconds and frequency in Hz of pitch estimates (such as those of a `vibrato::PitchTrack`),
+/// relative to the equal-tempered pitch of each note with A4 at `a4` Hz. Bends reach
+/// `BEND_RANGE` semitones either way, so the slides and vibrato within notes survive.
+pub fn write_midi_with_bends<W: Write>(
+    out: W,
+    notes: &[NoteEvent],
+    track: &[(f64, f64)],
+    a4: f64,
+) -> io::Result<()> {
+    write_track(out, notes, Some((track, a4)), None)
+}
+
+/// Write `notes` as `write_midi_with_bends` does (or without bends, if `track` is `None`), but
+/// quantized to sixteenth notes at `tempo` quarter notes per minute, as scores are, with that
+/// tempo and `time` set in the file, so that the notes fall on its beats and measures.
+pub fn write_quantized_midi<W: Write>(
+    out: W,
+    notes: &[NoteEvent],
+    tempo: f64,
+    time: TimeSignature,
+    track: Option<(&[(f64, f64)], f64)>,
+) -> io::Result<()> {
+    let notes = score::quantize_notes(notes, tempo);
+    write_track(out, &notes, track, Some((tempo, time)))
+}
+
+fn write_track<W: Write>(
+    mut out: W,
+    notes: &[NoteEvent],
+    bends: Option<(&[(f64, f64)], f64)>,
+    meter: Option<(f64, TimeSignature)>,
+) -> io::Result<()> {
+    let tempo = meter.map_or(TEMPO, |(tempo, _)| (60_000_000.0 / tempo).round() as u32);
+    let ticks_per_second = 1_000_000.0 / tempo as f64 * DIVISION as f64;
+    let to_ticks = |seconds: f64| (seconds * ticks_per_second).round() as u32;
+
+    let mut track = Track::default();
+
+    // Set the tempo, and the time signature if there is one, at time 0.
+    track.event(0, &[0xff, 0x51, 0x03]);
+    track.bytes.extend_from_slice(&tempo.to_be_bytes()[1..]);
+    if let Some((_, time)) = meter {
+        let clocks = (CLOCKS * 4 / time.beat_type).max(1) as u8;
+        let beat_type = time.beat_type.trailing_zeros() as u8;
+        // 8 thirty-second notes to a quarter note, as always.
+        track.event(
+            0,
+            &[
+                0xff,
+                0x58,
+                0x04,
+                time.beats.min(255) as u8,
+                beat_type,
+                clocks,
+                8,
+            ],
+        );
+    }
+
+    if bends.is_some() {
+        // Set the bend range with registered parameter 0, and leave no parameter selected.
+        track.event(0, &[0xb0, 101, 0]);
+        track.event(0, &[0xb0, 100, 0]);
+        track.event(0, &[0xb0, 6, BEND_RANGE]);
+        track.event(0, &[0xb0, 38, 0]);
+        track.event(0, &[0xb0, 101, 127]);
+        track.event(0, &[0xb0, 100, 127]);
+    }
+
+    for note in notes {
+        let key = note.pitch.midi().clamp(0, 127) as u8;
+        let (start, end) = (to_ticks(note.start), to_ticks(note.end));
+
+        match bends {
+            Some((points, a4)) => {
+                let freq = note.pitch.freq(a4);
+                let mut points = points
+                    .iter()
+                    .filter(|(time, _)| (note.start..note.end).contains(time))
+                    .map(|&(time, point)| (to_ticks(time), cents_between(freq, point)));
+
+                // Start the note already bent, so it doesn't swoop from the unbent pitch.
+                let mut bent = points.next().map_or(0.0, |(_, cents)| cents);
+                track.event(start, &bend(bent));
+                track.event(start, &[0x90, key, note.velocity]);
+                for (tick, cents) in points {
+                    if (cents - bent).abs() >= BEND_STEP {
+                        track.event(tick, &bend(cents));
+                        bent = cents;
+                    }
+                }
+            }
+            None => track.event(start, &[0x90, key, note.velocity]),
+        }
+        track.event(end, &[0x80, key, 0]);
+    }
+
+    if bends.is_some() {
+        track.event(track.now, &bend(0.0));
+    }
+
+    // End of track.
+    track.event(track.now, &[0xff, 0x2f, 0x00]);
+    let track = track.bytes;
+
+    out.write_all(b"MThd")?;
+    out.write_all(&6u32.to_be_bytes())?;
+    out.write_all(&0u16.to_be_bytes())?; // format 0
+    out.write_all(&1u16.to_be_bytes())?; // one track
+    out.write_all(&DIVISION.to_be_bytes())?;
+
+    out.write_all(b"MTrk")?;
+    out.write_all(&(track.len() as u32).to_be_bytes())?;
+    out.write_all(&track)
+}
+
+/// The events of a MIDI track being written.
+#[derive(Default)]
+struct Track {
+    bytes: Vec<u8>,
+    /// The time of the last event, in ticks.
+    now: u32,
+}
+
+impl Track {
+    /// Append `message` at `tick`, or now if that's already past.
+    fn event(&mut self, tick: u32, message: &[u8]) {
+        let tick = tick.max(self.now);
+        write_vlq(&mut self.bytes, tick - self.now);
+        self.bytes.extend_from_slice(message);
+        self.now = tick;
+    }
+}
+
+/// A pitch bend message bending by `cents`, as far as the bend range allows.
+fn bend(cents: f64) -> [u8; 3] {
+    let range = BEND_RANGE as f64 * 100.0;
+    let value = (BEND_CENTER as f64 * (1.0 + cents / range))
+        .round()
+        .clamp(0.0, (2 * BEND_CENTER - 1) as f64) as u16;
+    [0xe0, (value & 0x7f) as u8, (value >> 7) as u8]
+}
+
+/// Sends MIDI note-on and note-off messages to `out`, e.g. a raw MIDI device such as the virtual
+/// ports of the `snd-virmidi` kernel module, as the pitch being played changes.
+pub struct MidiOut<W: Write> {
+    out: W,
+    /// The key of the note playing, if any.
+    playing: Option<u8>,
+}
+
+impl<W: Write> MidiOut<W> {
+    pub fn new(out: W) -> Self {
+        MidiOut { out, playing: None }
+    }
+
+    /// Play `pitch`, or nothing if it is `None`, at `velocity`. A note already playing carries
+    /// on; any other is stopped first.
+    pub fn play(&mut self, pitch: Option<Pitch>, velocity: u8) -> io::Result<()> {
+        let key = pitch.map(|pitch| pitch.midi().clamp(0, 127) as u8);
+        if key == self.playing {
+            return Ok(());
+        }
+
+        if let Some(playing) = self.playing.take() {
+            self.out.write_all(&[0x80, playing, 0])?;
+        }
+        if let Some(key) = key {
+            self.out.write_all(&[0x90, key, velocity.clamp(1, 127)])?;
+            self.playing = Some(key);
+        }
+        self.out.flush()
+    }
+
+    /// Stop any note playing and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.play(None, 0)?;
+        Ok(self.out)
+    }
+}
+
+/// The channel of percussion in General MIDI (counting from 0), whose notes aren't pitches.
+const PERCUSSION: u8 = 9;
+
+/// Read the melody of the Standard MIDI File `file`: the notes of every track and channel but
+/// percussion, in order, with their times in seconds following the file's tempo changes. Where
+/// notes overlap, only the highest of those starting together is kept, and each is cut short
+/// where the next starts, so the melody is one note at a time.
+pub fn read_midi(file: &[u8]) -> Result<Vec<NoteEvent>, String> {
+    let mut reader = Reader {
+        bytes: file,
+        pos: 0,
+    };
+    if reader.take(4)? != b"MThd" {
+        return Err("not a MIDI file".into());
+    }
+    let len = reader.u32()? as usize;
+    let header = reader.take(len)?;
+    if header.len() < 6 {
+        return Err("truncated MIDI header".into());
+    }
+    let tracks = u16::from_be_bytes([header[2], header[3]]);
+    let division = u16::from_be_bytes([header[4], header[5]]);
+
+    // Every note and tempo change, by tick.
+    let mut notes = vec![];
+    let mut tempos = vec![(0, TEMPO)];
+    for _ in 0..tracks {
+        let id = reader.take(4)?;
+        let len = reader.u32()? as usize;
+        let track = reader.take(len)?;
+        if id == b"MTrk" {
+            read_track(track, &mut notes, &mut tempos)?;
+        }
+    }
+    tempos.sort_by_key(|(tick, _)| *tick);
+
+    // Seconds from ticks, through the tempo map unless the division is in SMPTE frames.
+    let seconds = |tick: u64| -> f64 {
+        if division & 0x8000 != 0 {
+            let frames_per_second = -((division >> 8) as i8) as f64;
+            let ticks_per_frame = (division & 0xff) as f64;
+            return tick as f64 / (frames_per_second * ticks_per_frame);
+        }
+        let (mut seconds, mut last, mut tempo) = (0.0, 0, TEMPO);
+        for &(at, next) in tempos.iter().take_while(|(at, _)| *at < tick) {
+            seconds += (at - last) as f64 * tempo as f64 / 1e6 / division.max(1) as f64;
+            last = at;
+            tempo = next;
+        }
+        seconds + (tick - last) as f64 * tempo as f64 / 1e6 / division.max(1) as f64
+    };
+
+    // The highest of the notes starting together first, so the others are dropped.
+    notes.sort_by_key(|(start, _, key, _)| (*start, std::cmp::Reverse(*key)));
+    let mut melody: Vec<NoteEvent> = vec![];
+    for (start, end, key, velocity) in notes {
+        let (start, end) = (seconds(start), seconds(end));
+        if let Some(last) = melody.last_mut() {
+            if start <= last.start {
+                continue;
+            }
+            last.end = last.end.min(start);
+        }
+        melody.push(NoteEvent {
+            pitch: Pitch::from_midi(key as i32),
+            start,
+            end,
+            velocity,
+        });
+    }
+    Ok(melody)
+}
+
+/// Add the notes of `track` to `notes`, as their start and end tick, key and velocity, and its
+/// tempo changes to `tempos`, as the tick and microseconds per quarter note.
+fn read_track(
+    track: &[u8],
+    notes: &mut Vec<(u64, u64, u8, u8)>,
+    tempos: &mut Vec<(u64, u32)>,
+) -> Result<(), String> {
+    let mut reader = Reader {
+        bytes: track,
+        pos: 0,
+    };
+    let (mut tick, mut status) = (0u64, 0u8);
+    // The start tick and velocity of the notes sounding, by channel and key.
+    let mut sounding = std::collections::HashMap::new();
+
+    while reader.pos < track.len() {
+        tick += reader.vlq()? as u64;
+        let mut byte = reader.byte()?;
+        if byte < 0x80 {
+            // Running status: the data of another message like the last.
+            if status == 0 {
+                return Err("MIDI data without a status".into());
+            }
+            reader.pos -= 1;
+            byte = status;
+        }
+        match byte {
+            0xff => {
+                let kind = reader.byte()?;
+                let len = reader.vlq()? as usize;
+                let data = reader.take(len)?;
+                match (kind, data) {
+                    (0x51, [a, b, c]) => tempos.push((tick, u32::from_be_bytes([0, *a, *b, *c]))),
+                    (0x2f, _) => break,
+                    _ => {}
+                }
+            }
+            0xf0 | 0xf7 => {
+                let len = reader.vlq()? as usize;
+                reader.take(len)?;
+            }
+            _ => {
+                status = byte;
+                let channel = byte & 0x0f;
+                let data = reader.take(if matches!(byte & 0xf0, 0xc0 | 0xd0) {
+                    1
+                } else {
+                    2
+                })?;
+                let on = byte & 0xf0 == 0x90 && data[1] > 0;
+                let off = byte & 0xf0 == 0x80 || (byte & 0xf0 == 0x90 && data[1] == 0);
+                if (on || off) && channel != PERCUSSION {
+                    let key = data[0];
+                    if let Some((start, velocity)) = sounding.remove(&(channel, key)) {
+                        notes.push((start, tick, key, velocity));
+                    }
+                    if on {
+                        sounding.insert((channel, key), (tick, data[1]));
+                    }
+                }
+            }
+        }
+    }
+
+    // Notes never released end with the track.
+    for ((_, key), (start, velocity)) in sounding {
+        notes.push((start, tick, key, velocity));
+    }
+    Ok(())
+}
+
+/// Reads the parts of a MIDI file in turn.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or("truncated MIDI file")?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// A variable-length quantity.
+    fn vlq(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid variable-length quantity in MIDI file".into())
+    }
+}
+
+/// Append `value` as a MIDI variable-length quantity.
+fn write_vlq(out: &mut Vec<u8>, value: u32) {
+    let mut bytes = [0u8; 5];
+    let mut i = bytes.len();
+    let mut value = value;
+    loop {
+        i -= 1;
+        bytes[i] = (value & 0x7f) as u8 | if i == bytes.len() - 1 { 0 } else { 0x80 };
+        value >>= 7;
+        if value == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(&bytes[i..]);
+}
@@ -0,0 +1,85 @@
+//! The McLeod Pitch Method (McLeod and Wyvill, 2005).
+
+/// The default fraction of the highest key maximum that the chosen one must reach.
+pub const DEFAULT_CUTOFF: f64 = 0.9;
+
+/// Estimate the fundamental frequency of `buffer`, sampled at `sample_rate` Hz. Returns `None` if
+/// the normalized square difference function has no key maximum, e.g. because the buffer is too
+/// short to hold a period.
+///
+/// Periods up to half the buffer length are considered. A key maximum is the highest point of the
+/// NSDF between a positive-going zero crossing and the next negative-going one; the first key
+/// maximum at least `cutoff` times as high as the highest one is taken as the period.
+pub fn mpm_freq(buffer: &[f32], sample_rate: usize, cutoff: f64) -> Option<f64> {
+    let nsdf = normalized_square_difference(buffer);
+    let maxima = key_maxima(&nsdf);
+
+    let highest = maxima
+        .iter()
+        .map(|tau| nsdf[*tau])
+        .fold(f64::NEG_INFINITY, f64::max);
+    let tau = *maxima.iter().find(|tau| nsdf[**tau] >= cutoff * highest)?;
+
+    Some((sample_rate as f64) / parabolic_interpolation(&nsdf, tau))
+}
+
+/// The normalized square difference function `n(tau)` for each lag `tau` in
+/// `0..buffer.len() / 2`: the autocorrelation divided by the energy of the overlapping parts, so
+/// that it lies in `-1.0..=1.0` and is 1 at lags the signal repeats at.
+pub fn normalized_square_difference(buffer: &[f32]) -> Vec<f64> {
+    (0..buffer.len() / 2)
+        .map(|tau| {
+            let (mut acf, mut energy) = (0.0, 0.0);
+            for (a, b) in buffer.iter().zip(buffer[tau..].iter()) {
+                let (a, b) = (*a as f64, *b as f64);
+                acf += a * b;
+                energy += a * a + b * b;
+            }
+            if energy == 0.0 {
+                0.0
+            } else {
+                2.0 * acf / energy
+            }
+        })
+        .collect()
+}
+
+/// The lag of the highest point of each positive region of `nsdf`, skipping the one around lag 0.
+fn key_maxima(nsdf: &[f64]) -> Vec<usize> {
+    let mut maxima = vec![];
+    let mut current: Option<usize> = None;
+
+    // Start after the first negative-going zero crossing.
+    let start = match nsdf.iter().position(|n| *n <= 0.0) {
+        Some(start) => start,
+        None => return maxima,
+    };
+
+    for tau in start..nsdf.len() {
+        if nsdf[tau] > 0.0 {
+            if current.is_none_or(|max| nsdf[tau] > nsdf[max]) {
+                current = Some(tau);
+            }
+        } else if let Some(max) = current.take() {
+            maxima.push(max);
+        }
+    }
+
+    // A region still open at the end only counts if its maximum is really a peak.
+    if let Some(max) = current {
+        if max + 1 < nsdf.len() {
+            maxima.push(max);
+        }
+    }
+
+    maxima
+}
+
+/// Refine `tau` to a fractional lag using the parabola through it and its neighbours.
+fn parabolic_interpolation(nsdf: &[f64], tau: usize) -> f64 {
+    if tau < 1 || tau + 1 >= nsdf.len() {
+        return tau as f64;
+    }
+
+    tau as f64 + crate::parabolic_offset(nsdf[tau - 1], nsdf[tau], nsdf[tau + 1])
+}
@@ -0,0 +1,159 @@
+//! MusicXML export, for importing a transcription into notation software such as MuseScore,
+//! Finale or Sibelius.
+
+use std::io::{self, Write};
+
+use crate::notes::NoteEvent;
+use crate::score::{self, Accidentals, Layout, Written};
+
+/// Write `notes`, which must be in order and not overlap, as a single-part MusicXML score called
+/// `title`.
+pub fn write_musicxml<W: Write>(
+    mut out: W,
+    title: &str,
+    notes: &[NoteEvent],
+    layout: &Layout,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n\
+         <!DOCTYPE score-partwise PUBLIC \"-//Recordare//DTD MusicXML 4.0 Partwise//EN\" \
+         \"http://www.musicxml.org/dtds/partwise.dtd\">\n\
+         <score-partwise version=\"4.0\">\n  \
+           <work>\n    <work-title>{}</work-title>\n  </work>\n  \
+           <part-list>\n    \
+             <score-part id=\"P1\">\n      <part-name>Melody</part-name>\n    </score-part>\n  \
+           </part-list>\n  \
+           <part id=\"P1\">",
+        escape(title)
+    )?;
+
+    let measures = score::measures(&score::quantize(notes, layout.tempo), layout.time);
+    let mut tied = false;
+    for (i, measure) in measures.iter().enumerate() {
+        writeln!(out, "    <measure number=\"{}\">", i + 1)?;
+        if i == 0 {
+            write_attributes(&mut out, layout)?;
+        }
+
+        let mut accidentals = Accidentals::default();
+        for note in measure {
+            write_note(&mut out, note, tied, layout, &mut accidentals)?;
+            tied = note.tie;
+        }
+        writeln!(out, "    </measure>")?;
+    }
+
+    writeln!(out, "  </part>\n</score-partwise>")?;
+    out.flush()
+}
+
+/// Write the key, time signature, clef and tempo that start the score.
+fn write_attributes<W: Write>(out: &mut W, layout: &Layout) -> io::Result<()> {
+    writeln!(
+        out,
+        "      <attributes>\n        \
+                 <divisions>{}</divisions>\n        \
+                 <key>\n          <fifths>{}</fifths>\n          <mode>{}</mode>\n        </key>\n        \
+                 <time>\n          <beats>{}</beats>\n          <beat-type>{}</beat-type>\n        </time>\n        \
+                 <clef>\n          <sign>G</sign>\n          <line>2</line>\n        </clef>\n      \
+               </attributes>",
+        score::DIVISION,
+        layout.key.fifths(),
+        if layout.key.minor { "minor" } else { "major" },
+        layout.time.beats,
+        layout.time.beat_type,
+    )?;
+
+    let tempo = layout.tempo.round();
+    writeln!(
+        out,
+        "      <direction placement=\"above\">\n        \
+                 <direction-type>\n          \
+                   <metronome>\n            <beat-unit>quarter</beat-unit>\n            \
+                     <per-minute>{}</per-minute>\n          </metronome>\n        \
+                 </direction-type>\n        \
+                 <sound tempo=\"{}\"/>\n      \
+               </direction>",
+        tempo, tempo
+    )
+}
+
+/// Write one `note`, which is `tied` from the one before if that is set.
+fn write_note<W: Write>(
+    out: &mut W,
+    note: &Written,
+    tied: bool,
+    layout: &Layout,
+    accidentals: &mut Accidentals,
+) -> io::Result<()> {
+    writeln!(out, "      <note>")?;
+
+    let mut accidental = None;
+    match note.pitch {
+        None => writeln!(out, "        <rest/>")?,
+        Some(pitch) => {
+            let spelling = layout.key.spell(pitch);
+            accidental = accidentals.next(layout.key, spelling);
+
+            writeln!(out, "        <pitch>")?;
+            writeln!(out, "          <step>{}</step>", spelling.letter)?;
+            if spelling.alter != 0 {
+                writeln!(out, "          <alter>{}</alter>", spelling.alter)?;
+            }
+            writeln!(out, "          <octave>{}</octave>", spelling.octave)?;
+            writeln!(out, "        </pitch>")?;
+        }
+    }
+
+    writeln!(out, "        <duration>{}</duration>", note.length)?;
+    let ties: Vec<&str> = [(tied, "stop"), (note.tie, "start")]
+        .iter()
+        .filter(|(tie, _)| *tie)
+        .map(|(_, kind)| *kind)
+        .collect();
+    for kind in &ties {
+        writeln!(out, "        <tie type=\"{}\"/>", kind)?;
+    }
+
+    let (value, dotted) = note.note_value();
+    writeln!(out, "        <type>{}</type>", type_name(value))?;
+    if dotted {
+        writeln!(out, "        <dot/>")?;
+    }
+    if let Some(alter) = accidental {
+        let name = match alter {
+            1 => "sharp",
+            -1 => "flat",
+            _ => "natural",
+        };
+        writeln!(out, "        <accidental>{}</accidental>", name)?;
+    }
+
+    if !ties.is_empty() {
+        writeln!(out, "        <notations>")?;
+        for kind in &ties {
+            writeln!(out, "          <tied type=\"{}\"/>", kind)?;
+        }
+        writeln!(out, "        </notations>")?;
+    }
+
+    writeln!(out, "      </note>")
+}
+
+/// The MusicXML name of the note value with `denominator`.
+fn type_name(denominator: u32) -> &'static str {
+    match denominator {
+        1 => "whole",
+        2 => "half",
+        4 => "quarter",
+        8 => "eighth",
+        _ => "16th",
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,71 @@
+//! Naming notes in other conventions than English letter names: German, where B is H and B flat
+//! is B, and fixed-do solfège, for the languages that name notes that way.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chord::Chord;
+use crate::{Note, Pitch};
+
+/// A convention for naming notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoteNames {
+    /// Letters with sharps, as `Note` is displayed: C, C#, D...
+    #[default]
+    English,
+    /// German letters: C, Cis, D... with H for B, and B for A sharp (B flat).
+    German,
+    /// Fixed-do solfège: Do, Do#, Re...
+    Solfege,
+}
+
+impl NoteNames {
+    /// The name of `note`.
+    pub fn note(self, note: Note) -> &'static str {
+        let names = match self {
+            NoteNames::English => [
+                "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+            ],
+            NoteNames::German => [
+                "C", "Cis", "D", "Dis", "E", "F", "Fis", "G", "Gis", "A", "B", "H",
+            ],
+            NoteNames::Solfege => [
+                "Do", "Do#", "Re", "Re#", "Mi", "Fa", "Fa#", "Sol", "Sol#", "La", "La#", "Si",
+            ],
+        };
+        names[note.semitone() as usize]
+    }
+
+    /// The name of `pitch`, with its octave: `A4`, `La4`...
+    pub fn pitch(self, pitch: Pitch) -> String {
+        format!("{}{}", self.note(pitch.note), pitch.octave)
+    }
+
+    /// The symbol of `chord`, such as `Am`, `Lam` or `Hm`.
+    pub fn chord(self, chord: Chord) -> String {
+        format!("{}{}", self.note(chord.root), chord.quality.suffix())
+    }
+}
+
+impl FromStr for NoteNames {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "english" => Ok(NoteNames::English),
+            "german" => Ok(NoteNames::German),
+            "solfege" => Ok(NoteNames::Solfege),
+            _ => Err(format!("unknown note names: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for NoteNames {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            NoteNames::English => "english",
+            NoteNames::German => "german",
+            NoteNames::Solfege => "solfege",
+        })
+    }
+}
@@ -0,0 +1,168 @@
+//! Turning the stream of per-chunk pitches into note events.
+
+use crate::{level, DetectedPitch, Pitch};
+
+/// The length of the frames the signal level is measured over to find onsets, in seconds. This is a
+/// period of `MIN_DETECTABLE_FREQ`, so that the level of a steady tone doesn't vary from frame to
+/// frame.
+pub const ONSET_FRAME: f64 = 1.0 / crate::MIN_DETECTABLE_FREQ as f64;
+
+/// How much louder than the previous frame a frame must be to be an onset, in dB.
+pub const ONSET_RISE: f64 = 6.0;
+
+/// Notes shorter than this (in seconds) are taken to be blips when cleaning up a transcription.
+pub const DEFAULT_MIN_DURATION: f64 = 0.1;
+
+/// Notes this close together (in seconds) are taken to follow on from each other.
+const CONTIGUOUS: f64 = 1e-6;
+
+/// The level below which frames can't be onsets, in dBFS, so that noise doesn't start notes.
+const ONSET_FLOOR: f64 = -50.0;
+
+/// A note played from `start` to `end` seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteEvent {
+    pub pitch: Pitch,
+    pub start: f64,
+    pub end: f64,
+    /// The MIDI velocity (1 to 127), estimated from how loud the note gets.
+    pub velocity: u8,
+}
+
+impl NoteEvent {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// The MIDI velocity for a note peaking at an RMS level of `dbfs`, mapping -60 dBFS to 0 dBFS
+/// linearly onto 1 to 127.
+pub fn velocity(dbfs: f64) -> u8 {
+    (127.0 * (dbfs + 60.0) / 60.0).round().clamp(1.0, 127.0) as u8
+}
+
+/// Follows the chunks of a signal and their detected pitches, and reports a note whenever one ends:
+/// when the pitch changes, at a rest, or at an onset (a sudden rise in level, as when a note is
+/// played again).
+#[derive(Debug, Clone)]
+pub struct NoteTracker {
+    sample_rate: usize,
+    /// Samples seen so far.
+    position: usize,
+    /// The level of the last frame, in dBFS.
+    prev_level: f64,
+    current: Option<NoteEvent>,
+    /// The loudest chunk of the current note, in dBFS.
+    peak: f64,
+}
+
+impl NoteTracker {
+    pub fn new(sample_rate: usize) -> Self {
+        NoteTracker {
+            sample_rate,
+            position: 0,
+            prev_level: f64::NEG_INFINITY,
+            current: None,
+            peak: f64::NEG_INFINITY,
+        }
+    }
+
+    /// The time at the end of the chunks pushed so far, in seconds.
+    pub fn time(&self) -> f64 {
+        self.position as f64 / self.sample_rate as f64
+    }
+
+    /// Feed the next `chunk` and what was `detected` in it. Returns the note that ended, if any.
+    pub fn push(&mut self, chunk: &[f32], detected: &DetectedPitch) -> Option<NoteEvent> {
+        let chunk_start = self.time();
+        let onset = self.onset(chunk).map(|i| chunk_start + self.seconds(i));
+        let level = level::to_dbfs(level::rms(chunk));
+
+        let mut ended = None;
+        if let Some(current) = self.current {
+            if detected.pitch != Some(current.pitch) || onset.is_some() {
+                ended = Some(NoteEvent {
+                    end: onset.unwrap_or(chunk_start),
+                    ..current
+                });
+                self.current = None;
+            }
+        }
+
+        self.position += chunk.len();
+        if let Some(pitch) = detected.pitch {
+            if self.current.is_none() {
+                self.peak = f64::NEG_INFINITY;
+            }
+            self.peak = self.peak.max(level);
+            self.current = Some(NoteEvent {
+                pitch,
+                start: self
+                    .current
+                    .map_or(onset.unwrap_or(chunk_start), |n| n.start),
+                end: self.time(),
+                velocity: velocity(self.peak),
+            });
+        }
+
+        ended
+    }
+
+    /// The note still playing at the end of the signal, if any.
+    pub fn finish(self) -> Option<NoteEvent> {
+        self.current
+    }
+
+    /// The offset in `chunk` of the first onset in it, if any. A trailing partial frame is ignored.
+    fn onset(&mut self, chunk: &[f32]) -> Option<usize> {
+        let frame_len = ((ONSET_FRAME * self.sample_rate as f64) as usize).max(1);
+        let mut onset = None;
+
+        for (i, frame) in chunk.chunks_exact(frame_len).enumerate() {
+            let level = level::to_dbfs(level::rms(frame));
+            if onset.is_none() && level >= ONSET_FLOOR && level - self.prev_level >= ONSET_RISE {
+                onset = Some(i * frame_len);
+            }
+            self.prev_level = level;
+        }
+
+        onset
+    }
+
+    fn seconds(&self, samples: usize) -> f64 {
+        samples as f64 / self.sample_rate as f64
+    }
+}
+
+/// Clean up `notes` for a transcription by dropping blips shorter than `min_duration` seconds. The
+/// note before a blip is stretched over it, and merged with the note after it if they have the
+/// same pitch, since the blip interrupted what was really one note.
+pub fn clean_up(notes: &[NoteEvent], min_duration: f64) -> Vec<NoteEvent> {
+    let mut cleaned: Vec<NoteEvent> = vec![];
+    let mut after_blip = false;
+
+    for note in notes {
+        let last = cleaned
+            .last_mut()
+            .filter(|last| note.start - last.end < CONTIGUOUS);
+
+        if note.duration() < min_duration {
+            if let Some(last) = last {
+                last.end = note.end;
+                after_blip = true;
+            }
+            continue;
+        }
+
+        match last {
+            Some(last) if after_blip && last.pitch == note.pitch => {
+                last.end = note.end;
+                last.velocity = last.velocity.max(note.velocity);
+            }
+            _ => cleaned.push(*note),
+        }
+        after_blip = false;
+    }
+
+    cleaned
+}
@@ -0,0 +1,58 @@
+//! Open Sound Control output, for live electronics environments such as Max/MSP, Pure Data and
+//! SuperCollider.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+
+use crate::DetectedPitch;
+
+/// The address pattern of the messages sent for each estimate.
+pub const PITCH_ADDRESS: &str = "/pitch";
+
+/// Sends a `/pitch` message over UDP for each estimate, with the arguments `freq` (Hz, or 0 if
+/// none was found), `note` (e.g. `A4`, or empty if there is none), `cents` and `confidence`.
+pub struct OscOut {
+    socket: UdpSocket,
+}
+
+impl OscOut {
+    /// Send messages to `addr`, e.g. `localhost:57120`.
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let addr = addr
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address to send to"))?;
+        let local: SocketAddr = match addr {
+            SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+            SocketAddr::V6(_) => ([0u16; 8], 0).into(),
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(OscOut { socket })
+    }
+
+    pub fn send(&self, detected: &DetectedPitch) -> io::Result<()> {
+        self.socket.send(&pitch_message(detected)).map(|_| ())
+    }
+}
+
+/// The `/pitch` message for `detected`.
+pub fn pitch_message(detected: &DetectedPitch) -> Vec<u8> {
+    let note = detected.pitch.map(|p| p.to_string()).unwrap_or_default();
+
+    let mut message = vec![];
+    write_string(&mut message, PITCH_ADDRESS);
+    write_string(&mut message, ",fsff");
+    message.extend_from_slice(&(detected.freq.unwrap_or(0.0) as f32).to_be_bytes());
+    write_string(&mut message, &note);
+    message.extend_from_slice(&(detected.cents as f32).to_be_bytes());
+    message.extend_from_slice(&(detected.confidence as f32).to_be_bytes());
+    message
+}
+
+/// Append `s` as an OSC string: null-terminated and padded with nulls to a multiple of 4 bytes.
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    let padding = 4 - s.len() % 4;
+    out.extend((0..padding).map(|_| 0));
+}
@@ -0,0 +1,840 @@
+//! Writing detection results in human- or machine-readable formats.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::chord::ChordEstimate;
+use crate::inharmonicity::Inharmonicity;
+use crate::interval::MelodicInterval;
+use crate::json::{self, ToJson, Value};
+use crate::level::Level;
+use crate::names::NoteNames;
+use crate::notes::NoteEvent;
+use crate::plot::IN_TUNE;
+use crate::summary::Summary;
+use crate::tuning::Tuning;
+use crate::vibrato::Vibrato;
+use crate::DetectedPitch;
+
+/// How results are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// One human-readable line per record.
+    #[default]
+    Text,
+    /// A JSON array with one object per record.
+    Json,
+    /// A header line and then one comma-separated line per record.
+    Csv,
+}
+
+impl Format {
+    /// The file extension for output in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Text => "txt",
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format: {}", s)),
+        }
+    }
+}
+
+/// The result for one chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Record {
+    /// When the chunk starts, in seconds from the start of the input.
+    pub timestamp: f64,
+    /// The channel the chunk is from, if channels are analyzed separately.
+    pub channel: Option<usize>,
+    /// Which of the pitches detected at once this is, lowest first, if there may be several.
+    pub voice: Option<usize>,
+    pub detected: DetectedPitch,
+    /// The levels of the chunk, if measured.
+    pub level: Option<Level>,
+    /// The inharmonicity of the chunk and its idealized pitch, if fit.
+    pub inharmonicity: Option<Inharmonicity>,
+}
+
+/// `detected` as a JSON object, with the same fields as a record but for the timestamp and
+/// channel.
+pub fn detected_json(detected: &DetectedPitch) -> String {
+    detected.to_json().to_string()
+}
+
+/// The `note`, `octave` and `cents` fields of `detected` in JSON, named in `tuning` if there is
+/// one, or with `names`. Rests and unvoiced chunks are flagged as such rather than named.
+fn json_naming(detected: &DetectedPitch, tuning: Option<&Tuning>, names: NoteNames) -> [Value; 3] {
+    let named = Named::new(detected, tuning, names);
+    [
+        named
+            .note
+            .filter(|_| detected.pitch.is_some())
+            .as_deref()
+            .into(),
+        named.octave.map(f64::from).into(),
+        named.cents.into(),
+    ]
+}
+
+/// How a detected pitch is named: as a note of the twelve (in the detector's temperament) with
+/// some `NoteNames`, or the nearest degree of a microtonal tuning.
+struct Named {
+    note: Option<String>,
+    octave: Option<i8>,
+    cents: Option<f64>,
+    /// The pitch in text output, such as `A4 +2¢`, `Unknown` or `Rest`.
+    text: String,
+}
+
+impl Named {
+    fn new(detected: &DetectedPitch, tuning: Option<&Tuning>, names: NoteNames) -> Self {
+        match (tuning, detected.freq) {
+            (Some(tuning), Some(freq)) if !detected.rest && detected.pitch.is_some() => {
+                let (pitch, cents) = tuning.identify(freq);
+                Named {
+                    note: Some(tuning.name(pitch.degree).to_string()),
+                    octave: Some(pitch.octave),
+                    cents: Some(cents),
+                    text: format!("{} {:+.0}¢", tuning.pitch_name(pitch), cents),
+                }
+            }
+            _ => Named {
+                note: note_name(detected, names),
+                octave: detected.pitch.map(|p| p.octave),
+                cents: detected.pitch.map(|_| detected.cents),
+                text: match detected.pitch {
+                    Some(pitch) => format!("{} {:+.0}¢", names.pitch(pitch), detected.cents),
+                    None => detected.to_string(),
+                },
+            },
+        }
+    }
+}
+
+const CSV_HEADER: &str = "timestamp_s,channel,freq_hz,note,octave,cents,confidence";
+
+/// The columns added to `CSV_HEADER` by `RecordWriter::with_levels`.
+const CSV_LEVELS_HEADER: &str = "rms_dbfs,peak_dbfs,a_weighted_dbfs";
+
+/// The column added to `CSV_HEADER` by `RecordWriter::with_voices`.
+const CSV_VOICES_HEADER: &str = "voice";
+
+/// The columns added to `CSV_HEADER` by `RecordWriter::with_inharmonicity`.
+const CSV_INHARMONICITY_HEADER: &str =
+    "inharmonicity,ideal_freq_hz,ideal_note,ideal_octave,ideal_cents";
+
+/// The columns records have besides those of `CSV_HEADER`, which CSV output needs to know for
+/// its header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Columns {
+    /// Whether records have levels.
+    pub levels: bool,
+    /// Whether records have voices, for several pitches per chunk.
+    pub voices: bool,
+    /// Whether records have inharmonicity, for pianos.
+    pub inharmonicity: bool,
+}
+
+/// Writes a stream of records to `out` in some `Format`.
+pub struct RecordWriter<W: Write> {
+    out: W,
+    format: Format,
+    count: usize,
+    columns: Columns,
+    /// The tuning to name pitches in, if not twelve-tone.
+    tuning: Option<Tuning>,
+    names: NoteNames,
+}
+
+impl<W: Write> RecordWriter<W> {
+    pub fn new(out: W, format: Format) -> io::Result<Self> {
+        RecordWriter::with_columns(out, format, Columns::default())
+    }
+
+    /// A writer for records with levels.
+    pub fn with_levels(out: W, format: Format) -> io::Result<Self> {
+        let columns = Columns {
+            levels: true,
+            ..Columns::default()
+        };
+        RecordWriter::with_columns(out, format, columns)
+    }
+
+    /// A writer for records with voices, for several pitches per chunk.
+    pub fn with_voices(out: W, format: Format) -> io::Result<Self> {
+        let columns = Columns {
+            voices: true,
+            ..Columns::default()
+        };
+        RecordWriter::with_columns(out, format, columns)
+    }
+
+    /// A writer for records with inharmonicity, for pianos.
+    pub fn with_inharmonicity(out: W, format: Format) -> io::Result<Self> {
+        let columns = Columns {
+            inharmonicity: true,
+            ..Columns::default()
+        };
+        RecordWriter::with_columns(out, format, columns)
+    }
+
+    /// A writer for records with any of the optional `columns`.
+    pub fn with_columns(mut out: W, format: Format, columns: Columns) -> io::Result<Self> {
+        match format {
+            Format::Text => {}
+            Format::Json => out.write_all(b"[")?,
+            Format::Csv => {
+                // In the order `write` writes them.
+                let mut header = CSV_HEADER.to_string();
+                for (enabled, group) in [
+                    (columns.levels, CSV_LEVELS_HEADER),
+                    (columns.voices, CSV_VOICES_HEADER),
+                    (columns.inharmonicity, CSV_INHARMONICITY_HEADER),
+                ] {
+                    if enabled {
+                        header.push(',');
+                        header.push_str(group);
+                    }
+                }
+                writeln!(out, "{}", header)?
+            }
+        }
+        Ok(RecordWriter {
+            out,
+            format,
+            count: 0,
+            columns,
+            tuning: None,
+            names: NoteNames::default(),
+        })
+    }
+
+    /// Name each pitch after the nearest degree of `tuning` rather than a note of the twelve.
+    pub fn with_tuning(mut self, tuning: Tuning) -> Self {
+        self.tuning = Some(tuning);
+        self
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Name notes with `names` rather than English letters.
+    pub fn with_note_names(mut self, names: NoteNames) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// How the writer names `detected` in text output, such as `A4 +2¢`.
+    pub fn describe(&self, detected: &DetectedPitch) -> String {
+        Named::new(detected, self.tuning.as_ref(), self.names).text
+    }
+
+    /// The underlying writer, e.g. to add notes between records.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    pub fn write(&mut self, record: &Record) -> io::Result<()> {
+        let detected = &record.detected;
+        let named = Named::new(detected, self.tuning.as_ref(), self.names);
+
+        match self.format {
+            Format::Text => {
+                let source = match (record.channel, record.voice) {
+                    (Some(channel), Some(voice)) => format!("Channel {}, voice {}", channel, voice),
+                    (Some(channel), None) => format!("Channel {}", channel),
+                    (None, Some(voice)) => format!("Voice {}", voice),
+                    (None, None) => String::new(),
+                };
+                match detected.freq {
+                    Some(freq) if source.is_empty() => {
+                        write!(self.out, "Estimated freq: {:0.0} Hz, {}", freq, named.text)?
+                    }
+                    Some(freq) => write!(
+                        self.out,
+                        "{}: estimated freq: {:0.0} Hz, {}",
+                        source, freq, named.text
+                    )?,
+                    None if source.is_empty() => write!(self.out, "{}", named.text)?,
+                    None => write!(self.out, "{}: {}", source, named.text)?,
+                }
+                if let Some(level) = record.level {
+                    write!(
+                        self.out,
+                        " (RMS {}, peak {}",
+                        text_dbfs(level.rms),
+                        text_dbfs(level.peak)
+                    )?;
+                    if let Some(a_weighted) = level.a_weighted {
+                        write!(self.out, ", A-weighted {}", text_dbfs(a_weighted))?;
+                    }
+                    write!(self.out, ")")?;
+                }
+                if let Some(inharmonicity) = &record.inharmonicity {
+                    let ideal = Named::new(&inharmonicity.ideal, self.tuning.as_ref(), self.names);
+                    write!(
+                        self.out,
+                        " (ideal: {:0.0} Hz, {}, B {:.5})",
+                        inharmonicity.ideal.freq.unwrap_or(f64::NAN),
+                        ideal.text,
+                        inharmonicity.coefficient
+                    )?;
+                }
+                writeln!(self.out)?;
+            }
+
+            Format::Json => {
+                if self.count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                writeln!(self.out)?;
+                let (tuning, names) = (self.tuning.as_ref(), self.names);
+                let naming = |detected: &DetectedPitch| json_naming(detected, tuning, names);
+                write!(self.out, "  {}", json::record(record, &naming))?;
+            }
+
+            Format::Csv => {
+                write!(
+                    self.out,
+                    "{},{},{},{},{},{},{}",
+                    record.timestamp,
+                    csv(record.channel),
+                    csv(detected.freq.and_then(finite)),
+                    csv(named.note),
+                    csv(named.octave),
+                    csv(named.cents),
+                    csv(finite(detected.confidence)),
+                )?;
+                if self.columns.levels {
+                    let level = record.level;
+                    write!(
+                        self.out,
+                        ",{},{},{}",
+                        csv(level.and_then(|level| finite(level.rms))),
+                        csv(level.and_then(|level| finite(level.peak))),
+                        csv(level.and_then(|level| level.a_weighted.and_then(finite))),
+                    )?;
+                }
+                if self.columns.voices {
+                    write!(self.out, ",{}", csv(record.voice))?;
+                }
+                if self.columns.inharmonicity {
+                    let inharmonicity = record.inharmonicity.as_ref();
+                    let ideal = inharmonicity.map(|inharmonicity| {
+                        Named::new(&inharmonicity.ideal, self.tuning.as_ref(), self.names)
+                    });
+                    write!(
+                        self.out,
+                        ",{},{},{},{},{}",
+                        csv(inharmonicity
+                            .and_then(|inharmonicity| finite(inharmonicity.coefficient))),
+                        csv(inharmonicity.and_then(|inharmonicity| inharmonicity.ideal.freq)),
+                        csv(ideal.as_ref().and_then(|ideal| ideal.note.as_ref())),
+                        csv(ideal.as_ref().and_then(|ideal| ideal.octave)),
+                        csv(ideal.as_ref().and_then(|ideal| ideal.cents)),
+                    )?;
+                }
+                writeln!(self.out)?;
+            }
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish the output (e.g. close the JSON array) and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == Format::Json {
+            if self.count > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "]")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+const NOTES_CSV_HEADER: &str = "start_s,duration_s,note,octave,velocity";
+
+/// The columns added to `NOTES_CSV_HEADER` by `NoteWriter::with_vibrato`.
+const NOTES_CSV_VIBRATO_HEADER: &str = "vibrato_hz,vibrato_cents";
+
+/// Writes a stream of note events to `out` in some `Format`.
+pub struct NoteWriter<W: Write> {
+    out: W,
+    format: Format,
+    count: usize,
+    /// Whether notes are written with their vibrato, or that they have none.
+    vibrato: bool,
+    names: NoteNames,
+}
+
+impl<W: Write> NoteWriter<W> {
+    pub fn new(out: W, format: Format) -> io::Result<Self> {
+        NoteWriter::start(out, format, false)
+    }
+
+    /// A writer for notes with their vibrato.
+    pub fn with_vibrato(out: W, format: Format) -> io::Result<Self> {
+        NoteWriter::start(out, format, true)
+    }
+
+    fn start(mut out: W, format: Format, vibrato: bool) -> io::Result<Self> {
+        match format {
+            Format::Text => {}
+            Format::Json => out.write_all(b"[")?,
+            Format::Csv if vibrato => {
+                writeln!(out, "{},{}", NOTES_CSV_HEADER, NOTES_CSV_VIBRATO_HEADER)?
+            }
+            Format::Csv => writeln!(out, "{}", NOTES_CSV_HEADER)?,
+        }
+        Ok(NoteWriter {
+            out,
+            format,
+            count: 0,
+            vibrato,
+            names: NoteNames::default(),
+        })
+    }
+
+    /// Name notes with `names` rather than English letters.
+    pub fn with_note_names(mut self, names: NoteNames) -> Self {
+        self.names = names;
+        self
+    }
+
+    /// The number of notes written so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn write(&mut self, note: &NoteEvent) -> io::Result<()> {
+        self.write_with_vibrato(note, None)
+    }
+
+    /// Write `note` with its `vibrato`, or none. It's only written if the writer is
+    /// `with_vibrato`.
+    pub fn write_with_vibrato(
+        &mut self,
+        note: &NoteEvent,
+        vibrato: Option<Vibrato>,
+    ) -> io::Result<()> {
+        let vibrato = vibrato.filter(|_| self.vibrato);
+        match self.format {
+            Format::Text => {
+                write!(
+                    self.out,
+                    "{:0.2}s–{:0.2}s {}, velocity {}",
+                    note.start,
+                    note.end,
+                    self.names.pitch(note.pitch),
+                    note.velocity
+                )?;
+                if let Some(vibrato) = vibrato {
+                    write!(
+                        self.out,
+                        ", vibrato {:.1} Hz ±{:.0}¢",
+                        vibrato.rate, vibrato.depth
+                    )?;
+                }
+                writeln!(self.out)?;
+            }
+
+            Format::Json => {
+                if self.count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                writeln!(self.out)?;
+                let mut value = note.to_json();
+                value.set("note", self.names.note(note.pitch.note).into());
+                if self.vibrato {
+                    value.set("vibrato_hz", vibrato.map(|v| v.rate).into());
+                    value.set("vibrato_cents", vibrato.map(|v| v.depth).into());
+                }
+                write!(self.out, "  {}", value)?;
+            }
+
+            Format::Csv => {
+                write!(
+                    self.out,
+                    "{},{},{},{},{}",
+                    note.start,
+                    note.duration(),
+                    self.names.note(note.pitch.note),
+                    note.pitch.octave,
+                    note.velocity,
+                )?;
+                if self.vibrato {
+                    write!(
+                        self.out,
+                        ",{},{}",
+                        csv(vibrato.map(|v| v.rate)),
+                        csv(vibrato.map(|v| v.depth)),
+                    )?;
+                }
+                writeln!(self.out)?;
+            }
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish the output (e.g. close the JSON array) and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == Format::Json {
+            if self.count > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "]")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+const INTERVALS_CSV_HEADER: &str = "time_s,from,to,semitones,interval,short,direction";
+
+/// Writes a stream of melodic intervals to `out` in some `Format`.
+pub struct IntervalWriter<W: Write> {
+    out: W,
+    format: Format,
+    count: usize,
+}
+
+impl<W: Write> IntervalWriter<W> {
+    pub fn new(mut out: W, format: Format) -> io::Result<Self> {
+        match format {
+            Format::Text => {}
+            Format::Json => out.write_all(b"[")?,
+            Format::Csv => writeln!(out, "{}", INTERVALS_CSV_HEADER)?,
+        }
+        Ok(IntervalWriter {
+            out,
+            format,
+            count: 0,
+        })
+    }
+
+    pub fn write(&mut self, melodic: &MelodicInterval) -> io::Result<()> {
+        let MelodicInterval {
+            time,
+            from,
+            to,
+            interval,
+        } = *melodic;
+
+        match self.format {
+            Format::Text => writeln!(self.out, "{:0.2}s {} → {}: {}", time, from, to, interval)?,
+
+            Format::Json => {
+                if self.count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                writeln!(self.out)?;
+                write!(self.out, "  {}", melodic.to_json())?;
+            }
+
+            Format::Csv => writeln!(
+                self.out,
+                "{},{},{},{},{},{},{}",
+                time,
+                from,
+                to,
+                interval.semitones,
+                interval.name(),
+                interval.short_name(),
+                csv(interval.direction()),
+            )?,
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish the output (e.g. close the JSON array) and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == Format::Json {
+            if self.count > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "]")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// The chord recognized in one chunk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChordRecord {
+    /// When the chunk starts, in seconds from the start of the input.
+    pub timestamp: f64,
+    /// The channel the chunk is from, if channels are analyzed separately.
+    pub channel: Option<usize>,
+    pub chord: Option<ChordEstimate>,
+}
+
+const CHORDS_CSV_HEADER: &str = "timestamp_s,channel,chord,root,quality,confidence";
+
+/// Writes a stream of chord records to `out` in some `Format`.
+pub struct ChordWriter<W: Write> {
+    out: W,
+    format: Format,
+    count: usize,
+    names: NoteNames,
+}
+
+impl<W: Write> ChordWriter<W> {
+    pub fn new(mut out: W, format: Format) -> io::Result<Self> {
+        match format {
+            Format::Text => {}
+            Format::Json => out.write_all(b"[")?,
+            Format::Csv => writeln!(out, "{}", CHORDS_CSV_HEADER)?,
+        }
+        Ok(ChordWriter {
+            out,
+            format,
+            count: 0,
+            names: NoteNames::default(),
+        })
+    }
+
+    /// Name chords' roots with `names` rather than English letters.
+    pub fn with_note_names(mut self, names: NoteNames) -> Self {
+        self.names = names;
+        self
+    }
+
+    pub fn write(&mut self, record: &ChordRecord) -> io::Result<()> {
+        let chord = record.chord.map(|estimate| estimate.chord);
+        let symbol = chord.map(|chord| self.names.chord(chord));
+        let root = chord.map(|chord| self.names.note(chord.root));
+
+        match self.format {
+            Format::Text => {
+                if let Some(channel) = record.channel {
+                    write!(self.out, "Channel {}: ", channel)?;
+                }
+                match record.chord {
+                    Some(estimate) => writeln!(
+                        self.out,
+                        "Chord: {} (confidence {:0.2})",
+                        self.names.chord(estimate.chord),
+                        estimate.confidence
+                    )?,
+                    None => writeln!(self.out, "No chord")?,
+                }
+            }
+
+            Format::Json => {
+                if self.count > 0 {
+                    self.out.write_all(b",")?;
+                }
+                writeln!(self.out)?;
+                let mut value = record.to_json();
+                value.set("chord", symbol.clone().into());
+                value.set("root", root.into());
+                write!(self.out, "  {}", value)?;
+            }
+
+            Format::Csv => writeln!(
+                self.out,
+                "{},{},{},{},{},{}",
+                record.timestamp,
+                csv(record.channel),
+                csv(symbol),
+                csv(root),
+                csv(chord.map(|chord| chord.quality)),
+                csv(record.chord.map(|estimate| estimate.confidence)),
+            )?,
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish the output (e.g. close the JSON array) and return the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.format == Format::Json {
+            if self.count > 0 {
+                writeln!(self.out)?;
+            }
+            writeln!(self.out, "]")?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+const SUMMARY_CSV_HEADER: &str = "note,octave,time_s,share,mean_cents,spread_cents";
+
+/// Write `summary` to `out` in `format`, naming notes with `names`. CSV output has a line per
+/// note, and a last one for all of them together, with `all` for its note.
+pub fn write_summary<W: Write>(
+    mut out: W,
+    summary: &Summary,
+    format: Format,
+    names: NoteNames,
+) -> io::Result<()> {
+    let notes = summary.notes();
+    match format {
+        Format::Text => {
+            writeln!(
+                out,
+                "{:<6} {:>9} {:>6} {:>7} {:>7}",
+                "Note", "Time", "Share", "Mean", "Spread"
+            )?;
+            for note in &notes {
+                writeln!(
+                    out,
+                    "{:<6} {:>8.2}s {:>5.1}% {:>+6.1}¢ {:>6.1}¢",
+                    names.pitch(note.pitch),
+                    note.time,
+                    100.0 * note.share,
+                    note.mean_cents,
+                    note.spread_cents,
+                )?;
+            }
+            let duration = summary.duration();
+            let percent = |time: f64| 100.0 * time / duration;
+            writeln!(
+                out,
+                "\nPitched {:.2}s of {:.2}s ({:.1}%), rests {:.2}s, unknown {:.2}s",
+                summary.pitched_time(),
+                duration,
+                if duration > 0.0 {
+                    percent(summary.pitched_time())
+                } else {
+                    0.0
+                },
+                summary.rest_time(),
+                summary.unknown_time(),
+            )?;
+            if let (Some(mean), Some(absolute), Some((in_tune, sharp, flat))) = (
+                summary.mean_cents(),
+                summary.mean_absolute_cents(),
+                summary.intonation(),
+            ) {
+                writeln!(
+                    out,
+                    "Mean deviation {:+.1}¢, {:.1}¢ either way; {:.1}% within {}¢ of the note, \
+                     {:.1}% sharp, {:.1}% flat",
+                    mean,
+                    absolute,
+                    100.0 * in_tune,
+                    IN_TUNE,
+                    100.0 * sharp,
+                    100.0 * flat,
+                )?;
+            }
+        }
+
+        Format::Json => {
+            let named = notes.iter().map(|note| {
+                let mut value = note.to_json();
+                value.set("note", names.note(note.pitch.note).into());
+                value
+            });
+            let mut value = summary.to_json();
+            value.set("notes", Value::Array(named.collect()));
+            write_lines(&mut out, &value)?;
+        }
+
+        Format::Csv => {
+            writeln!(out, "{}", SUMMARY_CSV_HEADER)?;
+            for note in &notes {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{}",
+                    names.note(note.pitch.note),
+                    note.pitch.octave,
+                    note.time,
+                    note.share,
+                    note.mean_cents,
+                    note.spread_cents,
+                )?;
+            }
+            writeln!(
+                out,
+                "all,,{},{},{},{}",
+                summary.pitched_time(),
+                csv(Some(1.0).filter(|_| !notes.is_empty())),
+                csv(summary.mean_cents()),
+                csv(summary.spread_cents()),
+            )?;
+        }
+    }
+    out.flush()
+}
+
+/// `value` with each field of an object on a line of its own, and each element of an array in
+/// one too, as summaries are written.
+fn write_lines<W: Write>(out: &mut W, value: &Value) -> io::Result<()> {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        value => return writeln!(out, "{}", value),
+    };
+    writeln!(out, "{{")?;
+    for (i, (key, value)) in fields.iter().enumerate() {
+        let comma = if i + 1 < fields.len() { "," } else { "" };
+        let key = Value::from(key.as_str());
+        match value {
+            Value::Array(values) if !values.is_empty() => {
+                write!(out, "  {}: [", key)?;
+                for (j, value) in values.iter().enumerate() {
+                    let comma = if j + 1 < values.len() { "," } else { "" };
+                    write!(out, "\n    {}{}", value, comma)?;
+                }
+                writeln!(out, "\n  ]{}", comma)?;
+            }
+            value => writeln!(out, "  {}: {}{}", key, value, comma)?,
+        }
+    }
+    writeln!(out, "}}")
+}
+
+/// The note of `detected` with `names`, or `Rest` for rests and `Unvoiced` for unvoiced chunks.
+fn note_name(detected: &DetectedPitch, names: NoteNames) -> Option<String> {
+    match detected.pitch {
+        Some(pitch) => Some(names.note(pitch.note).to_string()),
+        None if detected.rest => Some("Rest".into()),
+        None if detected.unvoiced => Some("Unvoiced".into()),
+        None => None,
+    }
+}
+
+/// A level for text output: in dBFS, or "silent" if there's no signal at all.
+fn text_dbfs(dbfs: f64) -> String {
+    if dbfs.is_finite() {
+        format!("{:.1} dBFS", dbfs)
+    } else {
+        "silent".to_string()
+    }
+}
+
+/// A CSV field, or an empty one if it's missing.
+fn csv<T: fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(String::new, |v| v.to_string())
+}
+
+/// `value`, unless it's not representable in JSON/CSV (NaN or infinite).
+fn finite(value: f64) -> Option<f64> {
+    Some(value).filter(|v| v.is_finite())
+}
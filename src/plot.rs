@@ -0,0 +1,286 @@
+//! Plotting a pitch track over time, to show a singer or player their intonation over a take.
+//! Each estimate is a dot, green when it's within `IN_TUNE` cents of its nearest note and shading
+//! to red as it goes sharp or blue as it goes flat, over a grid of the equally-tempered notes and
+//! the seconds. Plots are PNG images, or SVG, which also labels the grid.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::png::{self, Rgb};
+use crate::{cents_between, Pitch};
+
+/// The size of the plot in pixels.
+pub const WIDTH: usize = 1200;
+pub const HEIGHT: usize = 600;
+
+/// The space around the grid, for the labels, in pixels.
+const LEFT: usize = 50;
+const RIGHT: usize = 10;
+const TOP: usize = 10;
+const BOTTOM: usize = 30;
+
+/// Estimates within this many cents of a note are in tune.
+pub const IN_TUNE: f64 = 10.0;
+
+/// Estimates this many cents from a note, a quarter tone, are as out of tune as they're drawn.
+const OUT_OF_TUNE: f64 = 50.0;
+
+/// The fewest semitones the plot spans, so that a steady note isn't zoomed in on.
+const MIN_SPAN: f64 = 12.0;
+
+/// The most seconds gridlines across.
+const MAX_TIME_LINES: f64 = 20.0;
+
+/// The seconds between time gridlines to choose from.
+const TIME_STEPS: [f64; 13] = [
+    0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0,
+];
+
+const BACKGROUND: Rgb = [255, 255, 255];
+const GRID: Rgb = [225, 225, 225];
+/// The colour of the gridlines of the Cs, to find the octaves by.
+const OCTAVE_GRID: Rgb = [160, 160, 160];
+const IN_TUNE_COLOUR: Rgb = [0, 160, 0];
+const SHARP_COLOUR: Rgb = [220, 0, 0];
+const FLAT_COLOUR: Rgb = [0, 60, 220];
+
+/// The image formats plots can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    /// The format of an image at `path`, from its extension.
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(ImageFormat::Png),
+            "svg" => Some(ImageFormat::Svg),
+            _ => None,
+        }
+    }
+}
+
+/// The colour of an estimate `cents` from its nearest note.
+pub fn colour(cents: f64) -> Rgb {
+    let off = ((cents.abs() - IN_TUNE) / (OUT_OF_TUNE - IN_TUNE)).clamp(0.0, 1.0);
+    let to = if cents > 0.0 {
+        SHARP_COLOUR
+    } else {
+        FLAT_COLOUR
+    };
+    let mix = |c: usize| {
+        (IN_TUNE_COLOUR[c] as f64 + (to[c] as f64 - IN_TUNE_COLOUR[c] as f64) * off).round() as u8
+    };
+    [mix(0), mix(1), mix(2)]
+}
+
+/// Where the grid is, and what it spans.
+struct Axes {
+    duration: f64,
+    /// The lowest and highest pitches shown, as fractional MIDI note numbers.
+    low: f64,
+    high: f64,
+}
+
+impl Axes {
+    fn new(points: &[(f64, f64)], a4: f64) -> Self {
+        let duration = points.iter().map(|(time, _)| *time).fold(0.0, f64::max);
+        let (low, high) = match points.first() {
+            // Around A4, if there's nothing to plot.
+            None => (69.0, 69.0),
+            Some(&(_, first)) => points
+                .iter()
+                .map(|(_, freq)| midi(*freq, a4))
+                .fold((midi(first, a4), midi(first, a4)), |(low, high), midi| {
+                    (low.min(midi), high.max(midi))
+                }),
+        };
+        // A semitone either side, and at least `MIN_SPAN`.
+        let (mut low, mut high) = ((low - 1.0).floor(), (high + 1.0).ceil());
+        let short = MIN_SPAN - (high - low);
+        if short > 0.0 {
+            low -= (short / 2.0).floor();
+            high += (short / 2.0).ceil();
+        }
+        Axes {
+            duration: if duration > 0.0 { duration } else { 1.0 },
+            low,
+            high,
+        }
+    }
+
+    fn x(&self, time: f64) -> f64 {
+        LEFT as f64 + time / self.duration * (WIDTH - LEFT - RIGHT) as f64
+    }
+
+    fn y(&self, midi: f64) -> f64 {
+        TOP as f64 + (self.high - midi) / (self.high - self.low) * (HEIGHT - TOP - BOTTOM) as f64
+    }
+
+    /// The notes on the grid, lowest first.
+    fn notes(&self) -> impl Iterator<Item = Pitch> {
+        (self.low as i32..=self.high as i32).map(Pitch::from_midi)
+    }
+
+    /// The seconds between the times on the grid.
+    fn time_step(&self) -> f64 {
+        TIME_STEPS
+            .iter()
+            .copied()
+            .find(|step| self.duration / step <= MAX_TIME_LINES)
+            .unwrap_or(TIME_STEPS[TIME_STEPS.len() - 1])
+    }
+
+    /// The times on the grid, from 0.
+    fn times(&self) -> impl Iterator<Item = f64> {
+        let step = self.time_step();
+        (0..=(self.duration / step) as usize).map(move |i| i as f64 * step)
+    }
+}
+
+/// The pitch of `freq` as a fractional MIDI note number, with A4 tuned to `a4` Hz.
+fn midi(freq: f64, a4: f64) -> f64 {
+    69.0 + cents_between(a4, freq) / 100.0
+}
+
+/// How far `freq` is from its nearest note, in cents.
+fn cents_off(freq: f64, a4: f64) -> f64 {
+    let midi = midi(freq, a4);
+    (midi - midi.round()) * 100.0
+}
+
+/// Plot `points`, the time in seconds and frequency in Hz of each estimate, with notes tuned to
+/// `a4`, in `format`.
+pub fn write_plot<W: Write>(
+    out: W,
+    format: ImageFormat,
+    points: &[(f64, f64)],
+    a4: f64,
+) -> io::Result<()> {
+    match format {
+        ImageFormat::Png => write_png(out, points, a4),
+        ImageFormat::Svg => write_svg(out, points, a4),
+    }
+}
+
+/// Plot `points` as a PNG image, which has the grid but no labels.
+pub fn write_png<W: Write>(out: W, points: &[(f64, f64)], a4: f64) -> io::Result<()> {
+    let axes = Axes::new(points, a4);
+    let mut pixels = vec![BACKGROUND; WIDTH * HEIGHT];
+    let mut fill = |x: f64, y: f64, width: usize, height: usize, colour: Rgb| {
+        let (x, y) = (x.round().max(0.0) as usize, y.round().max(0.0) as usize);
+        for y in y..(y + height).min(HEIGHT) {
+            for x in x..(x + width).min(WIDTH) {
+                pixels[y * WIDTH + x] = colour;
+            }
+        }
+    };
+
+    let (grid_width, grid_height) = (WIDTH - LEFT - RIGHT, HEIGHT - TOP - BOTTOM);
+    for time in axes.times() {
+        fill(axes.x(time), TOP as f64, 1, grid_height, GRID);
+    }
+    for pitch in axes.notes() {
+        let colour = if pitch.note.semitone() == 0 {
+            OCTAVE_GRID
+        } else {
+            GRID
+        };
+        fill(
+            LEFT as f64,
+            axes.y(pitch.midi() as f64),
+            grid_width,
+            1,
+            colour,
+        );
+    }
+    for &(time, freq) in points {
+        let (x, y) = (axes.x(time), axes.y(midi(freq, a4)));
+        fill(x - 1.0, y - 1.0, 3, 3, colour(cents_off(freq, a4)));
+    }
+    png::write_png(out, WIDTH, HEIGHT, &pixels)
+}
+
+/// Plot `points` as an SVG image, with each note and time on the grid labelled.
+pub fn write_svg<W: Write>(mut out: W, points: &[(f64, f64)], a4: f64) -> io::Result<()> {
+    let axes = Axes::new(points, a4);
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"10\">",
+        w = WIDTH,
+        h = HEIGHT
+    )?;
+    writeln!(
+        out,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        hex(BACKGROUND)
+    )?;
+
+    let (right, bottom) = ((WIDTH - RIGHT) as f64, (HEIGHT - BOTTOM) as f64);
+    let decimals = if axes.time_step() < 1.0 { 1 } else { 0 };
+    for time in axes.times() {
+        let x = axes.x(time);
+        writeln!(
+            out,
+            "<line x1=\"{x:.1}\" y1=\"{}\" x2=\"{x:.1}\" y2=\"{}\" stroke=\"{}\"/>",
+            TOP,
+            bottom,
+            hex(GRID),
+            x = x
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{:.*}s</text>",
+            x,
+            bottom + 15.0,
+            decimals,
+            time
+        )?;
+    }
+
+    // Label every note if there's room, or just the Cs.
+    let every_note = (HEIGHT - TOP - BOTTOM) as f64 / (axes.high - axes.low) >= 12.0;
+    for pitch in axes.notes() {
+        let y = axes.y(pitch.midi() as f64);
+        let octave = pitch.note.semitone() == 0;
+        let colour = if octave { OCTAVE_GRID } else { GRID };
+        writeln!(
+            out,
+            "<line x1=\"{}\" y1=\"{y:.1}\" x2=\"{}\" y2=\"{y:.1}\" stroke=\"{}\"/>",
+            LEFT,
+            right,
+            hex(colour),
+            y = y
+        )?;
+        if octave || every_note {
+            writeln!(
+                out,
+                "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>",
+                LEFT - 5,
+                y + 3.5,
+                pitch
+            )?;
+        }
+    }
+
+    for &(time, freq) in points {
+        writeln!(
+            out,
+            "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"1.5\" fill=\"{}\"/>",
+            axes.x(time),
+            axes.y(midi(freq, a4)),
+            hex(colour(cents_off(freq, a4)))
+        )?;
+    }
+    writeln!(out, "</svg>")?;
+    out.flush()
+}
+
+/// `colour` as an SVG colour, such as `#00a000`.
+fn hex(colour: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+}
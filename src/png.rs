@@ -0,0 +1,99 @@
+//! Writing PNG images, for spectrograms and plots. Images are 8-bit RGB and stored without
+//! compression (in deflate's stored blocks), which keeps the encoder small at the cost of larger
+//! files.
+
+use std::io::{self, Write};
+
+/// The most a stored deflate block can hold.
+const MAX_BLOCK: usize = 65535;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// An RGB colour.
+pub type Rgb = [u8; 3];
+
+/// Write the `width` by `height` image `pixels`, row by row from the top left, as a PNG file.
+pub fn write_png<W: Write>(
+    mut out: W,
+    width: usize,
+    height: usize,
+    pixels: &[Rgb],
+) -> io::Result<()> {
+    assert_eq!(pixels.len(), width * height, "pixels don't fill the image");
+
+    out.write_all(&SIGNATURE)?;
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, the standard filters and no interlacing.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &header)?;
+
+    // Each row starts with its filter type, 0 for none.
+    let mut raw = Vec::with_capacity(height * (1 + 3 * width));
+    for row in pixels.chunks(width.max(1)).take(height) {
+        raw.push(0);
+        for pixel in row {
+            raw.extend_from_slice(pixel);
+        }
+    }
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&raw))?;
+    write_chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+/// Write a chunk of `kind` holding `data`, with its length and checksum.
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// `data` as a zlib stream of stored (uncompressed) deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32K window, no preset dictionary and the fastest compression (header check
+    // bits included).
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(MAX_BLOCK).collect();
+    if blocks.is_empty() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.iter().enumerate() {
+        let last = i + 1 == blocks.len();
+        out.push(last as u8);
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+/// The CRC-32 (as in PNG and zip) of `bytes`.
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// The Adler-32 checksum of `bytes`, which ends a zlib stream.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
@@ -0,0 +1,159 @@
+//! Detecting two simultaneous pitches, such as the root and fifth of a power chord or the parts of
+//! a two-part exercise, by iterative spectral peak picking (after Klapuri, 2006).
+//!
+//! Each peak in the spectrum is a candidate fundamental, scored by the weighted sum of the peaks
+//! at its harmonics. The best candidate is the first pitch; its harmonics are then cancelled from
+//! the spectrum, and the best of the candidates left is the second, if it's strong enough.
+//! Candidates must have a peak at the fundamental itself, so that the common subharmonic of two
+//! notes (an octave below the root of a power chord, say) isn't taken for a single note. A note an
+//! octave above another can't be told from its harmonics, though, so only the lower is found.
+
+use crate::spectrum;
+use crate::window::Window;
+
+/// The greatest number of harmonics (including the fundamental) scored per candidate.
+pub const HARMONICS: usize = 10;
+
+/// How many times longer than the chunk the FFT is, for finer frequency resolution.
+const ZERO_PADDING: usize = 4;
+
+/// Peaks below this fraction (-40 dB) of the highest aren't candidates.
+const PEAK_FLOOR: f64 = 0.01;
+
+/// How far a harmonic's peak may be from its exact multiple of the fundamental, as a fraction of
+/// that frequency.
+const HARMONIC_TOLERANCE: f64 = 0.03;
+
+/// How many bins of the unpadded FFT either side of a harmonic's peak are cancelled: the main
+/// lobe of the Hann window and its first sidelobes.
+const CANCEL_BINS: usize = 3;
+
+/// How strong the second pitch must be, as a fraction of the score of the first.
+const MIN_SECOND_SALIENCE: f64 = 0.2;
+
+/// Two candidates closer than this, in cents, are taken to be the same pitch.
+const MIN_SEPARATION: f64 = 50.0;
+
+/// A pitch found by `dual_freqs`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Voice {
+    pub freq: f64,
+    /// The share of the spectrum's magnitude at the pitch's harmonics, from 0 to 1.
+    pub share: f64,
+}
+
+/// Estimate up to two fundamental frequencies in `buffer`, sampled at `sample_rate` Hz, from
+/// `min_freq` up to `max_freq`, strongest first.
+pub fn dual_freqs(
+    buffer: &[f32],
+    sample_rate: usize,
+    min_freq: f64,
+    max_freq: Option<f64>,
+) -> Vec<Voice> {
+    let len = buffer.len() * ZERO_PADDING;
+    let mut magnitudes = spectrum::magnitudes(&Window::Hann.apply(buffer), len);
+    magnitudes.truncate(len / 2);
+    let bin_hz = sample_rate as f64 / len as f64;
+    let total: f64 = magnitudes.iter().sum();
+    let highest = magnitudes.iter().cloned().fold(0.0, f64::max);
+    if highest <= 0.0 {
+        return vec![];
+    }
+
+    let lowest = ((min_freq / bin_hz).ceil() as usize).max(1);
+    let top = max_freq.map_or(magnitudes.len(), |max| (max / bin_hz) as usize + 1);
+    let candidates: Vec<usize> = (lowest..top.min(magnitudes.len().saturating_sub(1)))
+        .filter(|&bin| {
+            magnitudes[bin] >= PEAK_FLOOR * highest
+                && magnitudes[bin - 1] < magnitudes[bin]
+                && magnitudes[bin] >= magnitudes[bin + 1]
+        })
+        .collect();
+
+    let mut voices = vec![];
+    let mut first_salience = None;
+    while voices.len() < 2 {
+        let best = candidates
+            .iter()
+            .filter(|&&bin| {
+                // Skip candidates cancelled with the first pitch's harmonics, or too close to it.
+                magnitudes[bin] >= PEAK_FLOOR * highest
+                    && voices.iter().all(|voice: &Voice| {
+                        let cents = 1200.0 * (bin as f64 * bin_hz / voice.freq).log2();
+                        cents.abs() >= MIN_SEPARATION
+                    })
+            })
+            .map(|&bin| (bin, salience(&magnitudes, bin as f64)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let (bin, salience) = match best {
+            Some(best) => best,
+            None => break,
+        };
+        match first_salience {
+            None => first_salience = Some(salience),
+            Some(first) if salience < MIN_SECOND_SALIENCE * first => break,
+            Some(_) => {}
+        }
+
+        let fundamental = bin as f64 + peak_offset(&magnitudes, bin);
+        let removed = cancel_harmonics(&mut magnitudes, fundamental);
+        voices.push(Voice {
+            freq: fundamental * bin_hz,
+            share: if total > 0.0 { removed / total } else { 0.0 },
+        });
+    }
+    voices
+}
+
+/// The peak near each harmonic of the fundamental at `bin`, up to `HARMONICS`, as a bin and its
+/// harmonic number. The fundamental is `bin` itself, so the sidelobes either side of a peak can't
+/// claim it.
+fn harmonic_peaks(magnitudes: &[f64], bin: f64) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let fundamental = (bin.round() as usize).min(magnitudes.len() - 1);
+    std::iter::once((fundamental, 1)).chain((2..=HARMONICS).map_while(move |harmonic| {
+        let centre = bin * harmonic as f64;
+        let width = (centre * HARMONIC_TOLERANCE).min(bin / 4.0).max(1.0);
+        let from = (centre - width).round().max(0.0) as usize;
+        let to = ((centre + width).round() as usize).min(magnitudes.len().checked_sub(1)?);
+        if from > to {
+            return None;
+        }
+        let peak = (from..=to).max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]))?;
+        Some((peak, harmonic))
+    }))
+}
+
+/// How strongly the spectrum suggests a fundamental at `bin`: the sum of the peaks at its
+/// harmonics, weighted down for the higher ones so that a candidate an octave up, which shares
+/// only every other harmonic, scores lower.
+fn salience(magnitudes: &[f64], bin: f64) -> f64 {
+    harmonic_peaks(magnitudes, bin)
+        .map(|(peak, harmonic)| magnitudes[peak] / harmonic as f64)
+        .sum()
+}
+
+/// Zero the peaks at the harmonics of the fundamental at `bin`, returning the magnitude removed.
+fn cancel_harmonics(magnitudes: &mut [f64], bin: f64) -> f64 {
+    let peaks: Vec<usize> = harmonic_peaks(magnitudes, bin)
+        .map(|(peak, _)| peak)
+        .collect();
+    let width = CANCEL_BINS * ZERO_PADDING;
+    let mut removed = 0.0;
+    for peak in peaks {
+        let to = (peak + width).min(magnitudes.len() - 1);
+        for magnitude in &mut magnitudes[peak.saturating_sub(width)..=to] {
+            removed += *magnitude;
+            *magnitude = 0.0;
+        }
+    }
+    removed
+}
+
+/// The offset of the true peak from `bin`, from the parabola through the log magnitudes around it.
+fn peak_offset(magnitudes: &[f64], bin: usize) -> f64 {
+    if bin < 1 || bin + 1 >= magnitudes.len() {
+        return 0.0;
+    }
+    let ln = |bin: usize| magnitudes[bin].max(f64::MIN_POSITIVE).ln();
+    crate::parabolic_offset(ln(bin - 1), ln(bin), ln(bin + 1))
+}
@@ -0,0 +1,107 @@
+//! A progress bar for long analyses, redrawn in place on one line of a terminal (usually stderr,
+//! so that it doesn't mix with the results): how many chunks are done, how much faster than real
+//! time they're going, and when the rest should be done.
+
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// The width of the bar, in columns.
+pub const WIDTH: usize = 30;
+
+/// How often the bar is redrawn at most, so that drawing doesn't slow the analysis.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Clear the line and go back to its start.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+/// The line showing that `done` of `total` chunks (if it's known), each `seconds_per_chunk` of
+/// audio, were analyzed in `elapsed` seconds.
+pub fn render(done: usize, total: Option<usize>, seconds_per_chunk: f64, elapsed: f64) -> String {
+    let audio = done as f64 * seconds_per_chunk;
+    let speed = if elapsed > 0.0 {
+        format!("{:.1}x realtime", audio / elapsed)
+    } else {
+        "-x realtime".to_string()
+    };
+    match total {
+        Some(total) if total > 0 => {
+            let fraction = (done as f64 / total as f64).min(1.0);
+            let filled = (fraction * WIDTH as f64).round() as usize;
+            let eta = if done > 0 {
+                clock(elapsed / done as f64 * total.saturating_sub(done) as f64)
+            } else {
+                "-:--".to_string()
+            };
+            format!(
+                "[{}{}] {:>3.0}%  {}/{} chunks  {}  ETA {}",
+                "#".repeat(filled),
+                "-".repeat(WIDTH - filled),
+                100.0 * fraction,
+                done,
+                total,
+                speed,
+                eta
+            )
+        }
+        _ => format!("{} chunks ({} of audio)  {}", done, clock(audio), speed),
+    }
+}
+
+/// `seconds` as `m:ss`, or `h:mm:ss` from an hour up.
+pub fn clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Draws the progress of an analysis to `out` as chunks are done.
+#[derive(Debug)]
+pub struct Progress<W: Write> {
+    out: W,
+    total: Option<usize>,
+    seconds_per_chunk: f64,
+    done: usize,
+    start: Instant,
+    drawn: Option<Instant>,
+}
+
+impl<W: Write> Progress<W> {
+    /// Progress through `total` chunks, if it's known, of `seconds_per_chunk` seconds each.
+    pub fn new(out: W, total: Option<usize>, seconds_per_chunk: f64) -> Self {
+        Progress {
+            out,
+            total,
+            seconds_per_chunk,
+            done: 0,
+            start: Instant::now(),
+            drawn: None,
+        }
+    }
+
+    /// Count `chunks` more as done, and redraw the bar if it's been a while.
+    pub fn advance(&mut self, chunks: usize) -> io::Result<()> {
+        self.done += chunks;
+        let now = Instant::now();
+        if self.drawn.is_some_and(|drawn| now - drawn < INTERVAL) {
+            return Ok(());
+        }
+        self.drawn = Some(now);
+        let elapsed = (now - self.start).as_secs_f64();
+        let line = render(self.done, self.total, self.seconds_per_chunk, elapsed);
+        write!(self.out, "{}{}", CLEAR_LINE, line)?;
+        self.out.flush()
+    }
+
+    /// Clear the bar, leaving the line as it was.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.drawn.is_some() {
+            write!(self.out, "{}", CLEAR_LINE)?;
+            self.out.flush()?;
+        }
+        Ok(self.out)
+    }
+}
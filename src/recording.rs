@@ -0,0 +1,113 @@
+//! Recording live input to a WAV file as it's analyzed, so that a session can be analyzed again
+//! afterwards, with other settings or algorithms.
+//!
+//! `Recorder` passes the raw s16le mono PCM it reads through unchanged, and hands each read to a
+//! thread to be written, so that a slow disk doesn't hold up detection.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::Result;
+
+type WavWriter = hound::WavWriter<BufWriter<File>>;
+
+/// Reads s16le mono PCM from a reader, recording everything read to a WAV file.
+pub struct Recorder<R> {
+    reader: R,
+    /// Sends what's read to the thread writing it.
+    sender: Option<Sender<Vec<u8>>>,
+    writer: Option<JoinHandle<Result<()>>>,
+}
+
+impl<R: Read> Recorder<R> {
+    /// Record what's read from `reader`, sampled at `sample_rate` Hz, to a WAV file at `path`.
+    pub fn create(reader: R, path: &Path, sample_rate: usize) -> Result<Self> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: sample_rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let wav = hound::WavWriter::create(path, spec)?;
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+        let writer = thread::spawn(move || {
+            let mut wav = wav;
+            // A sample can be split between reads.
+            let mut odd_byte = None;
+            for bytes in receiver {
+                write_samples(&mut wav, &mut odd_byte, &bytes)?;
+            }
+            wav.finalize()?;
+            Ok(())
+        });
+        Ok(Recorder {
+            reader,
+            sender: Some(sender),
+            writer: Some(writer),
+        })
+    }
+
+    /// Stop recording, and finish the WAV file.
+    pub fn finish(mut self) -> Result<()> {
+        self.stop()
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.sender = None;
+        match self.writer.take() {
+            Some(writer) => writer.join().unwrap(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn write_samples(wav: &mut WavWriter, odd_byte: &mut Option<u8>, bytes: &[u8]) -> Result<()> {
+    let mut bytes = bytes;
+    if let (Some(low), Some((high, rest))) = (*odd_byte, bytes.split_first()) {
+        wav.write_sample(i16::from_le_bytes([low, *high]))?;
+        *odd_byte = None;
+        bytes = rest;
+    }
+    let mut pairs = bytes.chunks_exact(2);
+    for pair in &mut pairs {
+        wav.write_sample(i16::from_le_bytes([pair[0], pair[1]]))?;
+    }
+    if let [byte] = pairs.remainder() {
+        *odd_byte = Some(*byte);
+    }
+    Ok(())
+}
+
+impl<R: Read> Read for Recorder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n > 0 {
+            let sent = match &self.sender {
+                Some(sender) => sender.send(buf[..n].to_vec()).is_ok(),
+                None => true,
+            };
+            // The writer only hangs up when it fails.
+            if !sent {
+                let e = self.stop().err().map_or_else(
+                    || io::Error::other("recording stopped"),
+                    |e| io::Error::other(format!("recording failed: {}", e)),
+                );
+                return Err(e);
+            }
+        }
+        Ok(n)
+    }
+}
+
+impl<R> Drop for Recorder<R> {
+    /// Finish the WAV file, if `finish` wasn't called.
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Decimation of high sample rate input, so 96 and 192 kHz recordings are analyzed at a rate
+//! where the detectors run in real time. Frequencies come out the same either way, since every
+//! detector works from its sample rate, but their cost grows with the square of it.
+
+use std::f64::consts::PI;
+
+use crate::window::Window;
+
+/// Input sampled faster than this is decimated by default.
+pub const DEFAULT_MAX_RATE: usize = 48000;
+
+/// The number of filter taps per unit of the decimation factor.
+const TAPS_PER_FACTOR: usize = 16;
+
+/// The fraction of the decimated Nyquist frequency that the low-pass filter passes.
+const PASSBAND: f64 = 0.9;
+
+/// The smallest whole factor that brings `sample_rate` down to `max_rate` or below.
+pub fn decimation_factor(sample_rate: usize, max_rate: usize) -> usize {
+    sample_rate.div_ceil(max_rate.max(1)).max(1)
+}
+
+/// Low-pass filters a stream of samples and keeps every `factor`-th one. The filter is a
+/// Blackman-windowed sinc, so the decimated signal has no aliases of anything above its new
+/// Nyquist frequency.
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    factor: usize,
+    taps: Vec<f32>,
+    /// The last `taps.len() - 1` samples of the previous input, then the current input.
+    buffer: Vec<f32>,
+}
+
+impl Decimator {
+    pub fn new(factor: usize) -> Self {
+        let factor = factor.max(1);
+        let len = TAPS_PER_FACTOR * factor + 1;
+        let cutoff = PASSBAND * 0.5 / factor as f64; // cycles per input sample
+        let middle = (len / 2) as f64;
+        let mut taps: Vec<f64> = (0..len)
+            .map(|i| {
+                let x = i as f64 - middle;
+                let sinc = if x == 0.0 {
+                    2.0 * cutoff
+                } else {
+                    (2.0 * PI * cutoff * x).sin() / (PI * x)
+                };
+                sinc * Window::Blackman.weight(i, len)
+            })
+            .collect();
+        // Unity gain at DC.
+        let sum: f64 = taps.iter().sum();
+        taps.iter_mut().for_each(|tap| *tap /= sum);
+
+        Decimator {
+            factor,
+            taps: taps.into_iter().map(|tap| tap as f32).collect(),
+            buffer: vec![0.0; len - 1],
+        }
+    }
+
+    pub fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Append `input`, decimated, to `out`. Its length should be a multiple of the factor, or the
+    /// remainder is dropped from the output (though not from the filter's history).
+    pub fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if self.factor == 1 {
+            out.extend_from_slice(input);
+            return;
+        }
+
+        let history = self.taps.len() - 1;
+        self.buffer.extend_from_slice(input);
+        out.extend((0..input.len() / self.factor).map(|i| {
+            let end = history + i * self.factor + 1;
+            self.buffer[end - self.taps.len()..end]
+                .iter()
+                .rev()
+                .zip(&self.taps)
+                .map(|(sample, tap)| sample * tap)
+                .sum::<f32>()
+        }));
+        self.buffer.drain(..self.buffer.len() - history);
+    }
+}
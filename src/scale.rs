@@ -0,0 +1,133 @@
+//! Identifying the scales and modes a melody fits, e.g. to see what an improvisation drew on. A
+//! scale fits as well as the share of the time its notes sound. Of the scales that fit equally
+//! well, those more of whose notes are heard come first, then those whose tonic sounds longest,
+//! then those with fewer notes that aren't heard (so a pentatonic melody fits the pentatonic scale
+//! before the major).
+
+use std::fmt;
+
+use crate::key::pitch_class_durations;
+use crate::notes::NoteEvent;
+use crate::Note;
+
+/// A scale or mode, in any key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Major,
+    NaturalMinor,
+    HarmonicMinor,
+    MelodicMinor,
+    MajorPentatonic,
+    MinorPentatonic,
+    Blues,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    Locrian,
+}
+
+impl Scale {
+    /// All scales, in order of how common they are.
+    pub const ALL: [Scale; 12] = [
+        Scale::Major,
+        Scale::NaturalMinor,
+        Scale::HarmonicMinor,
+        Scale::MelodicMinor,
+        Scale::MajorPentatonic,
+        Scale::MinorPentatonic,
+        Scale::Blues,
+        Scale::Dorian,
+        Scale::Mixolydian,
+        Scale::Lydian,
+        Scale::Phrygian,
+        Scale::Locrian,
+    ];
+
+    /// The notes of the scale, in semitones above the tonic. The melodic minor is the ascending
+    /// form; descending, it's the natural minor.
+    pub fn intervals(self) -> &'static [u8] {
+        match self {
+            Scale::Major => &[0, 2, 4, 5, 7, 9, 11],
+            Scale::NaturalMinor => &[0, 2, 3, 5, 7, 8, 10],
+            Scale::HarmonicMinor => &[0, 2, 3, 5, 7, 8, 11],
+            Scale::MelodicMinor => &[0, 2, 3, 5, 7, 9, 11],
+            Scale::MajorPentatonic => &[0, 2, 4, 7, 9],
+            Scale::MinorPentatonic => &[0, 3, 5, 7, 10],
+            Scale::Blues => &[0, 3, 5, 6, 7, 10],
+            Scale::Dorian => &[0, 2, 3, 5, 7, 9, 10],
+            Scale::Phrygian => &[0, 1, 3, 5, 7, 8, 10],
+            Scale::Lydian => &[0, 2, 4, 6, 7, 9, 11],
+            Scale::Mixolydian => &[0, 2, 4, 5, 7, 9, 10],
+            Scale::Locrian => &[0, 1, 3, 5, 6, 8, 10],
+        }
+    }
+}
+
+impl fmt::Display for Scale {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Scale::Major => "major",
+            Scale::NaturalMinor => "natural minor",
+            Scale::HarmonicMinor => "harmonic minor",
+            Scale::MelodicMinor => "melodic minor",
+            Scale::MajorPentatonic => "major pentatonic",
+            Scale::MinorPentatonic => "minor pentatonic",
+            Scale::Blues => "blues",
+            Scale::Dorian => "dorian",
+            Scale::Phrygian => "phrygian",
+            Scale::Lydian => "lydian",
+            Scale::Mixolydian => "mixolydian",
+            Scale::Locrian => "locrian",
+        })
+    }
+}
+
+/// How well a melody fits a scale on some tonic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleFit {
+    pub tonic: Note,
+    pub scale: Scale,
+    /// The share of the time notes sound that they're in the scale, from 0 to 1.
+    pub fit: f64,
+    /// How many of the scale's notes are heard.
+    pub used: usize,
+    /// How long the tonic sounds, in seconds.
+    tonic_duration: f64,
+}
+
+/// Every scale on every tonic, best fitting `notes` first. Empty if there are no notes to go on.
+pub fn rank_scales(notes: &[NoteEvent]) -> Vec<ScaleFit> {
+    let durations = pitch_class_durations(notes);
+    let total: f64 = durations.iter().sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+
+    let mut fits = vec![];
+    for tonic in Note::ALL {
+        for scale in Scale::ALL {
+            let in_scale = scale
+                .intervals()
+                .iter()
+                .map(|interval| durations[(tonic.semitone() + interval) as usize % 12]);
+            fits.push(ScaleFit {
+                tonic,
+                scale,
+                fit: in_scale.clone().sum::<f64>() / total,
+                used: in_scale.filter(|duration| *duration > 0.0).count(),
+                tonic_duration: durations[tonic.semitone() as usize],
+            });
+        }
+    }
+
+    // Sorting is stable, so the more common scales stay first among those that tie.
+    fits.sort_by(|a, b| {
+        b.fit
+            .total_cmp(&a.fit)
+            .then(b.used.cmp(&a.used))
+            .then(b.tonic_duration.total_cmp(&a.tonic_duration))
+            .then(a.scale.intervals().len().cmp(&b.scale.intervals().len()))
+    });
+    fits
+}
@@ -0,0 +1,401 @@
+//! Laying out notes as a score: quantizing them to a rhythmic grid, splitting them into measures
+//! and spelling their pitches in a key.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::notes::NoteEvent;
+use crate::Pitch;
+
+/// The tempo assumed when none is given, in quarter notes per minute.
+pub const DEFAULT_TEMPO: f64 = 120.0;
+
+/// The grid notes are quantized to, in steps per quarter note (so a step is a sixteenth note).
+pub const DIVISION: u32 = 4;
+
+/// The length of a whole note in steps.
+const WHOLE: u32 = 4 * DIVISION;
+
+/// The lengths, in steps, that can be written as a single note value (perhaps dotted), longest
+/// first.
+const NOTE_VALUES: &[u32] = &[16, 12, 8, 6, 4, 3, 2, 1];
+
+/// The letters of the natural notes in order of `semitone`, and their alterations, spelling the
+/// black keys as sharps.
+const SHARP_SPELLINGS: [(char, i8); 12] = [
+    ('C', 0),
+    ('C', 1),
+    ('D', 0),
+    ('D', 1),
+    ('E', 0),
+    ('F', 0),
+    ('F', 1),
+    ('G', 0),
+    ('G', 1),
+    ('A', 0),
+    ('A', 1),
+    ('B', 0),
+];
+
+/// The same, spelling the black keys as flats.
+const FLAT_SPELLINGS: [(char, i8); 12] = [
+    ('C', 0),
+    ('D', -1),
+    ('D', 0),
+    ('E', -1),
+    ('E', 0),
+    ('F', 0),
+    ('G', -1),
+    ('G', 0),
+    ('A', -1),
+    ('A', 0),
+    ('B', -1),
+    ('B', 0),
+];
+
+/// The order sharps are added to key signatures in. Flats are added in the reverse order.
+const ORDER_OF_SHARPS: &str = "FCGDAEB";
+
+/// A major or minor key, which decides the key signature and how pitches are spelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    /// The letter of the tonic, from `A` to `G`.
+    pub letter: char,
+    /// The alteration of the tonic: 1 for sharp, -1 for flat.
+    pub alter: i8,
+    pub minor: bool,
+}
+
+impl Default for Key {
+    fn default() -> Self {
+        Key {
+            letter: 'C',
+            alter: 0,
+            minor: false,
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// Parse a tonic such as `G`, `Bb` or `F#`, followed by `m` for a minor key.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("unknown key: {}", s);
+
+        let mut chars = s.chars();
+        let letter = chars
+            .next()
+            .map(|c| c.to_ascii_uppercase())
+            .filter(|c| ('A'..='G').contains(c))
+            .ok_or_else(err)?;
+        let rest = chars.as_str();
+        let (alter, rest) = match rest.chars().next() {
+            Some('#') => (1, &rest[1..]),
+            Some('b') => (-1, &rest[1..]),
+            _ => (0, rest),
+        };
+        let minor = match rest {
+            "" => false,
+            "m" => true,
+            _ => return Err(err()),
+        };
+
+        let key = Key {
+            letter,
+            alter,
+            minor,
+        };
+        if key.fifths().abs() > 7 {
+            return Err(err());
+        }
+        Ok(key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.letter)?;
+        match self.alter {
+            1 => write!(f, "#")?,
+            -1 => write!(f, "b")?,
+            _ => {}
+        }
+        if self.minor {
+            write!(f, "m")?;
+        }
+        Ok(())
+    }
+}
+
+impl Key {
+    /// The major or minor key on the tonic `semitone` semitones above C, spelled with the fewest
+    /// sharps or flats (F# major rather than Gb, which have six each).
+    pub fn from_tonic(semitone: u8, minor: bool) -> Key {
+        let semitone = semitone as usize % 12;
+        let [sharp, flat] =
+            [SHARP_SPELLINGS[semitone], FLAT_SPELLINGS[semitone]].map(|(letter, alter)| Key {
+                letter,
+                alter,
+                minor,
+            });
+        if flat.fifths().abs() < sharp.fifths().abs() {
+            flat
+        } else {
+            sharp
+        }
+    }
+
+    /// The number of sharps in the key signature, or minus the number of flats.
+    pub fn fifths(self) -> i8 {
+        let natural = ORDER_OF_SHARPS.find(self.letter).unwrap_or(1) as i8 - 1;
+        natural + 7 * self.alter - if self.minor { 3 } else { 0 }
+    }
+
+    /// The alteration the key signature gives notes with `letter`.
+    pub fn alteration(self, letter: char) -> i8 {
+        let fifths = self.fifths();
+        if fifths > 0 {
+            let position = ORDER_OF_SHARPS.find(letter).unwrap_or(7) as i8;
+            (position < fifths) as i8
+        } else {
+            let position = ORDER_OF_SHARPS.find(letter).map_or(7, |i| 6 - i as i8);
+            -((position < -fifths) as i8)
+        }
+    }
+
+    /// Spell `pitch` in this key: black keys are spelled as the key signature has them, or else
+    /// as sharps in sharp keys and flats in flat keys.
+    pub fn spell(self, pitch: Pitch) -> Spelling {
+        let semitone = pitch.note.semitone() as usize;
+        let (sharp, flat) = (SHARP_SPELLINGS[semitone], FLAT_SPELLINGS[semitone]);
+        let (letter, alter) = if self.alteration(sharp.0) == sharp.1 {
+            sharp
+        } else if self.alteration(flat.0) == flat.1 || self.fifths() < 0 {
+            flat
+        } else {
+            sharp
+        };
+
+        Spelling {
+            letter,
+            alter,
+            octave: pitch.octave,
+        }
+    }
+}
+
+/// A pitch as it is written: a letter, an alteration and an octave in scientific pitch notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spelling {
+    pub letter: char,
+    /// 1 for sharp, 0 for natural, -1 for flat.
+    pub alter: i8,
+    pub octave: i8,
+}
+
+/// Tracks the accidentals written so far in a measure, which last until the end of it.
+#[derive(Debug, Clone, Default)]
+pub struct Accidentals {
+    /// The alteration in force for each letter and octave.
+    written: HashMap<(char, i8), i8>,
+}
+
+impl Accidentals {
+    /// The accidental `spelling` must be written with in `key`, if any, given the ones written
+    /// before it in the measure.
+    pub fn next(&mut self, key: Key, spelling: Spelling) -> Option<i8> {
+        let place = (spelling.letter, spelling.octave);
+        let current = *self
+            .written
+            .get(&place)
+            .unwrap_or(&key.alteration(spelling.letter));
+        if spelling.alter == current {
+            return None;
+        }
+        self.written.insert(place, spelling.alter);
+        Some(spelling.alter)
+    }
+}
+
+/// A time signature, such as 3/4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeSignature {
+    pub beats: u32,
+    /// The note value of a beat: 4 for quarter notes. Always a power of two up to 16.
+    pub beat_type: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        TimeSignature {
+            beats: 4,
+            beat_type: 4,
+        }
+    }
+}
+
+impl FromStr for TimeSignature {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("unknown time signature: {}", s);
+        let (beats, beat_type) = s.split_once('/').ok_or_else(err)?;
+        let beats: u32 = beats.parse().map_err(|_| err())?;
+        let beat_type: u32 = beat_type.parse().map_err(|_| err())?;
+        if beats == 0 || !beat_type.is_power_of_two() || beat_type > WHOLE {
+            return Err(err());
+        }
+        Ok(TimeSignature { beats, beat_type })
+    }
+}
+
+impl fmt::Display for TimeSignature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.beats, self.beat_type)
+    }
+}
+
+impl TimeSignature {
+    /// The length of a measure in steps.
+    pub fn measure(self) -> u32 {
+        self.beats * WHOLE / self.beat_type
+    }
+}
+
+/// How notes are laid out as a score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    /// The tempo, in quarter notes per minute.
+    pub tempo: f64,
+    pub key: Key,
+    pub time: TimeSignature,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout {
+            tempo: DEFAULT_TEMPO,
+            key: Key::default(),
+            time: TimeSignature::default(),
+        }
+    }
+}
+
+/// A note, or a rest if `pitch` is `None`, lasting `length` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub pitch: Option<Pitch>,
+    pub length: u32,
+}
+
+/// Quantize `notes`, which must be in order, to the nearest steps at `tempo` quarter notes per
+/// minute, with rests between them. Notes are kept at least a step long, and start no earlier
+/// than the note before ends.
+pub fn quantize(notes: &[NoteEvent], tempo: f64) -> Vec<Event> {
+    let steps_per_second = tempo / 60.0 * DIVISION as f64;
+    let to_steps = |seconds: f64| (seconds * steps_per_second).round().max(0.0) as u32;
+
+    let mut events = vec![];
+    let mut now = 0;
+    for note in notes {
+        let start = to_steps(note.start).max(now);
+        let end = to_steps(note.end).max(start + 1);
+        if start > now {
+            events.push(Event {
+                pitch: None,
+                length: start - now,
+            });
+        }
+        events.push(Event {
+            pitch: Some(note.pitch),
+            length: end - start,
+        });
+        now = end;
+    }
+
+    events
+}
+
+/// `notes` quantized as `quantize` does, as notes again, starting and ending on the grid.
+pub fn quantize_notes(notes: &[NoteEvent], tempo: f64) -> Vec<NoteEvent> {
+    let seconds_per_step = 60.0 / tempo / DIVISION as f64;
+    let mut now = 0;
+    let mut quantized = vec![];
+    let mut notes = notes.iter();
+    for event in quantize(notes.as_slice(), tempo) {
+        if event.pitch.is_some() {
+            if let Some(note) = notes.next() {
+                quantized.push(NoteEvent {
+                    start: now as f64 * seconds_per_step,
+                    end: (now + event.length) as f64 * seconds_per_step,
+                    ..*note
+                });
+            }
+        }
+        now += event.length;
+    }
+    quantized
+}
+
+/// A note or rest as written in a measure: a single note value, perhaps dotted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Written {
+    pub pitch: Option<Pitch>,
+    pub length: u32,
+    /// Whether the note is tied to the next one.
+    pub tie: bool,
+}
+
+impl Written {
+    /// The note value this is written as: the denominator (4 for a quarter note) and whether
+    /// it is dotted.
+    pub fn note_value(&self) -> (u32, bool) {
+        if WHOLE.is_multiple_of(self.length) {
+            (WHOLE / self.length, false)
+        } else {
+            // A dotted note is one and a half times as long as a plain one.
+            (WHOLE * 3 / 2 / self.length, true)
+        }
+    }
+}
+
+/// Split `events` into measures of `time`, and each event into note values that fit in them,
+/// tying the parts of notes together. The last measure is filled out with rests.
+pub fn measures(events: &[Event], time: TimeSignature) -> Vec<Vec<Written>> {
+    let mut measures = vec![];
+    let mut measure = vec![];
+    let mut filled = 0;
+
+    let measure_length = time.measure();
+    let total: u32 = events.iter().map(|e| e.length).sum();
+    let rest = Event {
+        pitch: None,
+        length: (measure_length - total % measure_length) % measure_length,
+    };
+    for event in events.iter().chain(Some(&rest)) {
+        let mut left = event.length;
+        while left > 0 {
+            let length = NOTE_VALUES
+                .iter()
+                .cloned()
+                .find(|value| *value <= left && *value <= measure_length - filled)
+                .unwrap_or(1);
+            left -= length;
+            filled += length;
+            measure.push(Written {
+                pitch: event.pitch,
+                length,
+                tie: event.pitch.is_some() && left > 0,
+            });
+
+            if filled == measure_length {
+                measures.push(std::mem::take(&mut measure));
+                filled = 0;
+            }
+        }
+    }
+
+    measures
+}
@@ -0,0 +1,305 @@
+//! Scoring a sung (or played) performance against a reference melody, as a karaoke or practice
+//! tool does: the performance's pitch track is aligned with the melody in time, and each note is
+//! scored by how much of it was sung in tune.
+//!
+//! Alignment first finds the offset at which the performance best matches the melody, so that it
+//! needn't start on cue, and then follows it by dynamic time warping within `BAND` seconds of
+//! that offset, so that rushing or dragging a little isn't held against the singer. Pitches are
+//! compared in the nearest octave, since singers often take a melody an octave down.
+
+use crate::notes::NoteEvent;
+use crate::Pitch;
+
+/// How far from a note its frames may be, in cents, to count as in tune, when no tolerance is
+/// given.
+pub const DEFAULT_TOLERANCE: f64 = 50.0;
+
+/// How far the alignment may drift from the best offset, in seconds.
+pub const BAND: f64 = 2.0;
+
+/// The velocity of the notes of note lists, which don't give one.
+const NOTE_LIST_VELOCITY: u8 = 64;
+
+/// Mismatches of more than this many semitones cost no more, so that a wrong note costs as much
+/// as a missing one.
+const MAX_MISMATCH: f64 = 3.0;
+
+/// The cost of singing during a rest, less than of not singing a note, since notes often ring on.
+const REST_COST: f64 = 0.5;
+
+/// The extra cost of each step of the alignment that holds one of the melody and performance
+/// while the other moves on, so that it only warps when that pays.
+const WARP_COST: f64 = 0.5;
+
+/// How well one note of the melody was sung.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteScore {
+    pub note: NoteEvent,
+    /// The number of frames of the performance aligned with the note.
+    pub frames: usize,
+    /// How many of those had a pitch.
+    pub sung: usize,
+    /// How many of those were within the tolerance of the note, in the nearest octave.
+    pub in_tune: usize,
+    /// How far the frames with a pitch were from the note on average, in cents, in the nearest
+    /// octave, or `None` if none had one.
+    pub cents: Option<f64>,
+}
+
+impl NoteScore {
+    /// The share of the note's frames sung in tune, or 0 if it has none.
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.in_tune, self.frames)
+    }
+}
+
+/// How well a performance followed a melody.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    /// Each note of the melody, in order.
+    pub notes: Vec<NoteScore>,
+    /// How far behind the melody's timing the performance is, in seconds, by the best offset
+    /// (or ahead of it, if negative).
+    pub offset: f64,
+    /// The share of the frames of every note sung in tune.
+    pub accuracy: f64,
+}
+
+/// Parse a melody written as a note list: a pitch such as `A4` or `Bb3` (or `rest`, or `-`) and
+/// a duration in seconds per line, one after the other from time 0. Blank lines and comments,
+/// from a `#` at the start of a line or after a space, are skipped.
+pub fn parse_melody(text: &str) -> Result<Vec<NoteEvent>, String> {
+    let (mut melody, mut time) = (vec![], 0.0);
+    for (i, line) in text.lines().enumerate() {
+        let comment = line
+            .char_indices()
+            .find(|(i, c)| *c == '#' && (*i == 0 || line[..*i].ends_with(char::is_whitespace)))
+            .map_or(line.len(), |(i, _)| i);
+        let line = line[..comment].trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let (pitch, duration) = match fields[..] {
+            [pitch, duration] => (pitch, duration),
+            _ => return Err(format!("line {}: expected a note and a duration", i + 1)),
+        };
+        let duration: f64 = duration
+            .parse()
+            .ok()
+            .filter(|duration| *duration > 0.0)
+            .ok_or_else(|| format!("line {}: invalid duration: {}", i + 1, duration))?;
+        if pitch != "rest" && pitch != "-" {
+            let pitch: Pitch = pitch
+                .parse()
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            melody.push(NoteEvent {
+                pitch,
+                start: time,
+                end: time + duration,
+                velocity: NOTE_LIST_VELOCITY,
+            });
+        }
+        time += duration;
+    }
+    Ok(melody)
+}
+
+/// Score `performance`, the time in seconds and frequency in Hz (if any) of evenly spaced pitch
+/// estimates, against `melody`, whose notes must be in order and not overlap, with A4 at `a4` Hz.
+/// Frames within `tolerance` cents of their note count as in tune.
+pub fn score(
+    melody: &[NoteEvent],
+    performance: &[(f64, Option<f64>)],
+    a4: f64,
+    tolerance: f64,
+) -> Score {
+    let mut notes: Vec<NoteScore> = melody
+        .iter()
+        .map(|note| NoteScore {
+            note: *note,
+            frames: 0,
+            sung: 0,
+            in_tune: 0,
+            cents: None,
+        })
+        .collect();
+    let (first, last) = match (performance.first(), performance.last()) {
+        (Some(first), Some(last)) if performance.len() >= 2 && last.0 > first.0 => (first, last),
+        _ => {
+            return Score {
+                notes,
+                offset: 0.0,
+                accuracy: 0.0,
+            }
+        }
+    };
+    let step = (last.0 - first.0) / (performance.len() - 1) as f64;
+
+    // The melody and performance as fractional MIDI note numbers, a frame per step.
+    let end = melody.last().map_or(0.0, |note| note.end);
+    let reference: Vec<Option<(usize, f64)>> = (0..(end / step).ceil() as usize)
+        .map(|k| {
+            let time = (k as f64 + 0.5) * step;
+            let i = melody
+                .iter()
+                .position(|note| note.start <= time && time < note.end)?;
+            Some((i, melody[i].pitch.midi() as f64))
+        })
+        .collect();
+    let sung: Vec<Option<f64>> = performance
+        .iter()
+        .map(|(_, freq)| freq.filter(|freq| *freq > 0.0).map(|freq| midi(freq, a4)))
+        .collect();
+    let sung_at = |j: isize| -> Option<f64> {
+        if j < 0 {
+            return None;
+        }
+        sung.get(j as usize).copied().flatten()
+    };
+    let cost = |k: usize, j: isize| frame_cost(reference[k].map(|(_, key)| key), sung_at(j));
+
+    // The offset, in frames, with the least cost over the whole melody, the nearest 0 first.
+    let (n, m) = (reference.len() as isize, sung.len() as isize);
+    let mut offsets: Vec<isize> = (-(n - 1).max(0)..m).collect();
+    offsets.sort_by_key(|offset| offset.abs());
+    let offset = offsets
+        .into_iter()
+        .map(|offset| {
+            let total: f64 = (0..n).map(|k| cost(k as usize, k + offset)).sum();
+            (offset, total)
+        })
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map_or(0, |(offset, _)| offset);
+
+    // Warp within the band around that offset: cell `b` of row `k` aligns reference frame `k`
+    // with performance frame `k + offset - band + b`.
+    let band = (BAND / step).round() as isize;
+    let width = (2 * band + 1) as usize;
+    let column = |k: usize, b: usize| k as isize + offset - band + b as isize;
+    let mut totals = vec![vec![f64::INFINITY; width]; reference.len()];
+    let mut steps = vec![vec![Step::Start; width]; reference.len()];
+    for k in 0..reference.len() {
+        for b in 0..width {
+            let here = cost(k, column(k, b));
+            let mut best = (if k == 0 { 0.0 } else { f64::INFINITY }, Step::Start);
+            if k > 0 {
+                best = min(best, (totals[k - 1][b], Step::Both));
+                if b + 1 < width {
+                    best = min(best, (totals[k - 1][b + 1] + WARP_COST, Step::Melody));
+                }
+            }
+            if b > 0 {
+                best = min(best, (totals[k][b - 1] + WARP_COST, Step::Performance));
+            }
+            totals[k][b] = best.0 + here;
+            steps[k][b] = best.1;
+        }
+    }
+
+    // Trace the cheapest path back, and score each note by the frames it went through.
+    let mut path = vec![];
+    if let Some(k) = reference.len().checked_sub(1) {
+        let mut b = (0..width)
+            .min_by(|a, b| totals[k][*a].partial_cmp(&totals[k][*b]).unwrap())
+            .unwrap_or(0);
+        let mut k = k;
+        loop {
+            path.push((k, column(k, b)));
+            match steps[k][b] {
+                Step::Start => break,
+                Step::Both => k -= 1,
+                Step::Melody => {
+                    k -= 1;
+                    b += 1;
+                }
+                Step::Performance => b -= 1,
+            }
+        }
+    }
+    path.reverse();
+    let mut last_frame = None;
+    let mut cents_sums = vec![0.0; notes.len()];
+    for (k, j) in path {
+        let (i, key) = match reference[k] {
+            Some(frame) => frame,
+            None => continue,
+        };
+        if last_frame == Some((i, j)) {
+            continue;
+        }
+        last_frame = Some((i, j));
+        let note = &mut notes[i];
+        note.frames += 1;
+        if let Some(sung) = sung_at(j) {
+            let cents = 100.0 * nearest_octave(sung - key);
+            note.sung += 1;
+            note.in_tune += (cents.abs() <= tolerance) as usize;
+            cents_sums[i] += cents;
+        }
+    }
+    for (note, sum) in notes.iter_mut().zip(cents_sums) {
+        note.cents = Some(sum / note.sung as f64).filter(|_| note.sung > 0);
+    }
+
+    let frames = notes.iter().map(|note| note.frames).sum();
+    let in_tune = notes.iter().map(|note| note.in_tune).sum();
+    Score {
+        notes,
+        // Reference frames are at the middle of their steps.
+        offset: first.0 + (offset as f64 - 0.5) * step,
+        accuracy: ratio(in_tune, frames),
+    }
+}
+
+/// The ways of reaching a cell of the alignment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Step {
+    /// It's where the alignment starts.
+    Start,
+    /// From the previous frame of both.
+    Both,
+    /// From the previous frame of the melody, holding the performance's.
+    Melody,
+    /// From the previous frame of the performance, holding the melody's.
+    Performance,
+}
+
+fn min(a: (f64, Step), b: (f64, Step)) -> (f64, Step) {
+    if b.0 < a.0 {
+        b
+    } else {
+        a
+    }
+}
+
+/// The cost of aligning a frame of the melody with one of the performance, from 0 for the same
+/// pitch in any octave (or a rest with silence) to 1 for a wrong or missing note.
+fn frame_cost(reference: Option<f64>, sung: Option<f64>) -> f64 {
+    match (reference, sung) {
+        (Some(reference), Some(sung)) => {
+            nearest_octave(sung - reference).abs().min(MAX_MISMATCH) / MAX_MISMATCH
+        }
+        (Some(_), None) => 1.0,
+        (None, Some(_)) => REST_COST,
+        (None, None) => 0.0,
+    }
+}
+
+/// `semitones`, moved by octaves to within half an octave of 0.
+fn nearest_octave(semitones: f64) -> f64 {
+    semitones - 12.0 * (semitones / 12.0).round()
+}
+
+/// The fractional MIDI note number of `freq` Hz, with A4 at `a4` Hz.
+fn midi(freq: f64, a4: f64) -> f64 {
+    69.0 + 12.0 * (freq / a4).log2()
+}
+
+fn ratio(n: usize, d: usize) -> f64 {
+    if d > 0 {
+        n as f64 / d as f64
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,90 @@
+//! Sub-harmonic summation (Hermes, 1988), to catch estimates that are a harmonic of the
+//! fundamental rather than the fundamental itself.
+//!
+//! Low notes, such as the bottom strings of a bass or cello, often have a weak fundamental and
+//! strong 2nd and 3rd harmonics, so the autocorrelation repeats at a fraction of their period. Each
+//! candidate fundamental is scored by summing the spectrum at its harmonics, the higher ones
+//! counting less; a true fundamental collects every partial, while the harmonic mistaken for it
+//! collects only every 2nd or 3rd.
+
+use crate::spectrum;
+use crate::window::Window;
+
+/// The number of harmonics (including the fundamental) summed for each candidate.
+pub const HARMONICS: usize = 10;
+
+/// How much less each harmonic counts than the one before it.
+pub const COMPRESSION: f64 = 0.84;
+
+/// The largest divisor of the estimate tried as a fundamental.
+pub const MAX_DIVISOR: usize = 4;
+
+/// The highest candidate fundamental, in Hz: about C3, the bottom of a viola, of a tenor's range
+/// and of the piano's middle register. Notes above it have fundamentals strong enough to find.
+pub const LOW_REGISTER: f64 = 131.0;
+
+/// How many times the estimate's score a lower candidate must score to replace it.
+const MARGIN: f64 = 1.2;
+
+/// How strong, next to its strongest harmonic, a candidate's own frequency must be in the
+/// spectrum. Low strings have a weak fundamental, but not none; without it, a chunk holding two
+/// notes a fifth apart would pass for the 2nd and 3rd harmonics of one an octave below the lower.
+const FUNDAMENTAL_FLOOR: f64 = 0.05;
+
+/// How many times longer than the chunk the FFT is, for finer frequency resolution.
+const ZERO_PADDING: usize = 4;
+
+/// The fundamental `freq` is a harmonic of, if the spectrum of `buffer`, sampled at `sample_rate`
+/// Hz, says it's one: whichever of `freq / k` (for `k` up to `MAX_DIVISOR`, from `min_freq` up to
+/// `LOW_REGISTER`) has the highest sub-harmonic sum, if it's clearly higher than that of `freq` and
+/// the candidate is in the spectrum itself. Otherwise, `freq`, without taking the spectrum if
+/// there are no candidates, as for most estimates.
+pub fn fundamental(buffer: &[f32], sample_rate: usize, min_freq: f64, freq: f64) -> f64 {
+    if buffer.is_empty() || !freq.is_finite() || freq <= 0.0 {
+        return freq;
+    }
+    let candidates: Vec<f64> = (2..=MAX_DIVISOR)
+        .map(|k| freq / k as f64)
+        .filter(|candidate| (min_freq..=LOW_REGISTER).contains(candidate))
+        .collect();
+    if candidates.is_empty() {
+        return freq;
+    }
+    let len = buffer.len() * ZERO_PADDING;
+    let magnitudes = spectrum::magnitudes(&Window::Hann.apply(buffer), len);
+    let bin_width = sample_rate as f64 / len as f64;
+
+    let estimate = score(&magnitudes, bin_width, freq);
+    candidates
+        .into_iter()
+        .filter(|candidate| {
+            let strongest = (1..=HARMONICS)
+                .map(|harmonic| peak(&magnitudes, bin_width, harmonic as f64 * candidate))
+                .fold(0.0, f64::max);
+            peak(&magnitudes, bin_width, *candidate) >= FUNDAMENTAL_FLOOR * strongest
+        })
+        .map(|candidate| (candidate, score(&magnitudes, bin_width, candidate)))
+        .filter(|(_, sum)| *sum > MARGIN * estimate)
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .map_or(freq, |(candidate, _)| candidate)
+}
+
+/// The sub-harmonic sum of `freq` in `magnitudes`, a spectrum with bins `bin_width` Hz apart: the
+/// weighted sum of its highest bin within a bin of each harmonic, up to the Nyquist frequency.
+pub fn score(magnitudes: &[f64], bin_width: f64, freq: f64) -> f64 {
+    (1..=HARMONICS)
+        .map(|harmonic| {
+            let weight = COMPRESSION.powi(harmonic as i32 - 1);
+            weight * peak(magnitudes, bin_width, harmonic as f64 * freq)
+        })
+        .sum()
+}
+
+/// The highest bin of `magnitudes` within a bin of `freq`, or 0 above the Nyquist frequency.
+fn peak(magnitudes: &[f64], bin_width: f64, freq: f64) -> f64 {
+    let bin = (freq / bin_width).round() as usize;
+    let nyquist = magnitudes.len() / 2;
+    magnitudes
+        .get(bin.saturating_sub(1)..(bin + 2).min(nyquist))
+        .map_or(0.0, |bins| bins.iter().cloned().fold(0.0, f64::max))
+}
@@ -0,0 +1,102 @@
+//! Post-processing that steadies the stream of detected pitches.
+
+use std::collections::VecDeque;
+
+use crate::{cents_between, DetectedPitch, Pitch, PitchDetector};
+
+pub const DEFAULT_MEDIAN_LEN: usize = 3;
+pub const DEFAULT_HOLD: usize = 2;
+
+/// Smooths a stream of estimates with a median filter over the last `median_len` frequencies,
+/// then only switches to a new pitch once `hold` consecutive smoothed estimates agree on it.
+#[derive(Debug, Clone)]
+pub struct Smoother {
+    median_len: usize,
+    hold: usize,
+    /// The latest estimates with a pitch, or `None` where there was none.
+    history: VecDeque<Option<(f64, Pitch)>>,
+    current: Option<Pitch>,
+    pending: Option<Pitch>,
+    pending_count: usize,
+}
+
+impl Default for Smoother {
+    fn default() -> Self {
+        Smoother::new(DEFAULT_MEDIAN_LEN, DEFAULT_HOLD)
+    }
+}
+
+impl Smoother {
+    pub fn new(median_len: usize, hold: usize) -> Self {
+        Smoother {
+            median_len: median_len.max(1),
+            hold: hold.max(1),
+            history: VecDeque::with_capacity(median_len),
+            current: None,
+            pending: None,
+            pending_count: 0,
+        }
+    }
+
+    /// Feed the next raw estimate from `detector` and get the smoothed one.
+    pub fn push(&mut self, detector: &PitchDetector, raw: DetectedPitch) -> DetectedPitch {
+        if self.history.len() == self.median_len {
+            self.history.pop_front();
+        }
+        // Estimates without a pitch, such as those too unsure to name, count as none.
+        self.history.push_back(raw.freq.zip(raw.pitch));
+
+        let median = self.median();
+        let freq = median.map(|(freq, _)| freq);
+        let candidate = median.map(|(_, pitch)| pitch);
+
+        if self.history.len() == 1 {
+            // Nothing to hold on to yet.
+            self.current = candidate;
+        } else if candidate == self.current {
+            self.pending_count = 0;
+        } else {
+            if candidate == self.pending {
+                self.pending_count += 1;
+            } else {
+                self.pending = candidate;
+                self.pending_count = 1;
+            }
+            if self.pending_count >= self.hold {
+                self.current = candidate;
+                self.pending_count = 0;
+            }
+        }
+
+        let pitch = self.current;
+        DetectedPitch {
+            freq,
+            pitch,
+            cents: match (pitch, freq) {
+                (Some(pitch), Some(freq)) => cents_between(detector.pitch_freq(pitch), freq),
+                _ => 0.0,
+            },
+            confidence: raw.confidence,
+            rest: pitch.is_none() && raw.rest,
+            unvoiced: pitch.is_none() && raw.unvoiced,
+        }
+    }
+
+    /// The median of the frequencies in the history, with the pitch of the estimate in the middle
+    /// (of the two in the middle, the upper, which is the nearer their mean in cents), or `None`
+    /// if most of it has none.
+    fn median(&self) -> Option<(f64, Pitch)> {
+        let mut estimates: Vec<(f64, Pitch)> = self.history.iter().flatten().copied().collect();
+        if estimates.len() * 2 <= self.history.len() {
+            return None;
+        }
+        estimates.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+        let mid = estimates.len() / 2;
+        let (freq, pitch) = estimates[mid];
+        Some(if estimates.len().is_multiple_of(2) {
+            ((estimates[mid - 1].0 + freq) / 2.0, pitch)
+        } else {
+            (freq, pitch)
+        })
+    }
+}
@@ -0,0 +1,238 @@
+//! Rendering a spectrogram of a signal, with the detected pitch drawn over it, to check by eye
+//! where the detector went wrong. Frequency is on a log scale, so octaves are evenly spaced, from
+//! the detector's lowest frequency at the bottom.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+use crate::png::{self, Rgb};
+use crate::spectrum;
+use crate::window::Window;
+use crate::DetectedPitch;
+
+/// How often a column of the spectrogram is taken, in seconds.
+pub const HOP: f64 = 0.01;
+
+/// How long each column's window is, in seconds. Longer windows resolve low notes better, but
+/// smear quick changes.
+const FRAME: f64 = 0.09;
+
+/// How many times longer than the window the FFT is, to fill in the lowest rows.
+const ZERO_PADDING: usize = 2;
+
+/// The height of the image in pixels.
+pub const HEIGHT: usize = 480;
+
+/// The widest the image gets. Longer signals have several columns merged into each pixel.
+pub const MAX_WIDTH: usize = 4000;
+
+/// The highest frequency shown when the detector has no highest, in Hz.
+const DEFAULT_MAX_FREQ: f64 = 5000.0;
+
+/// How far below the loudest the quietest colour is, in dB.
+const DYNAMIC_RANGE: f64 = 80.0;
+
+/// The colours from quiet to loud, evenly spaced.
+const COLOURS: [Rgb; 5] = [
+    [0, 0, 0],
+    [80, 0, 120],
+    [200, 30, 30],
+    [250, 150, 0],
+    [255, 255, 200],
+];
+
+/// The colour of the pitch track.
+const TRACK_COLOUR: Rgb = [0, 255, 255];
+
+/// A spectrogram, made from the chunks of a signal as they're read.
+#[derive(Debug, Clone)]
+pub struct Spectrogram {
+    sample_rate: usize,
+    min_freq: f64,
+    max_freq: f64,
+    frame: usize,
+    hop: usize,
+    /// The latest samples, enough for the next window.
+    samples: Vec<f32>,
+    /// The position in the signal of the first of `samples`.
+    start: usize,
+    /// The position of the start of the next window.
+    next: usize,
+    /// The columns of the image so far, each merging `merge` columns of the spectrogram.
+    pixels: Vec<Pixel>,
+    merge: usize,
+    /// The highest magnitude so far.
+    loudest: f64,
+    /// Where each chunk not yet behind the next window's centre ends in the signal, and the
+    /// frequency detected in it.
+    chunks: VecDeque<(usize, Option<f64>)>,
+}
+
+/// A column of the image, the loudest of the spectrogram columns merged into it.
+#[derive(Debug, Clone)]
+struct Pixel {
+    /// The magnitude in each row, from the top.
+    magnitudes: Vec<f64>,
+    /// How many columns are merged into it.
+    columns: usize,
+    /// The frequency detected at the centre of its first column.
+    first: Option<f64>,
+    /// The frequency detected at the centre of its middle column, to draw.
+    freq: Option<f64>,
+}
+
+impl Spectrogram {
+    /// A spectrogram of a signal sampled at `sample_rate` Hz, from `min_freq` up to `max_freq`
+    /// (or `DEFAULT_MAX_FREQ`), and at most half the sample rate.
+    pub fn new(sample_rate: usize, min_freq: f64, max_freq: Option<f64>) -> Self {
+        let nyquist = sample_rate as f64 / 2.0;
+        Spectrogram {
+            sample_rate,
+            min_freq: min_freq.max(1.0),
+            max_freq: max_freq.unwrap_or(DEFAULT_MAX_FREQ).min(nyquist),
+            frame: ((FRAME * sample_rate as f64) as usize).max(2),
+            hop: ((HOP * sample_rate as f64) as usize).max(1),
+            samples: vec![],
+            start: 0,
+            next: 0,
+            pixels: vec![],
+            merge: 1,
+            loudest: 0.0,
+            chunks: VecDeque::new(),
+        }
+    }
+
+    /// Feed the next `chunk` and what was `detected` in it.
+    pub fn push(&mut self, chunk: &[f32], detected: &DetectedPitch) {
+        self.samples.extend_from_slice(chunk);
+        let end = self.start + self.samples.len();
+        self.chunks
+            .push_back((end, detected.freq.filter(|_| !detected.rest)));
+
+        while self.next + self.frame <= end {
+            let from = self.next - self.start;
+            let column = self.column(&self.samples[from..from + self.frame]);
+            // The frequency detected in the chunk the centre of the column is in.
+            let centre = self.next + self.frame / 2;
+            while self.chunks.front().is_some_and(|(end, _)| *end <= centre) {
+                self.chunks.pop_front();
+            }
+            let freq = self.chunks.front().and_then(|(_, freq)| *freq);
+            self.add(column, freq);
+            self.next += self.hop;
+        }
+
+        let excess = (self.next - self.start).min(self.samples.len());
+        self.samples.drain(..excess);
+        self.start += excess;
+    }
+
+    /// Merge `column`, with `freq` detected at its centre, into the image, halving the image by
+    /// merging its columns in pairs when it's `MAX_WIDTH` wide and full.
+    fn add(&mut self, column: Vec<f64>, freq: Option<f64>) {
+        self.loudest = column.iter().cloned().fold(self.loudest, f64::max);
+        let full = self
+            .pixels
+            .last()
+            .is_none_or(|pixel| pixel.columns == self.merge);
+        if full && self.pixels.len() == MAX_WIDTH {
+            let pixels = std::mem::take(&mut self.pixels);
+            let mut pairs = pixels.into_iter();
+            while let Some(mut pixel) = pairs.next() {
+                if let Some(next) = pairs.next() {
+                    for (merged, magnitude) in pixel.magnitudes.iter_mut().zip(next.magnitudes) {
+                        *merged = merged.max(magnitude);
+                    }
+                    pixel.columns += next.columns;
+                    pixel.freq = next.first;
+                }
+                self.pixels.push(pixel);
+            }
+            self.merge *= 2;
+        }
+
+        match self.pixels.last_mut() {
+            Some(pixel) if !full => {
+                for (merged, magnitude) in pixel.magnitudes.iter_mut().zip(column) {
+                    *merged = merged.max(magnitude);
+                }
+                if pixel.columns <= self.merge / 2 {
+                    pixel.freq = freq;
+                }
+                pixel.columns += 1;
+            }
+            _ => self.pixels.push(Pixel {
+                magnitudes: column,
+                columns: 1,
+                first: freq,
+                freq,
+            }),
+        }
+    }
+
+    /// The magnitude in each row of the spectrum of `frame`: the highest of the bins the row
+    /// covers.
+    fn column(&self, frame: &[f32]) -> Vec<f64> {
+        let len = frame.len() * ZERO_PADDING;
+        let magnitudes = spectrum::magnitudes(&Window::Hann.apply(frame), len);
+        let bin_hz = self.sample_rate as f64 / len as f64;
+        let top = len / 2;
+        (0..HEIGHT)
+            .map(|row| {
+                let low = (self.row_freq(row as f64 + 1.0) / bin_hz).floor() as usize;
+                let high = (self.row_freq(row as f64) / bin_hz).ceil() as usize;
+                magnitudes[low.min(top)..=high.min(top)]
+                    .iter()
+                    .cloned()
+                    .fold(0.0, f64::max)
+            })
+            .collect()
+    }
+
+    /// The frequency at `row` pixels down from the top.
+    fn row_freq(&self, row: f64) -> f64 {
+        self.max_freq * (self.min_freq / self.max_freq).powf(row / HEIGHT as f64)
+    }
+
+    /// The row `freq` is in, if it's shown.
+    fn freq_row(&self, freq: f64) -> Option<usize> {
+        let row =
+            HEIGHT as f64 * (freq / self.max_freq).ln() / (self.min_freq / self.max_freq).ln();
+        Some(row)
+            .filter(|row| (0.0..HEIGHT as f64).contains(row))
+            .map(|row| row as usize)
+    }
+
+    /// Write the spectrogram as a PNG image, with the detected frequencies drawn over it. It's
+    /// a pixel wide per column, up to `MAX_WIDTH`; longer signals have their columns merged in
+    /// twos, fours and so on to fit. It's `HEIGHT` pixels high.
+    pub fn write_png<W: Write>(&self, out: W) -> io::Result<()> {
+        let width = self.pixels.len().max(1);
+
+        let mut pixels = vec![COLOURS[0]; width * HEIGHT];
+        for (x, pixel) in self.pixels.iter().enumerate() {
+            for (row, magnitude) in pixel.magnitudes.iter().enumerate() {
+                pixels[row * width + x] = colour(*magnitude, self.loudest);
+            }
+            if let Some(row) = pixel.freq.and_then(|freq| self.freq_row(freq)) {
+                for row in row.saturating_sub(1)..=(row + 1).min(HEIGHT - 1) {
+                    pixels[row * width + x] = TRACK_COLOUR;
+                }
+            }
+        }
+        png::write_png(out, width, HEIGHT, &pixels)
+    }
+}
+
+/// The colour of `magnitude`, on a scale of `DYNAMIC_RANGE` dB up to `loudest`.
+fn colour(magnitude: f64, loudest: f64) -> Rgb {
+    if magnitude <= 0.0 || loudest <= 0.0 {
+        return COLOURS[0];
+    }
+    let db = 20.0 * (magnitude / loudest).log10();
+    let level = (1.0 + db / DYNAMIC_RANGE).clamp(0.0, 1.0) * (COLOURS.len() - 1) as f64;
+    let (i, fraction) = (level.floor() as usize, level.fract());
+    let (from, to) = (COLOURS[i], COLOURS[(i + 1).min(COLOURS.len() - 1)]);
+    let mix = |c: usize| (from[c] as f64 + (to[c] as f64 - from[c] as f64) * fraction).round();
+    [mix(0) as u8, mix(1) as u8, mix(2) as u8]
+}
@@ -0,0 +1,25 @@
+//! Fourier transforms of chunks.
+
+use rustfft::{num_complex::Complex, num_traits::Zero, FFTplanner};
+
+/// The magnitude of each bin of the discrete Fourier transform of `buffer`, zero-padded to `len`
+/// samples. Bin `k` is at `k * sample_rate / len` Hz.
+pub fn magnitudes(buffer: &[f32], len: usize) -> Vec<f64> {
+    fft(buffer.iter().map(|x| *x as f64), len, false)
+        .iter()
+        .map(|x| x.norm())
+        .collect()
+}
+
+/// The discrete Fourier transform (or its unscaled inverse) of the real `signal`, zero-padded or
+/// truncated to `len` samples.
+pub fn fft(signal: impl Iterator<Item = f64>, len: usize, inverse: bool) -> Vec<Complex<f64>> {
+    let mut input: Vec<_> = signal.map(|x| Complex::new(x, 0.0)).take(len).collect();
+    input.resize(len, Complex::zero());
+
+    let mut output = vec![Complex::zero(); len];
+    FFTplanner::new(inverse)
+        .plan_fft(len)
+        .process(&mut input, &mut output);
+    output
+}
@@ -0,0 +1,127 @@
+//! A lock-free ring buffer of samples, for handing audio from a real-time thread to the one
+//! analyzing it.
+//!
+//! There's one producer and one consumer. Each only ever stores to its own end's index, and the
+//! samples themselves are atomics, so neither ever waits on the other or allocates: an audio
+//! callback can push without risking an xrun, whatever the analysis is doing. When the ring is
+//! full, the producer's samples are turned away rather than waited for.
+
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+struct Shared {
+    /// The bits of each sample, as `f32::to_bits`.
+    slots: Box<[AtomicU32]>,
+    /// `slots.len() - 1`, as the length is a power of two.
+    mask: usize,
+    /// The number of samples ever popped, which only the consumer stores.
+    head: AtomicUsize,
+    /// The number of samples ever pushed, which only the producer stores.
+    tail: AtomicUsize,
+    /// Whether the producer has been dropped.
+    closed: AtomicBool,
+}
+
+/// The end of a ring that samples are pushed into, from one thread.
+pub struct Producer {
+    shared: Arc<Shared>,
+}
+
+/// The end of a ring that samples are popped from, from one thread.
+pub struct Consumer {
+    shared: Arc<Shared>,
+}
+
+/// A ring holding at least `capacity` samples, and its two ends.
+pub fn ring(capacity: usize) -> (Producer, Consumer) {
+    let len = capacity.max(1).next_power_of_two();
+    let shared = Arc::new(Shared {
+        slots: (0..len).map(|_| AtomicU32::new(0)).collect(),
+        mask: len - 1,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        closed: AtomicBool::new(false),
+    });
+    (
+        Producer {
+            shared: Arc::clone(&shared),
+        },
+        Consumer { shared },
+    )
+}
+
+impl Producer {
+    /// Push as many of `samples` as there's room for, in order. Returns how many were pushed.
+    pub fn push(&self, samples: &[f32]) -> usize {
+        self.push_with(samples.len(), |i| samples[i])
+    }
+
+    /// Push up to `n` samples of silence. Returns how many were pushed.
+    pub fn push_silence(&self, n: usize) -> usize {
+        self.push_with(n, |_| 0.0)
+    }
+
+    /// The number of samples there's room for.
+    pub fn free(&self) -> usize {
+        self.shared.slots.len() - self.shared.len()
+    }
+
+    fn push_with(&self, n: usize, sample: impl Fn(usize) -> f32) -> usize {
+        let shared = &*self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let n = n.min(self.free());
+        for i in 0..n {
+            let slot = &shared.slots[tail.wrapping_add(i) & shared.mask];
+            slot.store(sample(i).to_bits(), Ordering::Relaxed);
+        }
+        // Publishes the samples to the consumer.
+        shared.tail.store(tail.wrapping_add(n), Ordering::Release);
+        n
+    }
+}
+
+impl Drop for Producer {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+impl Consumer {
+    /// Pop as many samples as are waiting, up to the length of `out`, into its start. Returns how
+    /// many were popped.
+    pub fn pop(&self, out: &mut [f32]) -> usize {
+        let shared = &*self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let n = out.len().min(shared.len());
+        for (i, sample) in out[..n].iter_mut().enumerate() {
+            let slot = &shared.slots[head.wrapping_add(i) & shared.mask];
+            *sample = f32::from_bits(slot.load(Ordering::Relaxed));
+        }
+        // Hands the slots back to the producer.
+        shared.head.store(head.wrapping_add(n), Ordering::Release);
+        n
+    }
+
+    /// The number of samples waiting.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the producer has been dropped, so no more samples will come once those waiting
+    /// are popped.
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+}
+
+impl Shared {
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+}
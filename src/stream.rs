@@ -0,0 +1,131 @@
+//! Live detection as an asynchronous stream of estimates, for async applications such as bots and
+//! servers running on Tokio.
+//!
+//! `PitchStream` has the `poll_next` of a futures `Stream`, so wrapping it in one takes a line,
+//! and an async `next_frame` to await each frame directly. Reading the input blocks, so a thread
+//! of its own runs the `LiveInput`, handing frames over and waking the task waiting on them; no
+//! runtime is needed, or assumed.
+
+use std::future::Future;
+use std::io::{self, Read};
+use std::pin::Pin;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::live::LiveInput;
+use crate::DetectedPitch;
+
+/// How many frames the thread reading the input gets ahead of the stream's consumer before
+/// waiting for it.
+const BACKLOG: usize = 64;
+
+/// One estimate of a live stream.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PitchFrame {
+    /// When the window of the estimate starts, in seconds from the start of the stream.
+    pub timestamp: f64,
+    pub detected: DetectedPitch,
+    /// The RMS level of the window, in dBFS.
+    pub level: f64,
+}
+
+/// The estimates of a `LiveInput`, as they're made, ending with its input.
+pub struct PitchStream {
+    frames: Receiver<io::Result<PitchFrame>>,
+    /// The task to wake when the next frame arrives.
+    waker: Arc<Mutex<Option<Waker>>>,
+    /// The error reading the input ended with, if it did.
+    error: Option<io::Error>,
+}
+
+impl PitchStream {
+    /// Run `input` on a thread of its own, streaming its estimates.
+    pub fn new<R: Read + Send + 'static>(mut input: LiveInput<R>) -> Self {
+        let (sender, frames) = mpsc::sync_channel(BACKLOG);
+        let waker = Arc::new(Mutex::new(None::<Waker>));
+        let waking = Arc::clone(&waker);
+        thread::spawn(move || {
+            let wake = || {
+                if let Some(waker) = waking.lock().unwrap().take() {
+                    waker.wake();
+                }
+            };
+            loop {
+                let frame = input.next_estimate().map(|detected| {
+                    detected.map(|detected| PitchFrame {
+                        timestamp: input.timestamp(),
+                        detected,
+                        level: input.level(),
+                    })
+                });
+                let ended = !matches!(frame, Ok(Some(_)));
+                // Sending fails once the stream has been dropped.
+                let sent = match frame.transpose() {
+                    Some(frame) => sender.send(frame).is_ok(),
+                    None => true,
+                };
+                if ended || !sent {
+                    break;
+                }
+                wake();
+            }
+            // Disconnects, for the stream to see the end.
+            drop(sender);
+            wake();
+        });
+        PitchStream {
+            frames,
+            waker,
+            error: None,
+        }
+    }
+
+    /// The next frame, or `None` at the end of the stream, as a futures `Stream` polls for it.
+    pub fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<PitchFrame>> {
+        match self.try_next() {
+            Poll::Pending => {
+                *self.waker.lock().unwrap() = Some(cx.waker().clone());
+                // A frame may have arrived before the waker was stored.
+                self.try_next()
+            }
+            ready => ready,
+        }
+    }
+
+    /// Wait for the next frame, or `None` at the end of the stream.
+    pub fn next_frame(&mut self) -> impl Future<Output = Option<PitchFrame>> + '_ {
+        Next { stream: self }
+    }
+
+    /// The error reading the input ended the stream with, if any, once it has ended.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    fn try_next(&mut self) -> Poll<Option<PitchFrame>> {
+        match self.frames.try_recv() {
+            Ok(Ok(frame)) => Poll::Ready(Some(frame)),
+            Ok(Err(e)) => {
+                self.error = Some(e);
+                Poll::Ready(None)
+            }
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => Poll::Ready(None),
+        }
+    }
+}
+
+/// The future of `PitchStream::next_frame`.
+struct Next<'a> {
+    stream: &'a mut PitchStream,
+}
+
+impl Future for Next<'_> {
+    type Output = Option<PitchFrame>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Pin::new(&mut *self.stream).poll_next(cx)
+    }
+}
@@ -0,0 +1,163 @@
+//! Summing up a performance for practice review: how long was spent on each note, how far from it
+//! it was on average, and how in tune the whole was.
+
+use std::collections::BTreeMap;
+
+use crate::plot::IN_TUNE;
+use crate::{DetectedPitch, Pitch};
+
+/// The time spent on one note, and how in tune it was.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoteSummary {
+    pub pitch: Pitch,
+    /// How long the note was detected for, in seconds.
+    pub time: f64,
+    /// The share of the time with a pitch that was on this note.
+    pub share: f64,
+    /// How far from the note it was on average, in cents, sharp if positive.
+    pub mean_cents: f64,
+    /// The standard deviation of that, in cents: how steadily it was held.
+    pub spread_cents: f64,
+}
+
+/// Time and cents per note.
+#[derive(Debug, Clone, Copy, Default)]
+struct Totals {
+    time: f64,
+    cents: f64,
+    absolute_cents: f64,
+    squared_cents: f64,
+}
+
+/// Adds up the estimates of a performance into a summary of its notes and intonation.
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    /// The totals of each note, by MIDI note number.
+    notes: BTreeMap<i32, Totals>,
+    duration: f64,
+    rest_time: f64,
+    /// The time with a pitch that was within `IN_TUNE` cents of its note, and sharp or flat of
+    /// that.
+    in_tune_time: f64,
+    sharp_time: f64,
+    flat_time: f64,
+}
+
+impl Summary {
+    pub fn new() -> Self {
+        Summary::default()
+    }
+
+    /// Count `detected`, an estimate lasting `duration` seconds.
+    pub fn push(&mut self, detected: &DetectedPitch, duration: f64) {
+        self.duration += duration;
+        let pitch = match detected.pitch {
+            Some(pitch) => pitch,
+            None => {
+                if detected.rest {
+                    self.rest_time += duration;
+                }
+                return;
+            }
+        };
+        let cents = detected.cents;
+        let totals = self.notes.entry(pitch.midi()).or_default();
+        totals.time += duration;
+        totals.cents += cents * duration;
+        totals.absolute_cents += cents.abs() * duration;
+        totals.squared_cents += cents * cents * duration;
+        if cents > IN_TUNE {
+            self.sharp_time += duration;
+        } else if cents < -IN_TUNE {
+            self.flat_time += duration;
+        } else {
+            self.in_tune_time += duration;
+        }
+    }
+
+    /// Each note detected, from the lowest up.
+    pub fn notes(&self) -> Vec<NoteSummary> {
+        let pitched = self.pitched_time();
+        self.notes
+            .iter()
+            .map(|(midi, totals)| {
+                let mean = totals.cents / totals.time;
+                let variance = totals.squared_cents / totals.time - mean * mean;
+                NoteSummary {
+                    pitch: Pitch::from_midi(*midi),
+                    time: totals.time,
+                    share: totals.time / pitched,
+                    mean_cents: mean,
+                    spread_cents: variance.max(0.0).sqrt(),
+                }
+            })
+            .collect()
+    }
+
+    /// The length of everything counted, in seconds.
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// How long there was a pitch for, in seconds.
+    pub fn pitched_time(&self) -> f64 {
+        self.notes
+            .values()
+            .fold(0.0, |time, totals| time + totals.time)
+    }
+
+    /// How long there were rests for, in seconds.
+    pub fn rest_time(&self) -> f64 {
+        self.rest_time
+    }
+
+    /// How long there was sound without a pitch for (unvoiced or `Unknown`), in seconds.
+    pub fn unknown_time(&self) -> f64 {
+        (self.duration - self.pitched_time() - self.rest_time).max(0.0)
+    }
+
+    /// How far from their notes the estimates with a pitch were on average, in cents, or `None`
+    /// if there were none.
+    pub fn mean_cents(&self) -> Option<f64> {
+        let cents: f64 = self.notes.values().map(|totals| totals.cents).sum();
+        self.per_pitched_second(cents)
+    }
+
+    /// The standard deviation of how far from their notes the estimates with a pitch were, in
+    /// cents, or `None` if there were none.
+    pub fn spread_cents(&self) -> Option<f64> {
+        let squared: f64 = self.notes.values().map(|totals| totals.squared_cents).sum();
+        let mean = self.mean_cents()?;
+        Some(
+            (self.per_pitched_second(squared)? - mean * mean)
+                .max(0.0)
+                .sqrt(),
+        )
+    }
+
+    /// How far from their notes, either way, the estimates with a pitch were on average, in
+    /// cents, or `None` if there were none.
+    pub fn mean_absolute_cents(&self) -> Option<f64> {
+        let cents: f64 = self
+            .notes
+            .values()
+            .map(|totals| totals.absolute_cents)
+            .sum();
+        self.per_pitched_second(cents)
+    }
+
+    /// The shares of the time with a pitch that were within `plot::IN_TUNE` cents of their
+    /// notes, sharp of that and flat of that, or `None` if there was none.
+    pub fn intonation(&self) -> Option<(f64, f64, f64)> {
+        Some((
+            self.per_pitched_second(self.in_tune_time)?,
+            self.per_pitched_second(self.sharp_time)?,
+            self.per_pitched_second(self.flat_time)?,
+        ))
+    }
+
+    fn per_pitched_second(&self, total: f64) -> Option<f64> {
+        let pitched = self.pitched_time();
+        Some(total / pitched).filter(|_| pitched > 0.0)
+    }
+}
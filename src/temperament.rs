@@ -0,0 +1,130 @@
+//! Tuning notes to temperaments other than twelve-tone equal temperament, for early music, choirs
+//! and other ensembles that tune pure intervals. A temperament is built on a tonic, and tunes each
+//! of the twelve notes from it some number of cents above it; A4 stays at the reference pitch,
+//! so the tonic itself may move a little from its equal-tempered frequency.
+
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Note, Pitch};
+
+/// The cents above the tonic of each note of 5-limit just intonation, from the ratios 1/1, 16/15,
+/// 9/8, 6/5, 5/4, 4/3, 45/32, 3/2, 8/5, 5/3, 9/5 and 15/8.
+const JUST: [f64; 12] = [
+    0.0, 111.731, 203.910, 315.641, 386.314, 498.045, 590.224, 701.955, 813.686, 884.359, 1017.596,
+    1088.269,
+];
+
+/// The naturals' and accidentals' positions around the circle of fifths from the tonic, from
+/// three fifths below it to eight above, as tuned by `Pythagorean` and `Meantone`.
+const FIFTHS: [i32; 12] = [0, 7, 2, -3, 4, -1, 6, 1, 8, 3, -2, 5];
+
+/// A pure fifth, 3/2, in cents.
+const PURE_FIFTH: f64 = 701.955;
+
+/// The fifth of quarter-comma meantone, a quarter of a syntonic comma narrow, so that four of
+/// them make a pure major third (5/4) two octaves up.
+const MEANTONE_FIFTH: f64 = 696.578;
+
+/// A way of tuning the twelve notes of the octave.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Temperament {
+    /// Twelve-tone equal temperament: every semitone is 100 cents.
+    #[default]
+    Equal,
+    /// 5-limit just intonation, with pure thirds and fifths above the tonic.
+    Just,
+    /// Pure fifths, from three below the tonic to eight above.
+    Pythagorean,
+    /// Quarter-comma meantone: fifths narrowed for pure major thirds.
+    Meantone,
+    /// The cents above the tonic of each of the twelve notes from it.
+    Custom([f64; 12]),
+}
+
+impl Temperament {
+    /// The cents above the tonic of each of the twelve notes from it.
+    pub fn cents(&self) -> [f64; 12] {
+        let mut cents = [0.0; 12];
+        match self {
+            Temperament::Equal => {
+                for (i, cents) in cents.iter_mut().enumerate() {
+                    *cents = 100.0 * i as f64;
+                }
+            }
+            Temperament::Just => cents = JUST,
+            Temperament::Pythagorean | Temperament::Meantone => {
+                let fifth = match self {
+                    Temperament::Pythagorean => PURE_FIFTH,
+                    _ => MEANTONE_FIFTH,
+                };
+                for (cents, fifths) in cents.iter_mut().zip(FIFTHS.iter()) {
+                    *cents = (*fifths as f64 * fifth).rem_euclid(1200.0);
+                }
+            }
+            Temperament::Custom(custom) => cents = *custom,
+        }
+        cents
+    }
+
+    /// How far `pitch` is tuned from its equal-tempered frequency, in cents, in this
+    /// temperament on `tonic`.
+    pub fn deviation(&self, pitch: Pitch, tonic: Note) -> f64 {
+        if *self == Temperament::Equal {
+            return 0.0;
+        }
+        let cents = self.cents();
+        let from_equal = |note: Note| {
+            let degree = (note.semitone() + 12 - tonic.semitone()) as usize % 12;
+            cents[degree] - 100.0 * degree as f64
+        };
+        from_equal(pitch.note) - from_equal(Note::A)
+    }
+
+    /// The frequency of `pitch` in this temperament on `tonic`, with A4 tuned to `a4` Hz.
+    pub fn freq(&self, pitch: Pitch, tonic: Note, a4: f64) -> f64 {
+        pitch.freq(a4) * 2f64.powf(self.deviation(pitch, tonic) / 1200.0)
+    }
+}
+
+impl FromStr for Temperament {
+    type Err = String;
+
+    /// Parse a temperament's name, or twelve cents separated by commas for a custom one.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "equal" => Ok(Temperament::Equal),
+            "just" => Ok(Temperament::Just),
+            "pythagorean" => Ok(Temperament::Pythagorean),
+            "meantone" => Ok(Temperament::Meantone),
+            _ if s.contains(',') => {
+                let cents: Vec<f64> = s
+                    .split(',')
+                    .map(|cents| cents.trim().parse())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| format!("invalid cents: {}", s))?;
+                let cents: [f64; 12] = cents
+                    .try_into()
+                    .map_err(|_| "a custom temperament needs 12 cents".to_string())?;
+                Ok(Temperament::Custom(cents))
+            }
+            _ => Err(format!("unknown temperament: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Temperament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Temperament::Equal => f.write_str("equal"),
+            Temperament::Just => f.write_str("just"),
+            Temperament::Pythagorean => f.write_str("pythagorean"),
+            Temperament::Meantone => f.write_str("meantone"),
+            Temperament::Custom(cents) => {
+                let cents: Vec<String> = cents.iter().map(f64::to_string).collect();
+                f.write_str(&cents.join(","))
+            }
+        }
+    }
+}
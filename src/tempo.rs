@@ -0,0 +1,124 @@
+//! Estimating the tempo of a performance from its onsets, so that it can be quantized without
+//! being told the tempo.
+//!
+//! Onsets are spread into an envelope over short frames, and the envelope is autocorrelated: it
+//! correlates with itself at the beat period and its multiples, since notes keep starting on the
+//! beat. Each tempo is scored by the autocorrelation at its first few multiples, weighted toward
+//! moderate tempos, as tempos twice or half as fast fit almost as well (and a listener would tap
+//! along at the moderate one).
+
+use crate::notes::NoteEvent;
+
+/// The slowest and fastest tempos considered, in beats per minute.
+pub const MIN_TEMPO: f64 = 40.0;
+pub const MAX_TEMPO: f64 = 240.0;
+
+/// The tempo estimates are weighted toward, in beats per minute.
+pub const PREFERRED_TEMPO: f64 = 120.0;
+
+/// The fewest onsets to estimate a tempo from.
+pub const MIN_ONSETS: usize = 4;
+
+/// The length of the frames of the onset envelope, in seconds.
+const FRAME: f64 = 0.01;
+
+/// How far each onset is spread, in seconds (the standard deviation of a Gaussian), so that
+/// onsets a little early or late still line up.
+const SPREAD: f64 = 0.02;
+
+/// How many octaves from `PREFERRED_TEMPO` the weight falls to `1 / √e` at.
+const PREFERENCE_WIDTH: f64 = 1.0;
+
+/// How many multiples of the beat period a tempo is scored over.
+const MULTIPLES: usize = 4;
+
+/// The resolution of the estimate, in beats per minute.
+const RESOLUTION: f64 = 0.1;
+
+/// A tempo, and how clearly the onsets follow it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoEstimate {
+    /// Beats per minute.
+    pub bpm: f64,
+    /// The normalized autocorrelation of the onset envelope at the beat period and its multiples,
+    /// from 0 to 1: how many onsets fall a whole number of beats from others.
+    pub strength: f64,
+}
+
+/// The onsets of `notes`: when each starts, in seconds.
+pub fn onsets(notes: &[NoteEvent]) -> Vec<f64> {
+    notes.iter().map(|note| note.start).collect()
+}
+
+/// The onset envelope of `onsets`, in seconds: a Gaussian `SPREAD` wide around each, sampled
+/// every `FRAME`.
+pub fn onset_envelope(onsets: &[f64]) -> Vec<f64> {
+    let end = onsets.iter().cloned().fold(0.0, f64::max) + 4.0 * SPREAD;
+    let mut envelope = vec![0.0; (end / FRAME).ceil() as usize + 1];
+    let reach = (4.0 * SPREAD / FRAME).ceil() as isize;
+    for onset in onsets.iter().filter(|onset| **onset >= 0.0) {
+        let center = (onset / FRAME).round() as isize;
+        for i in (center - reach).max(0)..=(center + reach) {
+            if let Some(frame) = envelope.get_mut(i as usize) {
+                let distance = i as f64 * FRAME - onset;
+                *frame += (-0.5 * (distance / SPREAD).powi(2)).exp();
+            }
+        }
+    }
+    envelope
+}
+
+/// The most likely tempo of a performance with `onsets`, in seconds, in order or not. `None` if
+/// there are fewer than `MIN_ONSETS`, or they don't span a beat of even the fastest tempo.
+pub fn estimate_tempo(onsets: &[f64]) -> Option<TempoEstimate> {
+    if onsets.len() < MIN_ONSETS {
+        return None;
+    }
+    let envelope = onset_envelope(onsets);
+    let autocorrelation = autocorrelation(&envelope);
+    let at = |lag: f64| -> Option<f64> {
+        let i = lag.floor() as usize;
+        let (a, b) = (*autocorrelation.get(i)?, *autocorrelation.get(i + 1)?);
+        Some(a + (b - a) * lag.fract())
+    };
+
+    let candidates = ((MAX_TEMPO - MIN_TEMPO) / RESOLUTION).round() as usize;
+    (0..=candidates)
+        .filter_map(|i| {
+            let bpm = MIN_TEMPO + i as f64 * RESOLUTION;
+            let period = 60.0 / bpm / FRAME;
+            let correlations: Vec<f64> = (1..=MULTIPLES)
+                .map_while(|k| at(k as f64 * period))
+                .collect();
+            if correlations.is_empty() {
+                return None;
+            }
+            let strength = correlations.iter().sum::<f64>() / correlations.len() as f64;
+            let strength = strength.min(1.0);
+            let octaves = (bpm / PREFERRED_TEMPO).log2() / PREFERENCE_WIDTH;
+            let weight = (-0.5 * octaves * octaves).exp();
+            Some((TempoEstimate { bpm, strength }, strength * weight))
+        })
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(estimate, _)| estimate)
+}
+
+/// The autocorrelation of `envelope` up to half its length, normalized to 1 at lag 0 and
+/// corrected for the overlap shrinking as the lag grows.
+fn autocorrelation(envelope: &[f64]) -> Vec<f64> {
+    let n = envelope.len();
+    let energy: f64 = envelope.iter().map(|x| x * x).sum();
+    if energy <= 0.0 {
+        return vec![];
+    }
+    (0..=n / 2)
+        .map(|lag| {
+            let sum: f64 = envelope
+                .iter()
+                .zip(&envelope[lag..])
+                .map(|(a, b)| a * b)
+                .sum();
+            sum / energy * n as f64 / (n - lag) as f64
+        })
+        .collect()
+}
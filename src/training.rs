@@ -0,0 +1,260 @@
+//! An ear-training game: a random note is played, and the player sings or plays it back, holding
+//! it in tune for `HOLD` seconds to score, before `timeout` seconds are up. Their streak of notes
+//! got right, and how quickly and closely they got them, are kept as they go.
+
+use std::collections::BTreeMap;
+
+use crate::generate::XorShift;
+use crate::{cents_between, Pitch};
+
+/// How long each note is played, in seconds.
+pub const NOTE_DURATION: f64 = 1.0;
+
+/// How long after a note has been played to start listening, in seconds, so that its echo (or the
+/// speaker it's still ringing in) isn't taken for the answer.
+pub const ECHO: f64 = 0.3;
+
+/// How long the answer must be held in tune, in seconds.
+pub const HOLD: f64 = 0.3;
+
+/// How long the player has to answer, in seconds, when no timeout is given.
+pub const DEFAULT_TIMEOUT: f64 = 10.0;
+
+/// How far from the note an answer may be, in cents, to count, when no tolerance is given.
+pub const DEFAULT_TOLERANCE: f64 = 50.0;
+
+/// What came of a round.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The note was held in tune, `time` seconds after listening started, `cents` off it on
+    /// average.
+    Hit {
+        target: Pitch,
+        time: f64,
+        cents: f64,
+    },
+    /// Time ran out. `heard` is the last frequency heard, if any.
+    Miss { target: Pitch, heard: Option<f64> },
+}
+
+/// How often a note was asked for and got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NoteStats {
+    pub rounds: usize,
+    pub hits: usize,
+}
+
+/// The player's statistics so far.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stats {
+    pub rounds: usize,
+    pub hits: usize,
+    /// How many rounds in a row, up to the last, were hits.
+    pub streak: usize,
+    pub best_streak: usize,
+    /// The total time taken to answer the hits, in seconds.
+    pub answer_time: f64,
+    /// The total distance of the hits from their notes, in cents.
+    pub cents: f64,
+    /// The rounds and hits of each note, by MIDI note number.
+    pub notes: BTreeMap<i32, NoteStats>,
+}
+
+impl Stats {
+    /// The share of rounds that were hits, or 0 if there weren't any.
+    pub fn accuracy(&self) -> f64 {
+        if self.rounds > 0 {
+            self.hits as f64 / self.rounds as f64
+        } else {
+            0.0
+        }
+    }
+
+    /// The average time taken to answer the hits, in seconds.
+    pub fn mean_answer_time(&self) -> Option<f64> {
+        Some(self.answer_time / self.hits as f64).filter(|_| self.hits > 0)
+    }
+
+    /// The average distance of the hits from their notes, in cents.
+    pub fn mean_cents(&self) -> Option<f64> {
+        Some(self.cents / self.hits as f64).filter(|_| self.hits > 0)
+    }
+
+    fn record(&mut self, target: Pitch, outcome: &Outcome) {
+        let note = self.notes.entry(target.midi()).or_default();
+        note.rounds += 1;
+        self.rounds += 1;
+        match *outcome {
+            Outcome::Hit { time, cents, .. } => {
+                note.hits += 1;
+                self.hits += 1;
+                self.streak += 1;
+                self.best_streak = self.best_streak.max(self.streak);
+                self.answer_time += time;
+                self.cents += cents.abs();
+            }
+            Outcome::Miss { .. } => self.streak = 0,
+        }
+    }
+}
+
+/// The round being played.
+#[derive(Debug, Clone, Copy)]
+struct Round {
+    target: Pitch,
+    /// When listening for the answer starts, in seconds.
+    from: f64,
+    /// When the answer started being held in tune, and the sum of its distances from the note
+    /// since, in cents, and how many estimates that is.
+    held: Option<(f64, f64, usize)>,
+    heard: Option<f64>,
+}
+
+/// Plays the game: picks notes, and listens for them to be answered.
+#[derive(Debug, Clone)]
+pub struct Trainer {
+    /// The lowest and highest notes to pick, as MIDI note numbers.
+    low: i32,
+    high: i32,
+    a4: f64,
+    tolerance: f64,
+    timeout: f64,
+    exact_octave: bool,
+    rng: XorShift,
+    round: Option<Round>,
+    last: Option<Pitch>,
+    stats: Stats,
+}
+
+impl Trainer {
+    /// A game of notes from `low` to `high`, tuned to A4 at `a4` Hz, picked by a generator seeded
+    /// with `seed`: the same seed gives the same notes.
+    pub fn new(low: Pitch, high: Pitch, a4: f64, seed: u64) -> Self {
+        let (low, high) = (low.midi().min(high.midi()), low.midi().max(high.midi()));
+        Trainer {
+            low,
+            high,
+            a4,
+            tolerance: DEFAULT_TOLERANCE,
+            timeout: DEFAULT_TIMEOUT,
+            exact_octave: false,
+            rng: XorShift::new(seed),
+            round: None,
+            last: None,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Count answers within `cents` of the note.
+    pub fn with_tolerance(self, cents: f64) -> Self {
+        Trainer {
+            tolerance: cents,
+            ..self
+        }
+    }
+
+    /// Give the player `seconds` to answer each note.
+    pub fn with_timeout(self, seconds: f64) -> Self {
+        Trainer {
+            timeout: seconds,
+            ..self
+        }
+    }
+
+    /// Only count answers in the octave of the note, rather than in any octave (as when a singer
+    /// answers a note out of their range).
+    pub fn with_exact_octave(self, exact_octave: bool) -> Self {
+        Trainer {
+            exact_octave,
+            ..self
+        }
+    }
+
+    pub fn a4(&self) -> f64 {
+        self.a4
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Start a round with a random note, other than the last one if there's a choice, listening
+    /// for the answer from `from` seconds on.
+    pub fn next_note(&mut self, from: f64) -> Pitch {
+        let notes = (self.high - self.low + 1) as usize;
+        let target = loop {
+            let i = ((self.rng.next_f32() + 1.0) / 2.0 * notes as f32) as usize;
+            let target = Pitch::from_midi(self.low + i.min(notes - 1) as i32);
+            if notes == 1 || self.last != Some(target) {
+                break target;
+            }
+        };
+        self.last = Some(target);
+        self.round = Some(Round {
+            target,
+            from,
+            held: None,
+            heard: None,
+        });
+        target
+    }
+
+    /// Listen to `freq` Hz (or silence, if `None`), heard at `time` seconds, and end the round if
+    /// that answers it or its time is up.
+    pub fn listen(&mut self, freq: Option<f64>, time: f64) -> Option<Outcome> {
+        let (a4, exact_octave) = (self.a4, self.exact_octave);
+        let round = self.round.as_mut()?;
+        if time < round.from {
+            return None;
+        }
+        let freq = freq.filter(|freq| *freq > 0.0);
+        let cents = freq.map(|freq| {
+            let cents = cents_between(round.target.freq(a4), freq);
+            if exact_octave {
+                cents
+            } else {
+                cents - 1200.0 * (cents / 1200.0).round()
+            }
+        });
+
+        let mut outcome = None;
+        match cents {
+            Some(cents) if cents.abs() <= self.tolerance => {
+                let (since, sum, count) = round.held.get_or_insert((time, 0.0, 0));
+                *sum += cents;
+                *count += 1;
+                if time - *since >= HOLD {
+                    outcome = Some(Outcome::Hit {
+                        target: round.target,
+                        time: *since - round.from,
+                        cents: *sum / *count as f64,
+                    });
+                }
+            }
+            _ => {
+                round.held = None;
+                round.heard = freq.or(round.heard);
+            }
+        }
+        if outcome.is_none() && time - round.from >= self.timeout {
+            outcome = Some(Outcome::Miss {
+                target: round.target,
+                heard: round.heard,
+            });
+        }
+        let outcome = outcome?;
+
+        self.round = None;
+        self.stats.record(outcome.target(), &outcome);
+        Some(outcome)
+    }
+}
+
+impl Outcome {
+    /// The note the round asked for.
+    pub fn target(&self) -> Pitch {
+        match *self {
+            Outcome::Hit { target, .. } | Outcome::Miss { target, .. } => target,
+        }
+    }
+}
@@ -0,0 +1,176 @@
+//! A terminal tuner display: the nearest note, a needle showing how many cents off it the pitch
+//! is, the frequency and the signal level, drawn with plain ANSI escape codes. A strobe can be
+//! shown instead of the needle for finer tuning.
+
+use crate::DetectedPitch;
+
+/// The width of the cents scale and the level meter, in columns.
+pub const WIDTH: usize = 41;
+
+/// The range of the cents scale, either side of the note.
+const RANGE: f64 = 50.0;
+
+/// Pitches within this many cents of the note are in tune.
+pub const IN_TUNE: f64 = 5.0;
+
+/// Pitches within this many cents are nearly in tune.
+const NEARLY_IN_TUNE: f64 = 15.0;
+
+/// How fast the strobe pattern drifts, in pattern periods per second per cent off.
+const STROBE_SPEED: f64 = 0.1;
+
+/// The periods of the strobe's rows, in columns. Each row moves at the same speed, so the finer
+/// rows show slow drift more clearly.
+const STROBE_PERIODS: &[usize] = &[16, 8, 4];
+
+/// The quietest level the meter shows, in dBFS.
+const METER_FLOOR: f64 = -60.0;
+
+/// Move to the top left of the screen.
+pub const HOME: &str = "\x1b[H";
+
+/// Clear the screen.
+pub const CLEAR: &str = "\x1b[2J";
+
+/// Clear the rest of the line, so a shorter line doesn't leave bits of the last frame behind.
+const CLEAR_LINE: &str = "\x1b[K";
+
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+const RESET: &str = "\x1b[0m";
+
+/// A strobe tuner display: bands that drift right when the pitch is sharp and left when it is
+/// flat, faster the further off it is, and stand still when it is in tune.
+#[derive(Debug, Clone, Default)]
+pub struct Strobe {
+    /// How far the pattern has drifted, in periods of the coarsest row.
+    phase: f64,
+}
+
+impl Strobe {
+    pub fn new() -> Self {
+        Strobe::default()
+    }
+
+    /// Drift for `seconds` at a pitch `cents` off its note.
+    pub fn advance(&mut self, cents: f64, seconds: f64) {
+        self.phase = (self.phase + cents * STROBE_SPEED * seconds).rem_euclid(1.0);
+    }
+
+    /// The rows of the pattern, `WIDTH` columns wide.
+    fn rows(&self, colour: &str) -> Vec<String> {
+        let coarsest = STROBE_PERIODS[0] as f64;
+        STROBE_PERIODS
+            .iter()
+            .map(|period| {
+                let offset = self.phase * coarsest;
+                let bands: String = (0..WIDTH)
+                    .map(|i| {
+                        let x = (i as f64 - offset).rem_euclid(*period as f64);
+                        if x < *period as f64 / 2.0 {
+                            '█'
+                        } else {
+                            ' '
+                        }
+                    })
+                    .collect();
+                format!("  {}{}{}", colour, bands, RESET)
+            })
+            .collect()
+    }
+}
+
+/// Render one frame of the tuner for the latest estimate, `detected`, of a signal at `level`
+/// dBFS, with a `strobe` instead of the needle if given. Print `HOME` before each frame to draw it
+/// over the last one.
+pub fn render(detected: &DetectedPitch, level: f64, strobe: Option<&Strobe>) -> String {
+    let mut lines = vec![];
+
+    let freq = match detected.freq {
+        Some(freq) => format!("{:.1} Hz", freq),
+        None => "-".to_string(),
+    };
+    match detected.pitch {
+        Some(pitch) => {
+            let colour = colour(detected.cents);
+            lines.push(format!(
+                "  {}{:<4}{}  {:+4.0}¢  {:>12}",
+                colour,
+                pitch.to_string(),
+                RESET,
+                // Adding zero turns -0 into 0.
+                detected.cents.round() + 0.0,
+                freq
+            ));
+            match strobe {
+                Some(strobe) => lines.extend(strobe.rows(colour)),
+                None => {
+                    lines.push(scale());
+                    lines.push(needle(detected.cents, colour));
+                }
+            }
+        }
+        None => {
+            let status = if detected.rest { "Rest" } else { "--" };
+            lines.push(format!("  {:<4}         {:>12}", status, freq));
+            match strobe {
+                Some(strobe) => lines.extend(strobe.rows(RESET)),
+                None => lines.extend([scale(), String::new()]),
+            }
+        }
+    }
+    lines.push(meter(level));
+
+    lines
+        .iter()
+        .map(|line| format!("{}{}\n", line, CLEAR_LINE))
+        .collect()
+}
+
+/// The colour for a pitch `cents` off its note.
+fn colour(cents: f64) -> &'static str {
+    if cents.abs() <= IN_TUNE {
+        GREEN
+    } else if cents.abs() <= NEARLY_IN_TUNE {
+        YELLOW
+    } else {
+        RED
+    }
+}
+
+/// The cents scale, marked every 25 cents.
+fn scale() -> String {
+    let ticks: String = (0..WIDTH)
+        .map(|i| if i % ((WIDTH - 1) / 4) == 0 { '|' } else { '-' })
+        .collect();
+    format!("  {}  -50 … +50¢", ticks)
+}
+
+/// A line with a needle under the scale at `cents`.
+fn needle(cents: f64, colour: &str) -> String {
+    let position = (cents.clamp(-RANGE, RANGE) + RANGE) / (2.0 * RANGE) * (WIDTH - 1) as f64;
+    format!(
+        "  {}{}^{}",
+        " ".repeat(position.round() as usize),
+        colour,
+        RESET
+    )
+}
+
+/// A bar showing `level` dBFS from `METER_FLOOR` to 0.
+fn meter(level: f64) -> String {
+    let fraction = ((level - METER_FLOOR) / -METER_FLOOR).clamp(0.0, 1.0);
+    let filled = (fraction * WIDTH as f64).round() as usize;
+    let level = if level.is_finite() {
+        format!("{:.0} dBFS", level)
+    } else {
+        "silent".to_string()
+    };
+    format!(
+        "  [{}{}] {}",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        level
+    )
+}
@@ -0,0 +1,300 @@
+//! Naming pitches in tunings with other than twelve notes to the octave, for microtonal music:
+//! equal divisions of the octave (N-EDO), such as 19, 24 or 31-EDO, and scales read from Scala
+//! `.scl` files. Estimates are matched to the nearest degree of the tuning, so every one gets a
+//! name and a cents offset, however far it is from twelve-tone equal temperament.
+//!
+//! EDO degrees are named from C in ups-and-downs notation: the nearest of the naturals (which
+//! the EDO's fifths reach from C) and their sharps and flats, raised by `^` or lowered by `v` a
+//! step at a time, so 24-EDO runs C, C^, C#, Dv, D... A4 is tuned to the reference pitch. Scala
+//! degrees count up from a tonic at its equal-tempered frequency, and are named after the nearest
+//! equally-tempered note, with a `^` more for each earlier degree of the same name.
+
+use std::str::FromStr;
+
+use crate::{cents_between, Note, Pitch, DEFAULT_A4};
+
+/// The order naturals are tried in when naming EDO degrees, with how many fifths each is from
+/// C.
+const NATURALS: [(char, i64); 7] = [
+    ('C', 0),
+    ('D', 2),
+    ('E', 4),
+    ('F', -1),
+    ('G', 1),
+    ('A', 3),
+    ('B', 5),
+];
+
+/// The sharps (positive) or flats tried on each natural, in order of preference.
+const ACCIDENTALS: [i64; 5] = [0, 1, -1, 2, -2];
+
+/// The octave of the first period of a tuning, from its first degree.
+const ROOT_OCTAVE: i8 = 4;
+
+/// A degree of a tuning, in some octave (or period, if the tuning doesn't repeat every octave).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TunedPitch {
+    /// Which degree of the tuning this is, from 0.
+    pub degree: usize,
+    /// The octave, numbered as in scientific pitch notation from the first degree.
+    pub octave: i8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// An equal division of the octave, with A on degree `a`.
+    Edo { a: usize },
+    /// A scale from a Scala file.
+    Scala,
+}
+
+/// A tuning: the degrees of a scale, repeating every period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    kind: Kind,
+    /// What the tuning is, e.g. from the Scala file's description.
+    description: String,
+    /// The cents of each degree above the first, which is 0, in ascending order.
+    degrees: Vec<f64>,
+    /// The interval the scale repeats at, in cents: 1200 for an octave.
+    period: f64,
+    names: Vec<String>,
+    /// The frequency of the first degree in `ROOT_OCTAVE`, in Hz.
+    root: f64,
+}
+
+impl Tuning {
+    /// The octave divided into `steps` equal steps, with A4 at 440 Hz.
+    pub fn edo(steps: usize) -> Self {
+        assert!(steps > 0, "an EDO needs at least one step");
+        let n = steps as i64;
+        // The EDO's fifth, and the sharp: seven fifths up and four octaves down.
+        let fifth = (n as f64 * 1.5f64.log2()).round() as i64;
+        let sharp = 7 * fifth - 4 * n;
+        let names = (0..n)
+            .map(|degree| edo_name(degree, n, fifth, sharp))
+            .collect();
+        let a = (3 * fifth).rem_euclid(n) as usize;
+        Tuning {
+            kind: Kind::Edo { a },
+            description: format!("{}-EDO", steps),
+            degrees: (0..steps)
+                .map(|degree| 1200.0 * degree as f64 / steps as f64)
+                .collect(),
+            period: 1200.0,
+            names,
+            root: DEFAULT_A4,
+        }
+        .with_reference(DEFAULT_A4, Note::C)
+    }
+
+    /// Read a scale in the Scala `.scl` format: `!` comments, a description, the number of
+    /// degrees and then each degree above the first, as cents (with a `.`) or a ratio such as
+    /// `5/4`. The last is the period. The first degree is on C4 until `with_reference` moves it.
+    pub fn from_scala(text: &str) -> Result<Self, String> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with('!'));
+        let description = lines.next().map_or("", |(_, line)| line.trim()).to_string();
+        let mut lines = lines.filter(|(_, line)| !line.trim().is_empty());
+
+        let (i, count) = lines.next().ok_or("missing the number of degrees")?;
+        let count: usize = count
+            .split_whitespace()
+            .next()
+            .and_then(|count| count.parse().ok())
+            .filter(|count| *count > 0)
+            .ok_or_else(|| format!("line {}: expected the number of degrees", i + 1))?;
+
+        let mut degrees = vec![0.0];
+        for _ in 0..count {
+            let (i, line) = lines
+                .next()
+                .ok_or_else(|| format!("expected {} degrees", count))?;
+            let pitch = line.split_whitespace().next().unwrap_or("");
+            let cents = scala_cents(pitch)
+                .ok_or_else(|| format!("line {}: invalid pitch: {}", i + 1, pitch))?;
+            degrees.push(cents);
+        }
+
+        let period = degrees.pop().unwrap_or(0.0);
+        if period <= 0.0 {
+            return Err("the period (the last degree) must be above the first degree".into());
+        }
+        if degrees[1..]
+            .iter()
+            .any(|cents| *cents <= 0.0 || *cents >= period)
+        {
+            return Err("each degree must be between the first and the period".into());
+        }
+        degrees.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        Ok(Tuning {
+            kind: Kind::Scala,
+            description,
+            degrees,
+            period,
+            names: vec![],
+            root: DEFAULT_A4,
+        }
+        .with_reference(DEFAULT_A4, Note::C))
+    }
+
+    /// Tune A4 to `a4` Hz, keeping it in tune for an EDO, or the first degree of a Scala scale
+    /// to `tonic` in octave 4, at its equal-tempered frequency.
+    pub fn with_reference(mut self, a4: f64, tonic: Note) -> Self {
+        match self.kind {
+            Kind::Edo { a } => self.root = a4 / 2f64.powf(self.degrees[a] / 1200.0),
+            Kind::Scala => {
+                let tonic = Pitch {
+                    note: tonic,
+                    octave: ROOT_OCTAVE,
+                };
+                self.root = tonic.freq(a4);
+                self.names = scala_names(&self.degrees, tonic.note);
+            }
+        }
+        self
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// The number of degrees in each period.
+    pub fn len(&self) -> usize {
+        self.degrees.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.degrees.is_empty()
+    }
+
+    /// The cents of each degree above the first.
+    pub fn degrees(&self) -> &[f64] {
+        &self.degrees
+    }
+
+    /// The interval the degrees repeat at, in cents.
+    pub fn period(&self) -> f64 {
+        self.period
+    }
+
+    /// The name of `degree`, such as `C#^`.
+    pub fn name(&self, degree: usize) -> &str {
+        &self.names[degree]
+    }
+
+    /// The name of `pitch` and its octave, such as `C#^4`.
+    pub fn pitch_name(&self, pitch: TunedPitch) -> String {
+        format!("{}{}", self.name(pitch.degree), pitch.octave)
+    }
+
+    /// The frequency of `pitch`, in Hz.
+    pub fn freq(&self, pitch: TunedPitch) -> f64 {
+        let periods = pitch.octave as f64 - ROOT_OCTAVE as f64;
+        let cents = periods * self.period + self.degrees[pitch.degree];
+        self.root * 2f64.powf(cents / 1200.0)
+    }
+
+    /// The degree nearest `freq`, and how far `freq` is from it in cents.
+    pub fn identify(&self, freq: f64) -> (TunedPitch, f64) {
+        let cents = cents_between(self.root, freq);
+        let periods = (cents / self.period).floor();
+        let within = cents - periods * self.period;
+
+        // The first degree of the next period is a candidate too.
+        let (mut degree, mut offset) = (0, within);
+        for (i, degree_cents) in self.degrees.iter().enumerate() {
+            if (within - degree_cents).abs() < offset.abs() {
+                degree = i;
+                offset = within - degree_cents;
+            }
+        }
+        let mut octave = periods as i64 + ROOT_OCTAVE as i64;
+        if (within - self.period).abs() < offset.abs() {
+            degree = 0;
+            offset = within - self.period;
+            octave += 1;
+        }
+        let octave = octave.clamp(i8::MIN as i64, i8::MAX as i64) as i8;
+        (TunedPitch { degree, octave }, offset)
+    }
+}
+
+impl FromStr for Tuning {
+    type Err = String;
+
+    /// Parse an EDO, such as `19-edo` or `31edo`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = s
+            .to_ascii_lowercase()
+            .strip_suffix("edo")
+            .map(|steps| steps.trim_end_matches('-').to_string())
+            .and_then(|steps| steps.parse::<usize>().ok())
+            .ok_or_else(|| format!("unknown tuning: {}", s))?;
+        if steps == 0 {
+            return Err("an EDO needs at least one step".into());
+        }
+        Ok(Tuning::edo(steps))
+    }
+}
+
+/// The name of `degree` of an `n`-EDO with the given fifth and sharp, in steps.
+fn edo_name(degree: i64, n: i64, fifth: i64, sharp: i64) -> String {
+    // The fewest accidentals, preferring sharps and flats to ups and downs.
+    let mut best: Option<((i64, i64), String)> = None;
+    for accidentals in ACCIDENTALS.iter() {
+        for (letter, fifths) in NATURALS.iter() {
+            let natural = (fifths * fifth).rem_euclid(n);
+            let mut ups = (degree - natural - accidentals * sharp).rem_euclid(n);
+            if 2 * ups > n {
+                ups -= n;
+            }
+            let cost = (ups.abs() + accidentals.abs(), ups.abs());
+            if best.as_ref().is_none_or(|(best, _)| cost < *best) {
+                let mut name = letter.to_string();
+                let accidental = if *accidentals > 0 { "#" } else { "b" };
+                name.push_str(&accidental.repeat(accidentals.unsigned_abs() as usize));
+                let up = if ups > 0 { "^" } else { "v" };
+                name.push_str(&up.repeat(ups.unsigned_abs() as usize));
+                best = Some((cost, name));
+            }
+        }
+    }
+    best.map(|(_, name)| name).unwrap_or_default()
+}
+
+/// The cents of a Scala pitch: cents if it has a `.`, or a ratio such as `3/2` or `2`.
+fn scala_cents(pitch: &str) -> Option<f64> {
+    if pitch.contains('.') {
+        return pitch.parse().ok();
+    }
+    let (numerator, denominator) = match pitch.split_once('/') {
+        Some((numerator, denominator)) => (numerator, denominator),
+        None => (pitch, "1"),
+    };
+    let numerator: f64 = numerator.parse::<u64>().ok()? as f64;
+    let denominator: f64 = denominator.parse::<u64>().ok()? as f64;
+    if numerator <= 0.0 || denominator <= 0.0 {
+        return None;
+    }
+    Some(1200.0 * (numerator / denominator).log2())
+}
+
+/// The names of the degrees of a Scala scale on `tonic`: the nearest equally-tempered note to
+/// each, with a `^` for each earlier degree nearest the same note.
+fn scala_names(degrees: &[f64], tonic: Note) -> Vec<String> {
+    let mut names: Vec<String> = vec![];
+    for cents in degrees {
+        let semitones = (cents / 100.0).round() as i64 + tonic.semitone() as i64;
+        let note = Note::ALL[semitones.rem_euclid(12) as usize].to_string();
+        let earlier = names
+            .iter()
+            .filter(|earlier| earlier.trim_end_matches('^') == note)
+            .count();
+        names.push(format!("{}{}", note, "^".repeat(earlier)));
+    }
+    names
+}
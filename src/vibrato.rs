@@ -0,0 +1,210 @@
+//! Finding vibrato, the periodic wobble in the pitch of sustained notes. Chunks are too long to
+//! follow it, so a `PitchTrack` estimates the pitch again every few milliseconds over windows just
+//! a few periods long, and a sinusoid is fitted to the track over each note.
+//!
+//! The track always uses MPM, whatever the chunks were detected with: it interpolates between
+//! lags, so it stays accurate on windows this short, where the monotonic detector's estimates are
+//! coarse and smeared across the window.
+
+use std::f64::consts::PI;
+
+use crate::mpm;
+use crate::notes::NoteEvent;
+use crate::{cents_between, DetectedPitch};
+
+/// How often the pitch is tracked, in seconds.
+pub const TRACK_HOP: f64 = 0.01;
+
+/// How many periods of a chunk's pitch each window of the track holds. Longer windows are more
+/// accurate, but average the vibrato away.
+const TRACK_PERIODS: f64 = 4.0;
+
+/// Estimates further than this from the pitch of their chunk, in cents, are taken to be octave
+/// errors and dropped.
+const MAX_DEVIATION: f64 = 300.0;
+
+/// The range of vibrato rates searched, in Hz. Singers and string players vibrate at around 5 to
+/// 7 Hz.
+const MIN_RATE: f64 = 3.0;
+const MAX_RATE: f64 = 10.0;
+const RATE_STEP: f64 = 0.05;
+
+/// The least number of vibrato cycles a note must last for its vibrato to be measured.
+const MIN_CYCLES: f64 = 2.0;
+
+/// Vibrato shallower than this, in cents, is taken to be jitter.
+const MIN_DEPTH: f64 = 5.0;
+
+/// How much of the variation in pitch over a note the fitted sinusoid must account for.
+const MIN_FIT: f64 = 0.5;
+
+/// The vibrato of a note.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vibrato {
+    /// How many times a second the pitch goes up and down.
+    pub rate: f64,
+    /// How far the pitch goes either side of its centre, in cents.
+    pub depth: f64,
+}
+
+/// A pitch estimate every `TRACK_HOP` seconds, made from the chunks of a signal as they're read.
+#[derive(Debug, Clone)]
+pub struct PitchTrack {
+    sample_rate: usize,
+    /// The latest samples: the last chunk, and the end of the one before it.
+    samples: Vec<f32>,
+    /// The position in the signal of the first of `samples`.
+    start: usize,
+    /// The position of the centre of the next window.
+    next: usize,
+    /// The time and frequency of each estimate.
+    points: Vec<(f64, f64)>,
+}
+
+impl PitchTrack {
+    pub fn new(sample_rate: usize) -> Self {
+        PitchTrack {
+            sample_rate,
+            samples: vec![],
+            start: 0,
+            next: 0,
+            points: vec![],
+        }
+    }
+
+    /// Feed the next `chunk` and what was `detected` in it. Only chunks with a frequency are
+    /// tracked, over windows sized to hold `TRACK_PERIODS` periods of it.
+    pub fn push(&mut self, chunk: &[f32], detected: &DetectedPitch) {
+        let sample_rate = self.sample_rate as f64;
+        let hop = ((TRACK_HOP * sample_rate) as usize).max(1);
+        self.samples.extend_from_slice(chunk);
+        let end = self.start + self.samples.len();
+
+        match detected.freq.filter(|_| !detected.rest) {
+            Some(chunk_freq) => {
+                let window = ((TRACK_PERIODS * sample_rate / chunk_freq).ceil() as usize)
+                    .clamp(2, chunk.len().max(2));
+                let half = window / 2;
+                while self.next + window - half <= end {
+                    if self.next >= self.start + half {
+                        let from = self.next - half - self.start;
+                        let frame = &self.samples[from..from + window];
+                        let freq = mpm::mpm_freq(frame, self.sample_rate, mpm::DEFAULT_CUTOFF)
+                            .filter(|freq| cents_between(chunk_freq, *freq).abs() <= MAX_DEVIATION);
+                        if let Some(freq) = freq {
+                            self.points.push((self.next as f64 / sample_rate, freq));
+                        }
+                    }
+                    self.next += hop;
+                }
+            }
+            None => {
+                while self.next < end {
+                    self.next += hop;
+                }
+            }
+        }
+
+        // Windows are at most a chunk long, so no more than that is needed from before.
+        let excess = self.samples.len().saturating_sub(chunk.len());
+        self.samples.drain(..excess);
+        self.start += excess;
+    }
+
+    /// The time in seconds and frequency in Hz of each estimate so far.
+    pub fn points(&self) -> &[(f64, f64)] {
+        &self.points
+    }
+
+    /// The vibrato over `note`, if it has any.
+    pub fn vibrato(&self, note: &NoteEvent) -> Option<Vibrato> {
+        let points: Vec<_> = self
+            .points
+            .iter()
+            .filter(|(time, _)| (note.start..=note.end).contains(time))
+            .copied()
+            .collect();
+        fit(&points)
+    }
+}
+
+/// Fit vibrato to `points`, the time in seconds and frequency in Hz of pitch estimates over a
+/// note. A sinusoid is fitted to their pitch in cents at each rate from `MIN_RATE` to `MAX_RATE`,
+/// after taking out any steady drift, and the best fit is the vibrato. There's none if the note
+/// is too short for a couple of cycles of it, or it doesn't fit well or is too shallow.
+pub fn fit(points: &[(f64, f64)]) -> Option<Vibrato> {
+    let (first, last) = (points.first()?, points.last()?);
+    let span = last.0 - first.0;
+    if points.len() < 4 || span <= 0.0 {
+        return None;
+    }
+
+    let times: Vec<f64> = points.iter().map(|(time, _)| time - first.0).collect();
+    let cents: Vec<f64> = points
+        .iter()
+        .map(|(_, freq)| cents_between(first.1, *freq))
+        .collect();
+    let residuals = detrend(&times, &cents);
+    let variance: f64 = residuals.iter().map(|r| r * r).sum();
+    if variance == 0.0 {
+        return None;
+    }
+
+    let mut best: Option<(f64, Vibrato)> = None;
+    let steps = ((MAX_RATE - MIN_RATE) / RATE_STEP).round() as usize;
+    for step in 0..=steps {
+        let rate = MIN_RATE + step as f64 * RATE_STEP;
+        if span * rate < MIN_CYCLES {
+            continue;
+        }
+        if let Some((explained, depth)) = fit_sinusoid(&times, &residuals, rate) {
+            if best.is_none_or(|(best, _)| explained > best) {
+                best = Some((explained, Vibrato { rate, depth }));
+            }
+        }
+    }
+
+    let (explained, vibrato) = best?;
+    Some(vibrato).filter(|vibrato| explained >= MIN_FIT * variance && vibrato.depth >= MIN_DEPTH)
+}
+
+/// `values` less the straight line through them that best fits `times`.
+fn detrend(times: &[f64], values: &[f64]) -> Vec<f64> {
+    let n = times.len() as f64;
+    let mean_t = times.iter().sum::<f64>() / n;
+    let mean_v = values.iter().sum::<f64>() / n;
+    let (mut cov, mut var) = (0.0, 0.0);
+    for (t, v) in times.iter().zip(values) {
+        cov += (t - mean_t) * (v - mean_v);
+        var += (t - mean_t) * (t - mean_t);
+    }
+    let slope = if var == 0.0 { 0.0 } else { cov / var };
+    times
+        .iter()
+        .zip(values)
+        .map(|(t, v)| v - mean_v - slope * (t - mean_t))
+        .collect()
+}
+
+/// The least-squares fit of `a cos(2πft) + b sin(2πft)` to `values` at `times`, for `f` = `rate`:
+/// how much of the sum of their squares it accounts for, and its amplitude.
+fn fit_sinusoid(times: &[f64], values: &[f64], rate: f64) -> Option<(f64, f64)> {
+    let (mut cc, mut cs, mut ss, mut vc, mut vs) = (0.0, 0.0, 0.0, 0.0, 0.0);
+    for (t, v) in times.iter().zip(values) {
+        let (sin, cos) = (2.0 * PI * rate * t).sin_cos();
+        cc += cos * cos;
+        cs += cos * sin;
+        ss += sin * sin;
+        vc += v * cos;
+        vs += v * sin;
+    }
+
+    // Solve the 2x2 normal equations.
+    let det = cc * ss - cs * cs;
+    if det.abs() < 1e-9 {
+        return None;
+    }
+    let a = (vc * ss - vs * cs) / det;
+    let b = (vs * cc - vc * cs) / det;
+    Some((a * vc + b * vs, a.hypot(b)))
+}
@@ -0,0 +1,87 @@
+//! Deciding whether a chunk of speech is voiced (pitched, like a vowel) or unvoiced (noisy, like
+//! the fricatives `s` and `f`). Every algorithm finds some frequency in noise, so without this
+//! the unvoiced parts of speech come out as random pitches. A voiced chunk repeats clearly at its
+//! period, and crosses zero a few times a period; an unvoiced one repeats poorly, and crosses zero
+//! far more often, at the rate of its high-frequency noise.
+
+use crate::simd;
+
+/// The least clarity of a voiced chunk.
+pub const MIN_CLARITY: f64 = 0.5;
+
+/// The most zero crossings per period in a voiced chunk. A sine crosses twice a period, and
+/// strong formants add several more.
+pub const MAX_CROSSINGS_PER_PERIOD: f64 = 20.0;
+
+/// How near the highest autocorrelation the period's must be. Every multiple of the period
+/// correlates about as well as the period, so this takes the shortest of them.
+const PEAK_THRESHOLD: f64 = 0.9;
+
+/// The number of times `buffer` changes sign, per sample.
+pub fn zero_crossing_rate(buffer: &[f32]) -> f64 {
+    if buffer.len() < 2 {
+        return 0.0;
+    }
+    let crossings = buffer
+        .windows(2)
+        .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+        .count();
+    crossings as f64 / (buffer.len() - 1) as f64
+}
+
+/// How clearly `buffer` repeats, and at what period: the highest normalized autocorrelation at
+/// lags up to `max_lag`, past the first lag it falls to zero at (so not the lags too short to be
+/// a period, where any signal resembles itself), and the first peak with nearly that much. A
+/// clarity of 1 is a signal that repeats exactly; noise is near 0.
+pub fn clarity(buffer: &[f32], max_lag: usize) -> (f64, usize) {
+    // The energy of each prefix, to normalize by the energy of the overlapping parts.
+    let mut energy = vec![0.0; buffer.len() + 1];
+    for (i, x) in buffer.iter().enumerate() {
+        energy[i + 1] = energy[i] + (*x as f64) * (*x as f64);
+    }
+    let total = energy[buffer.len()];
+
+    let mut correlations = vec![0.0; max_lag.min(buffer.len().saturating_sub(1)) + 1];
+    for (lag, correlation) in correlations.iter_mut().enumerate().skip(1) {
+        let (head, tail) = (energy[buffer.len() - lag], total - energy[lag]);
+        if head > 0.0 && tail > 0.0 {
+            *correlation = simd::dot(buffer, &buffer[lag..]) / (head * tail).sqrt();
+        }
+    }
+
+    let first_zero = match correlations.iter().skip(1).position(|r| *r <= 0.0) {
+        Some(lag) => lag + 1,
+        None => return (0.0, 0),
+    };
+    let candidates = &correlations[first_zero..];
+    let best = candidates.iter().cloned().fold(0.0, f64::max);
+    let mut period = match candidates
+        .iter()
+        .position(|r| best > 0.0 && *r >= PEAK_THRESHOLD * best)
+    {
+        Some(lag) => lag,
+        None => return (0.0, 0),
+    };
+    // Climb to the top of that peak.
+    while period + 1 < candidates.len() && candidates[period + 1] > candidates[period] {
+        period += 1;
+    }
+    let period = period + first_zero;
+    (best.min(1.0), period)
+}
+
+/// Whether `buffer`, sampled at `sample_rate` Hz, is voiced with a pitch of at least `min_freq`
+/// Hz: whether it repeats clearly at some period that long or shorter, and crosses zero few enough
+/// times in it.
+pub fn is_voiced(buffer: &[f32], sample_rate: usize, min_freq: f64) -> bool {
+    let (clarity, crossings) = voicing(buffer, sample_rate, min_freq);
+    clarity >= MIN_CLARITY && crossings <= MAX_CROSSINGS_PER_PERIOD
+}
+
+/// What `is_voiced` goes by: the clarity of `buffer` at periods up to that of `min_freq`, and how
+/// many times it crosses zero in its period.
+pub fn voicing(buffer: &[f32], sample_rate: usize, min_freq: f64) -> (f64, f64) {
+    let max_lag = (sample_rate as f64 / min_freq.max(1.0)).ceil() as usize;
+    let (clarity, period) = clarity(buffer, max_lag);
+    (clarity, zero_crossing_rate(buffer) * period as f64)
+}
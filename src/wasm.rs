@@ -0,0 +1,62 @@
+//! Exports for running the detector in a browser, e.g. on Web Audio buffers for an online tuner.
+//! Only built with the `wasm` feature, for `wasm32-unknown-unknown`; `wasm/pitch-detector.js`
+//! wraps them in `analyzeChunk(samples, sampleRate)`, which returns the results as an object.
+//!
+//! Samples are passed through the module's memory: `alloc_samples` makes room for them, JavaScript
+//! copies them in, and `free_samples` releases them after the call.
+
+use std::cell::RefCell;
+use std::ptr;
+use std::slice;
+
+use crate::output;
+use crate::PitchDetector;
+
+thread_local! {
+    /// The JSON of the latest `analyze_chunk`.
+    static RESULT: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Room for `len` samples, which must be released with `free_samples`.
+#[no_mangle]
+pub extern "C" fn alloc_samples(len: usize) -> *mut f32 {
+    Box::into_raw(vec![0.0; len].into_boxed_slice()) as *mut f32
+}
+
+/// Release the room for `len` samples at `samples`.
+///
+/// # Safety
+///
+/// `samples` and `len` must be from one `alloc_samples` call, and not already released.
+#[no_mangle]
+pub unsafe extern "C" fn free_samples(samples: *mut f32, len: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(samples, len)));
+}
+
+/// Detect the pitch of the `len` samples at `samples`, sampled at `sample_rate` Hz. The result is
+/// a JSON object like those of `analyze --format json`, without the timestamp and channel: a
+/// pointer to its UTF-8, which is `result_len` bytes long and valid until the next call.
+///
+/// # Safety
+///
+/// `samples` must point to `len` samples, such as from `alloc_samples`.
+#[no_mangle]
+pub unsafe extern "C" fn analyze_chunk(
+    samples: *const f32,
+    len: usize,
+    sample_rate: u32,
+) -> *const u8 {
+    let samples = slice::from_raw_parts(samples, len);
+    let detected = PitchDetector::new(sample_rate as usize).detect(samples);
+    RESULT.with(|result| {
+        let mut result = result.borrow_mut();
+        *result = output::detected_json(&detected);
+        result.as_ptr()
+    })
+}
+
+/// The length in bytes of the latest result of `analyze_chunk`.
+#[no_mangle]
+pub extern "C" fn result_len() -> usize {
+    RESULT.with(|result| result.borrow().len())
+}
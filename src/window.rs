@@ -0,0 +1,58 @@
+//! Window functions applied to each chunk before analysis.
+
+use std::borrow::Cow;
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Window {
+    /// No windowing at all.
+    #[default]
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rectangular" | "none" => Ok(Window::Rectangular),
+            "hann" => Ok(Window::Hann),
+            "hamming" => Ok(Window::Hamming),
+            "blackman" => Ok(Window::Blackman),
+            _ => Err(format!("unknown window: {}", s)),
+        }
+    }
+}
+
+impl Window {
+    /// The weight of sample `i` of `len`.
+    pub fn weight(self, i: usize, len: usize) -> f64 {
+        if len < 2 {
+            return 1.0;
+        }
+        let x = 2.0 * PI * i as f64 / (len - 1) as f64;
+        match self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * x.cos(),
+            Window::Hamming => 0.54 - 0.46 * x.cos(),
+            Window::Blackman => 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos(),
+        }
+    }
+
+    /// `buffer` multiplied by this window. Borrows `buffer` for `Rectangular`.
+    pub fn apply(self, buffer: &[f32]) -> Cow<'_, [f32]> {
+        if self == Window::Rectangular {
+            return Cow::Borrowed(buffer);
+        }
+        let len = buffer.len();
+        buffer
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (*s as f64 * self.weight(i, len)) as f32)
+            .collect()
+    }
+}
@@ -0,0 +1,75 @@
+//! The YIN pitch detector (de Cheveigné and Kawahara, 2002).
+
+/// The default absolute threshold on the cumulative mean normalized difference.
+pub const DEFAULT_THRESHOLD: f64 = 0.1;
+
+/// Estimate the fundamental frequency of `buffer`, sampled at `sample_rate` Hz. Returns `None` if
+/// the buffer is too short to hold a period.
+///
+/// Periods up to half the buffer length are considered. The first dip of the cumulative mean
+/// normalized difference function below `threshold` is taken as the period; if there is none, the
+/// global minimum is used instead.
+pub fn yin_freq(buffer: &[f32], sample_rate: usize, threshold: f64) -> Option<f64> {
+    let cmnd = cumulative_mean_normalized_difference(buffer);
+
+    let tau = match absolute_threshold(&cmnd, threshold) {
+        Some(tau) => tau,
+        // No dip below the threshold: fall back to the best candidate we have.
+        None => (2..cmnd.len()).min_by(|a, b| cmnd[*a].partial_cmp(&cmnd[*b]).unwrap())?,
+    };
+
+    Some((sample_rate as f64) / parabolic_interpolation(&cmnd, tau))
+}
+
+/// The difference function `d(tau)` for each lag `tau` in `0..buffer.len() / 2`, normalized by
+/// its cumulative mean so that `d'(0) = 1` and dips are comparable across lags.
+pub fn cumulative_mean_normalized_difference(buffer: &[f32]) -> Vec<f64> {
+    let window = buffer.len() / 2;
+
+    let mut diff: Vec<f64> = (0..window)
+        .map(|tau| {
+            buffer[..window]
+                .iter()
+                .zip(buffer[tau..].iter())
+                .map(|(a, b)| {
+                    let d = (*a as f64) - (*b as f64);
+                    d * d
+                })
+                .sum()
+        })
+        .collect();
+
+    if let Some(first) = diff.first_mut() {
+        *first = 1.0;
+    }
+
+    let mut running_sum = 0.0;
+    for (tau, d) in diff.iter_mut().enumerate().skip(1) {
+        running_sum += *d;
+        *d = if running_sum == 0.0 {
+            1.0
+        } else {
+            *d * (tau as f64) / running_sum
+        };
+    }
+
+    diff
+}
+
+/// The first lag at which `cmnd` dips below `threshold`, followed down to the bottom of the dip.
+fn absolute_threshold(cmnd: &[f64], threshold: f64) -> Option<usize> {
+    let mut tau = (2..cmnd.len()).find(|tau| cmnd[*tau] < threshold)?;
+    while tau + 1 < cmnd.len() && cmnd[tau + 1] < cmnd[tau] {
+        tau += 1;
+    }
+    Some(tau)
+}
+
+/// Refine `tau` to a fractional lag using the parabola through it and its neighbours.
+fn parabolic_interpolation(cmnd: &[f64], tau: usize) -> f64 {
+    if tau < 1 || tau + 1 >= cmnd.len() {
+        return tau as f64;
+    }
+
+    tau as f64 + crate::parabolic_offset(cmnd[tau - 1], cmnd[tau], cmnd[tau + 1])
+}
@@ -0,0 +1,37 @@
+//! A quick frequency estimate from the rate of zero crossings.
+//!
+//! This is linear in the chunk length and needs only a few periods, so it suits tuners and
+//! embedded use where a rough estimate every few milliseconds beats a precise one every 250 ms.
+//! Partials strong enough to cross zero more than twice a period throw it off, though.
+
+/// The fraction of the peak amplitude a signal must go below zero by before the next positive-going
+/// crossing counts, so that noise around zero doesn't add crossings.
+pub const HYSTERESIS: f64 = 0.1;
+
+/// Estimate the fundamental frequency of `buffer`, sampled at `sample_rate` Hz, from the average
+/// time between its positive-going zero crossings. Returns `None` if there are fewer than two.
+pub fn zcr_freq(buffer: &[f32], sample_rate: usize) -> Option<f64> {
+    let peak = buffer.iter().fold(0.0f32, |peak, x| peak.max(x.abs()));
+    let level = -(HYSTERESIS as f32) * peak;
+
+    let mut crossings: Vec<f64> = vec![];
+    let mut armed = false;
+    for i in 1..buffer.len() {
+        let (prev, cur) = (buffer[i - 1], buffer[i]);
+        if cur < level {
+            armed = true;
+        } else if armed && prev < 0.0 && cur >= 0.0 {
+            // Interpolate linearly to where the signal crosses zero.
+            crossings.push((i - 1) as f64 + (-prev / (cur - prev)) as f64);
+            armed = false;
+        }
+    }
+
+    match (crossings.first(), crossings.last()) {
+        (Some(first), Some(last)) if crossings.len() >= 2 => {
+            let period = (last - first) / (crossings.len() - 1) as f64;
+            Some(sample_rate as f64 / period)
+        }
+        _ => None,
+    }
+}
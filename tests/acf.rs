@@ -0,0 +1,72 @@
+//! The unbiased autocorrelation should place the maxima where the periods are, rather than pulled
+//! towards short lags by the shrinking overlap.
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    simd, Acf, PitchDetector,
+};
+
+const FREQS: &[f64] = &[82.41, 110.0, 146.83, 196.0, 261.63];
+
+/// The error, in cents, in the frequency of a pure tone at each of `FREQS`, in chunks holding
+/// few periods of the lowest.
+fn errors(acf: Acf) -> Vec<f64> {
+    let detector = PitchDetector::default()
+        .with_min_freq(80)
+        .with_chunk_size(4096)
+        .with_octave_correction(false)
+        .with_acf(acf);
+    FREQS
+        .iter()
+        .map(|&freq| {
+            let samples = Generator::new(Tones::Steps(vec![freq]))
+                .with_duration(0.5)
+                .samples();
+            let chunk = detector.chunks(&samples).next().unwrap();
+            let detected = detector.compute_monotonic_freq(chunk).unwrap();
+            cents_between(freq, detected)
+        })
+        .collect()
+}
+
+#[test]
+fn biased_is_sharp_and_unbiased_is_closer() {
+    let (biased, unbiased) = (errors(Acf::Biased), errors(Acf::Unbiased));
+    assert!(biased.iter().all(|&cents| cents > 0.0), "{:?}", biased);
+    let worst = |errors: &[f64]| errors.iter().map(|cents| cents.abs()).fold(0.0, f64::max);
+    assert!(
+        worst(&unbiased) < worst(&biased),
+        "{:?} unbiased, {:?} biased",
+        unbiased,
+        biased
+    );
+}
+
+#[test]
+fn parses_acf_names() {
+    assert_eq!("unbiased".parse(), Ok(Acf::Unbiased));
+    assert_eq!("biased".parse(), Ok(Acf::Biased));
+    assert!("raw".parse::<Acf>().is_err());
+}
+
+#[test]
+fn detects_batches_from_autocorrelations_taken_elsewhere() {
+    let detector = PitchDetector::default();
+    let samples = Generator::new(Tones::Steps(vec![110.0, 440.0, 1318.5]))
+        .with_duration(1.5)
+        .samples();
+    let buffers: Vec<&[f32]> = detector.chunks(&samples).collect();
+    let acfs: Vec<Vec<f64>> = buffers
+        .iter()
+        .map(|buffer| {
+            (1..=detector.monotonic_lags(buffer.len()))
+                .map(|lag| simd::dot(buffer, &buffer[lag..]))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        detector.detect_parallel_with_autocorrelations(&buffers, &acfs, 3),
+        detector.detect_parallel_with_inharmonicity(&buffers, 3)
+    );
+}
@@ -0,0 +1,76 @@
+//! Chord recognition should name triads and sevenths played with harmonics, in any inversion, and
+//! find none in silence.
+
+use monophonic_detector::{
+    chord::ChordQuality,
+    generate::{Generator, Tones},
+    Pitch, PitchDetector, DEFAULT_A4,
+};
+
+const HARMONICS: &[f64] = &[1.0, 0.5, 0.33, 0.25, 0.2];
+
+/// Tones of each of the MIDI notes `midi` with `HARMONICS`, mixed.
+fn mix(midi: &[i32]) -> Vec<f32> {
+    let tones: Vec<Vec<f32>> = midi
+        .iter()
+        .map(|midi| {
+            Generator::new(Tones::Steps(vec![Pitch::from_midi(*midi).freq(DEFAULT_A4)]))
+                .with_duration(0.5)
+                .with_harmonics(HARMONICS.to_vec())
+                .samples()
+        })
+        .collect();
+    (0..tones[0].len())
+        .map(|i| tones.iter().map(|tone| tone[i]).sum::<f32>() / midi.len() as f32)
+        .collect()
+}
+
+fn chord(midi: &[i32]) -> Option<String> {
+    let detector = PitchDetector::default();
+    let samples = mix(midi);
+    let estimate = detector.detect_chord(&samples[..detector.chunk_size()])?;
+    Some(estimate.chord.to_string())
+}
+
+#[test]
+fn names_chords() {
+    for (midi, name) in &[
+        (&[57, 60, 64][..], "Am"),
+        (&[60, 64, 67], "C"),
+        (&[55, 59, 62, 65], "G7"),
+        (&[59, 62, 65], "Bdim"),
+        (&[60, 64, 68], "Caug"),
+        (&[52, 55, 59], "Em"),
+        (&[50, 54, 57], "D"),
+        (&[53, 56, 60], "Fm"),
+        // C major in its first and second inversions.
+        (&[52, 55, 60], "C"),
+        (&[55, 60, 64], "C"),
+    ] {
+        assert_eq!(chord(midi).as_deref(), Some(*name), "{:?}", midi);
+    }
+}
+
+#[test]
+fn is_confident_in_a_clean_chord() {
+    let detector = PitchDetector::default();
+    let samples = mix(&[57, 60, 64]);
+    let estimate = detector
+        .detect_chord(&samples[..detector.chunk_size()])
+        .unwrap();
+    assert_eq!(estimate.chord.quality, ChordQuality::Minor);
+    assert!(
+        (0.8..=1.0).contains(&estimate.confidence),
+        "{}",
+        estimate.confidence
+    );
+}
+
+#[test]
+fn finds_no_chord_in_silence() {
+    let detector = PitchDetector::default();
+    assert_eq!(
+        detector.detect_chord(&vec![0.0; detector.chunk_size()]),
+        None
+    );
+}
@@ -0,0 +1,109 @@
+//! Configuration files should turn into the flags they stand for, and say where they're wrong.
+
+use monophonic_detector::config::{Config, Value};
+
+const CONFIG: &str = r#"
+# Defaults for every command.
+algorithm = "yin"
+a4 = 442
+min_freq = 1_000   # a comment after a value
+voicing = true
+raw = false
+
+[analyze]
+format = 'json'
+note-names = "german # not a comment"
+
+[compare]
+algorithms = ["yin", "mpm"]
+"#;
+
+#[test]
+fn parses_values() {
+    let config = Config::parse(CONFIG).unwrap();
+    assert_eq!(
+        config.get(None, "algorithm"),
+        Some(&Value::String("yin".to_string()))
+    );
+    assert_eq!(
+        config.get(None, "min_freq"),
+        Some(&Value::Number("1000".to_string()))
+    );
+    assert_eq!(config.get(None, "voicing"), Some(&Value::Boolean(true)));
+    assert_eq!(
+        config.get(Some("analyze"), "note-names"),
+        Some(&Value::String("german # not a comment".to_string()))
+    );
+    assert_eq!(
+        config.get(Some("compare"), "algorithms"),
+        Some(&Value::Array(vec![
+            Value::String("yin".to_string()),
+            Value::String("mpm".to_string()),
+        ]))
+    );
+    assert_eq!(config.get(Some("analyze"), "algorithm"), None);
+    assert_eq!(
+        config.sections().collect::<Vec<_>>(),
+        vec!["analyze", "compare"]
+    );
+}
+
+#[test]
+fn turns_settings_into_flags() {
+    let config = Config::parse(CONFIG).unwrap();
+    assert_eq!(
+        config.args(None),
+        vec![
+            "--algorithm",
+            "yin",
+            "--a4",
+            "442",
+            "--min-freq",
+            "1000",
+            "--voicing"
+        ]
+    );
+    assert_eq!(
+        config.args(Some("analyze")),
+        vec!["--format", "json", "--note-names", "german # not a comment"]
+    );
+    assert_eq!(
+        config.args(Some("compare")),
+        vec!["--algorithms", "yin,mpm"]
+    );
+    assert!(config.args(Some("live")).is_empty());
+}
+
+#[test]
+fn reads_escapes() {
+    let config = Config::parse(r#"label = "a \"quoted\" \\ name""#).unwrap();
+    assert_eq!(
+        config.get(None, "label"),
+        Some(&Value::String(r#"a "quoted" \ name"#.to_string()))
+    );
+}
+
+#[test]
+fn rejects_invalid_files_by_line() {
+    for (text, error) in [
+        ("a4 = 442\nalgorithm yin", "line 2: expected key = value"),
+        ("algorithm = yin", "line 1: invalid value: yin"),
+        ("format = \"json", "line 1: unterminated string"),
+        ("a4 = 440\na4 = 442", "line 2: duplicate key: a4"),
+        ("[analyze]\n[analyze]", "line 2: duplicate section: analyze"),
+        ("[analyze", "line 1: invalid section: [analyze"),
+        ("a4 = 440 442", "line 1: invalid value: 440 442"),
+        (
+            "format = \"json\" csv",
+            "line 1: unexpected text after value: csv",
+        ),
+        (
+            "algorithms = [\"yin\" \"mpm\"]",
+            "line 1: expected , or ] in array",
+        ),
+    ]
+    .iter()
+    {
+        assert_eq!(Config::parse(text), Err(error.to_string()), "{}", text);
+    }
+}
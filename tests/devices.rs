@@ -0,0 +1,24 @@
+//! ALSA's capture devices should be found in its listing, and named so they can be recorded from.
+
+use monophonic_detector::devices::parse_alsa_pcm;
+
+const PCM: &str = "\
+00-00: ALC3246 Analog : ALC3246 Analog : playback 1 : capture 1
+00-03: HDMI 0 : HDMI 0 : playback 1
+01-00: USB Audio : USB Audio : playback 1 : capture 1
+";
+
+#[test]
+fn lists_only_capture_devices() {
+    let devices = parse_alsa_pcm(PCM);
+    let names: Vec<&str> = devices.iter().map(|device| device.name.as_str()).collect();
+    assert_eq!(names, ["plughw:0,0", "plughw:1,0"]);
+    assert_eq!(devices[1].description, "USB Audio");
+    assert!(devices.iter().all(|device| device.backend == "alsa"));
+}
+
+#[test]
+fn skips_lines_it_cannot_read() {
+    assert!(parse_alsa_pcm("").is_empty());
+    assert!(parse_alsa_pcm("not a device : capture 1\n").is_empty());
+}
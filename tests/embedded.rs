@@ -0,0 +1,78 @@
+//! The `no_std` detector should agree with `PitchDetector` running the same algorithm.
+
+use monophonic_detector::{
+    cents_between,
+    embedded::{Chunk, Detector, Peaks},
+    generate::{Generator, Tones},
+    PitchDetector,
+};
+
+const FREQS: &[f64] = &[110.0, 196.0, 261.63, 440.0, 659.26];
+
+const CHUNK_SIZE: usize = 4410;
+
+#[test]
+fn embedded_matches_pitch_detector() {
+    let detector = PitchDetector::default()
+        .with_min_freq(100)
+        .with_octave_correction(false);
+    let embedded = Detector::new(44100).with_min_freq(100.0);
+
+    for &freq in FREQS {
+        let samples = Generator::new(Tones::Steps(vec![freq]))
+            .with_duration(0.5)
+            .with_harmonics(vec![1.0, 0.5])
+            .samples();
+
+        let mut chunk = Chunk::<CHUNK_SIZE>::new();
+        let mut chunks = detector.chunks(&samples);
+        for &sample in &samples {
+            if let Some(samples) = chunk.push(sample) {
+                let expected = detector.detect(chunks.next().unwrap());
+                let detected = embedded.detect(samples).unwrap();
+                let tuning = embedded.tune(detected);
+
+                let cents = cents_between(expected.freq.unwrap(), detected as f64);
+                assert!(
+                    cents.abs() < 1.0,
+                    "{} Hz detected as {} Hz, not {:?}",
+                    freq,
+                    detected,
+                    expected.freq
+                );
+                if let Some(pitch) = expected.pitch {
+                    assert_eq!(tuning.pitch, pitch);
+                }
+                let cents = cents_between(tuning.pitch.freq(440.0), detected as f64);
+                assert!((tuning.cents as f64 - cents).abs() < 0.01);
+            }
+        }
+    }
+}
+
+/// An autocorrelation with a peak every 20 lags, up to lag 200, changed by `change`.
+fn peaks_of(change: impl Fn(usize, f64) -> f64) -> Peaks {
+    let mut peaks = Peaks::new(1);
+    for lag in 1..=210 {
+        let acf = (2.0 * std::f64::consts::PI * lag as f64 / 20.0).cos();
+        peaks.push(change(lag, acf));
+    }
+    peaks
+}
+
+#[test]
+fn spurious_maxima_hardly_count() {
+    // A bump on the slope down from the peak at 40.
+    let peaks = peaks_of(|lag, acf| if lag == 45 { 0.5 } else { acf });
+    assert_eq!(peaks.count(), 12);
+    let period = peaks.period().unwrap();
+    assert!((period - 20.0).abs() < 0.5, "{}", period);
+}
+
+#[test]
+fn spacings_across_a_missed_maximum_dont_count() {
+    let peaks = peaks_of(|lag, acf| if (51..70).contains(&lag) { -1.0 } else { acf });
+    assert_eq!(peaks.count(), 10);
+    let period = peaks.period().unwrap();
+    assert!((period - 20.0).abs() < 1e-9, "{}", period);
+}
@@ -0,0 +1,43 @@
+//! Scoring against reference annotations should follow the standard melody-extraction metrics.
+
+use monophonic_detector::evaluate::{accuracy, parse_reference};
+
+#[test]
+fn parses_annotations() {
+    let text = "# a comment\ntime,freq\n0.00,220.0\n0.01 0\n\n0.02\t-440 # unvoiced\n";
+    assert_eq!(
+        parse_reference(text).unwrap(),
+        vec![(0.0, Some(220.0)), (0.01, None), (0.02, None)]
+    );
+    assert_eq!(
+        parse_reference("0.0,220\n0.01,x").unwrap_err(),
+        "line 2: expected a time and a frequency"
+    );
+}
+
+#[test]
+fn scores_pitch_chroma_and_voicing() {
+    // Ten frames a chunk: voiced at 220 Hz, then at 440 Hz, then unvoiced.
+    let reference: Vec<_> = (0..30)
+        .map(|i| {
+            let freq = [Some(220.0), Some(440.0), None][i / 10];
+            (i as f64 * 0.1, freq)
+        })
+        .collect();
+    // Right, an octave low, and a false alarm.
+    let estimates = [Some(221.0), Some(220.0), Some(300.0)];
+    let scored = accuracy(&reference, &estimates, 1.0);
+
+    assert_eq!((scored.frames, scored.voiced), (30, 20));
+    assert_eq!(scored.raw_pitch, 0.5);
+    assert_eq!(scored.raw_chroma, 1.0);
+    assert_eq!(scored.voicing_recall, 1.0);
+    assert_eq!(scored.voicing_precision, 20.0 / 30.0);
+    assert_eq!(scored.voicing_false_alarm, 1.0);
+    assert_eq!(scored.overall, 10.0 / 30.0);
+
+    let unvoiced = accuracy(&reference, &[None, None, None], 1.0);
+    assert_eq!(unvoiced.voicing_recall, 0.0);
+    assert_eq!(unvoiced.voicing_precision, 0.0);
+    assert_eq!(unvoiced.overall, 10.0 / 30.0);
+}
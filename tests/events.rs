@@ -0,0 +1,94 @@
+//! Note events should start, bend and stop with the note the estimates play.
+
+use monophonic_detector::{
+    events::{Event, EventTracker},
+    generate::{Generator, Tones},
+    live::LiveInput,
+    DetectedPitch, Pitch, PitchDetector,
+};
+
+fn events(estimates: &[DetectedPitch]) -> Vec<Event> {
+    let mut tracker = EventTracker::new();
+    let mut events = vec![];
+    for (i, detected) in estimates.iter().enumerate() {
+        tracker.push(i as f64, detected, -20.0, |event| events.push(event));
+    }
+    tracker.finish(|event| events.push(event));
+    events
+}
+
+#[test]
+fn starts_bends_and_stops_notes() {
+    let detector = PitchDetector::default();
+    let a4 = "A4".parse::<Pitch>().unwrap();
+    let b4 = "B4".parse::<Pitch>().unwrap();
+    let events = events(&[
+        detector.identify(440.0),
+        detector.identify(441.0),
+        detector.identify(446.0),
+        detector.identify(493.88),
+        DetectedPitch {
+            freq: None,
+            pitch: None,
+            cents: 0.0,
+            confidence: 0.0,
+            rest: true,
+            unvoiced: false,
+        },
+    ]);
+    assert_eq!(events.len(), 5, "{:?}", events);
+    assert!(
+        matches!(events[0], Event::NoteOn { time, pitch, velocity: 85, .. }
+        if time == 0.0 && pitch == a4)
+    );
+    assert!(matches!(events[1], Event::PitchChanged { time, freq, .. }
+        if time == 2.0 && freq == 446.0));
+    assert_eq!(
+        events[2],
+        Event::NoteOff {
+            time: 3.0,
+            pitch: a4
+        }
+    );
+    assert!(matches!(events[3], Event::NoteOn { pitch, .. } if pitch == b4));
+    assert_eq!(
+        events[4],
+        Event::NoteOff {
+            time: 4.0,
+            pitch: b4
+        }
+    );
+}
+
+#[test]
+fn stops_the_last_note_at_the_end() {
+    let detector = PitchDetector::default();
+    let events = events(&[detector.identify(440.0), detector.identify(440.0)]);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1].time(), 1.0);
+    assert_eq!(events[1].pitch(), events[0].pitch());
+}
+
+#[test]
+fn reports_the_notes_of_a_live_stream() {
+    let pcm: Vec<u8> = Generator::new(Tones::Steps(vec![220.0, 329.63]))
+        .with_duration(2.0)
+        .samples()
+        .iter()
+        .flat_map(|s| ((s * 32767.0) as i16).to_le_bytes())
+        .collect();
+    let mut live = LiveInput::new(&pcm[..], PitchDetector::default());
+    let mut events = vec![];
+    live.on_note_event(|event| events.push(event)).unwrap();
+
+    let names: Vec<_> = events
+        .iter()
+        .map(|event| match event {
+            Event::NoteOn { pitch, .. } => format!("on {}", pitch),
+            Event::NoteOff { pitch, .. } => format!("off {}", pitch),
+            Event::PitchChanged { .. } => "bend".to_string(),
+        })
+        .filter(|name| name != "bend")
+        .collect();
+    assert_eq!(names, ["on A3", "off A3", "on E4", "off E4"]);
+}
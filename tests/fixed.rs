@@ -0,0 +1,77 @@
+//! The fixed-point detector should agree with the `f32` one on the same Q15 samples.
+
+use monophonic_detector::{
+    cents_between,
+    embedded::{self, Chunk},
+    fixed::{self, Detector},
+    generate::{Generator, Tones},
+};
+
+const FREQS: &[f64] = &[110.0, 196.0, 261.63, 440.0, 659.26, 1318.5];
+
+const CHUNK_SIZE: usize = 4410;
+
+/// A Q16.16 number as a float.
+fn float(q16: i64) -> f64 {
+    q16 as f64 / 65536.0
+}
+
+#[test]
+fn fixed_matches_float() {
+    let float_detector = embedded::Detector::new(44100).with_min_freq(100.0);
+    let detector = Detector::new(44100).with_min_freq(100);
+
+    for &freq in FREQS {
+        let samples: Vec<i16> = Generator::new(Tones::Steps(vec![freq]))
+            .with_duration(0.3)
+            .with_harmonics(vec![1.0, 0.5])
+            .samples()
+            .iter()
+            .map(|s| (s * 32767.0).round() as i16)
+            .collect();
+
+        let mut chunk = Chunk::<CHUNK_SIZE, i16>::with_hop(CHUNK_SIZE / 2);
+        for &sample in &samples {
+            if let Some(chunk) = chunk.push(sample) {
+                let floats: Vec<f32> = chunk.iter().map(|&s| s as f32 / 32768.0).collect();
+                let expected = float_detector.detect(&floats).unwrap();
+                let detected = float(detector.detect(chunk).unwrap() as i64);
+
+                let cents = cents_between(expected as f64, detected);
+                assert!(
+                    cents.abs() < 0.1,
+                    "{} Hz detected as {} Hz, not {} Hz",
+                    freq,
+                    detected,
+                    expected
+                );
+                let tuning = detector.tune(detector.detect(chunk).unwrap());
+                let expected_tuning = float_detector.tune(expected);
+                assert_eq!(tuning.pitch, expected_tuning.pitch);
+                assert!((float(tuning.cents as i64) - expected_tuning.cents as f64).abs() < 0.2);
+            }
+        }
+    }
+}
+
+#[test]
+fn finds_nothing_in_silence() {
+    let detector = Detector::new(44100);
+    assert_eq!(detector.detect(&[0; 2048]), None);
+    assert_eq!(detector.detect(&[10; 2048]), None);
+    assert_eq!(detector.detect(&[]), None);
+}
+
+#[test]
+fn log2_is_within_a_bit() {
+    for &x in &[1, 1000, 65536, 65537, 440 << 16, 12_345_678, u32::MAX] {
+        let expected = (x as f64 / 65536.0).log2();
+        let log = float(fixed::log2(x) as i64);
+        assert!(
+            (log - expected).abs() <= 2.0 / 65536.0,
+            "log2 {}: {}",
+            x,
+            log
+        );
+    }
+}
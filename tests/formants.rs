@@ -0,0 +1,78 @@
+//! Vocal tones whose formants boost an upper harmonic should be tracked at the fundamental when
+//! the detector is formant-robust.
+
+use monophonic_detector::{
+    cents_between, formants,
+    generate::{Generator, Noise, Tones},
+    PitchDetector,
+};
+
+const FREQS: &[f64] = &[
+    105.0, 110.0, 130.0, 150.0, 180.0, 220.0, 260.0, 330.0, 400.0, 500.0,
+];
+
+/// Series with a formant on the 3rd to 5th harmonic, stronger than the fundamental.
+const FORMANT_SERIES: &[&[f64]] = &[
+    &[1.0, 0.8, 1.2, 0.9, 0.5, 0.3],
+    &[0.3, 0.5, 1.0, 1.0, 0.4, 0.2],
+    &[0.2, 0.3, 0.6, 1.0, 1.0, 0.5, 0.3, 0.2],
+];
+
+#[test]
+fn tracks_the_fundamental() {
+    let detector = PitchDetector::default()
+        .with_min_freq(100)
+        .with_formant_robustness(true);
+
+    for &freq in FREQS {
+        for &harmonics in FORMANT_SERIES {
+            let samples = Generator::new(Tones::Steps(vec![freq]))
+                .with_duration(0.5)
+                .with_harmonics(harmonics.to_vec())
+                .with_noise(Noise::White, 25.0)
+                .samples();
+
+            for chunk in detector.chunks(&samples) {
+                let detected = detector.detect(chunk).freq.unwrap();
+                let cents = cents_between(freq, detected);
+                assert!(
+                    cents.abs() < 50.0,
+                    "{} Hz with harmonics {:?} detected as {} Hz ({:+.0}¢)",
+                    freq,
+                    harmonics,
+                    detected,
+                    cents,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn still_detects_pure_tones() {
+    let detector = PitchDetector::default().with_formant_robustness(true);
+    for &freq in &[82.41, 196.0, 440.0, 880.0] {
+        let samples = Generator::new(Tones::Steps(vec![freq]))
+            .with_duration(0.5)
+            .samples();
+        for chunk in detector.chunks(&samples) {
+            let detected = detector.detect(chunk).freq.unwrap();
+            assert!(
+                cents_between(freq, detected).abs() < 10.0,
+                "{} Hz: {}",
+                freq,
+                detected
+            );
+        }
+    }
+}
+
+#[test]
+fn center_clips() {
+    let mut buffer = [0.5, 1.0, 0.2, -0.1, -0.4, -1.0];
+    formants::center_clip(&mut buffer, 0.3);
+    let expected = [0.2, 0.7, 0.0, 0.0, -0.1, -0.7];
+    for (clipped, expected) in buffer.iter().zip(&expected) {
+        assert!((clipped - expected).abs() < 1e-6, "{:?}", buffer);
+    }
+}
@@ -0,0 +1,62 @@
+//! Batches should be detected on a GPU as they are on the CPU, and on the CPU where there's no GPU.
+//! Without OpenCL and a GPU, only the fallback is tested.
+
+#![cfg(feature = "gpu")]
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    gpu::{self, Gpu},
+    simd, PitchDetector,
+};
+
+fn samples() -> Vec<f32> {
+    Generator::new(Tones::Steps(vec![98.0, 440.0, 1046.5]))
+        .with_duration(1.5)
+        .samples()
+}
+
+#[test]
+fn falls_back_to_the_cpu() {
+    let detector = PitchDetector::default();
+    let samples = samples();
+    let buffers: Vec<&[f32]> = detector.chunks(&samples).collect();
+    assert_eq!(
+        gpu::detect_parallel(&detector, None, &buffers, 2),
+        detector.detect_parallel_with_inharmonicity(&buffers, 2)
+    );
+}
+
+#[test]
+fn autocorrelates_as_the_cpu_does() {
+    let gpu = match Gpu::shared() {
+        Some(gpu) => gpu,
+        None => return,
+    };
+    let detector = PitchDetector::default();
+    let samples = samples();
+    let buffers: Vec<&[f32]> = detector.chunks(&samples).collect();
+    let lags = detector.monotonic_lags(detector.chunk_size());
+    let acfs = gpu.autocorrelations(&buffers, lags).unwrap();
+    for (buffer, acf) in buffers.iter().zip(&acfs) {
+        let energy = simd::dot(buffer, buffer);
+        for (i, sum) in acf.iter().enumerate() {
+            let expected = simd::dot(buffer, &buffer[i + 1..]);
+            assert!(
+                (sum - expected).abs() <= 1e-4 * energy,
+                "{}: {}",
+                gpu.name(),
+                i + 1
+            );
+        }
+    }
+
+    let on_gpu = gpu::detect_parallel(&detector, Some(gpu), &buffers, 2);
+    let on_cpu = detector.detect_parallel_with_inharmonicity(&buffers, 2);
+    for ((gpu, _), (cpu, _)) in on_gpu.iter().zip(&on_cpu) {
+        assert_eq!(gpu.pitch, cpu.pitch);
+        if let (Some(a), Some(b)) = (gpu.freq, cpu.freq) {
+            assert!(cents_between(a, b).abs() < 0.1);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Harmonic-rich tones shouldn't be mistaken for their overtones.
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    Algorithm, PitchDetector,
+};
+
+const FREQS: &[f64] = &[110.0, 196.0, 261.63, 440.0, 659.26];
+
+/// Series with a strong 2nd harmonic, like many plucked and bowed strings and voices.
+const OCTAVE_SERIES: &[&[f64]] = &[
+    &[1.0, 1.0],
+    &[0.5, 1.0],
+    &[0.3, 1.0, 0.5],
+    &[0.2, 1.0, 0.3, 0.3],
+];
+
+/// A series with several strong partials.
+const BRIGHT_SERIES: &[&[f64]] = &[&[1.0, 0.8, 0.6, 0.4, 0.2]];
+
+/// Check that every chunk of each tone in `FREQS` played with each of `series` is detected within
+/// 50 cents of the fundamental.
+fn check_fundamentals(algorithm: Algorithm, series: &[&[f64]]) {
+    // Short chunks keep the test fast; they still hold 10 periods of the lowest tone.
+    let detector = PitchDetector::default()
+        .with_min_freq(100)
+        .with_algorithm(algorithm);
+
+    for &freq in FREQS {
+        for &harmonics in series {
+            let samples = Generator::new(Tones::Steps(vec![freq]))
+                .with_duration(0.5)
+                .with_harmonics(harmonics.to_vec())
+                .samples();
+
+            for chunk in detector.chunks(&samples) {
+                let detected = detector.detect(chunk).freq.unwrap();
+                let cents = cents_between(freq, detected);
+                assert!(
+                    cents.abs() < 50.0,
+                    "{} Hz with harmonics {:?} detected as {} Hz ({:+.0}¢)",
+                    freq,
+                    harmonics,
+                    detected,
+                    cents,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn monotonic_finds_fundamental() {
+    check_fundamentals(Algorithm::Monotonic, OCTAVE_SERIES);
+}
+
+#[test]
+fn monotonic_finds_fundamental_of_bright_tones() {
+    check_fundamentals(Algorithm::Monotonic, BRIGHT_SERIES);
+}
+
+#[test]
+fn yin_finds_fundamental() {
+    check_fundamentals(Algorithm::Yin, OCTAVE_SERIES);
+    check_fundamentals(Algorithm::Yin, BRIGHT_SERIES);
+}
+
+#[test]
+fn mpm_finds_fundamental() {
+    check_fundamentals(Algorithm::Mpm, OCTAVE_SERIES);
+    check_fundamentals(Algorithm::Mpm, BRIGHT_SERIES);
+}
@@ -0,0 +1,155 @@
+//! Piano mode should fit the stretched partials of a stiff string and report the fundamental as it
+//! sounds, as well as the idealized one.
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    inharmonicity,
+    instrument::Instrument,
+    output::{Columns, Format, Record, RecordWriter},
+    PitchDetector,
+};
+
+/// A0, C4 and C7, with inharmonicity coefficients typical of their strings.
+const STRINGS: &[(f64, f64)] = &[(27.5, 0.0004), (261.63, 0.0004), (2093.0, 0.01)];
+
+const PIANO_SERIES: &[f64] = &[
+    0.6, 1.0, 0.8, 0.6, 0.5, 0.4, 0.3, 0.25, 0.2, 0.15, 0.1, 0.08,
+];
+
+fn string(f0: f64, b: f64) -> Vec<f32> {
+    Generator::new(Tones::Steps(vec![f0]))
+        .with_duration(0.5)
+        .with_harmonics(PIANO_SERIES.to_vec())
+        .with_inharmonicity(b)
+        .samples()
+}
+
+fn piano() -> PitchDetector {
+    let (min_freq, max_freq) = Instrument::Piano.range();
+    PitchDetector::default()
+        .with_min_freq(min_freq)
+        .with_max_freq(max_freq)
+        .with_inharmonicity(true)
+}
+
+#[test]
+fn fits_the_coefficient_and_ideal_fundamental() {
+    for &(f0, b) in STRINGS {
+        let samples = string(f0, b);
+        // Started from an estimate a fifth off, as one assuming harmonics can be.
+        let fit = inharmonicity::fit(&samples, 44100, 26.0, 1.5 * f0).unwrap();
+        assert!(
+            (fit.coefficient - b).abs() < 0.02 * b,
+            "{} Hz: {:?}",
+            f0,
+            fit
+        );
+        assert!(
+            cents_between(f0, fit.ideal_freq).abs() < 0.5,
+            "{} Hz: {:?}",
+            f0,
+            fit
+        );
+        assert!(fit.partials >= inharmonicity::MIN_PARTIALS);
+    }
+}
+
+#[test]
+fn estimates_the_fundamental_as_it_sounds() {
+    let detector = piano();
+    for &(f0, b) in STRINGS {
+        let samples = string(f0, b);
+        let fundamental = f0 * (1.0 + b).sqrt();
+        for chunk in detector.chunks(&samples) {
+            let detected = detector.detect(chunk).freq.unwrap();
+            let cents = cents_between(fundamental, detected);
+            assert!(
+                cents.abs() < 1.0,
+                "{} Hz detected as {} Hz",
+                fundamental,
+                detected
+            );
+        }
+    }
+}
+
+#[test]
+fn harmonics_fit_no_inharmonicity() {
+    let samples = string(110.0, 0.0);
+    let fit = inharmonicity::fit(&samples, 44100, 26.0, 110.0).unwrap();
+    assert!(fit.coefficient < 1e-6, "{:?}", fit);
+    // A pure tone has no partials to fit, and keeps its estimate.
+    let sine = Generator::new(Tones::Steps(vec![440.0])).samples();
+    assert_eq!(inharmonicity::fit(&sine, 44100, 26.0, 440.0), None);
+    let detected = piano().detect(&sine[..piano().chunk_size()]).freq.unwrap();
+    assert!(cents_between(440.0, detected).abs() < 1.0);
+}
+
+#[test]
+fn writes_the_ideal_pitch() {
+    let detector = piano();
+    let samples = string(261.63, 0.0004);
+    let chunk = detector.chunks(&samples).next().unwrap();
+    let (detected, inharmonicity) = detector.detect_with_inharmonicity(chunk);
+    let record = Record {
+        timestamp: 0.0,
+        channel: None,
+        voice: None,
+        detected,
+        level: None,
+        inharmonicity,
+    };
+    let mut out = RecordWriter::with_inharmonicity(vec![], Format::Csv).unwrap();
+    out.write(&record).unwrap();
+    let csv = String::from_utf8(out.finish().unwrap()).unwrap();
+    let lines: Vec<_> = csv.lines().collect();
+    assert!(lines[0].ends_with(",inharmonicity,ideal_freq_hz,ideal_note,ideal_octave,ideal_cents"));
+    let fields: Vec<_> = lines[1].split(',').collect();
+    assert_eq!(fields[3..5], ["C", "4"]);
+    assert_eq!(fields[9..11], ["C", "4"]);
+    let ideal: f64 = fields[8].parse().unwrap();
+    assert!(cents_between(261.63, ideal).abs() < 0.5);
+}
+
+#[test]
+fn fits_short_chunks_without_reading_past_the_spectrum() {
+    // From piano's lowest note, the range each partial is looked for in is narrower than a bin.
+    let samples = string(27.5, 0.0004);
+    for len in 1..=256 {
+        for &freq in &[27.5, 440.0, 4186.0, 20000.0] {
+            let fit = inharmonicity::fit(&samples[..len], 44100, 26.0, freq);
+            assert!(fit.is_none_or(|fit| fit.partials >= inharmonicity::MIN_PARTIALS));
+        }
+    }
+    let detector = piano().with_chunk_size(64);
+    for chunk in detector.chunks(&samples) {
+        detector.detect_with_inharmonicity(chunk);
+    }
+}
+
+#[test]
+fn leaves_estimates_too_unsure_to_name_unfit() {
+    let detector = piano().with_min_confidence(1.1);
+    let samples = string(261.63, 0.0004);
+    let chunk = detector.chunks(&samples).next().unwrap();
+    let (detected, inharmonicity) = detector.detect_with_inharmonicity(chunk);
+    assert!(detected.freq.is_some() && detected.pitch.is_none());
+    assert_eq!(inharmonicity, None);
+}
+
+#[test]
+fn writes_levels_and_inharmonicity_together() {
+    let columns = Columns {
+        levels: true,
+        inharmonicity: true,
+        ..Columns::default()
+    };
+    let out = RecordWriter::with_columns(vec![], Format::Csv, columns).unwrap();
+    let csv = String::from_utf8(out.finish().unwrap()).unwrap();
+    assert!(csv.starts_with(
+        "timestamp_s,channel,freq_hz,note,octave,cents,confidence,\
+         rms_dbfs,peak_dbfs,a_weighted_dbfs,\
+         inharmonicity,ideal_freq_hz,ideal_note,ideal_octave,ideal_cents\n"
+    ));
+}
@@ -0,0 +1,44 @@
+//! Instrument presets should be named as they're written, and cover their instruments' notes.
+
+use monophonic_detector::instrument::{Instrument, INSTRUMENTS};
+use monophonic_detector::Pitch;
+
+#[test]
+fn parses_every_instrument_by_name() {
+    for instrument in INSTRUMENTS.iter() {
+        assert_eq!(instrument.to_string().parse(), Ok(*instrument));
+    }
+    assert_eq!(
+        "banjo".parse::<Instrument>(),
+        Err("unknown instrument: banjo".to_string())
+    );
+}
+
+#[test]
+fn covers_the_lowest_and_highest_notes() {
+    for (instrument, low, high) in [
+        (Instrument::Guitar, "E2", "E6"),
+        (Instrument::Bass, "E1", "G4"),
+        (Instrument::Violin, "G3", "A7"),
+        (Instrument::Piano, "A0", "C8"),
+    ]
+    .iter()
+    {
+        let (min, max) = instrument.range();
+        let freq = |pitch: &str| pitch.parse::<Pitch>().unwrap().freq(440.0);
+        assert!((min as f64) < freq(low), "{}", instrument);
+        assert!((max as f64) > freq(high), "{}", instrument);
+    }
+}
+
+#[test]
+fn only_the_piano_is_inharmonic() {
+    for instrument in INSTRUMENTS.iter() {
+        assert_eq!(
+            instrument.is_inharmonic(),
+            *instrument == Instrument::Piano,
+            "{}",
+            instrument
+        );
+    }
+}
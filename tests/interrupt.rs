@@ -0,0 +1,19 @@
+//! Ctrl-C, once caught, should only mark the process as interrupted.
+
+#![cfg(unix)]
+
+use std::os::raw::c_int;
+
+use monophonic_detector::interrupt;
+
+extern "C" {
+    fn raise(signum: c_int) -> c_int;
+}
+
+#[test]
+fn marks_the_process_as_interrupted() {
+    interrupt::catch();
+    assert!(!interrupt::interrupted());
+    assert_eq!(unsafe { raise(2) }, 0);
+    assert!(interrupt::interrupted());
+}
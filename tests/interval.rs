@@ -0,0 +1,63 @@
+//! Intervals between notes should be named by size and direction.
+
+use monophonic_detector::{
+    interval::{melodic_intervals, Interval},
+    notes::NoteEvent,
+    Note, Pitch,
+};
+
+fn pitch(s: &str) -> Pitch {
+    let (note, octave) = s.split_at(s.len() - 1);
+    let note = Note::ALL
+        .iter()
+        .copied()
+        .find(|n| n.to_string() == note)
+        .unwrap();
+    Pitch {
+        note,
+        octave: octave.parse().unwrap(),
+    }
+}
+
+fn interval(from: &str, to: &str) -> Interval {
+    Interval::between(pitch(from), pitch(to))
+}
+
+#[test]
+fn names_intervals() {
+    for &(from, to, name, short) in &[
+        ("C4", "C4", "unison", "P1"),
+        ("C4", "E4", "major third up", "M3"),
+        ("A4", "C5", "minor third up", "m3"),
+        ("G4", "C4", "perfect fifth down", "P5"),
+        ("F4", "B4", "tritone up", "TT"),
+        ("C5", "C4", "octave down", "P8"),
+        ("C4", "E5", "major tenth up", "M10"),
+        ("C6", "C4", "two octaves down", "P15"),
+        ("C2", "D4", "26 semitones up", "26st"),
+    ] {
+        let interval = interval(from, to);
+        assert_eq!(interval.to_string(), name, "{} to {}", from, to);
+        assert_eq!(interval.short_name(), short, "{} to {}", from, to);
+    }
+}
+
+#[test]
+fn follows_the_melody() {
+    let notes: Vec<NoteEvent> = ["C4", "G4", "E4", "E4"]
+        .iter()
+        .enumerate()
+        .map(|(i, p)| NoteEvent {
+            pitch: pitch(p),
+            start: i as f64,
+            // A rest before the last note.
+            end: i as f64 + if i == 2 { 0.5 } else { 1.0 },
+            velocity: 100,
+        })
+        .collect();
+    let intervals = melodic_intervals(&notes);
+    let semitones: Vec<i32> = intervals.iter().map(|i| i.interval.semitones).collect();
+    assert_eq!(semitones, [7, -3, 0]);
+    assert_eq!(intervals[1].from, pitch("G4"));
+    assert_eq!(intervals[2].time, 3.0);
+}
@@ -0,0 +1,211 @@
+//! Results should read back from JSON as they were written, including the records `analyze`
+//! writes, and malformed JSON should be turned away.
+
+#![cfg(feature = "json")]
+
+use monophonic_detector::{
+    events::Event,
+    json::{self, FromJson, ToJson, Value},
+    level::Level,
+    notes::NoteEvent,
+    output::{Format, NoteWriter, Record, RecordWriter},
+    summary::Summary,
+    vibrato::Vibrato,
+    DetectedPitch, Pitch, PitchDetector,
+};
+
+fn round_trip<T: ToJson + FromJson>(value: &T) -> T {
+    let text = value.to_json().to_string();
+    T::from_json(&json::parse(&text).unwrap()).unwrap()
+}
+
+#[test]
+fn reads_back_what_it_writes() {
+    let detector = PitchDetector::default();
+    let detected = detector.identify(446.3);
+    assert_eq!(round_trip(&detected), detected);
+    let rest = DetectedPitch {
+        freq: None,
+        pitch: None,
+        cents: 0.0,
+        confidence: 0.0,
+        rest: true,
+        unvoiced: false,
+    };
+    assert_eq!(round_trip(&rest), rest);
+
+    let pitch: Pitch = "F#3".parse().unwrap();
+    assert_eq!(round_trip(&pitch), pitch);
+    let note = NoteEvent {
+        pitch,
+        start: 0.25,
+        end: 1.5,
+        velocity: 90,
+    };
+    assert_eq!(round_trip(&note), note);
+    for event in &[
+        Event::NoteOn {
+            time: 0.1,
+            pitch,
+            freq: 185.0,
+            velocity: 64,
+        },
+        Event::PitchChanged {
+            time: 0.2,
+            pitch,
+            freq: 186.2,
+            cents: 11.5,
+        },
+        Event::NoteOff { time: 0.3, pitch },
+    ] {
+        assert_eq!(round_trip(event), *event);
+    }
+}
+
+#[test]
+fn reads_the_records_analyze_writes() {
+    let detected = PitchDetector::default().identify(220.5);
+    let record = Record {
+        timestamp: 1.5,
+        channel: None,
+        voice: None,
+        detected,
+        level: None,
+        inharmonicity: None,
+    };
+    let value = json::parse(&record.to_json().to_string()).unwrap();
+    assert_eq!(value.get("timestamp_s").and_then(Value::as_f64), Some(1.5));
+    assert_eq!(DetectedPitch::from_json(&value).unwrap(), detected);
+}
+
+#[test]
+fn reads_rests_back_from_the_records_writers_write() {
+    let rest = DetectedPitch {
+        freq: None,
+        pitch: None,
+        cents: 0.0,
+        confidence: 0.0,
+        rest: true,
+        unvoiced: false,
+    };
+    let record = Record {
+        timestamp: 0.5,
+        channel: None,
+        voice: None,
+        detected: rest,
+        level: Some(Level {
+            rms: -70.0,
+            peak: f64::NEG_INFINITY,
+            a_weighted: None,
+        }),
+        inharmonicity: None,
+    };
+    let mut out = RecordWriter::with_levels(vec![], Format::Json).unwrap();
+    out.write(&record).unwrap();
+    let text = String::from_utf8(out.finish().unwrap()).unwrap();
+
+    let values = json::parse(&text).unwrap();
+    let value = &values.as_array().unwrap()[0];
+    assert_eq!(value, &record.to_json());
+    assert_eq!(DetectedPitch::from_json(value).unwrap(), rest);
+    assert_eq!(value.get("rms_dbfs").and_then(Value::as_f64), Some(-70.0));
+    assert_eq!(value.get("peak_dbfs"), Some(&Value::Null));
+}
+
+#[test]
+fn reads_the_notes_transcribe_writes() {
+    let notes = [
+        NoteEvent {
+            pitch: "A4".parse().unwrap(),
+            start: 0.0,
+            end: 0.75,
+            velocity: 100,
+        },
+        NoteEvent {
+            pitch: "C#5".parse().unwrap(),
+            start: 0.75,
+            end: 2.0,
+            velocity: 64,
+        },
+    ];
+    let mut out = NoteWriter::with_vibrato(vec![], Format::Json).unwrap();
+    out.write(&notes[0]).unwrap();
+    let vibrato = Vibrato {
+        rate: 5.5,
+        depth: 30.0,
+    };
+    out.write_with_vibrato(&notes[1], Some(vibrato)).unwrap();
+    let text = String::from_utf8(out.finish().unwrap()).unwrap();
+
+    let values = json::parse(&text).unwrap();
+    let values = values.as_array().unwrap();
+    let read: Vec<_> = values
+        .iter()
+        .map(|value| NoteEvent::from_json(value).unwrap())
+        .collect();
+    assert_eq!(read, notes);
+    assert_eq!(values[0].get("vibrato_hz"), Some(&Value::Null));
+    assert_eq!(
+        values[1].get("vibrato_hz").and_then(Value::as_f64),
+        Some(5.5)
+    );
+}
+
+#[test]
+fn writes_summaries_with_their_notes() {
+    let detector = PitchDetector::default();
+    let mut summary = Summary::new();
+    summary.push(&detector.identify(440.0), 1.0);
+    summary.push(&detector.identify(494.0), 3.0);
+    let value = json::parse(&summary.to_json().to_string()).unwrap();
+    assert_eq!(value.get("duration_s").and_then(Value::as_f64), Some(4.0));
+    let notes = value.get("notes").and_then(Value::as_array).unwrap();
+    assert_eq!(notes.len(), 2);
+    let first = FromJson::from_json(&notes[0]).unwrap();
+    assert_eq!(summary.notes()[0], first);
+}
+
+#[test]
+fn parses_json() {
+    let value =
+        json::parse(r#" {"a": [1, -2.5e3, true, null], "b": "\"\u00e9\ud83c\udfb5\n"} "#).unwrap();
+    assert_eq!(
+        value,
+        Value::Object(vec![
+            (
+                "a".to_string(),
+                Value::Array(vec![
+                    Value::Number(1.0),
+                    Value::Number(-2500.0),
+                    Value::Bool(true),
+                    Value::Null,
+                ])
+            ),
+            ("b".to_string(), Value::String("\"é🎵\n".to_string())),
+        ])
+    );
+    assert_eq!(json::parse(&value.to_string()).unwrap(), value);
+}
+
+#[test]
+fn rejects_malformed_json() {
+    for text in &[
+        "",
+        "{",
+        "[1,]",
+        "{\"a\" 1}",
+        "1.",
+        ".5",
+        "\"\\x\"",
+        "\"\\ud83c\"",
+        "tru",
+        "[1] 2",
+    ] {
+        assert!(json::parse(text).is_err(), "{:?}", text);
+    }
+    let missing = json::parse("{\"note\": \"A\"}").unwrap();
+    assert_eq!(
+        Pitch::from_json(&missing).unwrap_err(),
+        "missing field: octave"
+    );
+}
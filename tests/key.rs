@@ -0,0 +1,134 @@
+//! Key estimation should find the keys of simple melodies, and name them as they're usually
+//! written.
+
+use monophonic_detector::{key, notes::NoteEvent, score::Key, Note, Pitch};
+
+/// A melody of `notes`, each a half second long with the durations in `lengths` (in half
+/// seconds) cycled over them.
+fn melody(notes: &[(Note, i8)], lengths: &[f64]) -> Vec<NoteEvent> {
+    let mut start = 0.0;
+    notes
+        .iter()
+        .zip(lengths.iter().cycle())
+        .map(|(&(note, octave), length)| {
+            let end = start + 0.5 * length;
+            let event = NoteEvent {
+                pitch: Pitch { note, octave },
+                start,
+                end,
+                velocity: 100,
+            };
+            start = end;
+            event
+        })
+        .collect()
+}
+
+fn key(s: &str) -> Key {
+    s.parse().unwrap()
+}
+
+#[test]
+fn estimates_keys() {
+    use Note::*;
+
+    // Up and down the C major scale, ending on a long tonic.
+    let c_major = melody(
+        &[
+            (C, 4),
+            (D, 4),
+            (E, 4),
+            (F, 4),
+            (G, 4),
+            (A, 4),
+            (B, 4),
+            (C, 5),
+            (G, 4),
+            (E, 4),
+            (C, 4),
+        ],
+        &[1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 4.0],
+    );
+    assert_eq!(key::estimate_key(&c_major).unwrap().key, key("C"));
+
+    // An A harmonic minor melody, leaning on the tonic and its leading note.
+    let a_minor = melody(
+        &[
+            (A, 3),
+            (C, 4),
+            (E, 4),
+            (GSharp, 4),
+            (A, 4),
+            (E, 4),
+            (D, 4),
+            (C, 4),
+            (B, 3),
+            (GSharp, 3),
+            (A, 3),
+        ],
+        &[2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 1.0, 1.0, 1.0, 4.0],
+    );
+    assert_eq!(key::estimate_key(&a_minor).unwrap().key, key("Am"));
+
+    // A G major arpeggio with its passing notes.
+    let g_major = melody(
+        &[
+            (G, 3),
+            (B, 3),
+            (D, 4),
+            (FSharp, 4),
+            (G, 4),
+            (D, 4),
+            (B, 3),
+            (A, 3),
+            (G, 3),
+        ],
+        &[2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 1.0, 4.0],
+    );
+    assert_eq!(key::estimate_key(&g_major).unwrap().key, key("G"));
+
+    // Eb major, spelled with flats.
+    let e_flat_major = melody(
+        &[
+            (DSharp, 4),
+            (F, 4),
+            (G, 4),
+            (GSharp, 4),
+            (ASharp, 4),
+            (C, 5),
+            (D, 5),
+            (DSharp, 5),
+            (ASharp, 4),
+            (DSharp, 4),
+        ],
+        &[2.0, 1.0, 1.0, 1.0, 2.0, 1.0, 1.0, 2.0, 1.0, 4.0],
+    );
+    assert_eq!(key::estimate_key(&e_flat_major).unwrap().key, key("Eb"));
+}
+
+#[test]
+fn ranks_every_key() {
+    let notes = melody(&[(Note::C, 4), (Note::E, 4), (Note::G, 4)], &[1.0]);
+    let ranked = key::rank_keys(&notes);
+    assert_eq!(ranked.len(), 24);
+    assert!(ranked
+        .windows(2)
+        .all(|w| w[0].correlation >= w[1].correlation));
+    assert!(key::rank_keys(&[]).is_empty());
+}
+
+#[test]
+fn spells_tonics() {
+    for &(semitone, minor, name) in &[
+        (0, false, "C"),
+        (1, false, "Db"),
+        (6, false, "F#"),
+        (10, false, "Bb"),
+        (1, true, "C#m"),
+        (3, true, "D#m"),
+        (8, true, "G#m"),
+        (10, true, "Bbm"),
+    ] {
+        assert_eq!(Key::from_tonic(semitone, minor), key(name), "{}", name);
+    }
+}
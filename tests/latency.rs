@@ -0,0 +1,45 @@
+//! Latency should be summed up per stage, and a target met by the window and hop it sizes.
+
+use std::time::Duration;
+
+use monophonic_detector::latency::{self, Stage, Timings};
+use monophonic_detector::PitchDetector;
+
+#[test]
+fn sums_up_each_stage() {
+    let mut timings = Timings::new();
+    for ms in 1..=100 {
+        timings.record(Stage::Detect, Duration::from_millis(ms));
+    }
+    let stats = timings.stats(Stage::Detect).unwrap();
+    assert_eq!(stats.count, 100);
+    assert!((stats.mean - 50.5).abs() < 1e-9, "{}", stats.mean);
+    assert!((stats.p95 - 95.0).abs() < 1e-9, "{}", stats.p95);
+    assert!((stats.max - 100.0).abs() < 1e-9, "{}", stats.max);
+    assert_eq!(timings.stats(Stage::Output), None);
+}
+
+#[test]
+fn plans_buffering_for_the_budget() {
+    for budget in [10.0, 25.0, 100.0].iter() {
+        let (window, hop) = latency::plan(*budget, 48000);
+        assert_eq!(hop, window / 4);
+        let buffering = latency::buffering(window, hop, 48000);
+        assert!(
+            (buffering - budget).abs() < 0.1,
+            "{} ms: {}",
+            budget,
+            buffering
+        );
+    }
+}
+
+#[test]
+fn fits_the_detector_within_the_target() {
+    let detector = PitchDetector::new(44100);
+    let (fitted, hop) = latency::fit(&detector, 30.0);
+    let window = fitted.chunk_size();
+    assert!(window < detector.chunk_size());
+    assert!(latency::buffering(window, hop, 44100) <= 30.0);
+    assert!(fitted.min_freq() as f64 >= latency::lowest_freq(window, 44100));
+}
@@ -0,0 +1,51 @@
+//! Levels should read as a meter would: a tone's peak at its amplitude, and A-weighting flat at
+//! 1 kHz and well down in the bass.
+
+use monophonic_detector::{
+    filters,
+    generate::{Generator, Tones},
+    level::{self, Meter},
+};
+
+const SAMPLE_RATE: usize = 44100;
+
+fn tone(freq: f64, amplitude: f64) -> Vec<f32> {
+    Generator::new(Tones::Steps(vec![freq]))
+        .with_duration(1.0)
+        .with_amplitude(amplitude)
+        .with_sample_rate(SAMPLE_RATE)
+        .samples()
+}
+
+/// The gain of A-weighting at `freq` Hz, in dB, from a steady tone past the filters settling.
+fn a_weighting_gain(freq: f64) -> f64 {
+    let samples = tone(freq, 0.5);
+    let mut weighted = samples.clone();
+    filters::a_weight(&mut weighted, SAMPLE_RATE);
+    let settled = SAMPLE_RATE / 4;
+    level::to_dbfs(level::rms(&weighted[settled..]) / level::rms(&samples[settled..]))
+}
+
+#[test]
+fn peak_is_the_amplitude() {
+    let samples = tone(440.0, 0.5);
+    assert!((level::peak(&samples) - 0.5).abs() < 1e-3);
+
+    let measured = Meter::default().measure(&samples, SAMPLE_RATE);
+    assert!((measured.peak - -6.02).abs() < 0.05, "{}", measured.peak);
+    // A sine's RMS is 3 dB under its peak.
+    assert!((measured.rms - -9.03).abs() < 0.05, "{}", measured.rms);
+    assert_eq!(measured.a_weighted, None);
+}
+
+#[test]
+fn a_weighting_is_flat_at_1_khz() {
+    let gain = a_weighting_gain(1000.0);
+    assert!(gain.abs() < 0.5, "{} dB", gain);
+}
+
+#[test]
+fn a_weighting_cuts_100_hz() {
+    let gain = a_weighting_gain(100.0);
+    assert!((gain - -19.1).abs() < 1.5, "{} dB", gain);
+}
@@ -0,0 +1,44 @@
+//! Live detection updates the autocorrelation as the window slides; it should come to the same
+//! estimates as detecting each window from scratch.
+
+use monophonic_detector::{
+    generate::{Generator, Tones},
+    live::LiveInput,
+    PitchDetector,
+};
+
+#[test]
+fn sliding_autocorrelation_matches_detect() {
+    let detector = PitchDetector::default().with_min_freq(100);
+    let chunk_size = detector.chunk_size();
+    let hop = chunk_size / 4;
+
+    let samples: Vec<f32> = Generator::new(Tones::Steps(vec![196.0, 261.63, 440.0, 329.63]))
+        .with_duration(4.0)
+        .with_harmonics(vec![1.0, 0.5, 0.3])
+        .samples()
+        .iter()
+        // As read from 16-bit PCM.
+        .map(|s| (s * 32767.0).round() / 32768.0)
+        .collect();
+    let pcm: Vec<u8> = samples
+        .iter()
+        .flat_map(|s| ((s * 32768.0) as i16).to_le_bytes())
+        .collect();
+
+    let mut live = LiveInput::new(&pcm[..], detector.clone()).with_hop(hop);
+    let mut end = chunk_size;
+    while let Some(detected) = live.next_estimate().unwrap() {
+        let expected = detector.detect(&samples[end - chunk_size..end]);
+        let (freq, expected_freq) = (detected.freq.unwrap(), expected.freq.unwrap());
+        assert!(
+            (freq - expected_freq).abs() < 1e-6 * expected_freq,
+            "window ending at {}: {} Hz, not {} Hz",
+            end,
+            freq,
+            expected_freq
+        );
+        end += hop;
+    }
+    assert!(end > samples.len() - hop);
+}
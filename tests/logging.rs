@@ -0,0 +1,54 @@
+//! Log messages should say which chunk they're about, and only be written at the verbosity asked
+//! for.
+
+use std::thread;
+
+use monophonic_detector::logging::{self, enabled, format, set_context, set_verbosity, Level};
+
+#[test]
+fn formats_messages_with_their_level() {
+    assert_eq!(
+        format(Level::Info, format_args!("rest: level={:.1}dBFS", -72.25)),
+        "[info] rest: level=-72.2dBFS"
+    );
+}
+
+#[test]
+fn formats_messages_in_their_context() {
+    set_context(Some("1.250s".to_string()));
+    assert_eq!(
+        format(Level::Debug, format_args!("pitch: freq={}Hz", 440)),
+        "[debug] 1.250s: pitch: freq=440Hz"
+    );
+    set_context(None);
+    assert_eq!(format(Level::Debug, format_args!("x")), "[debug] x");
+}
+
+#[test]
+fn keeps_contexts_per_thread() {
+    set_context(Some("0.500s".to_string()));
+    let other = thread::spawn(|| format(Level::Info, format_args!("x")))
+        .join()
+        .unwrap();
+    assert_eq!(other, "[info] x");
+    assert_eq!(format(Level::Info, format_args!("x")), "[info] 0.500s: x");
+    set_context(None);
+}
+
+#[test]
+fn logs_up_to_the_verbosity() {
+    // The verbosity is global, so it's only changed in this test.
+    assert_eq!(logging::verbosity(), 0);
+    assert!(!enabled(Level::Info));
+
+    set_verbosity(1);
+    assert!(enabled(Level::Info));
+    assert!(!enabled(Level::Debug));
+
+    set_verbosity(2);
+    assert!(enabled(Level::Info));
+    assert!(enabled(Level::Debug));
+
+    set_verbosity(0);
+    assert!(!enabled(Level::Info));
+}
@@ -0,0 +1,207 @@
+//! Pitch bends in exported MIDI files should follow the pitch within each note, quantized files
+//! should put notes on the beats of their tempo, and melodies should read back from MIDI files as
+//! they were written.
+
+use std::f64::consts::PI;
+
+use monophonic_detector::{
+    midi::{read_midi, write_midi, write_midi_with_bends, write_quantized_midi},
+    notes::NoteEvent,
+    score::TimeSignature,
+    Note, Pitch, DEFAULT_A4,
+};
+
+/// The messages of the track of a format 0 MIDI file, with their times in ticks.
+fn messages(file: &[u8]) -> Vec<(u32, Vec<u8>)> {
+    assert_eq!(&file[..4], b"MThd");
+    assert_eq!(&file[14..18], b"MTrk");
+    let mut track = &file[22..];
+    let (mut now, mut messages) = (0, vec![]);
+    while !track.is_empty() {
+        let mut delta = 0;
+        loop {
+            let byte = track[0];
+            track = &track[1..];
+            delta = delta << 7 | (byte & 0x7f) as u32;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        now += delta;
+        let len = match track[0] {
+            0xff => 3 + track[2] as usize,
+            _ => 3,
+        };
+        messages.push((now, track[..len].to_vec()));
+        track = &track[len..];
+    }
+    messages
+}
+
+/// The bend of a pitch bend message in cents, with the default range of 2 semitones.
+fn bend_cents(message: &[u8]) -> Option<f64> {
+    match message {
+        [0xe0, lsb, msb] => Some(((*msb as f64) * 128.0 + *lsb as f64 - 8192.0) / 8192.0 * 200.0),
+        _ => None,
+    }
+}
+
+fn a4(start: f64, end: f64) -> NoteEvent {
+    NoteEvent {
+        pitch: Pitch {
+            note: Note::A,
+            octave: 4,
+        },
+        start,
+        end,
+        velocity: 100,
+    }
+}
+
+#[test]
+fn bends_follow_the_pitch() {
+    let notes = [a4(0.0, 1.0), a4(1.5, 2.0)];
+    // Vibrato of 30 cents at 5 Hz over the first note, then a note 20 cents flat.
+    let track: Vec<(f64, f64)> = (0..200)
+        .map(|i| i as f64 * 0.01)
+        .filter(|time| *time < 1.0 || *time >= 1.5)
+        .map(|time| {
+            let cents = if time < 1.0 {
+                30.0 * (2.0 * PI * 5.0 * time).sin()
+            } else {
+                -20.0
+            };
+            (time, DEFAULT_A4 * 2f64.powf(cents / 1200.0))
+        })
+        .collect();
+
+    let mut file = vec![];
+    write_midi_with_bends(&mut file, &notes, &track, DEFAULT_A4).unwrap();
+    let messages = messages(&file);
+
+    // The bend range is set to 2 semitones.
+    let controls: Vec<&[u8]> = messages
+        .iter()
+        .map(|(_, message)| &message[..])
+        .filter(|message| message[0] == 0xb0)
+        .collect();
+    assert!(controls
+        .windows(3)
+        .any(|w| w == [[0xb0, 101, 0], [0xb0, 100, 0], [0xb0, 6, 2]]));
+
+    let bends: Vec<(u32, f64)> = messages
+        .iter()
+        .filter_map(|(tick, message)| Some((*tick, bend_cents(message)?)))
+        .collect();
+    // 960 ticks a second.
+    let first: Vec<f64> = bends
+        .iter()
+        .filter(|(tick, _)| *tick < 960)
+        .map(|(_, cents)| *cents)
+        .collect();
+    let max = first.iter().cloned().fold(f64::MIN, f64::max);
+    let min = first.iter().cloned().fold(f64::MAX, f64::min);
+    assert!((max - 30.0).abs() < 0.5, "highest bend {}¢", max);
+    assert!((min + 30.0).abs() < 0.5, "lowest bend {}¢", min);
+
+    // The second note starts bent, before it's played, and the bend is reset at the end.
+    let on = messages
+        .iter()
+        .position(|(tick, message)| *tick == 1440 && message[0] == 0x90)
+        .unwrap();
+    assert!((bend_cents(&messages[on - 1].1).unwrap() + 20.0).abs() < 0.5);
+    let last_bend = bends.last().unwrap();
+    assert_eq!((last_bend.0, last_bend.1), (1920, 0.0));
+}
+
+#[test]
+fn no_bends_without_a_track() {
+    let mut file = vec![];
+    write_midi(&mut file, &[a4(0.0, 1.0)]).unwrap();
+    let messages = messages(&file);
+    assert!(messages
+        .iter()
+        .all(|(_, message)| message[0] != 0xe0 && message[0] != 0xb0));
+    assert_eq!(messages[1], (0, vec![0x90, 69, 100]));
+    assert_eq!(messages[2], (960, vec![0x80, 69, 0]));
+}
+
+#[test]
+fn reads_back_written_melodies() {
+    let b4 = NoteEvent {
+        pitch: "B4".parse().unwrap(),
+        ..a4(0.5, 1.25)
+    };
+    let notes = [a4(0.0, 0.5), b4, a4(2.0, 3.0)];
+    let mut file = vec![];
+    write_midi(&mut file, &notes).unwrap();
+    let read = read_midi(&file).unwrap();
+    assert_eq!(read.len(), notes.len());
+    for (read, note) in read.iter().zip(&notes) {
+        assert_eq!(read.pitch, note.pitch);
+        assert!((read.start - note.start).abs() < 1e-3, "{:?}", read);
+        assert!((read.end - note.end).abs() < 1e-3, "{:?}", read);
+        assert_eq!(read.velocity, note.velocity);
+    }
+}
+
+#[test]
+fn reads_the_top_line_of_chords_at_the_file_tempo() {
+    // Format 0 at 96 ticks per quarter and 60 bpm: a C major triad for a beat, then D5, with
+    // running status and a drum hit on channel 10 that isn't part of the melody.
+    #[rustfmt::skip]
+    let track: Vec<u8> = vec![
+        0x00, 0xff, 0x51, 0x03, 0x0f, 0x42, 0x40,
+        0x00, 0x90, 60, 90, 0x00, 64, 90, 0x00, 67, 90,
+        0x00, 0x99, 36, 100,
+        0x60, 0x80, 60, 0, 0x00, 64, 0, 0x00, 67, 0,
+        0x00, 0x90, 74, 80,
+        0x60, 74, 0,
+        0x00, 0xff, 0x2f, 0x00,
+    ];
+    let mut file = b"MThd".to_vec();
+    file.extend_from_slice(&[0, 0, 0, 6, 0, 0, 0, 1, 0, 96]);
+    file.extend_from_slice(b"MTrk");
+    file.extend_from_slice(&(track.len() as u32).to_be_bytes());
+    file.extend_from_slice(&track);
+
+    let melody = read_midi(&file).unwrap();
+    let pitches: Vec<String> = melody.iter().map(|note| note.pitch.to_string()).collect();
+    assert_eq!(pitches, ["G4", "D5"]);
+    assert!((melody[0].end - 1.0).abs() < 1e-9);
+    assert!((melody[1].start - 1.0).abs() < 1e-9);
+    assert!((melody[1].end - 2.0).abs() < 1e-9);
+
+    assert!(read_midi(b"RIFF").is_err());
+    assert!(read_midi(&file[..30]).is_err());
+}
+
+#[test]
+fn quantizes_to_the_tempo() {
+    // Slightly off the beats at 100 bpm, where a sixteenth note is 0.15 s.
+    let notes = [a4(0.02, 0.58), a4(0.61, 0.9), a4(1.48, 2.43)];
+    let mut file = vec![];
+    let time = TimeSignature {
+        beats: 3,
+        beat_type: 4,
+    };
+    write_quantized_midi(&mut file, &notes, 100.0, time, None).unwrap();
+
+    let messages = messages(&file);
+    // 600,000 microseconds per quarter note, and 3/4 with a click every quarter note.
+    assert_eq!(messages[0], (0, vec![0xff, 0x51, 0x03, 0x09, 0x27, 0xc0]));
+    assert_eq!(messages[1], (0, vec![0xff, 0x58, 0x04, 3, 2, 24, 8]));
+    // Every note on and off falls on a sixteenth note, of 120 ticks.
+    let times: Vec<u32> = messages[2..messages.len() - 1]
+        .iter()
+        .map(|(time, _)| *time)
+        .collect();
+    assert_eq!(times, [0, 480, 480, 720, 1200, 1920]);
+
+    let read = read_midi(&file).unwrap();
+    let expected = [(0.0, 0.6), (0.6, 0.9), (1.5, 2.4)];
+    for (note, (start, end)) in read.iter().zip(expected) {
+        assert!((note.start - start).abs() < 1e-9, "{:?}", note);
+        assert!((note.end - end).abs() < 1e-9, "{:?}", note);
+    }
+}
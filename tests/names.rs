@@ -0,0 +1,78 @@
+//! Note names should follow each convention, and the writers should use them.
+
+use monophonic_detector::{
+    chord::{Chord, ChordQuality},
+    names::NoteNames,
+    output::{Format, Record, RecordWriter},
+    Note, Pitch, PitchDetector,
+};
+
+#[test]
+fn names_notes() {
+    let names = |names: NoteNames| -> Vec<&str> {
+        Note::ALL.iter().map(|note| names.note(*note)).collect()
+    };
+    assert_eq!(
+        names(NoteNames::English),
+        ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"]
+    );
+    assert_eq!(
+        names(NoteNames::German),
+        ["C", "Cis", "D", "Dis", "E", "F", "Fis", "G", "Gis", "A", "B", "H"]
+    );
+    assert_eq!(
+        names(NoteNames::Solfege),
+        ["Do", "Do#", "Re", "Re#", "Mi", "Fa", "Fa#", "Sol", "Sol#", "La", "La#", "Si"]
+    );
+    for note in Note::ALL.iter() {
+        assert_eq!(NoteNames::English.note(*note), note.to_string());
+    }
+}
+
+#[test]
+fn names_pitches_and_chords() {
+    let b3 = Pitch {
+        note: Note::B,
+        octave: 3,
+    };
+    assert_eq!(NoteNames::German.pitch(b3), "H3");
+    assert_eq!(NoteNames::Solfege.pitch(b3), "Si3");
+
+    let chord = Chord {
+        root: Note::A,
+        quality: ChordQuality::Minor,
+    };
+    assert_eq!(NoteNames::English.chord(chord), "Am");
+    assert_eq!(NoteNames::Solfege.chord(chord), "Lam");
+}
+
+#[test]
+fn writes_records_with_note_names() {
+    let detected = PitchDetector::default().identify(440.0);
+    let record = Record {
+        timestamp: 0.0,
+        channel: None,
+        voice: None,
+        detected,
+        level: None,
+        inharmonicity: None,
+    };
+    let write = |format: Format| {
+        let mut out = RecordWriter::new(vec![], format)
+            .unwrap()
+            .with_note_names(NoteNames::Solfege);
+        out.write(&record).unwrap();
+        String::from_utf8(out.finish().unwrap()).unwrap()
+    };
+    assert_eq!(write(Format::Text), "Estimated freq: 440 Hz, La4 +0¢\n");
+    assert!(write(Format::Csv).contains(",La,4,"));
+    assert!(write(Format::Json).contains("\"note\": \"La\""));
+}
+
+#[test]
+fn parses_note_names() {
+    for names in &["english", "german", "solfege"] {
+        assert_eq!(names.parse::<NoteNames>().unwrap().to_string(), *names);
+    }
+    assert!("dutch".parse::<NoteNames>().is_err());
+}
@@ -0,0 +1,73 @@
+//! Pitch plots should show each estimate coloured by how in tune it is, over a grid of the notes
+//! around them.
+
+use std::path::Path;
+
+use monophonic_detector::{
+    plot::{colour, write_png, write_svg, ImageFormat, HEIGHT, WIDTH},
+    DEFAULT_A4,
+};
+
+/// A4 in tune, and then 30 cents sharp and 30 cents flat.
+fn points() -> Vec<(f64, f64)> {
+    let sharp = DEFAULT_A4 * 2f64.powf(0.3 / 12.0);
+    let flat = DEFAULT_A4 * 2f64.powf(-0.3 / 12.0);
+    vec![(0.0, DEFAULT_A4), (1.0, sharp), (2.0, flat)]
+}
+
+#[test]
+fn colours_by_intonation() {
+    assert_eq!(colour(0.0), colour(-9.0));
+    let (sharp, flat) = (colour(30.0), colour(-30.0));
+    assert!(
+        sharp[0] > flat[0] && sharp[2] < flat[2],
+        "{:?} {:?}",
+        sharp,
+        flat
+    );
+    assert_eq!(colour(50.0), colour(80.0));
+}
+
+#[test]
+fn labels_the_notes_in_svg() {
+    let mut svg = vec![];
+    write_svg(&mut svg, &points(), DEFAULT_A4).unwrap();
+    let svg = String::from_utf8(svg).unwrap();
+    assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+
+    // An octave around A4, with every note labelled.
+    for note in &["D#4", "A4", "C5", "D#5"] {
+        assert!(svg.contains(&format!(">{}</text>", note)), "{}", note);
+    }
+    assert!(svg.contains(">2.0s</text>"));
+    assert_eq!(svg.matches("<circle").count(), 3);
+    for cents in &[0.0, 30.0, -30.0] {
+        let [r, g, b] = colour(*cents);
+        let fill = format!("fill=\"#{:02x}{:02x}{:02x}\"", r, g, b);
+        assert!(svg.contains(&fill), "{}", fill);
+    }
+}
+
+#[test]
+fn draws_a_png() {
+    let mut png = vec![];
+    write_png(&mut png, &points(), DEFAULT_A4).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    assert_eq!(&png[16..20], &(WIDTH as u32).to_be_bytes());
+    assert_eq!(&png[20..24], &(HEIGHT as u32).to_be_bytes());
+}
+
+#[test]
+fn formats_follow_the_extension() {
+    assert_eq!(
+        ImageFormat::of(Path::new("take.svg")),
+        Some(ImageFormat::Svg)
+    );
+    assert_eq!(
+        ImageFormat::of(Path::new("take.PNG")),
+        Some(ImageFormat::Png)
+    );
+    assert_eq!(ImageFormat::of(Path::new("take.jpg")), None);
+    assert_eq!(ImageFormat::of(Path::new("take")), None);
+}
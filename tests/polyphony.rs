@@ -0,0 +1,75 @@
+//! Dual-pitch detection should find both notes of power chords and two-part intervals, and only
+//! one in a single tone.
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    PitchDetector,
+};
+
+const HARMONICS: &[f64] = &[1.0, 0.5, 0.33, 0.25, 0.2];
+
+/// Tones of each of `freqs` with `HARMONICS`, mixed.
+fn mix(freqs: &[f64]) -> Vec<f32> {
+    let tones: Vec<Vec<f32>> = freqs
+        .iter()
+        .map(|freq| {
+            Generator::new(Tones::Steps(vec![*freq]))
+                .with_duration(0.5)
+                .with_harmonics(HARMONICS.to_vec())
+                .samples()
+        })
+        .collect();
+    (0..tones[0].len())
+        .map(|i| tones.iter().map(|tone| tone[i]).sum::<f32>() / freqs.len() as f32)
+        .collect()
+}
+
+fn dual_freqs(freqs: &[f64]) -> Vec<f64> {
+    let detector = PitchDetector::default();
+    let samples = mix(freqs);
+    detector
+        .detect_dual(&samples[..detector.chunk_size()])
+        .iter()
+        .map(|detected| detected.freq.unwrap())
+        .collect()
+}
+
+#[test]
+fn finds_both_pitches() {
+    for freqs in &[
+        // Power chords on E2, A2 and G3.
+        [82.41, 123.47],
+        [110.0, 164.81],
+        [196.0, 293.66],
+        // A major third, a minor third and a semitone.
+        [261.63, 329.63],
+        [146.83, 174.61],
+        [329.63, 349.23],
+    ] {
+        let found = dual_freqs(freqs);
+        assert_eq!(found.len(), 2, "{:?}: {:?}", freqs, found);
+        for (found, freq) in found.iter().zip(freqs) {
+            assert!(
+                cents_between(*freq, *found).abs() < 5.0,
+                "{:?}: {:?}",
+                freqs,
+                found
+            );
+        }
+    }
+}
+
+#[test]
+fn finds_one_pitch_in_a_single_tone() {
+    for &freq in &[55.0, 110.0, 440.0, 1046.5] {
+        let found = dual_freqs(&[freq]);
+        assert_eq!(found.len(), 1, "{}: {:?}", freq, found);
+        assert!(
+            cents_between(freq, found[0]).abs() < 5.0,
+            "{}: {:?}",
+            freq,
+            found
+        );
+    }
+}
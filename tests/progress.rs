@@ -0,0 +1,66 @@
+//! The progress bar should show how far an analysis has got, how fast it's going and when it
+//! should be done, and leave nothing behind when it's finished.
+
+use monophonic_detector::progress::{clock, render, Progress, WIDTH};
+
+#[test]
+fn shows_progress_speed_and_time_left() {
+    // A quarter of 400 chunks of 0.1 s, in 2 s: 5 times real time, 6 s to go.
+    let line = render(100, Some(400), 0.1, 2.0);
+    let filled = (WIDTH as f64 / 4.0).round() as usize;
+    let bar = format!("[{}{}]", "#".repeat(filled), "-".repeat(WIDTH - filled));
+    assert!(line.starts_with(&bar), "{}", line);
+    assert!(
+        line.ends_with(" 25%  100/400 chunks  5.0x realtime  ETA 0:06"),
+        "{}",
+        line
+    );
+
+    let done = render(400, Some(400), 0.1, 8.0);
+    assert!(
+        done.starts_with(&format!("[{}]", "#".repeat(WIDTH))),
+        "{}",
+        done
+    );
+    assert!(
+        done.ends_with("100%  400/400 chunks  5.0x realtime  ETA 0:00"),
+        "{}",
+        done
+    );
+}
+
+#[test]
+fn shows_how_much_was_read_of_streams() {
+    assert_eq!(
+        render(1500, None, 0.1, 10.0),
+        "1500 chunks (2:30 of audio)  15.0x realtime"
+    );
+    assert_eq!(
+        render(0, None, 0.1, 0.0),
+        "0 chunks (0:00 of audio)  -x realtime"
+    );
+}
+
+#[test]
+fn formats_times() {
+    assert_eq!(clock(0.0), "0:00");
+    assert_eq!(clock(59.6), "1:00");
+    assert_eq!(clock(754.0), "12:34");
+    assert_eq!(clock(3725.0), "1:02:05");
+}
+
+#[test]
+fn clears_the_line_when_done() {
+    let mut out = vec![];
+    let mut progress = Progress::new(&mut out, Some(10), 0.1);
+    progress.advance(5).unwrap();
+    // Too soon to draw again.
+    progress.advance(5).unwrap();
+    progress.finish().unwrap();
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.split('\r').filter(|line| !line.is_empty()).collect();
+    assert_eq!(lines.len(), 2, "{:?}", out);
+    assert!(lines[0].contains("5/10 chunks"), "{:?}", lines[0]);
+    assert_eq!(lines[1], "\x1b[2K");
+}
@@ -0,0 +1,179 @@
+//! Property tests: detection should hold up over random tones across the supported range, whatever
+//! their amplitude, phase and waveform, with harmonics and noise. Each case is drawn from a seeded
+//! generator, so a failure names the case to reproduce; set `PROPERTY_SEED` to draw different
+//! ones, and `PROPERTY_CASES` to draw more.
+
+use std::env;
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Noise, Tones, Waveform},
+    Algorithm, PitchDetector,
+};
+
+/// How many cases each property is checked on, by default.
+const CASES: usize = 32;
+
+/// The range of frequencies drawn, in Hz, log-uniformly.
+const MIN_FREQ: f64 = 80.0;
+const MAX_FREQ: f64 = 2000.0;
+
+/// The lowest signal-to-noise ratio of noisy tones, in dB.
+const MIN_SNR_DB: f64 = 30.0;
+
+/// A xorshift64* generator, as the noise is made with.
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Self {
+        let seed = env::var("PROPERTY_SEED").map_or(1, |seed| seed.parse().unwrap());
+        Rng(seed.max(1))
+    }
+
+    /// A uniformly distributed number in `low..high`.
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let unit = (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11) as f64 / (1u64 << 53) as f64;
+        low + (high - low) * unit
+    }
+
+    fn log_range(&mut self, low: f64, high: f64) -> f64 {
+        self.range(low.ln(), high.ln()).exp()
+    }
+}
+
+fn cases() -> usize {
+    env::var("PROPERTY_CASES").map_or(CASES, |cases| cases.parse().unwrap())
+}
+
+/// A random tone.
+#[derive(Debug)]
+struct Case {
+    freq: f64,
+    amplitude: f64,
+    phase: f64,
+    waveform: Waveform,
+    harmonics: Vec<f64>,
+    snr_db: Option<f64>,
+    seed: u64,
+}
+
+impl Case {
+    /// A random tone of one of `waveforms`, with up to `partials` partials and noise if `noisy`.
+    fn draw(rng: &mut Rng, waveforms: &[Waveform], partials: usize, noisy: bool) -> Self {
+        let waveform = waveforms[rng.range(0.0, waveforms.len() as f64) as usize];
+        let partials = rng.range(1.0, partials as f64 + 1.0) as usize;
+        // The fundamental is always the strongest partial.
+        let harmonics = (0..partials)
+            .map(|k| if k == 0 { 1.0 } else { rng.range(0.0, 0.8) })
+            .collect();
+        Case {
+            freq: rng.log_range(MIN_FREQ, MAX_FREQ),
+            amplitude: rng.log_range(0.01, 1.0),
+            phase: rng.range(0.0, 1.0),
+            waveform,
+            harmonics,
+            snr_db: Some(rng.range(MIN_SNR_DB, 60.0)).filter(|_| noisy),
+            seed: rng.range(1.0, 1e9) as u64,
+        }
+    }
+
+    fn samples(&self, detector: &PitchDetector) -> Vec<f32> {
+        let mut generator = Generator::new(Tones::Steps(vec![self.freq]))
+            .with_duration(2.0 * detector.chunk_size() as f64 / detector.sample_rate() as f64)
+            .with_amplitude(self.amplitude)
+            .with_phase(self.phase)
+            .with_waveform(self.waveform)
+            .with_harmonics(self.harmonics.clone())
+            .with_seed(self.seed);
+        if let Some(snr_db) = self.snr_db {
+            generator = generator.with_noise(Noise::White, snr_db);
+        }
+        generator.samples()
+    }
+}
+
+/// Check that `detector` finds every case within `tolerance` cents of its frequency.
+fn check(
+    detector: &PitchDetector,
+    waveforms: &[Waveform],
+    partials: usize,
+    noisy: bool,
+    tolerance: f64,
+) {
+    let mut rng = Rng::new();
+    for _ in 0..cases() {
+        let case = Case::draw(&mut rng, waveforms, partials, noisy);
+        let samples = case.samples(detector);
+        let chunk = &samples[..detector.chunk_size()];
+        let detected = detector.detect(chunk);
+        let cents = detected.freq.map(|freq| cents_between(case.freq, freq));
+        assert!(
+            cents.is_some_and(|cents| cents.abs() <= tolerance),
+            "{:?}: {:?} ({:?} cents)",
+            case,
+            detected,
+            cents
+        );
+    }
+}
+
+// The monotonic detector is only checked on single partials: it can take strong higher ones for
+// the fundamental (see `harmonics.rs`).
+
+#[test]
+fn monotonic_tracks_clean_tones() {
+    let detector = PitchDetector::default();
+    check(
+        &detector,
+        &[Waveform::Sine, Waveform::Triangle],
+        1,
+        false,
+        10.0,
+    );
+}
+
+#[test]
+fn monotonic_tracks_noisy_tones() {
+    let detector = PitchDetector::default();
+    check(&detector, &[Waveform::Sine], 1, true, 20.0);
+}
+
+#[test]
+fn yin_and_mpm_track_noisy_tones_with_harmonics() {
+    for &algorithm in &[Algorithm::Yin, Algorithm::Mpm] {
+        let detector = PitchDetector::default().with_algorithm(algorithm);
+        check(
+            &detector,
+            &[Waveform::Sine, Waveform::Triangle],
+            3,
+            true,
+            20.0,
+        );
+    }
+}
+
+#[test]
+fn detection_ignores_the_level() {
+    let detector = PitchDetector::default();
+    let mut rng = Rng::new();
+    for _ in 0..cases() {
+        let case = Case::draw(&mut rng, &[Waveform::Sine], 1, false);
+        let samples = case.samples(&detector);
+        let chunk = &samples[..detector.chunk_size()];
+        // Not so quiet as to be a rest.
+        let scale = rng.log_range(0.1 / case.amplitude.max(0.1), 1.0) as f32;
+        let quieter: Vec<f32> = chunk.iter().map(|sample| sample * scale).collect();
+        let (loud, quiet) = (detector.detect(chunk).freq, detector.detect(&quieter).freq);
+        let cents = cents_between(loud.unwrap(), quiet.unwrap());
+        assert!(
+            cents.abs() < 0.1,
+            "{:?} at {}: {} cents",
+            case,
+            scale,
+            cents
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! Recording live input should pass it through untouched, whatever size the reads come in, and
+//! leave every sample in the WAV file.
+
+use std::env;
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+
+use monophonic_detector::recording::Recorder;
+
+/// A reader that returns at most `size` bytes per read, as a pipe might.
+struct Trickle<R>(R, usize);
+
+impl<R: Read> Read for Trickle<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let size = buf.len().min(self.1);
+        self.0.read(&mut buf[..size])
+    }
+}
+
+fn path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("recording-{}-{}.wav", std::process::id(), name))
+}
+
+fn pcm(samples: &[i16]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn records_what_is_read() {
+    let samples: Vec<i16> = (0..1000).map(|i| (i * 37 % 2000 - 1000) as i16).collect();
+    let bytes = pcm(&samples);
+    let path = path("odd-reads");
+
+    // Reads of 3 bytes split samples between them.
+    let mut recorder =
+        Recorder::create(Trickle(Cursor::new(bytes.clone()), 3), &path, 8000).unwrap();
+    let mut read = vec![];
+    recorder.read_to_end(&mut read).unwrap();
+    recorder.finish().unwrap();
+    assert_eq!(read, bytes);
+
+    let mut wav = hound::WavReader::open(&path).unwrap();
+    assert_eq!(wav.spec().sample_rate, 8000);
+    assert_eq!(wav.spec().channels, 1);
+    let recorded: Vec<i16> = wav.samples().map(|sample| sample.unwrap()).collect();
+    assert_eq!(recorded, samples);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn finishes_the_file_when_dropped() {
+    let samples = [1, -2, 3, -4];
+    let path = path("dropped");
+    {
+        let mut recorder = Recorder::create(Cursor::new(pcm(&samples)), &path, 44100).unwrap();
+        let mut read = vec![];
+        recorder.read_to_end(&mut read).unwrap();
+    }
+    let mut wav = hound::WavReader::open(&path).unwrap();
+    let recorded: Vec<i16> = wav.samples().map(|sample| sample.unwrap()).collect();
+    assert_eq!(recorded, samples);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn fails_to_record_where_it_cannot_write() {
+    let path = env::temp_dir()
+        .join("no-such-directory")
+        .join("session.wav");
+    assert!(Recorder::create(Cursor::new(vec![]), &path, 44100).is_err());
+}
@@ -0,0 +1,64 @@
+//! Melodies should fit the scales they're drawn from best.
+
+use monophonic_detector::{
+    notes::NoteEvent,
+    scale::{self, Scale},
+    Note::{self, *},
+    Pitch,
+};
+
+/// A melody of `notes` in octave 4, each a half second long but the first, the tonic, which is
+/// held for two seconds.
+fn melody(notes: &[Note]) -> Vec<NoteEvent> {
+    let mut start = 0.0;
+    notes
+        .iter()
+        .enumerate()
+        .map(|(i, &note)| {
+            let end = start + if i == 0 { 2.0 } else { 0.5 };
+            let event = NoteEvent {
+                pitch: Pitch { note, octave: 4 },
+                start,
+                end,
+                velocity: 100,
+            };
+            start = end;
+            event
+        })
+        .collect()
+}
+
+fn best(notes: &[Note]) -> (Note, Scale) {
+    let fit = scale::rank_scales(&melody(notes))[0];
+    assert_eq!(fit.fit, 1.0);
+    (fit.tonic, fit.scale)
+}
+
+#[test]
+fn finds_scales() {
+    assert_eq!(best(&[C, D, E, F, G, A, B, C]), (C, Scale::Major));
+    assert_eq!(
+        best(&[A, B, C, D, E, F, GSharp, A]),
+        (A, Scale::HarmonicMinor)
+    );
+    assert_eq!(best(&[D, E, F, G, A, B, C, D]), (D, Scale::Dorian));
+    assert_eq!(best(&[G, A, B, C, D, E, F, G]), (G, Scale::Mixolydian));
+    // Only five notes, so the pentatonic scale fits better than the major one.
+    assert_eq!(best(&[G, A, B, D, E, G]), (G, Scale::MajorPentatonic));
+    assert_eq!(best(&[E, G, A, ASharp, B, D, E]), (E, Scale::Blues));
+}
+
+#[test]
+fn ranks_partial_fits() {
+    // A C major scale with a passing F#: it fits G major, and C lydian, better than C major.
+    let ranked = scale::rank_scales(&melody(&[C, D, E, FSharp, G, A, B, C]));
+    assert_eq!(ranked.len(), 144);
+    assert!(ranked.windows(2).all(|w| w[0].fit >= w[1].fit));
+    assert_eq!((ranked[0].tonic, ranked[0].scale), (C, Scale::Lydian));
+    let c_major = ranked
+        .iter()
+        .find(|fit| fit.tonic == C && fit.scale == Scale::Major)
+        .unwrap();
+    assert!((c_major.fit - 5.0 / 5.5).abs() < 1e-9);
+    assert!(scale::rank_scales(&[]).is_empty());
+}
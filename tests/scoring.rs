@@ -0,0 +1,134 @@
+//! Performances should be aligned with the melody they follow, however late they start or how
+//! they drift, and each note scored by how much of it was in tune.
+
+use monophonic_detector::{
+    notes::NoteEvent,
+    scoring::{parse_melody, score, DEFAULT_TOLERANCE},
+    DEFAULT_A4,
+};
+
+const MELODY: &str = "\
+# Twinkle, twinkle
+C4 0.5
+C4 0.5
+G4 0.5
+G4 0.5
+A4 0.5
+A4 0.5
+G4 1.0
+rest 0.5
+F4 0.5
+F#4 0.5
+";
+
+/// Estimates every 50 ms of `melody` sung `delay` seconds late, at `rate` times its tempo, with
+/// each frequency passed through `sing`.
+fn sing(
+    melody: &[NoteEvent],
+    delay: f64,
+    rate: f64,
+    sing: impl Fn(usize, f64) -> f64,
+) -> Vec<(f64, Option<f64>)> {
+    let end = delay + melody.last().unwrap().end / rate + 1.0;
+    (0..(end / 0.05) as usize)
+        .map(|i| {
+            let time = i as f64 * 0.05;
+            let at = (time - delay) * rate;
+            let freq = melody
+                .iter()
+                .position(|note| note.start <= at && at < note.end)
+                .map(|i| sing(i, melody[i].pitch.freq(DEFAULT_A4)));
+            (time, freq)
+        })
+        .collect()
+}
+
+#[test]
+fn parses_note_lists() {
+    let melody = parse_melody(MELODY).unwrap();
+    assert_eq!(melody.len(), 9);
+    assert_eq!(melody[0].pitch.to_string(), "C4");
+    assert_eq!(melody[8].pitch.to_string(), "F#4");
+    assert_eq!(melody[6].start, 3.0);
+    assert_eq!(melody[6].end, 4.0);
+    // After the rest.
+    assert_eq!(melody[7].start, 4.5);
+
+    assert!(parse_melody("A4").is_err());
+    assert_eq!(
+        parse_melody("A4 0.5\nH4 0.5\n"),
+        Err("line 2: unknown note: H".to_string())
+    );
+    assert!(parse_melody("A4 -1").is_err());
+}
+
+#[test]
+fn scores_a_perfect_performance() {
+    let melody = parse_melody(MELODY).unwrap();
+    let score = score(
+        &melody,
+        &sing(&melody, 0.0, 1.0, |_, freq| freq),
+        DEFAULT_A4,
+        DEFAULT_TOLERANCE,
+    );
+    assert!(score.accuracy > 0.95, "{:?}", score);
+    assert!(score.offset.abs() < 0.06, "{}", score.offset);
+    for note in &score.notes {
+        assert!(note.frames > 0);
+        assert!(note.cents.unwrap().abs() < 1e-6);
+    }
+}
+
+#[test]
+fn finds_late_starts() {
+    let melody = parse_melody(MELODY).unwrap();
+    let performance = sing(&melody, 1.3, 1.0, |_, freq| freq);
+    let score = score(&melody, &performance, DEFAULT_A4, DEFAULT_TOLERANCE);
+    assert!((score.offset - 1.3).abs() < 0.06, "{}", score.offset);
+    assert!(score.accuracy > 0.95, "{:?}", score);
+}
+
+#[test]
+fn follows_a_dragging_tempo() {
+    let melody = parse_melody(MELODY).unwrap();
+    let performance = sing(&melody, 0.0, 0.9, |_, freq| freq);
+    let score = score(&melody, &performance, DEFAULT_A4, DEFAULT_TOLERANCE);
+    assert!(score.accuracy > 0.9, "{:?}", score);
+}
+
+#[test]
+fn scores_each_note() {
+    let melody = parse_melody(MELODY).unwrap();
+    // The third note sharp by 80 cents, the fifth an octave down, and the rest 10 cents flat.
+    let performance = sing(&melody, 0.4, 1.0, |i, freq| match i {
+        2 => freq * 2f64.powf(80.0 / 1200.0),
+        4 => freq / 2.0,
+        _ => freq * 2f64.powf(-10.0 / 1200.0),
+    });
+    let score = score(&melody, &performance, DEFAULT_A4, DEFAULT_TOLERANCE);
+
+    let third = &score.notes[2];
+    assert!(third.accuracy() < 0.2, "{:?}", third);
+    assert!((third.cents.unwrap() - 80.0).abs() < 10.0, "{:?}", third);
+    let fifth = &score.notes[4];
+    assert!(fifth.accuracy() > 0.8, "{:?}", fifth);
+    assert!(fifth.cents.unwrap().abs() < 5.0, "{:?}", fifth);
+    assert!(score.notes[0].accuracy() > 0.8);
+    assert!(
+        score.accuracy > 0.7 && score.accuracy < 0.95,
+        "{}",
+        score.accuracy
+    );
+}
+
+#[test]
+fn scores_silence_as_nothing_sung() {
+    let melody = parse_melody(MELODY).unwrap();
+    let performance: Vec<_> = (0..100).map(|i| (i as f64 * 0.05, None)).collect();
+    let score = score(&melody, &performance, DEFAULT_A4, DEFAULT_TOLERANCE);
+    assert_eq!(score.accuracy, 0.0);
+    assert!(score
+        .notes
+        .iter()
+        .all(|note| note.sung == 0 && note.cents.is_none()));
+}
@@ -0,0 +1,71 @@
+//! Low notes with a weak fundamental shouldn't be mistaken for their stronger harmonics.
+
+use monophonic_detector::{
+    cents_between,
+    generate::{Generator, Tones},
+    shs, PitchDetector,
+};
+
+/// E1, G1 and A1, the bottom of a bass, and C2, the bottom of a cello.
+const FREQS: &[f64] = &[41.2, 49.0, 55.0, 65.41];
+
+/// Series with a weak fundamental under strong 2nd and 3rd harmonics.
+const LOW_SERIES: &[&[f64]] = &[&[0.1, 0.8, 1.0, 0.6, 0.4], &[0.2, 1.0, 0.6, 0.4, 0.3]];
+
+fn tone(freq: f64, harmonics: &[f64]) -> Vec<f32> {
+    Generator::new(Tones::Steps(vec![freq]))
+        .with_duration(0.5)
+        .with_harmonics(harmonics.to_vec())
+        .samples()
+}
+
+#[test]
+fn finds_the_fundamental_of_low_tones() {
+    let detector = PitchDetector::default();
+    for &freq in FREQS {
+        for &harmonics in LOW_SERIES {
+            for chunk in detector.chunks(&tone(freq, harmonics)) {
+                let detected = detector.detect(chunk).freq.unwrap();
+                let cents = cents_between(freq, detected);
+                assert!(
+                    cents.abs() < 50.0,
+                    "{} Hz with harmonics {:?} detected as {} Hz ({:+.0}¢)",
+                    freq,
+                    harmonics,
+                    detected,
+                    cents,
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn moves_harmonics_down_to_the_fundamental() {
+    let samples = tone(55.0, LOW_SERIES[0]);
+    let sample_rate = PitchDetector::default().sample_rate();
+    for harmonic in 1..=3 {
+        let fundamental = shs::fundamental(&samples, sample_rate, 40.0, 55.0 * harmonic as f64);
+        assert_eq!(fundamental, 55.0, "from harmonic {}", harmonic);
+    }
+    // Not below the lowest frequency searched.
+    assert_eq!(shs::fundamental(&samples, sample_rate, 60.0, 165.0), 165.0);
+}
+
+#[test]
+fn leaves_two_notes_a_fifth_apart_alone() {
+    // A3 and E4 at once, the 2nd and 3rd harmonics of A2.
+    let note = |freq| {
+        Generator::new(Tones::Steps(vec![freq]))
+            .with_duration(0.5)
+            .with_amplitude(0.5)
+            .samples()
+    };
+    let samples: Vec<f32> = note(220.0)
+        .iter()
+        .zip(note(329.63))
+        .map(|(a, b)| a + b)
+        .collect();
+    let sample_rate = PitchDetector::default().sample_rate();
+    assert_eq!(shs::fundamental(&samples, sample_rate, 40.0, 220.0), 220.0);
+}
@@ -0,0 +1,29 @@
+//! The vectorized dot product should match the scalar one, whatever the length and alignment.
+
+use monophonic_detector::{
+    generate::{Generator, Noise, Tones},
+    simd,
+};
+
+#[test]
+fn dot_matches_scalar() {
+    let samples = Generator::new(Tones::Steps(vec![440.0]))
+        .with_duration(0.01)
+        .with_noise(Noise::White, 0.0)
+        .samples();
+
+    for len in 0..100 {
+        for lag in 0..5 {
+            let (a, b) = (&samples[..len], &samples[lag..lag + len]);
+            let (fast, scalar) = (simd::dot(a, b), simd::dot_scalar(a, b));
+            assert!(
+                (fast - scalar).abs() <= 1e-9 * scalar.abs().max(1.0),
+                "length {}, lag {}: {} != {}",
+                len,
+                lag,
+                fast,
+                scalar
+            );
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! Smoothing should only hold on to notes the detector named.
+
+use monophonic_detector::{smooth::Smoother, DetectedPitch, PitchDetector};
+
+#[test]
+fn estimates_too_unsure_to_name_stay_unknown() {
+    let detector = PitchDetector::default();
+    let named = detector.identify(440.0);
+    // A frequency, but below the min confidence, so without a pitch.
+    let unsure = DetectedPitch {
+        pitch: None,
+        cents: 0.0,
+        confidence: 0.2,
+        ..detector.identify(330.0)
+    };
+
+    let mut smoother = Smoother::default();
+    for _ in 0..4 {
+        assert_eq!(smoother.push(&detector, unsure).pitch, None);
+    }
+    for _ in 0..4 {
+        smoother.push(&detector, named);
+    }
+    let held = smoother.push(&detector, named);
+    assert_eq!(held.pitch, named.pitch);
+    assert_eq!(held.freq, Some(440.0));
+}
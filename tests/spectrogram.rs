@@ -0,0 +1,123 @@
+//! Spectrograms should be valid PNG images, a pixel wide per column, with the detected pitch
+//! drawn over them where it is.
+
+use std::convert::TryInto;
+
+use monophonic_detector::{
+    generate::{Generator, Tones},
+    png::write_png,
+    spectrogram::{Spectrogram, HEIGHT, HOP, MAX_WIDTH},
+    PitchDetector,
+};
+
+const CYAN: [u8; 3] = [0, 255, 255];
+
+/// The width, height and rows of pixels of a PNG image as `write_png` writes it.
+fn decode(file: &[u8]) -> (usize, usize, Vec<Vec<[u8; 3]>>) {
+    assert_eq!(&file[..8], b"\x89PNG\r\n\x1a\n");
+    let (mut at, mut size, mut idat) = (8, (0, 0), vec![]);
+    while at < file.len() {
+        let len = u32::from_be_bytes(file[at..at + 4].try_into().unwrap()) as usize;
+        let data = &file[at + 8..at + 8 + len];
+        match &file[at + 4..at + 8] {
+            b"IHDR" => {
+                let dimension = |i: usize| u32::from_be_bytes(data[i..i + 4].try_into().unwrap());
+                size = (dimension(0) as usize, dimension(4) as usize);
+                assert_eq!(&data[8..], &[8, 2, 0, 0, 0]);
+            }
+            b"IDAT" => idat.extend_from_slice(data),
+            _ => {}
+        }
+        at += 12 + len;
+    }
+    assert_eq!(&file[file.len() - 8..file.len() - 4], b"IEND");
+
+    // Stored deflate blocks, after the zlib header.
+    let (mut at, mut raw) = (2, vec![]);
+    loop {
+        let last = idat[at] & 1 == 1;
+        assert_eq!(idat[at] >> 1, 0, "not a stored block");
+        let len = u16::from_le_bytes([idat[at + 1], idat[at + 2]]) as usize;
+        raw.extend_from_slice(&idat[at + 5..at + 5 + len]);
+        at += 5 + len;
+        if last {
+            break;
+        }
+    }
+
+    let (width, height) = size;
+    let rows = raw
+        .chunks(1 + 3 * width)
+        .map(|row| {
+            assert_eq!(row[0], 0, "filtered row");
+            row[1..].chunks(3).map(|p| [p[0], p[1], p[2]]).collect()
+        })
+        .collect();
+    (width, height, rows)
+}
+
+#[test]
+fn writes_an_image() {
+    let pixels = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [1, 2, 3]];
+    let mut file = vec![];
+    write_png(&mut file, 2, 2, &pixels).unwrap();
+    let (width, height, rows) = decode(&file);
+    assert_eq!((width, height), (2, 2));
+    assert_eq!(rows, vec![pixels[..2].to_vec(), pixels[2..].to_vec()]);
+}
+
+#[test]
+fn draws_the_pitch_over_the_spectrum() {
+    let detector = PitchDetector::default();
+    let samples = Generator::new(Tones::Steps(vec![220.0, 880.0]))
+        .with_duration(2.0)
+        .samples();
+    let mut spectrogram = Spectrogram::new(detector.sample_rate(), 40.0, Some(5000.0));
+    for chunk in detector.chunks(&samples) {
+        spectrogram.push(chunk, &detector.detect(chunk));
+    }
+    let mut file = vec![];
+    spectrogram.write_png(&mut file).unwrap();
+
+    let (width, height, rows) = decode(&file);
+    assert_eq!(height, HEIGHT);
+    assert!((width as f64 * HOP - 2.0).abs() < 0.2, "{}", width);
+
+    // The row of each frequency on the log scale from 5000 Hz down to 40 Hz.
+    let row = |freq: f64| (HEIGHT as f64 * (5000.0 / freq).ln() / (5000.0f64 / 40.0).ln()) as usize;
+    for (x, freq) in &[(width / 4, 220.0), (width * 3 / 4, 880.0)] {
+        let track: Vec<usize> = (0..height).filter(|&y| rows[y][*x] == CYAN).collect();
+        assert!(track.contains(&row(*freq)), "{}: {:?}", freq, track);
+        // The tone itself is loud just beside the track.
+        let beside = rows[row(*freq) + 2][*x];
+        assert!(
+            beside.iter().map(|c| *c as u32).sum::<u32>() > 500,
+            "{:?}",
+            beside
+        );
+    }
+}
+
+#[test]
+fn merges_long_signals_to_fit() {
+    // 50 s of columns, 10 ms apart, is more than `MAX_WIDTH`.
+    let sample_rate = 8000;
+    let samples = Generator::new(Tones::Steps(vec![880.0]))
+        .with_duration(50.0)
+        .with_sample_rate(sample_rate)
+        .samples();
+    let detected = PitchDetector::default().identify(880.0);
+    let mut spectrogram = Spectrogram::new(sample_rate, 40.0, Some(4000.0));
+    for chunk in samples.chunks(1024) {
+        spectrogram.push(chunk, &detected);
+    }
+    let mut file = vec![];
+    spectrogram.write_png(&mut file).unwrap();
+
+    let (width, _, rows) = decode(&file);
+    assert!(width <= MAX_WIDTH && width > MAX_WIDTH / 2, "{}", width);
+    let row = (HEIGHT as f64 * (4000.0f64 / 880.0).ln() / 100.0f64.ln()) as usize;
+    for x in &[0, width / 2, width - 2] {
+        assert_eq!(rows[row][*x], CYAN, "{}", x);
+    }
+}
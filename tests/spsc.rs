@@ -0,0 +1,68 @@
+//! The lock-free ring should hand samples over in order, turning away what doesn't fit.
+
+use std::thread;
+
+use monophonic_detector::spsc;
+
+#[test]
+fn pops_what_was_pushed_in_order() {
+    let (producer, consumer) = spsc::ring(8);
+    assert_eq!(producer.push(&[1.0, 2.0, 3.0]), 3);
+    let mut out = [0.0; 2];
+    assert_eq!(consumer.pop(&mut out), 2);
+    assert_eq!(out, [1.0, 2.0]);
+    assert_eq!(consumer.len(), 1);
+}
+
+#[test]
+fn turns_away_samples_when_full() {
+    let (producer, consumer) = spsc::ring(4);
+    assert_eq!(producer.push(&[1.0; 6]), 4);
+    assert_eq!(producer.push_silence(1), 0);
+    let mut out = [0.0; 3];
+    consumer.pop(&mut out);
+    // Round and round past the end.
+    assert_eq!(producer.push_silence(3), 3);
+    let mut out = [9.0; 8];
+    assert_eq!(consumer.pop(&mut out), 4);
+    assert_eq!(out[..4], [1.0, 0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn closes_when_the_producer_is_dropped() {
+    let (producer, consumer) = spsc::ring(4);
+    producer.push(&[0.5]);
+    assert!(!consumer.is_closed());
+    drop(producer);
+    assert!(consumer.is_closed());
+    assert_eq!(consumer.len(), 1);
+}
+
+#[test]
+fn hands_samples_across_threads() {
+    let (producer, consumer) = spsc::ring(64);
+    let total = 100_000;
+    let pushing = thread::spawn(move || {
+        let mut next = 0;
+        while next < total {
+            let block: Vec<f32> = (next..(next + 10).min(total)).map(|i| i as f32).collect();
+            match producer.push(&block) {
+                0 => thread::yield_now(),
+                n => next += n,
+            }
+        }
+    });
+    let mut expected = 0;
+    let mut out = [0.0; 16];
+    while expected < total {
+        let n = consumer.pop(&mut out);
+        if n == 0 {
+            thread::yield_now();
+        }
+        for sample in &out[..n] {
+            assert_eq!(*sample, expected as f32);
+            expected += 1;
+        }
+    }
+    pushing.join().unwrap();
+}
@@ -0,0 +1,97 @@
+//! The async stream should yield the same estimates as the live input it runs, then end.
+
+#![cfg(feature = "async")]
+
+use std::future::Future;
+use std::io::{self, Read};
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake};
+use std::thread::{self, Thread};
+
+use monophonic_detector::{
+    generate::{Generator, Tones},
+    live::LiveInput,
+    PitchDetector,
+};
+
+/// Wakes a task by unparking the thread blocking on it.
+struct Unpark(Thread);
+
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Run `future` to completion on this thread, as an async runtime would.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Arc::new(Unpark(thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+fn pcm(seconds: f64) -> Vec<u8> {
+    Generator::new(Tones::Steps(vec![440.0]))
+        .with_duration(seconds)
+        .samples()
+        .iter()
+        .flat_map(|s| ((s * 32767.0) as i16).to_le_bytes())
+        .collect()
+}
+
+#[test]
+fn yields_the_live_estimates() {
+    let pcm = pcm(1.0);
+    let mut expected = vec![];
+    let mut live = LiveInput::new(&pcm[..], PitchDetector::default());
+    while let Some(detected) = live.next_estimate().unwrap() {
+        expected.push((live.timestamp(), detected));
+    }
+
+    let mut stream = LiveInput::new(io::Cursor::new(pcm), PitchDetector::default()).into_stream();
+    let frames = block_on(async {
+        let mut frames = vec![];
+        while let Some(frame) = stream.next_frame().await {
+            frames.push((frame.timestamp, frame.detected));
+        }
+        frames
+    });
+    assert!(!frames.is_empty());
+    assert_eq!(frames, expected);
+    assert!(stream.take_error().is_none());
+}
+
+/// Reads a second of PCM, then fails.
+struct Failing(io::Cursor<Vec<u8>>);
+
+impl Read for Failing {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf)? {
+            0 => Err(io::Error::other("unplugged")),
+            n => Ok(n),
+        }
+    }
+}
+
+#[test]
+fn ends_with_the_error_reading_failed_with() {
+    let input = Failing(io::Cursor::new(pcm(1.0)));
+    let mut stream = LiveInput::new(input, PitchDetector::default()).into_stream();
+    let count = block_on(async {
+        let mut count = 0;
+        while stream.next_frame().await.is_some() {
+            count += 1;
+        }
+        count
+    });
+    assert!(count > 0);
+    assert_eq!(stream.take_error().unwrap().to_string(), "unplugged");
+    // It stays ended.
+    assert_eq!(block_on(stream.next_frame()), None);
+}
@@ -0,0 +1,111 @@
+//! Summaries should add up the time on each note and how far from it it was, and leave out rests
+//! and chunks with no pitch.
+
+use monophonic_detector::{
+    names::NoteNames,
+    output::{write_summary, Format},
+    summary::Summary,
+    DetectedPitch, Pitch, PitchDetector, DEFAULT_A4,
+};
+
+fn pitch(name: &str) -> Pitch {
+    name.parse().unwrap()
+}
+
+/// An estimate `cents` off `name`.
+fn sung(name: &str, cents: f64) -> DetectedPitch {
+    let freq = pitch(name).freq(DEFAULT_A4) * 2f64.powf(cents / 1200.0);
+    PitchDetector::default().identify(freq)
+}
+
+fn rest() -> DetectedPitch {
+    DetectedPitch {
+        freq: None,
+        pitch: None,
+        cents: 0.0,
+        confidence: 0.0,
+        rest: true,
+        unvoiced: false,
+    }
+}
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-6
+}
+
+#[test]
+fn adds_up_the_time_and_cents_on_each_note() {
+    let mut summary = Summary::new();
+    for cents in [10.0, 20.0, 30.0].iter() {
+        summary.push(&sung("A4", *cents), 0.5);
+    }
+    summary.push(&sung("C4", -5.0), 0.5);
+    summary.push(&rest(), 1.0);
+
+    let notes = summary.notes();
+    assert_eq!(notes.len(), 2);
+    // From the lowest up.
+    assert_eq!(notes[0].pitch, pitch("C4"));
+    assert!(close(notes[0].time, 0.5));
+    assert!(close(notes[0].share, 0.25));
+    assert!(close(notes[0].mean_cents, -5.0), "{}", notes[0].mean_cents);
+    assert!(
+        close(notes[0].spread_cents, 0.0),
+        "{}",
+        notes[0].spread_cents
+    );
+    assert_eq!(notes[1].pitch, pitch("A4"));
+    assert!(close(notes[1].time, 1.5));
+    assert!(close(notes[1].share, 0.75));
+    assert!(close(notes[1].mean_cents, 20.0), "{}", notes[1].mean_cents);
+    let spread = (200.0f64 / 3.0).sqrt();
+    assert!(
+        close(notes[1].spread_cents, spread),
+        "{}",
+        notes[1].spread_cents
+    );
+
+    assert!(close(summary.duration(), 3.0));
+    assert!(close(summary.pitched_time(), 2.0));
+    assert!(close(summary.rest_time(), 1.0));
+    assert!(close(summary.unknown_time(), 0.0));
+    assert!(close(summary.mean_cents().unwrap(), 13.75));
+    assert!(close(summary.mean_absolute_cents().unwrap(), 16.25));
+}
+
+#[test]
+fn measures_the_intonation() {
+    let mut summary = Summary::new();
+    for cents in [0.0, 5.0, -8.0, 25.0, -30.0, 40.0, 2.0, -1.0].iter() {
+        summary.push(&sung("E4", *cents), 0.25);
+    }
+    let (in_tune, sharp, flat) = summary.intonation().unwrap();
+    assert!(close(in_tune, 0.625));
+    assert!(close(sharp, 0.25));
+    assert!(close(flat, 0.125));
+}
+
+#[test]
+fn has_no_intonation_without_a_pitch() {
+    let mut summary = Summary::new();
+    summary.push(&rest(), 0.25);
+    summary.push(&PitchDetector::default().identify(10_000.0), 0.25);
+    assert!(summary.notes().is_empty());
+    assert!(close(summary.unknown_time(), 0.25));
+    assert_eq!(summary.mean_cents(), None);
+    assert_eq!(summary.intonation(), None);
+}
+
+#[test]
+fn writes_csv_with_a_line_for_all_notes() {
+    let mut summary = Summary::new();
+    summary.push(&sung("G3", 10.0), 1.0);
+    summary.push(&sung("G3", -10.0), 1.0);
+    let mut out = vec![];
+    write_summary(&mut out, &summary, Format::Csv, NoteNames::German).unwrap();
+    let lines: Vec<&str> = std::str::from_utf8(&out).unwrap().lines().collect();
+    assert_eq!(lines[0], "note,octave,time_s,share,mean_cents,spread_cents");
+    assert!(lines[1].starts_with("G,3,2,1,"), "{}", lines[1]);
+    assert!(lines[2].starts_with("all,,2,1,"), "{}", lines[2]);
+    assert_eq!(lines.len(), 3);
+}
@@ -0,0 +1,164 @@
+//! Temperaments should tune pure intervals from the tonic, keep A4 at the reference pitch, and
+//! leave equal temperament exactly as it was.
+
+use monophonic_detector::{temperament::Temperament, Note, Pitch, PitchDetector, DEFAULT_A4};
+
+fn pitch(name: &str, octave: i8) -> Pitch {
+    Pitch {
+        note: name.parse().unwrap(),
+        octave,
+    }
+}
+
+#[test]
+fn equal_temperament_is_unchanged() {
+    let detector = PitchDetector::default();
+    for midi in 21..=108 {
+        let pitch = Pitch::from_midi(midi);
+        assert_eq!(detector.pitch_freq(pitch), pitch.freq(DEFAULT_A4));
+    }
+}
+
+#[test]
+fn keeps_a4_at_the_reference() {
+    for temperament in &[
+        Temperament::Just,
+        Temperament::Pythagorean,
+        Temperament::Meantone,
+    ] {
+        for tonic in Note::ALL.iter() {
+            let freq = temperament.freq(pitch("A", 4), *tonic, DEFAULT_A4);
+            assert!(
+                (freq - DEFAULT_A4).abs() < 1e-9,
+                "{} on {}",
+                temperament,
+                tonic
+            );
+        }
+    }
+}
+
+#[test]
+fn tunes_pure_intervals_from_the_tonic() {
+    let a4 = DEFAULT_A4;
+    let ratio = |temperament: Temperament, tonic: &str, from: Pitch, to: Pitch| {
+        let tonic = tonic.parse().unwrap();
+        temperament.freq(to, tonic, a4) / temperament.freq(from, tonic, a4)
+    };
+    let close = |ratio: f64, expected: f64| (ratio / expected - 1.0).abs() < 1e-5;
+
+    // A just major third and fifth above the tonic.
+    assert!(close(
+        ratio(Temperament::Just, "A", pitch("A", 4), pitch("C#", 5)),
+        1.25
+    ));
+    assert!(close(
+        ratio(Temperament::Just, "C", pitch("C", 4), pitch("G", 4)),
+        1.5
+    ));
+    // Pythagorean fifths everywhere in the chain, and a wide third.
+    assert!(close(
+        ratio(Temperament::Pythagorean, "C", pitch("D", 4), pitch("A", 4)),
+        1.5
+    ));
+    assert!(close(
+        ratio(Temperament::Pythagorean, "C", pitch("C", 4), pitch("E", 4)),
+        81.0 / 64.0
+    ));
+    // Meantone's pure thirds, and its narrow fifths.
+    assert!(close(
+        ratio(Temperament::Meantone, "D", pitch("F", 4), pitch("A", 4)),
+        1.25
+    ));
+    assert!(ratio(Temperament::Meantone, "D", pitch("D", 4), pitch("A", 4)) < 1.4983);
+}
+
+#[test]
+fn names_notes_in_the_temperament() {
+    // A just major third above A4 is 550 Hz, 14 cents flat of the equally-tempered C#5.
+    let equal = PitchDetector::default().with_note_tolerance(5.0);
+    assert_eq!(equal.identify(550.0).pitch, None);
+    let detected = PitchDetector::default().identify(550.0);
+    assert_eq!(detected.pitch, Some(pitch("C#", 5)));
+    assert!((detected.cents + 13.7).abs() < 0.1, "{}", detected.cents);
+
+    let just = PitchDetector::default()
+        .with_temperament(Temperament::Just, Note::A)
+        .with_note_tolerance(5.0);
+    let detected = just.identify(550.0);
+    assert_eq!(detected.pitch, Some(pitch("C#", 5)));
+    assert!(detected.cents.abs() < 0.01, "{}", detected.cents);
+    assert_eq!(just.identify(554.37).pitch, None);
+}
+
+#[test]
+fn names_notes_within_a_tolerance_in_cents() {
+    // 1 Hz is 53 cents at C1, and less than a cent at B6, but the tolerance is the same for both.
+    let detector = PitchDetector::default().with_strict_match(true);
+    let c1 = pitch("C", 1).freq(DEFAULT_A4);
+    let b6 = pitch("B", 6).freq(DEFAULT_A4);
+    assert_eq!(detector.identify(c1 + 0.3).pitch, Some(pitch("C", 1)));
+    assert_eq!(detector.identify(c1 + 1.0).pitch, None);
+    assert_eq!(detector.identify(b6 + 10.0).pitch, Some(pitch("B", 6)));
+    assert_eq!(detector.identify(b6 + 40.0).pitch, None);
+
+    let nearest = PitchDetector::default();
+    assert_eq!(nearest.identify(c1 + 1.0).pitch, Some(pitch("C#", 1)));
+    assert_eq!(nearest.identify(b6 + 40.0).pitch, Some(pitch("B", 6)));
+}
+
+#[test]
+#[allow(deprecated)]
+fn still_takes_a_tolerance_in_hz() {
+    // 1 Hz above A4 is 3.9 cents.
+    let detector = PitchDetector::default().with_epsilon(1.0);
+    assert!((detector.note_tolerance().unwrap() - 3.93).abs() < 0.01);
+    assert!((detector.epsilon() - 1.0).abs() < 1e-9);
+    assert_eq!(detector.identify(441.5).pitch, None);
+    assert_eq!(PitchDetector::default().epsilon(), f64::INFINITY);
+}
+
+#[test]
+fn always_names_the_nearest_note_unless_strict() {
+    let detector = PitchDetector::default();
+    for midi in 12..=119 {
+        let note = Pitch::from_midi(midi);
+        let freq = note.freq(DEFAULT_A4);
+        for cents in [-49.0, -20.0, 0.0, 20.0, 49.0].iter() {
+            let detected = detector.identify(freq * 2f64.powf(cents / 1200.0));
+            assert_eq!(detected.pitch, Some(note), "{} {:+}", note, cents);
+            assert!((detected.cents - cents).abs() < 1e-6, "{}", detected.cents);
+        }
+    }
+    assert_eq!(detector.identify(5.0).pitch, None);
+    assert_eq!(detector.identify(0.0).pitch, None);
+
+    let strict = PitchDetector::default().with_strict_match(true);
+    assert_eq!(strict.identify(440.0 * 2f64.powf(0.3 / 12.0)).pitch, None);
+}
+
+#[test]
+fn parses_temperaments() {
+    for name in &["equal", "just", "pythagorean", "meantone"] {
+        let temperament: Temperament = name.parse().unwrap();
+        assert_eq!(temperament.to_string(), *name);
+    }
+
+    let cents = "0,100,200,300,400,500,600,700,800,900,1000,1100";
+    let custom: Temperament = cents.parse().unwrap();
+    assert_eq!(custom.cents(), Temperament::Equal.cents());
+    assert_eq!(custom.to_string(), cents);
+    assert_eq!(custom.deviation(pitch("E", 4), Note::C), 0.0);
+
+    assert!("0,100".parse::<Temperament>().is_err());
+    assert!("werckmeister".parse::<Temperament>().is_err());
+}
+
+#[test]
+fn parses_notes() {
+    assert_eq!("C".parse(), Ok(Note::C));
+    assert_eq!("f#".parse(), Ok(Note::FSharp));
+    assert_eq!("Bb".parse(), Ok(Note::ASharp));
+    assert_eq!("Cb".parse(), Ok(Note::B));
+    assert!("H".parse::<Note>().is_err());
+}
@@ -0,0 +1,77 @@
+//! Tempos should be estimated from onsets in common rhythms, played a little unevenly, at the
+//! tempo a listener would tap along to rather than twice or half as fast.
+
+use monophonic_detector::tempo::{estimate_tempo, onsets};
+use monophonic_detector::{notes::NoteEvent, Pitch};
+
+/// Onsets at `bpm`, on the beats and fractions of a beat in `rhythm` (the length of each note in
+/// beats, repeated to make `bars` times as many), each up to `jitter` seconds early or late.
+fn play(bpm: f64, rhythm: &[f64], bars: usize, jitter: f64) -> Vec<f64> {
+    let beat = 60.0 / bpm;
+    let mut onsets = vec![];
+    let mut time = 0.0;
+    for i in 0..bars * rhythm.len() {
+        // Deterministic jitter, evenly spread from -1 to 1.
+        let offset = ((i * 7919) % 21) as f64 / 10.0 - 1.0;
+        onsets.push(time + offset * jitter);
+        time += rhythm[i % rhythm.len()] * beat;
+    }
+    onsets
+}
+
+fn assert_tempo(onsets: &[f64], bpm: f64) {
+    let estimate = estimate_tempo(onsets).expect("no tempo");
+    assert!(
+        (estimate.bpm - bpm).abs() < 1.0,
+        "{:?}, not {}",
+        estimate,
+        bpm
+    );
+    assert!(estimate.strength > 0.3, "{:?}", estimate);
+}
+
+#[test]
+fn finds_the_tempo_of_steady_beats() {
+    for &bpm in &[60.0, 90.0, 100.0, 128.0, 160.0] {
+        assert_tempo(&play(bpm, &[1.0], 16, 0.0), bpm);
+    }
+}
+
+#[test]
+fn finds_the_tempo_of_uneven_playing() {
+    assert_tempo(&play(110.0, &[1.0], 24, 0.015), 110.0);
+}
+
+#[test]
+fn finds_the_beat_under_mixed_rhythms() {
+    // Quarter, two eighths, dotted quarter and eighth, and a half note, in 4/4.
+    let rhythm = [1.0, 0.5, 0.5, 1.5, 0.5, 2.0];
+    assert_tempo(&play(96.0, &rhythm, 8, 0.01), 96.0);
+}
+
+#[test]
+fn prefers_moderate_tempos() {
+    // Eighth notes at 120 fit 240 beats per minute as well, and sixteenths at 60 fit 120.
+    assert_tempo(&play(120.0, &[0.5], 32, 0.0), 120.0);
+    assert_tempo(&play(60.0, &[0.25, 0.25, 0.5, 1.0], 12, 0.0), 120.0);
+}
+
+#[test]
+fn reads_onsets_from_notes() {
+    let notes: Vec<NoteEvent> = play(100.0, &[1.0], 8, 0.0)
+        .into_iter()
+        .map(|start| NoteEvent {
+            pitch: Pitch::from_midi(60),
+            start,
+            end: start + 0.5,
+            velocity: 100,
+        })
+        .collect();
+    assert_tempo(&onsets(&notes), 100.0);
+}
+
+#[test]
+fn needs_a_few_onsets() {
+    assert_eq!(estimate_tempo(&[]), None);
+    assert_eq!(estimate_tempo(&[0.0, 0.5, 1.0]), None);
+}
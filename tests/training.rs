@@ -0,0 +1,144 @@
+//! The ear-training game should score a note held in tune, in any octave unless told otherwise,
+//! and nothing else, and keep count of how the player is doing.
+
+use monophonic_detector::{
+    training::{Outcome, Trainer, DEFAULT_TIMEOUT, HOLD},
+    Pitch, DEFAULT_A4,
+};
+
+fn pitch(name: &str) -> Pitch {
+    name.parse().unwrap()
+}
+
+/// Answer the round with `freq` until it ends, every 50 ms from `from` seconds on.
+fn answer(trainer: &mut Trainer, freq: Option<f64>, from: f64) -> (Outcome, f64) {
+    (0..)
+        .map(|i| from + i as f64 * 0.05)
+        .take_while(|time| *time < from + 2.0 * DEFAULT_TIMEOUT)
+        .find_map(|time| trainer.listen(freq, time).map(|outcome| (outcome, time)))
+        .expect("the round never ended")
+}
+
+#[test]
+fn picks_notes_in_range() {
+    let mut trainer = Trainer::new(pitch("C4"), pitch("E4"), DEFAULT_A4, 7);
+    let notes: Vec<Pitch> = (0..100).map(|_| trainer.next_note(0.0)).collect();
+    for note in &notes {
+        assert!((60..=64).contains(&note.midi()), "{}", note);
+    }
+    for pair in notes.windows(2) {
+        assert_ne!(pair[0], pair[1]);
+    }
+    for midi in 60..=64 {
+        assert!(notes.iter().any(|note| note.midi() == midi), "{}", midi);
+    }
+
+    // The same seed plays the same game.
+    let mut again = Trainer::new(pitch("C4"), pitch("E4"), DEFAULT_A4, 7);
+    let replayed: Vec<Pitch> = (0..100).map(|_| again.next_note(0.0)).collect();
+    assert_eq!(notes, replayed);
+}
+
+#[test]
+fn scores_a_held_note() {
+    let mut trainer = Trainer::new(pitch("A4"), pitch("A4"), DEFAULT_A4, 1);
+    assert_eq!(trainer.next_note(1.0), pitch("A4"));
+
+    // The note itself, heard before listening starts, doesn't count.
+    assert_eq!(trainer.listen(Some(440.0), 0.5), None);
+    assert_eq!(trainer.listen(Some(440.0), 0.99), None);
+
+    let sharp = 440.0 * 2f64.powf(20.0 / 1200.0);
+    let (outcome, time) = answer(&mut trainer, Some(sharp), 1.5);
+    match outcome {
+        Outcome::Hit {
+            target,
+            time,
+            cents,
+        } => {
+            assert_eq!(target, pitch("A4"));
+            assert!((time - 0.5).abs() < 1e-9, "{}", time);
+            assert!((cents - 20.0).abs() < 1e-6, "{}", cents);
+        }
+        outcome => panic!("{:?}", outcome),
+    }
+    assert!(time - 1.5 >= HOLD);
+
+    // The round is over.
+    assert_eq!(trainer.listen(Some(440.0), time + 1.0), None);
+}
+
+#[test]
+fn waits_for_the_note_to_be_held() {
+    let mut trainer = Trainer::new(pitch("A4"), pitch("A4"), DEFAULT_A4, 1);
+    trainer.next_note(0.0);
+    assert_eq!(trainer.listen(Some(440.0), 0.0), None);
+    assert_eq!(trainer.listen(Some(466.16), 0.2), None);
+    assert_eq!(trainer.listen(Some(440.0), 0.3), None);
+    assert_eq!(trainer.listen(None, 0.5), None);
+    assert_eq!(trainer.listen(Some(440.0), 0.6), None);
+    assert!(trainer.listen(Some(440.0), 0.65 + HOLD).is_some());
+}
+
+#[test]
+fn counts_other_octaves_unless_told_not_to() {
+    let mut trainer = Trainer::new(pitch("A4"), pitch("A4"), DEFAULT_A4, 1);
+    trainer.next_note(0.0);
+    let (outcome, _) = answer(&mut trainer, Some(220.0), 0.0);
+    assert!(matches!(outcome, Outcome::Hit { .. }), "{:?}", outcome);
+
+    let mut trainer = trainer.with_exact_octave(true);
+    trainer.next_note(0.0);
+    let (outcome, time) = answer(&mut trainer, Some(220.0), 0.0);
+    assert_eq!(
+        outcome,
+        Outcome::Miss {
+            target: pitch("A4"),
+            heard: Some(220.0)
+        }
+    );
+    assert!(time >= DEFAULT_TIMEOUT);
+}
+
+#[test]
+fn misses_out_of_tune_notes() {
+    let mut trainer = Trainer::new(pitch("A4"), pitch("A4"), DEFAULT_A4, 1)
+        .with_tolerance(25.0)
+        .with_timeout(3.0);
+    trainer.next_note(0.0);
+    let flat = 440.0 * 2f64.powf(-30.0 / 1200.0);
+    let (outcome, time) = answer(&mut trainer, Some(flat), 0.0);
+    assert!(matches!(outcome, Outcome::Miss { .. }), "{:?}", outcome);
+    assert!((3.0..3.1).contains(&time), "{}", time);
+}
+
+#[test]
+fn keeps_statistics() {
+    let mut trainer = Trainer::new(pitch("C4"), pitch("B4"), DEFAULT_A4, 3);
+    for round in 0..6 {
+        let note = trainer.next_note(0.0);
+        // Miss the third round.
+        let freq = Some(note.freq(DEFAULT_A4)).filter(|_| round != 2);
+        answer(&mut trainer, freq, 0.0);
+    }
+    let stats = trainer.stats();
+    assert_eq!(stats.rounds, 6);
+    assert_eq!(stats.hits, 5);
+    assert_eq!(stats.streak, 3);
+    assert_eq!(stats.best_streak, 3);
+    assert!((stats.accuracy() - 5.0 / 6.0).abs() < 1e-9);
+    assert!(stats.mean_cents().unwrap() < 1e-6);
+    assert!(stats.mean_answer_time().unwrap() < 1e-9);
+    assert_eq!(
+        stats.notes.values().map(|note| note.rounds).sum::<usize>(),
+        6
+    );
+    assert_eq!(
+        stats
+            .notes
+            .values()
+            .filter(|note| note.hits < note.rounds)
+            .count(),
+        1
+    );
+}
@@ -0,0 +1,48 @@
+//! Transposed detectors should name pitches as written for the instrument, and measure cents from
+//! the written note's sounding pitch.
+
+use monophonic_detector::{Note, Pitch, PitchDetector};
+
+#[test]
+fn names_written_pitches() {
+    // A concert A4 is written B4 for a Bb clarinet.
+    let clarinet = PitchDetector::default().with_transposition(2);
+    let detected = clarinet.identify(440.0);
+    assert_eq!(
+        detected.pitch,
+        Some(Pitch {
+            note: Note::B,
+            octave: 4
+        })
+    );
+    assert!(detected.cents.abs() < 1e-9);
+    assert!((clarinet.written_a4() - 391.995).abs() < 1e-3);
+
+    // A concert C4 is written A4 for an alto sax.
+    let sax = PitchDetector::default().with_transposition(9);
+    let detected = sax.identify(261.63);
+    assert_eq!(
+        detected.pitch.map(|pitch| pitch.to_string()),
+        Some("A4".to_string())
+    );
+}
+
+#[test]
+fn measures_cents_from_the_sounding_pitch() {
+    let detector = PitchDetector::default().with_transposition(-12);
+    let written = Pitch {
+        note: Note::A,
+        octave: 3,
+    };
+    assert_eq!(detector.pitch_freq(written), 440.0);
+    let detected = detector.identify(440.5);
+    assert_eq!(detected.pitch, Some(written));
+    assert!((detected.cents - 1.97).abs() < 0.01, "{}", detected.cents);
+}
+
+#[test]
+fn doesnt_transpose_by_default() {
+    let detector = PitchDetector::default();
+    assert_eq!(detector.transposition(), 0);
+    assert_eq!(detector.written_a4(), detector.a4());
+}
@@ -0,0 +1,183 @@
+//! Microtonal tunings should name every degree, match estimates to the nearest one, and read
+//! Scala files.
+
+use monophonic_detector::{
+    output::{Format, Record, RecordWriter},
+    tuning::{TunedPitch, Tuning},
+    DetectedPitch, Note, PitchDetector,
+};
+
+fn names(tuning: &Tuning) -> Vec<&str> {
+    (0..tuning.len())
+        .map(|degree| tuning.name(degree))
+        .collect()
+}
+
+#[test]
+fn names_edo_degrees() {
+    assert_eq!(
+        names(&Tuning::edo(12)),
+        ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"]
+    );
+    assert_eq!(
+        names(&Tuning::edo(19)),
+        [
+            "C", "C#", "Db", "D", "D#", "Eb", "E", "E#", "F", "F#", "Gb", "G", "G#", "Ab", "A",
+            "A#", "Bb", "B", "B#"
+        ]
+    );
+    assert_eq!(
+        &names(&Tuning::edo(24))[..6],
+        ["C", "C^", "C#", "Dv", "D", "D^"]
+    );
+    assert_eq!(
+        &names(&Tuning::edo(31))[..6],
+        ["C", "C^", "C#", "Db", "Dv", "D"]
+    );
+}
+
+#[test]
+fn keeps_a4_at_the_reference() {
+    for steps in &[12, 19, 24, 31, 53] {
+        let tuning = Tuning::edo(*steps);
+        let (pitch, cents) = tuning.identify(440.0);
+        assert_eq!(tuning.pitch_name(pitch), "A4", "{}-EDO", steps);
+        assert!(cents.abs() < 1e-9);
+    }
+
+    let tuning = Tuning::edo(24).with_reference(415.0, Note::C);
+    let (pitch, _) = tuning.identify(415.0);
+    assert_eq!(tuning.pitch_name(pitch), "A4");
+}
+
+#[test]
+fn matches_the_nearest_degree() {
+    let tuning = Tuning::edo(19);
+    // A quarter of a 19-EDO step above A#4, and the same below C5.
+    let a_sharp = tuning.freq(TunedPitch {
+        degree: 15,
+        octave: 4,
+    });
+    let step = 1200.0 / 19.0;
+    let (pitch, cents) = tuning.identify(a_sharp * 2f64.powf(step / 4.0 / 1200.0));
+    assert_eq!(tuning.pitch_name(pitch), "A#4");
+    assert!((cents - step / 4.0).abs() < 1e-6);
+
+    let c5 = tuning.freq(TunedPitch {
+        degree: 0,
+        octave: 5,
+    });
+    let (pitch, cents) = tuning.identify(c5 * 2f64.powf(-step / 4.0 / 1200.0));
+    assert_eq!(tuning.pitch_name(pitch), "C5");
+    assert!((cents + step / 4.0).abs() < 1e-6);
+}
+
+const JUST_MAJOR: &str = "\
+! just.scl
+!
+5-limit just major
+ 7
+!
+ 9/8
+ 5/4
+ 4/3
+ 701.955 a pure fifth
+ 5/3
+ 15/8
+ 2/1
+";
+
+#[test]
+fn reads_scala_files() {
+    let tuning = Tuning::from_scala(JUST_MAJOR)
+        .unwrap()
+        .with_reference(440.0, Note::A);
+    assert_eq!(tuning.description(), "5-limit just major");
+    assert_eq!(tuning.period(), 1200.0);
+    assert_eq!(names(&tuning), ["A", "B", "C#", "D", "E", "F#", "G#"]);
+
+    // A just major third above A4.
+    let (pitch, cents) = tuning.identify(550.0);
+    assert_eq!(tuning.pitch_name(pitch), "C#4");
+    assert!(cents.abs() < 1e-6);
+    let (pitch, _) = tuning.identify(880.0);
+    assert_eq!(tuning.pitch_name(pitch), "A5");
+}
+
+#[test]
+fn reads_scales_that_repeat_at_other_intervals() {
+    // Bohlen-Pierce, thirteen equal divisions of the tritave (3/1).
+    let mut scala = "Bohlen-Pierce\n13\n".to_string();
+    for step in 1..=13 {
+        scala.push_str(&format!(
+            "{:.5}\n",
+            1200.0 * 3f64.log2() * step as f64 / 13.0
+        ));
+    }
+    let tuning = Tuning::from_scala(&scala).unwrap();
+    assert_eq!(tuning.len(), 13);
+    let c4 = tuning.freq(TunedPitch {
+        degree: 0,
+        octave: 4,
+    });
+    let (pitch, cents) = tuning.identify(3.0 * c4);
+    assert_eq!(
+        pitch,
+        TunedPitch {
+            degree: 0,
+            octave: 5
+        }
+    );
+    assert!(cents.abs() < 1e-3);
+}
+
+#[test]
+fn rejects_invalid_scala_files() {
+    assert!(Tuning::from_scala("").is_err());
+    assert!(Tuning::from_scala("too few\n3\n9/8\n3/2\n").is_err());
+    assert_eq!(
+        Tuning::from_scala("bad\n2\n3/2\nfoo\n"),
+        Err("line 4: invalid pitch: foo".to_string())
+    );
+    assert!(Tuning::from_scala("degree past the period\n2\n3/1\n2/1\n").is_err());
+}
+
+#[test]
+fn parses_edos() {
+    assert_eq!("19-edo".parse::<Tuning>().unwrap().len(), 19);
+    assert_eq!("31EDO".parse::<Tuning>().unwrap().len(), 31);
+    assert!("0-edo".parse::<Tuning>().is_err());
+    assert!("just".parse::<Tuning>().is_err());
+}
+
+#[test]
+fn leaves_estimates_too_unsure_to_name_unnamed() {
+    // A frequency, but below the min confidence, so without a pitch.
+    let detected = DetectedPitch {
+        pitch: None,
+        cents: 0.0,
+        confidence: 0.2,
+        ..PitchDetector::default().identify(440.0)
+    };
+    let record = Record {
+        timestamp: 0.0,
+        channel: None,
+        voice: None,
+        detected,
+        level: None,
+        inharmonicity: None,
+    };
+    let write = |format: Format| {
+        let mut out = RecordWriter::new(vec![], format)
+            .unwrap()
+            .with_tuning(Tuning::edo(24));
+        out.write(&record).unwrap();
+        String::from_utf8(out.finish().unwrap()).unwrap()
+    };
+    assert!(
+        !write(Format::Text).contains("A4"),
+        "{}",
+        write(Format::Text)
+    );
+    assert!(write(Format::Json).contains("\"note\": null"));
+}
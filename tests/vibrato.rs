@@ -0,0 +1,76 @@
+//! Vibrato should be measured on notes that have it, at the right rate and depth, and not found on
+//! steady ones.
+
+use std::f64::consts::PI;
+
+use monophonic_detector::{
+    notes::NoteTracker,
+    vibrato::{PitchTrack, Vibrato},
+    PitchDetector,
+};
+
+/// The vibrato of each note in `samples`.
+fn vibrato_of_notes(samples: &[f32]) -> Vec<Option<Vibrato>> {
+    // Chunks only cover a cycle or so of vibrato, so their pitch can be well off the note's.
+    let detector = PitchDetector::default().with_note_tolerance(50.0);
+    let mut tracker = NoteTracker::new(detector.sample_rate());
+    let mut track = PitchTrack::new(detector.sample_rate());
+    let mut notes = vec![];
+    for chunk in detector.chunks(samples) {
+        let detected = detector.detect(chunk);
+        notes.extend(tracker.push(chunk, &detected));
+        track.push(chunk, &detected);
+    }
+    notes.extend(tracker.finish());
+    notes.iter().map(|note| track.vibrato(note)).collect()
+}
+
+/// A sine of `freq` Hz lasting `duration` seconds, its pitch going `depth` cents either way
+/// `rate` times a second.
+fn tone(freq: f64, rate: f64, depth: f64, duration: f64) -> Vec<f32> {
+    let sample_rate = PitchDetector::default().sample_rate() as f64;
+    let mut phase = 0.0;
+    (0..(duration * sample_rate) as usize)
+        .map(|i| {
+            let t = i as f64 / sample_rate;
+            let cents = depth * (2.0 * PI * rate * t).sin();
+            phase += 2.0 * PI * freq * 2f64.powf(cents / 1200.0) / sample_rate;
+            (0.5 * phase.sin()) as f32
+        })
+        .collect()
+}
+
+#[test]
+fn measures_vibrato() {
+    for &(freq, rate, depth) in &[
+        (110.0, 6.0, 50.0),
+        (220.0, 5.5, 40.0),
+        (440.0, 6.5, 25.0),
+        (660.0, 4.5, 30.0),
+    ] {
+        let vibrato = vibrato_of_notes(&tone(freq, rate, depth, 3.0));
+        assert_eq!(vibrato.len(), 1, "{} Hz: {:?}", freq, vibrato);
+        let vibrato = vibrato[0].unwrap_or_else(|| panic!("no vibrato at {} Hz", freq));
+        assert!(
+            (vibrato.rate - rate).abs() <= 0.1,
+            "{} Hz: rate {} Hz, not {} Hz",
+            freq,
+            vibrato.rate,
+            rate
+        );
+        assert!(
+            (vibrato.depth - depth).abs() <= 0.15 * depth,
+            "{} Hz: depth {}¢, not {}¢",
+            freq,
+            vibrato.depth,
+            depth
+        );
+    }
+}
+
+#[test]
+fn steady_notes_have_no_vibrato() {
+    assert_eq!(vibrato_of_notes(&tone(220.0, 5.5, 0.0, 3.0)), vec![None]);
+    // Too shallow to be anything but jitter.
+    assert_eq!(vibrato_of_notes(&tone(440.0, 5.5, 3.0, 3.0)), vec![None]);
+}
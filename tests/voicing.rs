@@ -0,0 +1,62 @@
+//! Voiced chunks, even with strong formants, should be told apart from noise, and only reported
+//! as unvoiced when asked to.
+
+use monophonic_detector::{
+    generate::{Generator, Noise, Tones},
+    voicing, PitchDetector,
+};
+
+/// A vowel-like tone: a low fundamental with strong upper harmonics.
+fn vowel() -> Generator {
+    Generator::new(Tones::Steps(vec![150.0]))
+        .with_duration(0.5)
+        .with_harmonics(vec![1.0, 0.8, 1.2, 0.9, 0.5, 0.3])
+}
+
+/// Noise, like a fricative, with a faint tone buried in it.
+fn fricative() -> Generator {
+    Generator::new(Tones::Steps(vec![200.0]))
+        .with_duration(0.5)
+        .with_noise(Noise::White, -40.0)
+}
+
+#[test]
+fn measures_clarity() {
+    let samples = vowel().with_noise(Noise::White, 25.0).samples();
+    let (clarity, period) = voicing::clarity(&samples[..4096], 44100 / 50);
+    assert!(clarity > 0.9, "{}", clarity);
+    assert!((period as f64 - 44100.0 / 150.0).abs() < 3.0, "{}", period);
+
+    let samples = fricative().samples();
+    let (clarity, _) = voicing::clarity(&samples[..4096], 44100 / 50);
+    assert!(clarity < 0.2, "{}", clarity);
+}
+
+#[test]
+fn tells_voiced_from_unvoiced() {
+    let detector = PitchDetector::default().with_voicing(true);
+
+    let samples = vowel().with_noise(Noise::White, 25.0).samples();
+    for chunk in detector.chunks(&samples) {
+        let detected = detector.detect(chunk);
+        assert!(!detected.unvoiced);
+        assert!(detected.freq.is_some());
+    }
+
+    let samples = fricative().samples();
+    for chunk in detector.chunks(&samples) {
+        let detected = detector.detect(chunk);
+        assert!(detected.unvoiced);
+        assert_eq!(detected.freq, None);
+        assert_eq!(detected.to_string(), "Unvoiced");
+    }
+}
+
+#[test]
+fn doesnt_classify_by_default() {
+    let detector = PitchDetector::default();
+    let samples = fricative().samples();
+    for chunk in detector.chunks(&samples) {
+        assert!(!detector.detect(chunk).unvoiced);
+    }
+}
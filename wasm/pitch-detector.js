@@ -0,0 +1,27 @@
+// The detector in a browser. Build it with
+//
+//     cargo rustc --release --lib --features wasm --target wasm32-unknown-unknown --crate-type cdylib
+//
+// and serve target/wasm32-unknown-unknown/release/monophonic_detector.wasm alongside this module.
+
+/// Load the detector from `url`. The result has one method, `analyzeChunk(samples, sampleRate)`,
+/// which detects the pitch of a `Float32Array` (such as from `AudioBuffer.getChannelData`) and
+/// returns it as an object like `{freq_hz: 440, note: "A", octave: 4, cents: 0.2, confidence: 0.99}`.
+export async function load(url) {
+  const { instance } = await WebAssembly.instantiateStreaming(fetch(url), {});
+  const wasm = instance.exports;
+
+  return {
+    analyzeChunk(samples, sampleRate) {
+      const ptr = wasm.alloc_samples(samples.length);
+      try {
+        new Float32Array(wasm.memory.buffer, ptr, samples.length).set(samples);
+        const result = wasm.analyze_chunk(ptr, samples.length, sampleRate);
+        const json = new Uint8Array(wasm.memory.buffer, result, wasm.result_len());
+        return JSON.parse(new TextDecoder().decode(json));
+      } finally {
+        wasm.free_samples(ptr, samples.length);
+      }
+    },
+  };
+}